@@ -0,0 +1,110 @@
+/**
+ * File: albums.rs
+ *
+ * Rolls the songs up into albums, for a quick look at what a (recovered) library holds and how complete it is.
+ * The album list in the iTunesDB (mhla) only has the album and artist names, and only from iTunes 7.1 on,
+ * so the albums are made up from the songs' own tags instead, which works for every database version.
+ *
+ */
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+#[derive(Serialize)]
+pub struct Album {
+    /// The album artist, or the artist of the first song if there's none
+    pub artist: String,
+    pub album: String,
+    /// The year most of its songs have. 0 if none has one
+    pub year: u16,
+    pub genre: String,
+    pub is_compilation: bool,
+    /// Songs of the album on the iPod
+    pub num_tracks: u32,
+    /// As tagged, which tells whether songs are missing. 0 if not tagged
+    pub num_tracks_in_album: u32,
+    pub num_discs: u32,
+    pub total_duration_s: u64,
+    pub total_duration_friendly: String,
+    pub total_size_bytes: u64,
+    pub total_size_friendly: String,
+    pub num_plays: u32,
+    /// Whether any of its songs has cover art
+    pub has_artwork: bool,
+}
+
+/// Albums are told apart by their album artist (or the artist, if there's none) and name, ignoring case,
+/// like the iPod's Albums menu does. Songs without an album name aren't on any. Sorted by artist, then album
+pub fn build_albums(songs: &[itunesdb::Song]) -> Vec<Album> {
+    let mut album_songs: BTreeMap<(String, String), Vec<&itunesdb::Song>> = BTreeMap::new();
+
+    for song in songs.iter() {
+        if song.song_album.is_empty() {
+            continue;
+        }
+
+        let album_artist = if song.song_album_artist.is_empty() {
+            &song.song_artist
+        } else {
+            &song.song_album_artist
+        };
+
+        album_songs
+            .entry((album_artist.to_lowercase(), song.song_album.to_lowercase()))
+            .or_default()
+            .push(song);
+    }
+
+    let mut albums: Vec<Album> = Vec::new();
+
+    for (_, songs_on_album) in album_songs.into_iter() {
+        let first_song = songs_on_album[0];
+
+        let mut num_songs_by_year: HashMap<u16, u32> = HashMap::new();
+
+        for song in songs_on_album.iter().filter(|song| song.song_year > 0) {
+            *num_songs_by_year.entry(song.song_year).or_insert(0) += 1;
+        }
+
+        // Ties go to the earlier year
+        let year = num_songs_by_year
+            .into_iter()
+            .max_by_key(|(year, num_songs)| (*num_songs, std::cmp::Reverse(*year)))
+            .map(|(year, _)| year)
+            .unwrap_or(0);
+
+        let total_duration_s: u64 = songs_on_album.iter().map(|song| song.song_duration_s as u64).sum();
+        let total_size_bytes: u64 = songs_on_album.iter().map(|song| song.file_size_bytes).sum();
+
+        albums.push(Album {
+            artist: if first_song.song_album_artist.is_empty() {
+                first_song.song_artist.to_string()
+            } else {
+                first_song.song_album_artist.to_string()
+            },
+            album: first_song.song_album.to_string(),
+            year,
+            genre: songs_on_album
+                .iter()
+                .map(|song| song.song_genre.to_string())
+                .find(|genre| !genre.is_empty())
+                .unwrap_or_default(),
+            is_compilation: songs_on_album.iter().any(|song| song.is_compilation),
+            num_tracks: songs_on_album.len() as u32,
+            num_tracks_in_album: songs_on_album.iter().map(|song| song.num_tracks_in_album).max().unwrap_or(0),
+            num_discs: songs_on_album.iter().map(|song| song.num_discs).max().unwrap_or(0),
+            total_duration_s,
+            total_duration_friendly: helpers::convert_seconds_to_human_readable_duration(total_duration_s as u32),
+            total_size_bytes,
+            total_size_friendly: helpers::convert_bytes_to_human_readable_size(total_size_bytes),
+            num_plays: songs_on_album.iter().map(|song| song.num_plays).sum(),
+            has_artwork: songs_on_album.iter().any(|song| song.has_artwork),
+        });
+    }
+
+    return albums;
+}
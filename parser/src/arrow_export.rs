@@ -0,0 +1,183 @@
+/**
+ * File: arrow_export.rs
+ *
+ * The track list as an Arrow record batch, one row per track of any media type, so it can be handed
+ * to dataframe libraries (Polars, pyarrow, pandas) without going through CSV and guessing the column types.
+ * The batch is written out as a Feather (v2, i.e. Arrow IPC) file. Only built with `--features arrow`.
+ *
+ */
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, RecordBatch, StringArray, TimestampSecondArray, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+
+use crate::helpers::helpers;
+use crate::itunesdb;
+use crate::smart_playlist;
+
+/// A track of any media type, with what only the concrete types know
+struct ArrowTrack<'a> {
+    dbid: u64,
+    media_kind: &'a str,
+    track: &'a dyn smart_playlist::SmartPlaylistTrack,
+}
+
+/// Same columns as the `tracks` table of the SQLite export, with the album spelled out.
+/// Unlike SQLite, Arrow has unsigned 64-bit integers, so the DBIDs are as everywhere else
+fn get_tracks_schema() -> Schema {
+    return Schema::new(vec![
+        Field::new("dbid", DataType::UInt64, false),
+        Field::new("track_id", DataType::UInt32, false),
+        Field::new("media_kind", DataType::Utf8, false),
+        Field::new("title", DataType::Utf8, true),
+        Field::new("artist", DataType::Utf8, true),
+        Field::new("album", DataType::Utf8, true),
+        Field::new("genre", DataType::Utf8, true),
+        Field::new("composer", DataType::Utf8, true),
+        Field::new("year", DataType::UInt64, true),
+        Field::new("track_number", DataType::UInt64, true),
+        Field::new("disc_number", DataType::UInt64, true),
+        Field::new("duration_ms", DataType::UInt64, true),
+        Field::new("bitrate_kbps", DataType::UInt64, true),
+        Field::new("file_size_bytes", DataType::UInt64, true),
+        Field::new("play_count", DataType::UInt64, true),
+        Field::new("rating", DataType::UInt64, true),
+        Field::new("added_at", DataType::Timestamp(TimeUnit::Second, Some("UTC".into())), true),
+        Field::new("filename", DataType::Utf8, true),
+    ]);
+}
+
+/// One row per track of every media type, in the same order as the exports.
+/// Columns a media type doesn't have are null, and so are empty strings and dates that were never set.
+/// `track_filenames` are relative to the iPod's root, by track ID
+pub fn build_tracks_record_batch(
+    songs: &[itunesdb::Song],
+    podcasts: &[itunesdb::Podcast],
+    videos: &[itunesdb::Video],
+    audiobooks: &[itunesdb::Audiobook],
+    itunesu_items: &[itunesdb::ITunesUItem],
+    ringtones: &[itunesdb::Ringtone],
+    voice_memos: &[itunesdb::VoiceMemo],
+    track_filenames: &HashMap<u32, String>,
+) -> RecordBatch {
+    let mut arrow_tracks: Vec<ArrowTrack> = Vec::new();
+
+    for song in songs.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: song.dbid, media_kind: &song.media_kind, track: song });
+    }
+
+    for podcast in podcasts.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: podcast.dbid, media_kind: &podcast.media_kind, track: podcast });
+    }
+
+    for video in videos.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: video.track.dbid, media_kind: &video.track.media_kind, track: &video.track });
+    }
+
+    for audiobook in audiobooks.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: audiobook.track.dbid, media_kind: &audiobook.track.media_kind, track: &audiobook.track });
+    }
+
+    for itunesu_item in itunesu_items.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: itunesu_item.track.dbid, media_kind: &itunesu_item.track.media_kind, track: &itunesu_item.track });
+    }
+
+    for ringtone in ringtones.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: ringtone.track.dbid, media_kind: &ringtone.track.media_kind, track: &ringtone.track });
+    }
+
+    for voice_memo in voice_memos.iter() {
+        arrow_tracks.push(ArrowTrack { dbid: voice_memo.track.dbid, media_kind: &voice_memo.track.media_kind, track: &voice_memo.track });
+    }
+
+    let get_string_column = |rule_field: smart_playlist::SmartPlaylistRuleField| -> ArrayRef {
+        let rule_field = rule_field as u32;
+
+        return Arc::new(StringArray::from(
+            arrow_tracks
+                .iter()
+                .map(|arrow_track| {
+                    arrow_track
+                        .track
+                        .get_string_field(rule_field)
+                        .filter(|string_field| !string_field.is_empty())
+                })
+                .collect::<Vec<Option<String>>>(),
+        ));
+    };
+
+    let get_numeric_column = |rule_field: smart_playlist::SmartPlaylistRuleField| -> ArrayRef {
+        let rule_field = rule_field as u32;
+
+        return Arc::new(UInt64Array::from(
+            arrow_tracks
+                .iter()
+                .map(|arrow_track| arrow_track.track.get_numeric_field(rule_field))
+                .collect::<Vec<Option<u64>>>(),
+        ));
+    };
+
+    let added_at_column = TimestampSecondArray::from(
+        arrow_tracks
+            .iter()
+            .map(|arrow_track| {
+                arrow_track
+                    .track
+                    .get_numeric_field(smart_playlist::SmartPlaylistRuleField::DateAdded as u32)
+                    .filter(|added_at_epoch| *added_at_epoch != 0)
+                    .map(|added_at_epoch| helpers::get_timestamp_as_mac(added_at_epoch).timestamp())
+            })
+            .collect::<Vec<Option<i64>>>(),
+    )
+    .with_timezone("UTC");
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from(arrow_tracks.iter().map(|arrow_track| arrow_track.dbid).collect::<Vec<u64>>())),
+        Arc::new(UInt32Array::from(
+            arrow_tracks.iter().map(|arrow_track| arrow_track.track.get_track_id()).collect::<Vec<u32>>(),
+        )),
+        Arc::new(StringArray::from(
+            arrow_tracks.iter().map(|arrow_track| arrow_track.media_kind).collect::<Vec<&str>>(),
+        )),
+        get_string_column(smart_playlist::SmartPlaylistRuleField::SongName),
+        get_string_column(smart_playlist::SmartPlaylistRuleField::Artist),
+        get_string_column(smart_playlist::SmartPlaylistRuleField::Album),
+        get_string_column(smart_playlist::SmartPlaylistRuleField::Genre),
+        get_string_column(smart_playlist::SmartPlaylistRuleField::Composer),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::Year),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::TrackNumber),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::DiscNumber),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::Time),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::Bitrate),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::Size),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::PlayCount),
+        get_numeric_column(smart_playlist::SmartPlaylistRuleField::Rating),
+        Arc::new(added_at_column),
+        Arc::new(StringArray::from(
+            arrow_tracks
+                .iter()
+                .map(|arrow_track| track_filenames.get(&arrow_track.track.get_track_id()).map(|track_filename| track_filename.as_str()))
+                .collect::<Vec<Option<&str>>>(),
+        )),
+    ];
+
+    return RecordBatch::try_new(Arc::new(get_tracks_schema()), columns)
+        .expect("Can't build the Arrow record batch of the tracks");
+}
+
+/// Feather v2 is the Arrow IPC file format, uncompressed here so any Arrow reader can open it
+pub fn write_feather_file(feather_filename: &str, record_batch: &RecordBatch) {
+    let feather_file = helpers::create_output_file(feather_filename);
+
+    let mut feather_writer = arrow_ipc::writer::FileWriter::try_new(feather_file, &record_batch.schema())
+        .expect(&format!("Can't start writing Feather file '{}'", feather_filename));
+
+    feather_writer
+        .write(record_batch)
+        .expect(&format!("Can't write the tracks to '{}'", feather_filename));
+
+    feather_writer
+        .finish()
+        .expect(&format!("Can't finish writing Feather file '{}'", feather_filename));
+}
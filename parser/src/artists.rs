@@ -0,0 +1,72 @@
+/**
+ * File: artists.rs
+ *
+ * Rolls the songs up into artists: how much of each is on the iPod, how much they were played,
+ * and when they were added, for a first look at a (recovered) library without a spreadsheet.
+ *
+ */
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+#[derive(Serialize)]
+pub struct Artist {
+    pub artist: String,
+    pub num_tracks: u32,
+    /// Albums with at least one of the artist's songs, as the iPod's Artists menu counts them
+    pub num_albums: u32,
+    pub num_plays: u32,
+    pub total_duration_s: u64,
+    pub total_duration_friendly: String,
+    pub total_size_bytes: u64,
+    pub total_size_friendly: String,
+    /// When the first and the latest of the artist's songs were added. None if none of them has an added time
+    pub first_added_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_added_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Artists are told apart by the songs' artist (not the album artist, so featured artists on compilations
+/// count), ignoring case. Songs without an artist are left out. Sorted by artist
+pub fn build_artists(songs: &[itunesdb::Song]) -> Vec<Artist> {
+    let mut artist_songs: BTreeMap<String, Vec<&itunesdb::Song>> = BTreeMap::new();
+
+    for song in songs.iter() {
+        if song.song_artist.is_empty() {
+            continue;
+        }
+
+        artist_songs.entry(song.song_artist.to_lowercase()).or_default().push(song);
+    }
+
+    let mut artists: Vec<Artist> = Vec::new();
+
+    for (_, songs_by_artist) in artist_songs.into_iter() {
+        let album_names: HashSet<String> = songs_by_artist
+            .iter()
+            .filter(|song| !song.song_album.is_empty())
+            .map(|song| song.song_album.to_lowercase())
+            .collect();
+
+        let total_duration_s: u64 = songs_by_artist.iter().map(|song| song.song_duration_s as u64).sum();
+        let total_size_bytes: u64 = songs_by_artist.iter().map(|song| song.file_size_bytes).sum();
+
+        artists.push(Artist {
+            artist: songs_by_artist[0].song_artist.to_string(),
+            num_tracks: songs_by_artist.len() as u32,
+            num_albums: album_names.len() as u32,
+            num_plays: songs_by_artist.iter().map(|song| song.num_plays).sum(),
+            total_duration_s,
+            total_duration_friendly: helpers::convert_seconds_to_human_readable_duration(total_duration_s as u32),
+            total_size_bytes,
+            total_size_friendly: helpers::convert_bytes_to_human_readable_size(total_size_bytes),
+            first_added_at: songs_by_artist.iter().filter_map(|song| song.song_added_to_library_ts).min(),
+            last_added_at: songs_by_artist.iter().filter_map(|song| song.song_added_to_library_ts).max(),
+        });
+    }
+
+    return artists;
+}
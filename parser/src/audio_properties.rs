@@ -0,0 +1,153 @@
+/**
+ * File: audio_properties.rs
+ *
+ * The iTunesDB stores the bitrate and sample rate of every track, but not its bit depth, and it
+ * gives AAC and Apple Lossless files the same "M4A" filetype. Both can only be told from the
+ * audio file itself: the "fmt " chunk of a WAV, the "COMM" chunk of an AIFF,
+ * or the sample description (stsd) of an MP4.
+ *
+ */
+use crate::helpers::helpers;
+
+const RIFF_CHUNK_HEADER_LEN: usize = 8;
+/// "RIFF", the file size, then "WAVE"
+const RIFF_FILE_HEADER_LEN: usize = 12;
+const WAV_FORMAT_BITS_PER_SAMPLE_OFFSET: usize = 14;
+
+/// "FORM", the file size, then "AIFF" or "AIFC"
+const AIFF_FILE_HEADER_LEN: usize = 12;
+/// Number of channels (2 bytes) and number of frames (4 bytes) come first
+const AIFF_COMM_SAMPLE_SIZE_OFFSET: usize = 6;
+
+/// The sample description box has a 4-byte version/flags field and a 4-byte entry count
+const MP4_STSD_PREAMBLE_LEN: usize = 8;
+/// Audio sample entries start with 6 reserved bytes, a 2-byte data reference index and
+/// 8 more reserved bytes, then the channel count (2 bytes) and the sample size (2 bytes)
+const MP4_AUDIO_SAMPLE_ENTRY_SAMPLE_SIZE_OFFSET: usize = 18;
+
+pub struct AudioProperties {
+    pub codec: String,
+    pub is_lossless: bool,
+    /// Lossy codecs don't have a bit depth
+    pub bit_depth: Option<u16>,
+}
+
+/// Returns `None` if the file can't be read, or isn't a WAV, AIFF or MP4 file
+pub fn read_audio_properties_from_audio_file(
+    audio_file_path: &std::path::Path,
+) -> Option<AudioProperties> {
+    let audio_file_as_bytes = std::fs::read(audio_file_path).ok()?;
+
+    if audio_file_as_bytes.len() < RIFF_FILE_HEADER_LEN {
+        return None;
+    }
+
+    if audio_file_as_bytes.starts_with(b"RIFF") && &audio_file_as_bytes[8..12] == b"WAVE" {
+        return read_wav_audio_properties(&audio_file_as_bytes);
+    } else if audio_file_as_bytes.starts_with(b"FORM")
+        && (&audio_file_as_bytes[8..12] == b"AIFF" || &audio_file_as_bytes[8..12] == b"AIFC")
+    {
+        return read_aiff_audio_properties(&audio_file_as_bytes);
+    }
+
+    return read_mp4_audio_properties(&audio_file_as_bytes);
+}
+
+/// WAV chunks are little-endian, and padded to an even length
+fn read_wav_audio_properties(file_as_bytes: &[u8]) -> Option<AudioProperties> {
+    let mut idx = RIFF_FILE_HEADER_LEN;
+
+    while idx + RIFF_CHUNK_HEADER_LEN <= file_as_bytes.len() {
+        let chunk_id = &file_as_bytes[idx..idx + 4];
+        let chunk_len = helpers::build_le_u32_from_bytes(&file_as_bytes[idx + 4..idx + 8]) as usize;
+        let chunk_start = idx + RIFF_CHUNK_HEADER_LEN;
+
+        if chunk_id == b"fmt " {
+            if chunk_start + WAV_FORMAT_BITS_PER_SAMPLE_OFFSET + 2 > file_as_bytes.len() {
+                return None;
+            }
+
+            return Some(AudioProperties {
+                codec: "PCM (WAV)".to_string(),
+                is_lossless: true,
+                bit_depth: Some(helpers::build_le_u16_from_bytes(
+                    &file_as_bytes[chunk_start + WAV_FORMAT_BITS_PER_SAMPLE_OFFSET
+                        ..chunk_start + WAV_FORMAT_BITS_PER_SAMPLE_OFFSET + 2],
+                )),
+            });
+        }
+
+        idx = chunk_start + chunk_len + (chunk_len % 2);
+    }
+
+    return None;
+}
+
+/// Unlike WAV, AIFF is big-endian
+fn read_aiff_audio_properties(file_as_bytes: &[u8]) -> Option<AudioProperties> {
+    let mut idx = AIFF_FILE_HEADER_LEN;
+
+    while idx + RIFF_CHUNK_HEADER_LEN <= file_as_bytes.len() {
+        let chunk_id = &file_as_bytes[idx..idx + 4];
+        let chunk_len = helpers::build_be_u32_from_bytes(&file_as_bytes[idx + 4..idx + 8]) as usize;
+        let chunk_start = idx + RIFF_CHUNK_HEADER_LEN;
+
+        if chunk_id == b"COMM" {
+            if chunk_start + AIFF_COMM_SAMPLE_SIZE_OFFSET + 2 > file_as_bytes.len() {
+                return None;
+            }
+
+            return Some(AudioProperties {
+                codec: "PCM (AIFF)".to_string(),
+                is_lossless: true,
+                bit_depth: Some(helpers::build_be_u32_from_bytes(
+                    &file_as_bytes[chunk_start + AIFF_COMM_SAMPLE_SIZE_OFFSET
+                        ..chunk_start + AIFF_COMM_SAMPLE_SIZE_OFFSET + 2],
+                ) as u16),
+            });
+        }
+
+        idx = chunk_start + chunk_len + (chunk_len % 2);
+    }
+
+    return None;
+}
+
+/// The codec is the type of the first sample entry at moov > trak > mdia > minf > stbl > stsd.
+/// Only the first track is looked at, which is the audio track for anything iTunes creates
+fn read_mp4_audio_properties(file_as_bytes: &[u8]) -> Option<AudioProperties> {
+    let (moov_start, moov_end) =
+        helpers::find_mp4_atom(file_as_bytes, 0, file_as_bytes.len(), b"moov")?;
+    let (trak_start, trak_end) = helpers::find_mp4_atom(file_as_bytes, moov_start, moov_end, b"trak")?;
+    let (mdia_start, mdia_end) = helpers::find_mp4_atom(file_as_bytes, trak_start, trak_end, b"mdia")?;
+    let (minf_start, minf_end) = helpers::find_mp4_atom(file_as_bytes, mdia_start, mdia_end, b"minf")?;
+    let (stbl_start, stbl_end) = helpers::find_mp4_atom(file_as_bytes, minf_start, minf_end, b"stbl")?;
+    let (stsd_start, stsd_end) = helpers::find_mp4_atom(file_as_bytes, stbl_start, stbl_end, b"stsd")?;
+
+    let sample_entry_start = stsd_start + MP4_STSD_PREAMBLE_LEN;
+
+    if sample_entry_start + 8 + MP4_AUDIO_SAMPLE_ENTRY_SAMPLE_SIZE_OFFSET + 2 > stsd_end {
+        return None;
+    }
+
+    let sample_entry_type = &file_as_bytes[sample_entry_start + 4..sample_entry_start + 8];
+    let sample_size_start = sample_entry_start + 8 + MP4_AUDIO_SAMPLE_ENTRY_SAMPLE_SIZE_OFFSET;
+
+    if sample_entry_type == b"alac" {
+        return Some(AudioProperties {
+            codec: "Apple Lossless".to_string(),
+            is_lossless: true,
+            bit_depth: Some(helpers::build_be_u32_from_bytes(
+                &file_as_bytes[sample_size_start..sample_size_start + 2],
+            ) as u16),
+        });
+    } else if sample_entry_type == b"mp4a" {
+        return Some(AudioProperties {
+            codec: "AAC".to_string(),
+            is_lossless: false,
+            bit_depth: None,
+        });
+    }
+
+    return None;
+}
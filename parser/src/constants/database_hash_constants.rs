@@ -0,0 +1,37 @@
+/**
+ * File: database_hash_constants.rs
+ *
+ * The hash58 database signature, as libgpod computes it (src/itdb_hash58.c). The key derivation
+ * tables aren't part of this repository, they are read from libgpod's source with --hash58-tables
+ */
+
+/// Names of the C arrays in itdb_hash58.c, with the number of bytes in each
+pub const HASH58_INV_TABLE_NAME: &str = "invTable";
+pub const HASH58_TABLE1_NAME: &str = "table1";
+pub const HASH58_TABLE2_NAME: &str = "table2";
+pub const HASH58_TABLE_LEN: usize = 256;
+
+pub const HASH58_FIXED_NAME: &str = "fixed";
+pub const HASH58_FIXED_LEN: usize = 18;
+
+/// The FireWire GUID is 8 bytes, read from its hex digits in order
+pub const FIREWIRE_GUID_LEN: usize = 8;
+
+/// Each byte of the key derivation is `table1[x] * 0xB5 - 3` or `table2[x] * 0xB7 + 0x49`
+pub const HASH58_TABLE1_MULTIPLIER: u8 = 0xB5;
+pub const HASH58_TABLE1_SUBTRAHEND: u8 = 3;
+pub const HASH58_TABLE2_MULTIPLIER: u8 = 0xB7;
+pub const HASH58_TABLE2_ADDEND: u8 = 0x49;
+
+/// HMAC-SHA1, with the key zero-padded to a block
+pub const HMAC_BLOCK_LEN: usize = 64;
+pub const HMAC_INNER_PAD: u8 = 0x36;
+pub const HMAC_OUTER_PAD: u8 = 0x5C;
+
+// Fields of the database object that are zeroed before hashing, besides the hash itself.
+// The hashing scheme is set to hash58 (1) while hashing, whatever the database says
+pub const DATABASE_OBJECT_DATABASE_ID_OFFSET: usize = 0x18;
+pub const DATABASE_OBJECT_DATABASE_ID_LEN: usize = 8;
+
+pub const DATABASE_OBJECT_UNKNOWN_0X32_OFFSET: usize = 0x32;
+pub const DATABASE_OBJECT_UNKNOWN_0X32_LEN: usize = 20;
@@ -5,4 +5,16 @@
  */
 
 pub const DEVICEINFO_FILE_SIZE: usize = 1536; // 0x600
-pub const DEVICEINFO_MAX_STRING_LENGTH: usize = 510;
\ No newline at end of file
+pub const DEVICEINFO_MAX_STRING_LENGTH: usize = 510;
+
+pub const DEVICEINFO_FILENAME: &str = "DeviceInfo";
+
+// Each string is its length (in UTF-16 characters), then the characters themselves
+pub const DEVICEINFO_STRING_LENGTH_LEN: usize = 2;
+
+/// The name given to the iPod in iTunes
+pub const DEVICEINFO_IPOD_NAME_OFFSET: usize = 0x000;
+
+/// User and computer the iPod was last synced with. Often left empty
+pub const DEVICEINFO_USER_NAME_OFFSET: usize = 0x200;
+pub const DEVICEINFO_HOST_NAME_OFFSET: usize = 0x400;
@@ -13,6 +13,12 @@ pub const EQUALIZER_PRESET_CHILDSIZE_OFFSET : usize = 20;
 pub const EQUALIZER_PRESET_CHILDSIZE_LEN : usize = 4;
 pub const EQUALIZER_PRESET_CHILDSIZE_VALUE : usize = 588;
 
+pub const EQUALIZER_PRESETS_FILENAME : &str = "iTunesEQPresets";
+
+/// Songs refer to presets by number, starting from this one for the first preset in the file
+/// (e.g. "#!#100#!#" is "Acoustic", the first of iTunes' presets)
+pub const EQUALIZER_FIRST_PRESET_ID : u32 = 100;
+
 
 pub const EQUALIZER_PRESET_PRESET_OBJECT_KEY: &str = "pqed";
 pub const EQUALIZER_PRESET_PRESET_NAME_LENGTH_OFFSET : usize = 4;
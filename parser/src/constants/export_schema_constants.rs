@@ -0,0 +1,229 @@
+/**
+ * File: export_schema_constants.rs
+ *
+ * The version of the record layout of music.json, podcasts.json and playlists.json (and their YAML and
+ * NDJSON versions), and every field of it, as checked by the tests in json_schema.rs.
+ *
+ * Compatibility policy: within a major version, fields are only ever added. A new field gets
+ * `#[serde(default)]`, so it's optional in the JSON Schema, is listed below with the version it was added in,
+ * and the minor version goes up. Removing, renaming or changing the type of a field means a new major version,
+ * and starting the list over.
+ */
+
+/// "major.minor", written into every record as `schema_version`
+pub const EXPORT_SCHEMA_VERSION: &str = "2.0";
+
+/// A field of a record type: name, JSON type (as the JSON Schema tests spell it)
+/// and the schema version it was added in
+#[cfg(test)]
+pub type ExportSchemaField = (&'static str, &'static str, &'static str);
+
+/// The fields of each record type
+#[cfg(test)]
+pub const EXPORT_SCHEMA_FIELDS: &[(&str, &[ExportSchemaField])] = &[
+    (
+        "Song",
+        &[
+            ("schema_version", "string", "2.0"),
+            ("apple_user_id", "integer", "2.0"),
+            ("artwork_count", "integer", "2.0"),
+            ("artwork_id", "integer", "2.0"),
+            ("artwork_size_bytes", "integer", "2.0"),
+            ("audio_type_raw", "integer", "2.0"),
+            ("bit_depth", "integer|null", "2.0"),
+            ("bitrate_kbps", "integer", "2.0"),
+            ("bpm", "integer", "2.0"),
+            ("codec", "string", "2.0"),
+            ("dbid", "integer", "2.0"),
+            ("disc_number", "integer", "2.0"),
+            ("equalizer_preset", "string", "2.0"),
+            ("equalizer_preset_id", "integer|null", "2.0"),
+            ("equalizer_preset_name", "string", "2.0"),
+            ("explicitness", "string", "2.0"),
+            ("file_checksum", "string|null", "2.0"),
+            ("file_extension", "string", "2.0"),
+            ("file_size_bytes", "integer", "2.0"),
+            ("file_size_friendly", "string", "2.0"),
+            ("file_size_is_estimated", "boolean", "2.0"),
+            ("gapless_enabled", "boolean", "2.0"),
+            ("has_artwork", "boolean", "2.0"),
+            ("has_lyrics", "boolean", "2.0"),
+            ("is_checked", "boolean", "2.0"),
+            ("is_compilation", "boolean", "2.0"),
+            ("is_lossless", "boolean", "2.0"),
+            ("is_protected", "boolean", "2.0"),
+            ("last_played_at", "string|null", "2.0"),
+            ("last_skipped_at", "string|null", "2.0"),
+            ("lyrics", "string|null", "2.0"),
+            ("media_kind", "string", "2.0"),
+            ("media_type_raw", "integer", "2.0"),
+            ("num_discs", "integer", "2.0"),
+            ("num_plays", "integer", "2.0"),
+            ("num_samples", "integer", "2.0"),
+            ("num_skips", "integer", "2.0"),
+            ("num_tracks_in_album", "integer", "2.0"),
+            ("postgap_samples", "integer", "2.0"),
+            ("pregap_samples", "integer", "2.0"),
+            ("rating_source", "string", "2.0"),
+            ("remember_playback_position", "boolean", "2.0"),
+            ("replaygain_track_gain", "string", "2.0"),
+            ("resume_position_ms", "integer", "2.0"),
+            ("sample_rate_hz", "integer", "2.0"),
+            ("skip_when_shuffling", "boolean", "2.0"),
+            ("song_absolute_path", "string|null", "2.0"),
+            ("song_added_to_library_epoch", "integer", "2.0"),
+            ("song_added_to_library_ts", "string|null", "2.0"),
+            ("song_album", "string", "2.0"),
+            ("song_album_artist", "string", "2.0"),
+            ("song_artist", "string", "2.0"),
+            ("song_comment", "string", "2.0"),
+            ("song_composer", "string", "2.0"),
+            ("song_description", "string", "2.0"),
+            ("song_duration_friendly", "string", "2.0"),
+            ("song_duration_s", "integer", "2.0"),
+            ("song_filename", "string", "2.0"),
+            ("song_filename_raw", "string", "2.0"),
+            ("song_genre", "string", "2.0"),
+            ("song_grouping", "string", "2.0"),
+            ("song_keywords", "string", "2.0"),
+            ("song_modified_epoch", "integer", "2.0"),
+            ("song_modified_ts", "string|null", "2.0"),
+            ("song_previous_rating_raw", "integer", "2.0"),
+            ("song_rating_raw", "integer", "2.0"),
+            ("song_released_epoch", "integer", "2.0"),
+            ("song_released_ts", "string|null", "2.0"),
+            ("song_sort_album", "string", "2.0"),
+            ("song_sort_album_artist", "string", "2.0"),
+            ("song_sort_artist", "string", "2.0"),
+            ("song_sort_composer", "string", "2.0"),
+            ("song_sort_title", "string", "2.0"),
+            ("song_title", "string", "2.0"),
+            ("song_year", "integer", "2.0"),
+            ("sound_check_gain_db", "number|null", "2.0"),
+            ("sound_check_raw", "integer", "2.0"),
+            ("start_time_ms", "integer", "2.0"),
+            ("stop_time_ms", "integer", "2.0"),
+            ("track_id", "integer", "2.0"),
+            ("track_index", "integer", "2.0"),
+            ("track_number", "integer", "2.0"),
+            ("volume_adjustment", "integer", "2.0"),
+        ],
+    ),
+    (
+        "Podcast",
+        &[
+            ("schema_version", "string", "2.0"),
+            ("chapters", "array<Chapter>", "2.0"),
+            ("dbid", "integer", "2.0"),
+            ("duration_s", "integer", "2.0"),
+            ("enclosure_url", "string", "2.0"),
+            ("episode_order", "integer|null", "2.0"),
+            ("explicitness", "string", "2.0"),
+            ("file_size_bytes", "integer", "2.0"),
+            ("file_size_friendly", "string", "2.0"),
+            ("file_size_is_estimated", "boolean", "2.0"),
+            ("is_new", "boolean", "2.0"),
+            ("is_played", "boolean", "2.0"),
+            ("media_kind", "string", "2.0"),
+            ("media_type_raw", "integer", "2.0"),
+            ("podcast_description", "string", "2.0"),
+            ("podcast_file_type", "string", "2.0"),
+            ("podcast_genre", "string", "2.0"),
+            ("podcast_grouping", "string", "2.0"),
+            ("podcast_keywords", "string", "2.0"),
+            ("podcast_publisher", "string", "2.0"),
+            ("podcast_show", "string", "2.0"),
+            ("podcast_subtitle", "string", "2.0"),
+            ("podcast_title", "string", "2.0"),
+            ("release_date", "string|null", "2.0"),
+            ("remember_playback_position", "boolean", "2.0"),
+            ("resume_position_ms", "integer", "2.0"),
+            ("rss_url", "string", "2.0"),
+            ("skip_when_shuffling", "boolean", "2.0"),
+            ("track_id", "integer", "2.0"),
+        ],
+    ),
+    (
+        "Playlist",
+        &[
+            ("schema_version", "string", "2.0"),
+            ("column_settings", "PlaylistColumnSettings|null", "2.0"),
+            ("created_ts", "string", "2.0"),
+            ("is_genius_playlist", "boolean", "2.0"),
+            ("is_master_playlist", "boolean", "2.0"),
+            ("is_podcast_playlist", "boolean", "2.0"),
+            ("is_smart_playlist", "boolean", "2.0"),
+            ("membership_source", "string", "2.0"),
+            ("persistent_playlist_id", "integer", "2.0"),
+            ("playlist_kind", "string", "2.0"),
+            ("playlist_name", "string", "2.0"),
+            ("smart_playlist_preferences", "SmartPlaylistPreferences|null", "2.0"),
+            ("smart_playlist_rules", "SmartPlaylistRules|null", "2.0"),
+            ("sort_order", "string", "2.0"),
+            ("sort_order_raw", "integer", "2.0"),
+            ("stored_track_ids", "array<integer>", "2.0"),
+            ("track_ids", "array<integer>", "2.0"),
+        ],
+    ),
+    (
+        "Chapter",
+        &[
+            ("start_ms", "integer", "2.0"),
+            ("title", "string", "2.0"),
+        ],
+    ),
+    (
+        "PlaylistColumnSettings",
+        &[
+            ("columns", "array<PlaylistColumn>", "2.0"),
+            ("sort_type", "integer", "2.0"),
+        ],
+    ),
+    (
+        "PlaylistColumn",
+        &[
+            ("column_id", "integer", "2.0"),
+            ("column_name", "string", "2.0"),
+            ("is_sort_reversed", "boolean", "2.0"),
+            ("width_px", "integer", "2.0"),
+        ],
+    ),
+    (
+        "SmartPlaylistPreferences",
+        &[
+            ("check_limits", "boolean", "2.0"),
+            ("check_rules", "boolean", "2.0"),
+            ("limit_sort", "integer", "2.0"),
+            ("limit_sort_name", "string", "2.0"),
+            ("limit_type", "integer", "2.0"),
+            ("limit_type_name", "string", "2.0"),
+            ("limit_value", "integer", "2.0"),
+            ("live_update", "boolean", "2.0"),
+            ("match_checked_only", "boolean", "2.0"),
+            ("reverse_limit_sort", "boolean", "2.0"),
+        ],
+    ),
+    (
+        "SmartPlaylistRules",
+        &[
+            ("match_any", "boolean", "2.0"),
+            ("rules", "array<SmartPlaylistRule>", "2.0"),
+        ],
+    ),
+    (
+        "SmartPlaylistRule",
+        &[
+            ("action", "integer", "2.0"),
+            ("action_name", "string", "2.0"),
+            ("field", "integer", "2.0"),
+            ("field_name", "string", "2.0"),
+            ("from_date", "integer", "2.0"),
+            ("from_units", "integer", "2.0"),
+            ("from_value", "integer", "2.0"),
+            ("string_value", "string|null", "2.0"),
+            ("to_date", "integer", "2.0"),
+            ("to_units", "integer", "2.0"),
+            ("to_value", "integer", "2.0"),
+        ],
+    ),
+];
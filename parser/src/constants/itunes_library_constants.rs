@@ -0,0 +1,94 @@
+/**
+ * File: itunes_library_constants.rs
+ *
+ * The "iTunes Music Library.xml" (or "iTunes Library.xml") file is an XML plist that iTunes on the
+ * computer keeps next to its own library, for other apps to read. Only the keys the parser reads or
+ * writes (see `itunes_library::build_itunes_library_plist`) are listed here.
+ */
+
+pub const ITUNES_LIBRARY_MAJOR_VERSION_KEY: &str = "Major Version";
+pub const ITUNES_LIBRARY_MINOR_VERSION_KEY: &str = "Minor Version";
+/// When the file was written
+pub const ITUNES_LIBRARY_DATE_KEY: &str = "Date";
+/// A file:// URL, the folder the tracks' locations are under
+pub const ITUNES_LIBRARY_MUSIC_FOLDER_KEY: &str = "Music Folder";
+
+/// A dict of every track, keyed by track ID
+pub const ITUNES_LIBRARY_TRACKS_KEY: &str = "Tracks";
+
+pub const ITUNES_LIBRARY_TRACK_ID_KEY: &str = "Track ID";
+/// 16 hex digits, the same 64-bit value as the dbid of the track in the iTunesDB
+pub const ITUNES_LIBRARY_PERSISTENT_ID_KEY: &str = "Persistent ID";
+pub const ITUNES_LIBRARY_NAME_KEY: &str = "Name";
+pub const ITUNES_LIBRARY_ARTIST_KEY: &str = "Artist";
+pub const ITUNES_LIBRARY_ALBUM_KEY: &str = "Album";
+pub const ITUNES_LIBRARY_PLAY_COUNT_KEY: &str = "Play Count";
+/// 0 to 100, same as the iTunesDB
+pub const ITUNES_LIBRARY_RATING_KEY: &str = "Rating";
+/// Only there (as <true/>) when the rating is the album's, rather than one given to the track itself
+pub const ITUNES_LIBRARY_RATING_COMPUTED_KEY: &str = "Rating Computed";
+
+pub const ITUNES_LIBRARY_ALBUM_ARTIST_KEY: &str = "Album Artist";
+pub const ITUNES_LIBRARY_COMPOSER_KEY: &str = "Composer";
+pub const ITUNES_LIBRARY_GENRE_KEY: &str = "Genre";
+pub const ITUNES_LIBRARY_GROUPING_KEY: &str = "Grouping";
+pub const ITUNES_LIBRARY_COMMENTS_KEY: &str = "Comments";
+pub const ITUNES_LIBRARY_YEAR_KEY: &str = "Year";
+pub const ITUNES_LIBRARY_TRACK_NUMBER_KEY: &str = "Track Number";
+pub const ITUNES_LIBRARY_TRACK_COUNT_KEY: &str = "Track Count";
+pub const ITUNES_LIBRARY_DISC_NUMBER_KEY: &str = "Disc Number";
+pub const ITUNES_LIBRARY_DISC_COUNT_KEY: &str = "Disc Count";
+pub const ITUNES_LIBRARY_BPM_KEY: &str = "BPM";
+/// In milliseconds
+pub const ITUNES_LIBRARY_TOTAL_TIME_KEY: &str = "Total Time";
+/// In bytes
+pub const ITUNES_LIBRARY_SIZE_KEY: &str = "Size";
+/// In kbps
+pub const ITUNES_LIBRARY_BIT_RATE_KEY: &str = "Bit Rate";
+/// In Hz
+pub const ITUNES_LIBRARY_SAMPLE_RATE_KEY: &str = "Sample Rate";
+pub const ITUNES_LIBRARY_DATE_ADDED_KEY: &str = "Date Added";
+pub const ITUNES_LIBRARY_DATE_MODIFIED_KEY: &str = "Date Modified";
+pub const ITUNES_LIBRARY_PLAY_DATE_UTC_KEY: &str = "Play Date UTC";
+pub const ITUNES_LIBRARY_SKIP_COUNT_KEY: &str = "Skip Count";
+pub const ITUNES_LIBRARY_SKIP_DATE_KEY: &str = "Skip Date";
+pub const ITUNES_LIBRARY_COMPILATION_KEY: &str = "Compilation";
+pub const ITUNES_LIBRARY_SORT_NAME_KEY: &str = "Sort Name";
+pub const ITUNES_LIBRARY_SORT_ARTIST_KEY: &str = "Sort Artist";
+pub const ITUNES_LIBRARY_SORT_ALBUM_KEY: &str = "Sort Album";
+pub const ITUNES_LIBRARY_SORT_ALBUM_ARTIST_KEY: &str = "Sort Album Artist";
+pub const ITUNES_LIBRARY_SORT_COMPOSER_KEY: &str = "Sort Composer";
+/// "File" for tracks with a file of their own, "URL" for streams
+pub const ITUNES_LIBRARY_TRACK_TYPE_KEY: &str = "Track Type";
+/// A file:// URL
+pub const ITUNES_LIBRARY_LOCATION_KEY: &str = "Location";
+
+pub const ITUNES_LIBRARY_PODCAST_KEY: &str = "Podcast";
+pub const ITUNES_LIBRARY_MOVIE_KEY: &str = "Movie";
+pub const ITUNES_LIBRARY_TV_SHOW_KEY: &str = "TV Show";
+pub const ITUNES_LIBRARY_MUSIC_VIDEO_KEY: &str = "Music Video";
+pub const ITUNES_LIBRARY_HAS_VIDEO_KEY: &str = "Has Video";
+
+/// Flags (<true/>) of tracks that aren't music, and so aren't compared with the iPod's songs
+pub const ITUNES_LIBRARY_NON_MUSIC_KEYS: [&str; 5] = [
+    ITUNES_LIBRARY_PODCAST_KEY,
+    ITUNES_LIBRARY_MOVIE_KEY,
+    ITUNES_LIBRARY_TV_SHOW_KEY,
+    ITUNES_LIBRARY_MUSIC_VIDEO_KEY,
+    ITUNES_LIBRARY_HAS_VIDEO_KEY,
+];
+
+/// An array of every playlist, the library itself first
+pub const ITUNES_LIBRARY_PLAYLISTS_KEY: &str = "Playlists";
+
+pub const ITUNES_LIBRARY_PLAYLIST_ID_KEY: &str = "Playlist ID";
+/// 16 hex digits, the same 64-bit value as the persistent playlist ID in the iTunesDB
+pub const ITUNES_LIBRARY_PLAYLIST_PERSISTENT_ID_KEY: &str = "Playlist Persistent ID";
+/// Only on the library playlist, which iTunes doesn't show as a playlist (so it isn't "Visible" either)
+pub const ITUNES_LIBRARY_MASTER_KEY: &str = "Master";
+pub const ITUNES_LIBRARY_VISIBLE_KEY: &str = "Visible";
+pub const ITUNES_LIBRARY_ALL_ITEMS_KEY: &str = "All Items";
+/// Only on the playlist iTunes shows as "Podcasts"
+pub const ITUNES_LIBRARY_PODCASTS_KEY: &str = "Podcasts";
+/// An array of dicts, each with just the "Track ID"
+pub const ITUNES_LIBRARY_PLAYLIST_ITEMS_KEY: &str = "Playlist Items";
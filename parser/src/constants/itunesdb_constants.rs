@@ -13,13 +13,50 @@
 
 pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
 
+    // ----- CHUNK HEADER ----- //
+    // Every chunk starts with its key, then its header length. The third field is the chunk's total length
+    // (header and children), except for the lists (mhlt, mhlp, mhla, mhli), where it's the number of items
+    pub const CHUNK_HEADER_LEN_OFFSET: usize = 4;
+    pub const CHUNK_HEADER_LEN_LEN: usize = 4;
+
+    pub const CHUNK_TOTAL_LEN_OFFSET: usize = 8;
+    pub const CHUNK_TOTAL_LEN_LEN: usize = 4;
+
+    pub const CHUNK_MIN_HEADER_LEN: usize = 12;
+
+    // The header lengths libgpod writes, which are the same as iTunes'. Headers of the database object,
+    // track items, playlists and album items grew with the iTunes versions, so they aren't fixed
+    pub const LIST_LIBGPOD_HEADER_LEN: u32 = 0x5C;
+    pub const DATASET_LIBGPOD_HEADER_LEN: u32 = 0x60;
+    pub const PLAYLIST_ITEM_LIBGPOD_HEADER_LEN: u32 = 0x4C;
+    pub const DATA_OBJECT_LIBGPOD_HEADER_LEN: u32 = 0x18;
+
 
     // ----- DATABASE OBJECT ----- //
     pub const DATABASE_OBJECT_KEY: &str = "mhbd";
 
-    pub const DATABASE_OBJECT_VERSION_NUMBER_OFFSET: usize = 4;
+    pub const DATABASE_OBJECT_VERSION_NUMBER_OFFSET: usize = 16;
     pub const DATABASE_OBJECT_VERSION_NUMBER_LEN: usize = 4;
 
+    pub const DATABASE_OBJECT_NUM_DATASETS_OFFSET: usize = 20;
+    pub const DATABASE_OBJECT_NUM_DATASETS_LEN: usize = 4;
+
+    // iTunes 4.2's, the oldest database documented
+    pub const DATABASE_OBJECT_MIN_HEADER_LEN: u32 = 0x68;
+
+    // Which of the hashes below the device checks the database against. The hashes are named after their offsets
+    pub const DATABASE_OBJECT_HASHING_SCHEME_OFFSET: usize = 48;
+    pub const DATABASE_OBJECT_HASHING_SCHEME_LEN: usize = 2;
+
+    pub const DATABASE_OBJECT_HASH58_OFFSET: usize = 0x58;
+    pub const DATABASE_OBJECT_HASH58_LEN: usize = 20;
+
+    pub const DATABASE_OBJECT_HASH72_OFFSET: usize = 0x72;
+    pub const DATABASE_OBJECT_HASH72_LEN: usize = 46;
+
+    pub const DATABASE_OBJECT_HASHAB_OFFSET: usize = 0xAB;
+    pub const DATABASE_OBJECT_HASHAB_LEN: usize = 57;
+
     pub const DATABASE_OBJECT_LANGUAGE_OFFSET: usize = 70;
     pub const DATABASE_OBJECT_LANGUAGE_LEN: usize = 2;
 
@@ -44,6 +81,17 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     // ----- TRACK ITEM ----- //
     pub const TRACK_ITEM_KEY: &str = "mhit";
 
+    // How many data objects (mhod) belong to this track
+    pub const TRACK_ITEM_NUM_DATA_OBJECTS_OFFSET: usize = 12;
+    pub const TRACK_ITEM_NUM_DATA_OBJECTS_LEN: usize = 4;
+
+    pub const TRACK_ITEM_TRACK_UNIQUE_ID_OFFSET: usize = 16;
+    pub const TRACK_ITEM_TRACK_UNIQUE_ID_LEN: usize = 4;
+
+    // 1 if the track shows up on the device. Anything else is a housekeeping entry (e.g. pending deletion)
+    pub const TRACK_ITEM_TRACK_VISIBLE_SETTING_OFFSET: usize = 20;
+    pub const TRACK_ITEM_TRACK_VISIBLE_SETTING_LEN: usize = 4;
+
     pub const TRACK_ITEM_TRACK_FILETYPE_OFFSET: usize = 24;
     pub const TRACK_ITEM_TRACK_FILETYPE_LEN: usize = 4;
 
@@ -51,7 +99,6 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_BITRATE_SETTING_LEN: usize = 1;
 
     pub const TRACK_ITEM_IS_COMPILATION_SETTING_OFFSET: usize = 30;
-    pub const TRACK_ITEM_IS_COMPILATION_SETTING_LEN: usize = 1;
 
     pub const TRACK_ITEM_TRACK_RATING_OFFSET: usize = 31;
     pub const TRACK_ITEM_TRACK_RATING_LEN: usize = 1;
@@ -89,6 +136,10 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_STOP_TIME_OFFSET: usize = 72;
     pub const TRACK_ITEM_TRACK_STOP_TIME_LEN: usize = 4;
 
+    // 0 if Sound Check hasn't analysed the track, see `decode_sound_check_gain_db`
+    pub const TRACK_ITEM_TRACK_SOUND_CHECK_OFFSET: usize = 76;
+    pub const TRACK_ITEM_TRACK_SOUND_CHECK_LEN: usize = 4;
+
     pub const TRACK_ITEM_TRACK_PLAY_COUNT_OFFSET: usize = 80;
     pub const TRACK_ITEM_TRACK_PLAY_COUNT_LEN: usize = 4;
 
@@ -107,8 +158,14 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_ADDED_TIMESTAMP_OFFSET: usize = 104;
     pub const TRACK_ITEM_TRACK_ADDED_TIMESTAMP_LEN: usize = 4;
 
-    // pub const TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_OFFSET : usize = 108;
-    // pub const TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_LEN : usize = 4;
+    pub const TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_OFFSET: usize = 108;
+    pub const TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_LEN: usize = 4;
+
+    pub const TRACK_ITEM_TRACK_DBID_OFFSET: usize = 112;
+    pub const TRACK_ITEM_TRACK_DBID_LEN: usize = 8;
+
+    // 0 if the track is checked, 1 if it is not
+    pub const TRACK_ITEM_TRACK_CHECKED_SETTING_OFFSET: usize = 120;
 
     pub const TRACK_ITEM_TRACK_PREVIOUS_RATING_OFFSET: usize = 121;
     pub const TRACK_ITEM_TRACK_PREVIOUS_RATING_LEN: usize = 1;
@@ -116,11 +173,13 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_BPM_OFFSET: usize = 122;
     pub const TRACK_ITEM_TRACK_BPM_LEN: usize = 2;
 
+    pub const TRACK_ITEM_TRACK_ARTWORK_COUNT_OFFSET: usize = 124;
+    pub const TRACK_ITEM_TRACK_ARTWORK_COUNT_LEN: usize = 2;
+
     pub const TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_OFFSET: usize = 128;
     pub const TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_LEN: usize = 4;
 
     pub const TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_OFFSET: usize = 164;
-    pub const TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_LEN: usize = 1;
 
     pub const TRACK_ITEM_TRACK_RELEASED_TIMESTAMP_OFFSET: usize = 140;
     pub const TRACK_ITEM_TRACK_RELEASED_TIMESTAMP_LEN: usize = 4;
@@ -129,6 +188,10 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_ADVANCED_TRACK_TYPE_OFFSET: usize = 144;
     pub const TRACK_ITEM_ADVANCED_TRACK_TYPE_LEN: usize = 2;
 
+    // Content advisory: 1 = explicit, 2 = clean, 0 = not rated
+    pub const TRACK_ITEM_TRACK_EXPLICIT_FLAG_OFFSET: usize = 146;
+    pub const TRACK_ITEM_TRACK_EXPLICIT_FLAG_LEN: usize = 1;
+
     pub const TRACK_ITEM_TRACK_SKIPPED_COUNT_OFFSET: usize = 156;
     pub const TRACK_ITEM_TRACK_SKIPPED_COUNT_LEN: usize = 4;
 
@@ -136,16 +199,16 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_LAST_SKIPPED_TIMESTAMP_LEN: usize = 4;
 
     pub const TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET: usize = 165;
-    pub const TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_LEN: usize = 1;
 
-    // pub const TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFSET : usize = 166;
-    // pub const TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_LEN : usize = 1;
+    pub const TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET: usize = 166;
 
     pub const TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_OFFSET: usize = 176;
-    pub const TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_LEN: usize = 1;
 
     pub const TRACK_ITEM_TRACK_MOVIE_FLAG_SETTING_OFFSET: usize = 177;
-    pub const TRACK_ITEM_TRACK_MOVIE_FLAG_SETTING_LEN: usize = 1;
+
+    // Podcasts only: 0x01 once played, 0x02 while the "new episode" bullet is shown
+    pub const TRACK_ITEM_TRACK_UNPLAYED_MARK_OFFSET: usize = 178;
+    pub const TRACK_ITEM_TRACK_UNPLAYED_MARK_LEN: usize = 1;
 
     pub const TRACK_ITEM_TRACK_BEGINNING_SILENCE_SAMPLE_COUNT_OFFSET: usize = 184;
     pub const TRACK_ITEM_TRACK_BEGINNING_SILENCE_SAMPLE_COUNT_LEN: usize = 4;
@@ -173,34 +236,91 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
     pub const TRACK_ITEM_TRACK_CROSSFADING_SETTING_OFFSET: usize = 258;
     pub const TRACK_ITEM_TRACK_CROSSFADING_SETTING_LEN: usize = 2;
 
-    pub const TRACK_ITEM_LAST_OFFSET: usize = 356;
+    // The ID of the image item (mhii) in the ArtworkDB holding this track's cover art
+    pub const TRACK_ITEM_TRACK_ARTWORK_ID_OFFSET: usize = 352;
+    pub const TRACK_ITEM_TRACK_ARTWORK_ID_LEN: usize = 4;
+
+
+    pub const TRACK_ITEM_HEADER_LEN_OFFSET: usize = 4;
+    pub const TRACK_ITEM_HEADER_LEN_LEN: usize = 4;
+
+    // dbversion 0x14 (iTunes 7.1) through 0x19 (iTunes 7.4). Newer databases have a longer header,
+    // but nothing past this is documented, so it isn't decoded
+    pub const TRACK_ITEM_DOCUMENTED_HEADER_LEN: u32 = 0x184;
+
+    // Media type value for audio, the only kind of track before the field was added in dbversion 0x0c
+    pub const TRACK_ITEM_TRACK_MEDIA_TYPE_AUDIO: u32 = 0x01;
+
+    // ----- PLAYLIST LIST ----- //
+    pub const PLAYLIST_LIST_KEY: &str = "mhlp";
+
+    pub const PLAYLIST_LIST_NUM_PLAYLISTS_OFFSET: usize = 8;
+    pub const PLAYLIST_LIST_NUM_PLAYLISTS_LEN: usize = 4;
+
+    pub const PLAYLIST_LIST_LAST_OFFSET: usize = 12;
 
     // ----- PLAYLIST ----- //
     pub const PLAYLIST_KEY: &str = "mhyp";
 
+    // Only counts the playlist's own data objects, not the ones of its playlist items
+    pub const PLAYLIST_NUM_DATA_OBJECTS_OFFSET: usize = 12;
+    pub const PLAYLIST_NUM_DATA_OBJECTS_LEN: usize = 4;
+
+    pub const PLAYLIST_NUM_PLAYLIST_ITEMS_OFFSET: usize = 16;
+    pub const PLAYLIST_NUM_PLAYLIST_ITEMS_LEN: usize = 4;
+
     pub const PLAYLIST_IS_MASTER_PLAYLIST_SETTING_OFFSET: usize = 20;
     pub const PLAYLIST_IS_MASTER_PLAYLIST_SETTING_LEN: usize = 1;
 
+    // The first of three undocumented flags after the master flag, seen set on some playlists. Genius
+    // playlists have it set, along with a Genius data object (type 102)
+    pub const PLAYLIST_IS_GENIUS_PLAYLIST_SETTING_OFFSET: usize = 21;
+
     pub const PLAYLIST_CREATED_TIMESTAMP_OFFSET: usize = 24;
     pub const PLAYLIST_CREATED_TIMESTAMP_LEN: usize = 4;
 
+    pub const PLAYLIST_PERSISTENT_ID_OFFSET: usize = 28;
+    pub const PLAYLIST_PERSISTENT_ID_LEN: usize = 8;
+
     pub const PLAYLIST_PLAYLIST_SORT_ORDER_OFFSET: usize = 44;
     pub const PLAYLIST_PLAYLIST_SORT_ORDER_LEN: usize = 4;
 
+    pub const PLAYLIST_IS_PODCAST_PLAYLIST_SETTING_OFFSET: usize = 42;
+    pub const PLAYLIST_IS_PODCAST_PLAYLIST_SETTING_LEN: usize = 2;
+
     pub const PLAYLIST_LAST_OFFSET: usize = 48;
 
     // ----- PLAYLIST ITEM ----- //
     pub const PLAYLIST_ITEM_KEY: &str = "mhip";
 
+    pub const PLAYLIST_ITEM_NUM_DATA_OBJECTS_OFFSET: usize = 12;
+    pub const PLAYLIST_ITEM_NUM_DATA_OBJECTS_LEN: usize = 4;
+
+    // 0x100 = Podcast Group, only found in the Podcast List
+    pub const PLAYLIST_ITEM_PODCAST_GROUPING_FLAG_OFFSET: usize = 16;
+    pub const PLAYLIST_ITEM_PODCAST_GROUPING_FLAG_LEN: usize = 2;
+
+    pub const PLAYLIST_ITEM_GROUP_ID_OFFSET: usize = 20;
+    pub const PLAYLIST_ITEM_GROUP_ID_LEN: usize = 4;
+
+    pub const PLAYLIST_ITEM_TRACK_ID_OFFSET: usize = 24;
+    pub const PLAYLIST_ITEM_TRACK_ID_LEN: usize = 4;
+
     pub const PLAYLIST_ITEM_ADDED_TIMESTAMP_OFFSET: usize = 28;
     pub const PLAYLIST_ITEM_ADDED_TIMESTAMP_LEN: usize = 4;
 
+    pub const PLAYLIST_ITEM_PODCAST_GROUPING_REFERENCE_OFFSET: usize = 32;
+    pub const PLAYLIST_ITEM_PODCAST_GROUPING_REFERENCE_LEN: usize = 4;
+
     pub const PLAYLIST_ITEM_LAST_OFFSET: usize = 36;
 
     // ----- DATA OBJECT ----- //
 
     pub const DATA_OBJECT_KEY: &str = "mhod";
 
+    pub const DATA_OBJECT_TOTAL_LENGTH_OFFSET: usize = 8;
+    pub const DATA_OBJECT_TOTAL_LENGTH_LEN: usize = 4;
+
     // Must parse this first, to decide how to handle the rest of the object
 
     pub const DATA_OBJECT_TYPE_OFFSET: usize = 12;
@@ -215,19 +335,83 @@ pub const DEFAULT_SUBSTRUCTURE_SIZE: usize = 4;
 
     pub const DATA_OBJECT_LAST_OFFSET: usize = 0x18; // 24d
 
+    // ----- CHAPTER DATA (mhod type 17) ----- //
+    // After the mhod header and 12 unknown bytes, the chapters are stored as (big-endian!)
+    // MP4-style atoms: a "sean" atom, holding one "chap" atom per chapter, each holding a "name" atom
+
+    pub const CHAPTER_DATA_UNKNOWN_LEN: usize = 12;
+
+    pub const CHAPTER_DATA_ATOM_SIZE_OFFSET: usize = 0;
+    pub const CHAPTER_DATA_ATOM_SIZE_LEN: usize = 4;
+
+    pub const CHAPTER_DATA_ATOM_NAME_OFFSET: usize = 4;
+    pub const CHAPTER_DATA_ATOM_NAME_LEN: usize = 4;
+
+    // Size, name and 3 more 4-byte fields; child atoms start right after this
+    pub const CHAPTER_DATA_ATOM_HEADER_LEN: usize = 20;
+
+    pub const CHAPTER_DATA_CONTAINER_ATOM_KEY: &str = "sean";
+    pub const CHAPTER_DATA_CHAPTER_ATOM_KEY: &str = "chap";
+    pub const CHAPTER_DATA_NAME_ATOM_KEY: &str = "name";
+
+    pub const CHAPTER_DATA_CHAPTER_START_MILLISECONDS_OFFSET: usize = 8;
+    pub const CHAPTER_DATA_CHAPTER_START_MILLISECONDS_LEN: usize = 4;
+
+    // Length in UTF-16 characters, not bytes
+    pub const CHAPTER_DATA_NAME_STRING_LENGTH_OFFSET: usize = 20;
+    pub const CHAPTER_DATA_NAME_STRING_LENGTH_LEN: usize = 2;
+
+    pub const CHAPTER_DATA_NAME_STRING_OFFSET: usize = 22;
+
+    // ----- PLAYLIST COLUMN DEFINITIONS (mhod type 100) ----- //
+    // Only the type 100 data object before the first playlist item holds these, the ones after
+    // each playlist item are (much shorter) order entries
+
+    pub const PLAYLIST_COLUMNS_TOTAL_LEN: usize = 0x288;
+
+    pub const PLAYLIST_COLUMNS_SORT_TYPE_OFFSET: usize = 44;
+    pub const PLAYLIST_COLUMNS_SORT_TYPE_LEN: usize = 4;
+
+    pub const PLAYLIST_COLUMNS_NUM_COLUMNS_OFFSET: usize = 48;
+    pub const PLAYLIST_COLUMNS_NUM_COLUMNS_LEN: usize = 4;
+
+    pub const PLAYLIST_COLUMNS_FIRST_COLUMN_OFFSET: usize = 56;
+    pub const PLAYLIST_COLUMNS_COLUMN_DEFINITION_LEN: usize = 16;
+
+    // Offsets are relative to the start of each column definition
+    pub const PLAYLIST_COLUMN_ID_OFFSET: usize = 0;
+    pub const PLAYLIST_COLUMN_ID_LEN: usize = 2;
+
+    pub const PLAYLIST_COLUMN_WIDTH_OFFSET: usize = 2;
+    pub const PLAYLIST_COLUMN_WIDTH_LEN: usize = 2;
+
+    pub const PLAYLIST_COLUMN_SORT_DIRECTION_OFFSET: usize = 4;
+    pub const PLAYLIST_COLUMN_SORT_DIRECTION_LEN: usize = 4;
+
     // ----- ALBUM LIST ----- //
     pub const ALBUM_LIST_KEY: &str = "mhla";
 
-    pub const ALBUM_LIST_TOTAL_NUM_SONGS_OFFSET: usize = 8;
-    pub const ALBUM_LIST_TOTAL_NUM_SONGS_LEN: usize = 4;
+    pub const ALBUM_LIST_NUM_ALBUMS_OFFSET: usize = 8;
+    pub const ALBUM_LIST_NUM_ALBUMS_LEN: usize = 4;
 
     pub const ALBUM_LIST_LAST_OFFSET: usize = 12;
 
 
-    // TODO: The iTunesDB file I was provided didn't have anything in this section, even though this is documented in the iTunesDB wiki.
-     
+    // TODO: Only the album items themselves are counted, none of their fields are parsed yet
+
     // ----- ALBUM ITEM ----- //
-    // pub const ALBUM_ITEM_KEY: &str = "mhia";
+    pub const ALBUM_ITEM_KEY: &str = "mhia";
+
+    pub const ALBUM_ITEM_NUM_DATA_OBJECTS_OFFSET: usize = 12;
+    pub const ALBUM_ITEM_NUM_DATA_OBJECTS_LEN: usize = 4;
 
     // pub const ALBUM_ITEM_LAST_OFFSET: usize = 32;
 
+    // ----- ARTIST LIST ----- //
+    pub const ARTIST_LIST_KEY: &str = "mhli";
+
+    // ----- ARTIST ITEM ----- //
+    pub const ARTIST_ITEM_KEY: &str = "mhii";
+
+    pub const ARTIST_ITEM_NUM_DATA_OBJECTS_OFFSET: usize = 12;
+    pub const ARTIST_ITEM_NUM_DATA_OBJECTS_LEN: usize = 4;
@@ -7,6 +7,8 @@
  * 
  */
 
+ pub const ITUNESPREFS_FILENAME : &str = "iTunesPrefs";
+
  pub const ITUNESPREF_OBJECT_KEY : &str = "frpd";
  
  pub const IPOD_SET_UP_YET_SETTING_OFFSET : usize = 8;
@@ -0,0 +1,29 @@
+/**
+ * File: itunespstate_constants.rs
+ *
+ * Provides the constants needed for the iTunesPState file, where the iPod shuffle
+ * keeps its playback state. The file is 21 bytes of little-endian 3-byte fields.
+ *
+ * Follows same format as itunesdb_constants.rs
+ */
+
+pub const ITUNESPSTATE_FILE_LENGTH: usize = 21;
+
+pub const ITUNESPSTATE_VOLUME_OFFSET: usize = 0;
+pub const ITUNESPSTATE_VOLUME_LEN: usize = 3;
+
+pub const ITUNESPSTATE_SHUFFLE_POSITION_OFFSET: usize = 3;
+pub const ITUNESPSTATE_SHUFFLE_POSITION_LEN: usize = 3;
+
+/// Index of the song in the iTunesSD
+pub const ITUNESPSTATE_TRACK_INDEX_OFFSET: usize = 6;
+pub const ITUNESPSTATE_TRACK_INDEX_LEN: usize = 3;
+
+pub const ITUNESPSTATE_SHUFFLE_FLAG_OFFSET: usize = 9;
+pub const ITUNESPSTATE_SHUFFLE_FLAG_LEN: usize = 3;
+
+/// In units of 256 ms, like the times in the iTunesSD
+pub const ITUNESPSTATE_TRACK_POSITION_OFFSET: usize = 12;
+pub const ITUNESPSTATE_TRACK_POSITION_LEN: usize = 3;
+
+pub const ITUNESPSTATE_FILENAME: &str = "iTunesPState";
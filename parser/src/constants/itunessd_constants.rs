@@ -27,4 +27,86 @@ pub const ITUNESSD_FILE_TYPE : usize = 27; // 3 * 9
 pub const ITUNESSD_FILE_TYPE_LEN : usize = 3;
 
 pub const ITUNESSD_SONG_ENTRY_FILENAME_OFFSET : usize = 33; // 3 * 11
-pub const ITUNESSD_SONG_ENTRY_FILENAME_LEN : usize = 522; // 0x20A
\ No newline at end of file
+pub const ITUNESSD_SONG_ENTRY_FILENAME_LEN : usize = 522; // 0x20A
+
+/// Both are single bytes right after the filename
+pub const ITUNESSD_SHUFFLE_FLAG_OFFSET : usize = 555; // 0x22B
+pub const ITUNESSD_SHUFFLE_FLAG_LEN : usize = 1;
+pub const ITUNESSD_BOOKMARK_FLAG_OFFSET : usize = 556; // 0x22C
+pub const ITUNESSD_BOOKMARK_FLAG_LEN : usize = 1;
+
+/// The start and stop times are stored in units of 256 ms
+pub const ITUNESSD_TIME_UNIT_MS : u32 = 256;
+/// The volume is a signed 3-byte percentage, from -100 to +100
+pub const ITUNESSD_VOLUME_SIGN_BIT : u32 = 0x800000;
+
+/// iTunesSD version 3 (iPod shuffle 3rd/4th gen), which is little-endian throughout
+pub const ITUNESSD_V3_HEADER_KEY : &str = "bdhs";
+/// Up to the end of the playlist header offset, the last field read
+pub const ITUNESSD_V3_HEADER_MIN_LEN : usize = 44;
+pub const ITUNESSD_V3_NUM_TRACKS_OFFSET : usize = 12;
+pub const ITUNESSD_V3_NUM_TRACKS_LEN : usize = 4;
+pub const ITUNESSD_V3_NUM_PLAYLISTS_OFFSET : usize = 16;
+pub const ITUNESSD_V3_NUM_PLAYLISTS_LEN : usize = 4;
+pub const ITUNESSD_V3_MAX_VOLUME_OFFSET : usize = 28;
+pub const ITUNESSD_V3_VOICEOVER_ENABLED_OFFSET : usize = 29;
+pub const ITUNESSD_V3_TRACK_HEADER_OFFSET_OFFSET : usize = 36;
+pub const ITUNESSD_V3_TRACK_HEADER_OFFSET_LEN : usize = 4;
+pub const ITUNESSD_V3_PLAYLIST_HEADER_OFFSET_OFFSET : usize = 40;
+pub const ITUNESSD_V3_PLAYLIST_HEADER_OFFSET_LEN : usize = 4;
+
+/// Track header ("hths"), followed by the offset of each track
+pub const ITUNESSD_V3_TRACK_HEADER_KEY : &str = "hths";
+pub const ITUNESSD_V3_TRACK_HEADER_NUM_TRACKS_OFFSET : usize = 8;
+pub const ITUNESSD_V3_TRACK_HEADER_NUM_TRACKS_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_HEADER_OFFSETS_OFFSET : usize = 20;
+pub const ITUNESSD_V3_CHUNK_OFFSET_LEN : usize = 4;
+
+/// Track ("rths")
+pub const ITUNESSD_V3_TRACK_KEY : &str = "rths";
+pub const ITUNESSD_V3_TRACK_LEN : usize = 0x174;
+pub const ITUNESSD_V3_TRACK_START_TIME_OFFSET : usize = 8;
+pub const ITUNESSD_V3_TRACK_START_TIME_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_STOP_TIME_OFFSET : usize = 12;
+pub const ITUNESSD_V3_TRACK_STOP_TIME_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_VOLUME_OFFSET : usize = 16;
+pub const ITUNESSD_V3_TRACK_VOLUME_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_FILE_TYPE_OFFSET : usize = 20;
+pub const ITUNESSD_V3_TRACK_FILE_TYPE_LEN : usize = 4;
+/// UTF-8 this time, padded with null bytes
+pub const ITUNESSD_V3_TRACK_FILENAME_OFFSET : usize = 24;
+pub const ITUNESSD_V3_TRACK_FILENAME_LEN : usize = 256;
+pub const ITUNESSD_V3_TRACK_BOOKMARK_TIME_OFFSET : usize = 280; // 0x118
+pub const ITUNESSD_V3_TRACK_BOOKMARK_TIME_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_DONT_SKIP_OFFSET : usize = 284; // 0x11C
+pub const ITUNESSD_V3_TRACK_REMEMBER_POSITION_OFFSET : usize = 285; // 0x11D
+pub const ITUNESSD_V3_TRACK_PREGAP_OFFSET : usize = 288; // 0x120
+pub const ITUNESSD_V3_TRACK_PREGAP_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_POSTGAP_OFFSET : usize = 292; // 0x124
+pub const ITUNESSD_V3_TRACK_POSTGAP_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_NUM_SAMPLES_OFFSET : usize = 296; // 0x128
+pub const ITUNESSD_V3_TRACK_NUM_SAMPLES_LEN : usize = 4;
+pub const ITUNESSD_V3_TRACK_TRACK_NUMBER_OFFSET : usize = 316; // 0x13C
+pub const ITUNESSD_V3_TRACK_TRACK_NUMBER_LEN : usize = 2;
+pub const ITUNESSD_V3_TRACK_DISC_NUMBER_OFFSET : usize = 318; // 0x13E
+pub const ITUNESSD_V3_TRACK_DISC_NUMBER_LEN : usize = 2;
+/// Same as the track item's dbid in the iTunesDB
+pub const ITUNESSD_V3_TRACK_DBID_OFFSET : usize = 328; // 0x148
+pub const ITUNESSD_V3_TRACK_DBID_LEN : usize = 8;
+
+/// Playlist header ("hphs"), followed by the offset of each playlist
+pub const ITUNESSD_V3_PLAYLIST_HEADER_KEY : &str = "hphs";
+pub const ITUNESSD_V3_PLAYLIST_HEADER_NUM_PLAYLISTS_OFFSET : usize = 8;
+pub const ITUNESSD_V3_PLAYLIST_HEADER_NUM_PLAYLISTS_LEN : usize = 2;
+pub const ITUNESSD_V3_PLAYLIST_HEADER_OFFSETS_OFFSET : usize = 20;
+
+/// Playlist ("lphs"), followed by the index of each of its tracks in the track list
+pub const ITUNESSD_V3_PLAYLIST_KEY : &str = "lphs";
+pub const ITUNESSD_V3_PLAYLIST_NUM_TRACKS_OFFSET : usize = 8;
+pub const ITUNESSD_V3_PLAYLIST_NUM_TRACKS_LEN : usize = 4;
+pub const ITUNESSD_V3_PLAYLIST_ID_OFFSET : usize = 16;
+pub const ITUNESSD_V3_PLAYLIST_ID_LEN : usize = 8;
+pub const ITUNESSD_V3_PLAYLIST_TYPE_OFFSET : usize = 24;
+pub const ITUNESSD_V3_PLAYLIST_TYPE_LEN : usize = 4;
+pub const ITUNESSD_V3_PLAYLIST_TRACK_INDICES_OFFSET : usize = 44; // 0x2C
+pub const ITUNESSD_V3_PLAYLIST_TRACK_INDEX_LEN : usize = 4;
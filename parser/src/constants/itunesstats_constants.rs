@@ -0,0 +1,30 @@
+/**
+ * File: itunesstats_constants.rs
+ *
+ * Provides the constants needed for the iTunesStats file, the iPod shuffle's
+ * equivalent of the Play Counts file. Like the iTunesSD it sits next to, it uses
+ * 3-byte fields, but little-endian ones this time.
+ *
+ * Follows same format as itunesdb_constants.rs
+ */
+
+pub const ITUNESSTATS_NUM_ENTRIES_OFFSET: usize = 0;
+pub const ITUNESSTATS_NUM_ENTRIES_LEN: usize = 3;
+
+pub const ITUNESSTATS_FILE_HEADER_LENGTH: usize = 6;
+
+/// Offsets of the fields in each entry, all counted from the last sync
+pub const ITUNESSTATS_ENTRY_LENGTH_OFFSET: usize = 0;
+pub const ITUNESSTATS_ENTRY_LENGTH_LEN: usize = 3;
+
+/// In units of 256 ms, like the start/stop times in the iTunesSD
+pub const ITUNESSTATS_ENTRY_BOOKMARK_TIME_OFFSET: usize = 3;
+pub const ITUNESSTATS_ENTRY_BOOKMARK_TIME_LEN: usize = 3;
+
+pub const ITUNESSTATS_ENTRY_NUM_PLAYS_OFFSET: usize = 12;
+pub const ITUNESSTATS_ENTRY_NUM_PLAYS_LEN: usize = 3;
+
+pub const ITUNESSTATS_ENTRY_NUM_SKIPS_OFFSET: usize = 15;
+pub const ITUNESSTATS_ENTRY_NUM_SKIPS_LEN: usize = 3;
+
+pub const ITUNESSTATS_FILENAME: &str = "iTunesStats";
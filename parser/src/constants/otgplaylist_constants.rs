@@ -0,0 +1,26 @@
+/**
+ * File: otgplaylist_constants.rs
+ *
+ * Provides the constants needed to read the OTGPlaylistInfo files, which hold the
+ * On-The-Go playlists made on the iPod that haven't been synced back to iTunes yet.
+ *
+ * Follows same format as itunesdb_constants.rs
+ */
+
+pub const OTG_PLAYLIST_KEY: &str = "mhpo";
+
+pub const OTG_PLAYLIST_HEADER_LEN_OFFSET: usize = 4;
+pub const OTG_PLAYLIST_HEADER_LEN_LEN: usize = 4;
+
+pub const OTG_PLAYLIST_ENTRY_LEN_OFFSET: usize = 8;
+pub const OTG_PLAYLIST_ENTRY_LEN_LEN: usize = 4;
+
+pub const OTG_PLAYLIST_NUM_ENTRIES_OFFSET: usize = 12;
+pub const OTG_PLAYLIST_NUM_ENTRIES_LEN: usize = 4;
+
+/// Each entry starts with the position of the track in the iTunesDB's Track List
+pub const OTG_ENTRY_TRACK_INDEX_OFFSET: usize = 0;
+pub const OTG_ENTRY_TRACK_INDEX_LEN: usize = 4;
+
+/// The first file has no suffix, the others are "OTGPlaylistInfo_1", "OTGPlaylistInfo_2" etc.
+pub const OTG_PLAYLIST_FILENAME: &str = "OTGPlaylistInfo";
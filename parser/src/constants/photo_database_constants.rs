@@ -15,6 +15,14 @@ pub const IMAGE_LIST_LAST_OFFSET: usize = 12;
 // ----- IMAGE ITEM ----- //
 pub const IMAGE_ITEM_KEY: &str = "mhii";
 
+/// Album Items refer to the image by this
+pub const IMAGE_ITEM_ID_OFFSET: usize = 16; // 4 * 4
+pub const IMAGE_ITEM_ID_LEN: usize = 4;
+
+/// Only set in the ArtworkDB: the dbid of the track the cover art belongs to
+pub const IMAGE_ITEM_SONG_DBID_OFFSET: usize = IMAGE_ITEM_ID_OFFSET + IMAGE_ITEM_ID_LEN;
+pub const IMAGE_ITEM_SONG_DBID_LEN: usize = 8;
+
 pub const IMAGE_ITEM_RATING_OFFSET: usize = 32; // 4 * 8
 pub const IMAGE_ITEM_RATING_LEN: usize = 4;
 
@@ -34,7 +42,12 @@ pub const IMAGE_ITEM_LAST_OFFSET: usize = 52; // 4 * 13
 // ----- IMAGE NAME ----- //
 pub const IMAGE_NAME_KEY: &str = "mhni";
 
-pub const IMAGE_NAME_ITHMB_OFFSET_OFFSET : usize = 16;
+/// Identifies the thumbnail format, and which F<correlation ID>_<n>.ithmb file it's in.
+/// The full resolution original (Photo Database only) has a correlation ID of 1
+pub const IMAGE_NAME_CORRELATION_ID_OFFSET : usize = 16;
+pub const IMAGE_NAME_CORRELATION_ID_LEN : usize = 4;
+
+pub const IMAGE_NAME_ITHMB_OFFSET_OFFSET : usize = 20;
 pub const IMAGE_NAME_ITHMB_OFFSET_LEN : usize = 4;
 
 // TODO #1 ~ There's 2 size fields in this key list, and I don't understand what the difference between the two is.
@@ -45,6 +58,13 @@ pub const IMAGE_NAME_ITHMB_OFFSET_LEN : usize = 4;
 pub const IMAGE_NAME_IMG_SIZE_OFFSET: usize = 24; // 4 * 6
 pub const IMAGE_NAME_IMG_SIZE_LEN: usize = 4;
 
+/// How much of the stored bitmap is padding around the image itself
+pub const IMAGE_NAME_VERTICAL_PADDING_OFFSET: usize = 28; // 4 * 7
+pub const IMAGE_NAME_VERTICAL_PADDING_LEN: usize = 2;
+
+pub const IMAGE_NAME_HORIZONTAL_PADDING_OFFSET: usize = 30;
+pub const IMAGE_NAME_HORIZONTAL_PADDING_LEN: usize = IMAGE_NAME_VERTICAL_PADDING_LEN;
+
 pub const IMAGE_NAME_IMG_HEIGHT_OFFSET: usize = 32; // 4 * 8
 pub const IMAGE_NAME_IMG_HEIGHT_LEN: usize = 2;
 
@@ -59,8 +79,26 @@ pub const PHOTO_ALBUM_KEY: &str = "mhba";
 pub const PHOTO_ALBUM_ALBUM_ITEM_CNT_OFFSET: usize = 16; // 4 * 4
 pub const PHOTO_ALBUM_ALBUM_ITEM_CNT_LEN: usize = 4;
 
+pub const PHOTO_ALBUM_ID_OFFSET: usize = 20; // 4 * 5
+pub const PHOTO_ALBUM_ID_LEN: usize = 4;
+
+/// 1 for the master album (the whole Photo Library), other values for regular albums
+pub const PHOTO_ALBUM_TYPE_OFFSET: usize = 30;
+pub const PHOTO_ALBUM_TYPE_LEN: usize = 1;
+
+pub const PHOTO_ALBUM_TYPE_MASTER: u32 = 1;
+
 pub const PHOTO_ALBUM_LAST_OFFSET: usize = 64; // 4 * 16
 
+// ----- ALBUM ITEM ----- //
+pub const ALBUM_ITEM_KEY: &str = "mhia";
+
+/// ID of the Image Item in the album
+pub const ALBUM_ITEM_IMAGE_ID_OFFSET: usize = 16; // 4 * 4
+pub const ALBUM_ITEM_IMAGE_ID_LEN: usize = 4;
+
+pub const ALBUM_ITEM_LAST_OFFSET: usize = 36; // 4 * 9
+
 // ----- DATA OBJECT (for photosDB only!!) ----- //
 pub const DATA_OBJECT_KEY: &str = "mhod";
 
@@ -77,7 +115,8 @@ pub const DATA_OBJECT_STRING_SUBCONTAINER_LENGTH_LEN: usize = 4;
 pub const DATA_OBJECT_STRING_SUBCONTAINER_ENCODING_OFFSET: usize = DATA_OBJECT_HEADER_LENGTH + 4;
 pub const DATA_OBJECT_STRING_SUBCONTAINER_ENCODING_LEN: usize = 4;
 
+// There's 4 unknown bytes between the encoding and the string itself
 pub const DATA_OBJECT_STRING_SUBCONTAINER_DATA_OFFSET: usize =
-    DATA_OBJECT_STRING_SUBCONTAINER_ENCODING_OFFSET + 4;
+    DATA_OBJECT_STRING_SUBCONTAINER_ENCODING_OFFSET + 8;
 
 pub const DATA_OBJECT_LAST_OFFSET: usize = 16; // 4 * 4
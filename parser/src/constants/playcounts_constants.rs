@@ -13,6 +13,9 @@
 
 pub const PLAYCOUNTS_OBJECT_KEY : &str = "mhdp";
 
+/// Only needed when writing the file, the header is always `PLAYCOUNTS_FILE_HEADER_LENGTH` long
+pub const PLAYCOUNTS_HEADER_LENGTH_OFFSET : usize = 4;
+
 pub const PLAYCOUNTS_ENTRY_LENGTH_OFFSET : usize = 8;
 pub const PLAYCOUNTS_ENTRY_LENGTH_LEN : usize = 4;
 
@@ -7,6 +7,8 @@
  * 
 */
 
+pub const PREFERENCES_FILENAME : &str = "Preferences";
+
 /// Daylight Savings Time
 pub const DST_SETTING_OFFSET : usize = 1724; // 0x6BC
 pub const DST_SETTING_LEN : usize = 1;
@@ -29,5 +31,7 @@ pub const TIMEZONE_INFO_LEN : usize = 1;
 pub const VOLUME_LIMIT_OFFSET : usize = 2896; // 0xB50
 pub const VOLUME_LIMIT_LEN : usize = 1;
 
+// Past the end of some Preferences files (e.g. the 2924 byte sample)
+
 pub const REGION_OFFSET : usize = 2928; // 0xB70
 pub const REGION_LEN : usize = 2;
@@ -0,0 +1,99 @@
+/*
+ * File: smart_playlist_constants.rs
+ *
+ * Provides the different constants needed for the Smart Playlist data objects (mhod types 50 and 51)
+ * inside of the iTunesDB file.
+ *
+ * Follows same format as itunesdb_constants.rs
+ *
+ * Glossary:
+ *
+ * "SPL" = Smart Playlist
+ *
+*/
+
+// ----- SMART PLAYLIST DATA (mhod type 50) ----- //
+
+pub const SPL_DATA_LIVE_UPDATE_SETTING_OFFSET: usize = 24;
+pub const SPL_DATA_LIVE_UPDATE_SETTING_LEN: usize = 1;
+
+pub const SPL_DATA_CHECK_RULES_SETTING_OFFSET: usize = 25;
+pub const SPL_DATA_CHECK_RULES_SETTING_LEN: usize = 1;
+
+pub const SPL_DATA_CHECK_LIMITS_SETTING_OFFSET: usize = 26;
+pub const SPL_DATA_CHECK_LIMITS_SETTING_LEN: usize = 1;
+
+pub const SPL_DATA_LIMIT_TYPE_OFFSET: usize = 27;
+pub const SPL_DATA_LIMIT_TYPE_LEN: usize = 1;
+
+pub const SPL_DATA_LIMIT_SORT_OFFSET: usize = 28;
+pub const SPL_DATA_LIMIT_SORT_LEN: usize = 1;
+
+pub const SPL_DATA_LIMIT_VALUE_OFFSET: usize = 32;
+pub const SPL_DATA_LIMIT_VALUE_LEN: usize = 4;
+
+pub const SPL_DATA_MATCH_CHECKED_ONLY_SETTING_OFFSET: usize = 36;
+pub const SPL_DATA_MATCH_CHECKED_ONLY_SETTING_LEN: usize = 1;
+
+pub const SPL_DATA_REVERSE_LIMIT_SORT_SETTING_OFFSET: usize = 37;
+pub const SPL_DATA_REVERSE_LIMIT_SORT_SETTING_LEN: usize = 1;
+
+// ----- SMART PLAYLIST RULES (mhod type 51) ----- //
+// Everything from the "SLst" identifier onwards is big-endian!
+
+pub const SPL_RULES_IDENTIFIER_KEY: &str = "SLst";
+
+pub const SPL_RULES_IDENTIFIER_OFFSET: usize = 24;
+pub const SPL_RULES_IDENTIFIER_LEN: usize = 4;
+
+pub const SPL_RULES_NUM_RULES_OFFSET: usize = 32;
+pub const SPL_RULES_NUM_RULES_LEN: usize = 4;
+
+pub const SPL_RULES_OPERATOR_OFFSET: usize = 36;
+pub const SPL_RULES_OPERATOR_LEN: usize = 4;
+
+pub const SPL_RULES_FIRST_RULE_OFFSET: usize = 160;
+
+// ----- SMART PLAYLIST RULE ----- //
+// Offsets are relative to the start of each rule
+
+pub const SPL_RULE_FIELD_OFFSET: usize = 0;
+pub const SPL_RULE_FIELD_LEN: usize = 4;
+
+pub const SPL_RULE_ACTION_OFFSET: usize = 4;
+pub const SPL_RULE_ACTION_LEN: usize = 4;
+
+pub const SPL_RULE_DATA_LENGTH_OFFSET: usize = 52;
+pub const SPL_RULE_DATA_LENGTH_LEN: usize = 4;
+
+// String rules store a UTF-16 (big-endian) string here, non-string rules store the 64-bit values below
+pub const SPL_RULE_DATA_OFFSET: usize = 56;
+
+pub const SPL_RULE_FROM_VALUE_OFFSET: usize = 56;
+pub const SPL_RULE_FROM_DATE_OFFSET: usize = 64;
+pub const SPL_RULE_FROM_UNITS_OFFSET: usize = 72;
+pub const SPL_RULE_TO_VALUE_OFFSET: usize = 80;
+pub const SPL_RULE_TO_DATE_OFFSET: usize = 88;
+pub const SPL_RULE_TO_UNITS_OFFSET: usize = 96;
+pub const SPL_RULE_VALUE_LEN: usize = 8;
+
+/// "For 'in the last' type rules, the from and to values are set to a constant of 0x2dae2dae2dae2dae"
+/// which stands for the current time
+pub const SPL_RULE_VALUE_NOW: u64 = 0x2dae2dae2dae2dae;
+
+// ----- SMART PLAYLIST RULE ACTION BITS ----- //
+
+pub const SPL_ACTION_IS_STRING_FLAG: u32 = 0x01000000;
+pub const SPL_ACTION_NOT_FLAG: u32 = 0x02000000;
+
+pub const SPL_ACTION_IS: u32 = 0x001;
+pub const SPL_ACTION_CONTAINS: u32 = 0x002;
+pub const SPL_ACTION_STARTS_WITH: u32 = 0x004;
+pub const SPL_ACTION_ENDS_WITH: u32 = 0x008;
+pub const SPL_ACTION_GREATER_THAN: u32 = 0x010;
+pub const SPL_ACTION_GREATER_THAN_OR_EQUAL: u32 = 0x020;
+pub const SPL_ACTION_LESS_THAN: u32 = 0x040;
+pub const SPL_ACTION_LESS_THAN_OR_EQUAL: u32 = 0x080;
+pub const SPL_ACTION_IN_RANGE: u32 = 0x100;
+pub const SPL_ACTION_IN_THE_LAST: u32 = 0x200;
+pub const SPL_ACTION_BINARY_AND: u32 = 0x400;
@@ -0,0 +1,49 @@
+/**
+ * File: sysinfo_constants.rs
+ *
+ * The SysInfo file (iPod_Control/Device/SysInfo) is plain text, written by the iPod itself,
+ * one "Key: value" pair per line. Only the keys used by the parser are listed here.
+ */
+
+pub const SYSINFO_FILENAME: &str = "SysInfo";
+
+pub const SYSINFO_KEY_VALUE_SEPARATOR: char = ':';
+
+/// 16 hex digits, e.g. "0x000A27001234ABCD". The hash58 database signature is derived from it
+pub const SYSINFO_FIREWIRE_GUID_KEY: &str = "FirewireGuid";
+
+/// e.g. "xA446", where the first letter isn't part of the model number
+pub const SYSINFO_MODEL_NUMBER_KEY: &str = "ModelNumStr";
+
+pub const SYSINFO_SERIAL_NUMBER_KEY: &str = "pszSerialNumber";
+
+/// e.g. "0x06128000 (6.1.2)". The visible one is what the iPod shows in "About"
+pub const SYSINFO_BUILD_ID_KEY: &str = "buildID";
+pub const SYSINFO_VISIBLE_BUILD_ID_KEY: &str = "visibleBuildID";
+
+pub const SYSINFO_BOARD_NAME_KEY: &str = "BoardHwName";
+
+/// e.g. "0x000B0005 (iPod video 5.5G)"
+pub const SYSINFO_BOARD_REVISION_KEY: &str = "boardHwSwInterfaceRev";
+
+// ----- SYSINFOEXTENDED ----- //
+// Newer iPods (and iTunes, once it has asked the iPod) write an XML plist next to SysInfo,
+// with the same information as exact values, and the artwork formats the iPod expects
+
+pub const SYSINFO_EXTENDED_FILENAME: &str = "SysInfoExtended";
+
+pub const SYSINFO_EXTENDED_SERIAL_NUMBER_KEY: &str = "SerialNumber";
+pub const SYSINFO_EXTENDED_MODEL_NUMBER_KEY: &str = "ModelNumStr";
+pub const SYSINFO_EXTENDED_FIREWIRE_GUID_KEY: &str = "FireWireGUID";
+pub const SYSINFO_EXTENDED_VISIBLE_BUILD_ID_KEY: &str = "VisibleBuildID";
+
+/// Arrays of dicts, one per format. The format ID is the correlation ID of the Image Names (and .ithmb files)
+pub const SYSINFO_EXTENDED_ALBUM_ART_KEY: &str = "AlbumArt";
+pub const SYSINFO_EXTENDED_PHOTO_FORMATS_KEY: &str = "ImageSpecifications";
+pub const SYSINFO_EXTENDED_CHAPTER_ART_KEY: &str = "ChapterImageSpecs";
+
+pub const SYSINFO_EXTENDED_FORMAT_ID_KEY: &str = "FormatId";
+pub const SYSINFO_EXTENDED_FORMAT_WIDTH_KEY: &str = "RenderWidth";
+pub const SYSINFO_EXTENDED_FORMAT_HEIGHT_KEY: &str = "RenderHeight";
+/// A four character code as an integer, e.g. 0x4C353635 ("L565")
+pub const SYSINFO_EXTENDED_FORMAT_PIXEL_FORMAT_KEY: &str = "PixelFormat";
@@ -0,0 +1,446 @@
+/**
+ * File: database_hash.rs
+ *
+ * The hash58 signature iPod Classics and Nano 3Gs check the iTunesDB against: an HMAC-SHA1 of the
+ * whole database, keyed on the device's FireWire GUID. The key derivation follows libgpod's
+ * itdb_hash58.c, whose tables are read from that file (see `read_hash58_tables`) rather than
+ * copied into this repository.
+ *
+ * hash72 and hashAB need keys taken from the device firmware, so they can't be checked or generated.
+ */
+use sha1::Digest;
+
+use crate::constants::database_hash_constants;
+use crate::constants::itunesdb_constants;
+use crate::helpers::helpers;
+use crate::itunesdb;
+use crate::parsers::sysinfo_parser;
+
+pub struct Hash58Tables {
+    pub inv_table: Vec<u8>,
+    pub table1: Vec<u8>,
+    pub table2: Vec<u8>,
+    pub fixed: Vec<u8>,
+}
+
+/// Reads `<array_name>[...] = { 0x.., 0x.., ... }` out of C source. None if the array isn't there,
+/// has anything but numbers in it, or isn't `array_len` bytes long
+fn read_c_byte_array(c_source: &str, array_name: &str, array_len: usize) -> Option<Vec<u8>> {
+    let array_declaration_start = c_source.match_indices(&format!("{}[", array_name)).find_map(|(name_idx, _)| {
+        // Skips the uses of the array, e.g. "table1[hi]", the declaration is the one with an initializer
+        let after_brackets = c_source[name_idx..].split_once(']')?.1;
+
+        if after_brackets.trim_start().starts_with('=') {
+            return Some(name_idx);
+        }
+
+        return None;
+    })?;
+
+    let array_values = c_source[array_declaration_start..].split_once('{')?.1.split_once('}')?.0;
+
+    let mut array_bytes: Vec<u8> = Vec::new();
+
+    for array_value in array_values.split(',').map(|array_value| array_value.trim()).filter(|array_value| !array_value.is_empty()) {
+        let array_byte = if let Some(hex_value) = array_value.strip_prefix("0x").or(array_value.strip_prefix("0X")) {
+            u8::from_str_radix(hex_value, 16).ok()?
+        } else {
+            array_value.parse::<u8>().ok()?
+        };
+
+        array_bytes.push(array_byte);
+    }
+
+    if array_bytes.len() != array_len {
+        return None;
+    }
+
+    return Some(array_bytes);
+}
+
+/// `c_source` is libgpod's src/itdb_hash58.c
+pub fn read_hash58_tables(c_source: &str) -> Option<Hash58Tables> {
+    return Some(Hash58Tables {
+        inv_table: read_c_byte_array(c_source, database_hash_constants::HASH58_INV_TABLE_NAME, database_hash_constants::HASH58_TABLE_LEN)?,
+        table1: read_c_byte_array(c_source, database_hash_constants::HASH58_TABLE1_NAME, database_hash_constants::HASH58_TABLE_LEN)?,
+        table2: read_c_byte_array(c_source, database_hash_constants::HASH58_TABLE2_NAME, database_hash_constants::HASH58_TABLE_LEN)?,
+        fixed: read_c_byte_array(c_source, database_hash_constants::HASH58_FIXED_NAME, database_hash_constants::HASH58_FIXED_LEN)?,
+    });
+}
+
+/// Set once in main from `--hash58-tables=<path to itdb_hash58.c>`. Hashes can't be checked if never set
+static HASH58_TABLES: std::sync::OnceLock<Hash58Tables> = std::sync::OnceLock::new();
+
+pub fn set_hash58_tables(hash58_tables: Hash58Tables) {
+    if HASH58_TABLES.set(hash58_tables).is_err() {
+        eprintln!("The hash58 tables were already set, keeping the first ones");
+    }
+}
+
+/// "000A27001234ABCD" (as in SysInfo, with or without "0x") to its 8 bytes, in the order they are written.
+/// Shorter GUIDs are padded with leading zeros, like libgpod reads them as a number
+fn parse_firewire_guid(firewire_guid: &str) -> Option<[u8; database_hash_constants::FIREWIRE_GUID_LEN]> {
+    let firewire_guid = firewire_guid.trim().trim_start_matches("0x").trim_start_matches("0X");
+
+    if firewire_guid.is_empty() || firewire_guid.len() > database_hash_constants::FIREWIRE_GUID_LEN * 2 {
+        return None;
+    }
+
+    let firewire_guid_number = u64::from_str_radix(firewire_guid, 16).ok()?;
+
+    return Some(firewire_guid_number.to_be_bytes());
+}
+
+fn get_gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+
+    return a;
+}
+
+/// libgpod's `lcm`, which is 1 if either number is 0
+fn get_lcm(a: u32, b: u32) -> u32 {
+    if a == 0 || b == 0 {
+        return 1;
+    }
+
+    return a * b / get_gcd(a, b);
+}
+
+/// The HMAC key: SHA-1 of the fixed bytes and 16 bytes derived from the pairs of GUID bytes
+fn generate_hash58_key(firewire_guid_bytes: &[u8], hash58_tables: &Hash58Tables) -> Vec<u8> {
+    let mut derived_bytes: Vec<u8> = Vec::new();
+
+    for guid_byte_pair in firewire_guid_bytes.chunks(2) {
+        let pair_lcm = get_lcm(guid_byte_pair[0] as u32, guid_byte_pair[1] as u32);
+
+        for lcm_byte in [(pair_lcm >> 8) as u8, pair_lcm as u8] {
+            derived_bytes.push(
+                hash58_tables.table1[lcm_byte as usize]
+                    .wrapping_mul(database_hash_constants::HASH58_TABLE1_MULTIPLIER)
+                    .wrapping_sub(database_hash_constants::HASH58_TABLE1_SUBTRAHEND),
+            );
+            derived_bytes.push(
+                hash58_tables.table2[lcm_byte as usize]
+                    .wrapping_mul(database_hash_constants::HASH58_TABLE2_MULTIPLIER)
+                    .wrapping_add(database_hash_constants::HASH58_TABLE2_ADDEND),
+            );
+        }
+    }
+
+    let mut key_hasher = sha1::Sha1::new();
+    key_hasher.update(&hash58_tables.fixed);
+    key_hasher.update(derived_bytes.iter().map(|derived_byte| hash58_tables.inv_table[*derived_byte as usize]).collect::<Vec<u8>>());
+
+    return key_hasher.finalize().to_vec();
+}
+
+/// HMAC-SHA1 of the message parts, one after the other. Keys are never longer than a block here
+fn compute_hmac_sha1(key: &[u8], message_parts: &[&[u8]]) -> Vec<u8> {
+    let mut padded_key = key.to_vec();
+    padded_key.resize(database_hash_constants::HMAC_BLOCK_LEN, 0);
+
+    let mut inner_hasher = sha1::Sha1::new();
+    inner_hasher.update(padded_key.iter().map(|key_byte| key_byte ^ database_hash_constants::HMAC_INNER_PAD).collect::<Vec<u8>>());
+
+    for message_part in message_parts {
+        inner_hasher.update(message_part);
+    }
+
+    let mut outer_hasher = sha1::Sha1::new();
+    outer_hasher.update(padded_key.iter().map(|key_byte| key_byte ^ database_hash_constants::HMAC_OUTER_PAD).collect::<Vec<u8>>());
+    outer_hasher.update(inner_hasher.finalize());
+
+    return outer_hasher.finalize().to_vec();
+}
+
+/// The length of the database object's header, if it's long enough to hold a hash58
+fn get_signable_header_len(db_bytes: &[u8]) -> Result<usize, String> {
+    if db_bytes.len() < itunesdb_constants::CHUNK_MIN_HEADER_LEN
+        || &db_bytes[0..itunesdb_constants::DATABASE_OBJECT_KEY.len()] != itunesdb_constants::DATABASE_OBJECT_KEY.as_bytes()
+    {
+        return Err("Not an iTunesDB, it doesn't start with a database object (mhbd)".to_string());
+    }
+
+    let header_len = helpers::get_slice_as_le_u32(
+        0,
+        db_bytes,
+        itunesdb_constants::CHUNK_HEADER_LEN_OFFSET,
+        itunesdb_constants::CHUNK_HEADER_LEN_LEN,
+    ) as usize;
+
+    if header_len < itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET + itunesdb_constants::DATABASE_OBJECT_HASH58_LEN
+        || header_len > db_bytes.len()
+    {
+        return Err(format!("The database object's header ({} bytes) is too short for a hash58", header_len));
+    }
+
+    return Ok(header_len);
+}
+
+/// The hash58 of the database for the device with that FireWire GUID. Whatever the database holds in
+/// the hash, the database ID and the 20 bytes at 0x32 don't count, those are zeroed first
+fn compute_hash58(db_bytes: &[u8], firewire_guid: &str, hash58_tables: &Hash58Tables) -> Result<Vec<u8>, String> {
+    let header_len = get_signable_header_len(db_bytes)?;
+
+    let firewire_guid_bytes = parse_firewire_guid(firewire_guid)
+        .ok_or(format!("'{}' isn't a FireWire GUID, which is 16 hex digits", firewire_guid))?;
+
+    let mut hashed_header = db_bytes[0..header_len].to_vec();
+
+    for (zeroed_offset, zeroed_len) in [
+        (database_hash_constants::DATABASE_OBJECT_DATABASE_ID_OFFSET, database_hash_constants::DATABASE_OBJECT_DATABASE_ID_LEN),
+        (database_hash_constants::DATABASE_OBJECT_UNKNOWN_0X32_OFFSET, database_hash_constants::DATABASE_OBJECT_UNKNOWN_0X32_LEN),
+        (itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET, itunesdb_constants::DATABASE_OBJECT_HASH58_LEN),
+    ] {
+        hashed_header[zeroed_offset..zeroed_offset + zeroed_len].fill(0);
+    }
+
+    hashed_header[itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET
+        ..itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET + itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_LEN]
+        .copy_from_slice(&(itunesdb::HandleableHashingScheme::Hash58 as u16).to_le_bytes());
+
+    let hash58_key = generate_hash58_key(&firewire_guid_bytes, hash58_tables);
+
+    return Ok(compute_hmac_sha1(&hash58_key, &[&hashed_header, &db_bytes[header_len..]]));
+}
+
+fn verify_database_hash_with_tables(db_bytes: &[u8], firewire_guid: &str, hash58_tables: &Hash58Tables) -> Result<bool, String> {
+    get_signable_header_len(db_bytes)?;
+
+    let (hashing_scheme_name, hashing_scheme) = itunesdb::decode_hashing_scheme(helpers::get_slice_as_le_u32(
+        0,
+        db_bytes,
+        itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET,
+        itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_LEN,
+    ));
+
+    if hashing_scheme != Some(itunesdb::HandleableHashingScheme::Hash58) {
+        return Err(format!("The database is signed with {}, only hash58 can be verified", hashing_scheme_name));
+    }
+
+    let stored_hash = &db_bytes[itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET
+        ..itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET + itunesdb_constants::DATABASE_OBJECT_HASH58_LEN];
+
+    return Ok(compute_hash58(db_bytes, firewire_guid, hash58_tables)? == stored_hash);
+}
+
+/// Whether the database's hash58 matches the device with that FireWire GUID (as in SysInfo or
+/// SysInfoExtended). Errors if it can't be told: the database isn't signed with hash58, the GUID
+/// isn't one, or the tables weren't given with --hash58-tables
+pub fn verify_database_hash(db_bytes: &[u8], firewire_guid: &str) -> Result<bool, String> {
+    let hash58_tables = HASH58_TABLES
+        .get()
+        .ok_or("Checking hash58 needs libgpod's tables, add --hash58-tables=<path to itdb_hash58.c>".to_string())?;
+
+    return verify_database_hash_with_tables(db_bytes, firewire_guid, hash58_tables);
+}
+
+/// A database for a hash72 or hashAB iPod wouldn't be accepted with a hash58 signature either,
+/// so only databases that already use hash58 are signed
+fn check_hash58_signable(db_bytes: &[u8]) -> Result<(), String> {
+    get_signable_header_len(db_bytes)?;
+
+    let (hashing_scheme_name, hashing_scheme) = itunesdb::decode_hashing_scheme(helpers::get_slice_as_le_u32(
+        0,
+        db_bytes,
+        itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET,
+        itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_LEN,
+    ));
+
+    if hashing_scheme != Some(itunesdb::HandleableHashingScheme::Hash58) {
+        return Err(format!("The database is signed with {}, only hash58 can be generated", hashing_scheme_name));
+    }
+
+    return Ok(());
+}
+
+fn write_database_hash_with_tables(db_bytes: &mut [u8], firewire_guid: &str, hash58_tables: &Hash58Tables) -> Result<(), String> {
+    check_hash58_signable(db_bytes)?;
+
+    let hash58 = compute_hash58(db_bytes, firewire_guid, hash58_tables)?;
+
+    db_bytes[itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET
+        ..itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET + itunesdb_constants::DATABASE_OBJECT_HASH58_LEN]
+        .copy_from_slice(&hash58);
+
+    return Ok(());
+}
+
+/// Signs the database for the device with that FireWire GUID, as iTunes does after every change.
+/// Only databases whose hashing scheme is hash58 can be signed. Needs the tables from --hash58-tables
+pub fn write_database_hash(db_bytes: &mut [u8], firewire_guid: &str) -> Result<(), String> {
+    check_hash58_signable(db_bytes)?;
+
+    let hash58_tables = HASH58_TABLES
+        .get()
+        .ok_or("Signing with hash58 needs libgpod's tables, add --hash58-tables=<path to itdb_hash58.c>".to_string())?;
+
+    return write_database_hash_with_tables(db_bytes, firewire_guid, hash58_tables);
+}
+
+/// Writes a hash58-signed copy of the database. The FireWire GUID is the one given, otherwise the one in
+/// the SysInfo (or SysInfoExtended) of the iPod at the mount point
+pub fn sign_itunesdb_file(
+    mut itunesdb_file_as_bytes: Vec<u8>,
+    firewire_guid: Option<String>,
+    ipod_mount_point: Option<String>,
+    signed_itunesdb_filename: &str,
+) {
+    let firewire_guid = match firewire_guid.or_else(|| {
+        ipod_mount_point
+            .as_deref()
+            .and_then(sysinfo_parser::read_device_sysinfo)
+            .map(|sysinfo| sysinfo.firewire_guid)
+            .filter(|firewire_guid| !firewire_guid.is_empty())
+    }) {
+        Some(firewire_guid) => firewire_guid,
+        None => {
+            eprintln!("Signing needs the iPod's FireWire GUID, give --firewire-guid=<16 hex digits> or the iPod mount point");
+            return;
+        }
+    };
+
+    if let Err(signing_error) = write_database_hash(&mut itunesdb_file_as_bytes, &firewire_guid) {
+        eprintln!("Can't sign the database: {}", signing_error);
+        return;
+    }
+
+    std::fs::write(signed_itunesdb_filename, itunesdb_file_as_bytes).expect("Can't write signed iTunesDB file");
+
+    println!("Created '{}', signed with hash58 for the iPod with FireWire GUID {}", signed_itunesdb_filename, firewire_guid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_FIREWIRE_GUID: &str = "000A27001234ABCD";
+
+    /// Not libgpod's tables, any bytes do to check that a hash round-trips
+    fn build_test_tables() -> Hash58Tables {
+        return Hash58Tables {
+            inv_table: (0..=255u8).rev().collect(),
+            table1: (0..=255u8).map(|table_idx| table_idx.wrapping_mul(7)).collect(),
+            table2: (0..=255u8).map(|table_idx| table_idx ^ 0x5A).collect(),
+            fixed: (0..database_hash_constants::HASH58_FIXED_LEN as u8).collect(),
+        };
+    }
+
+    /// A database object with a hash58-sized header, the hashing scheme set to hash58, and some children bytes
+    fn build_test_database() -> Vec<u8> {
+        let header_len: usize = 0xF4;
+
+        let mut db_bytes: Vec<u8> = vec![0; header_len];
+        db_bytes[0..4].copy_from_slice(itunesdb_constants::DATABASE_OBJECT_KEY.as_bytes());
+        db_bytes[4..8].copy_from_slice(&(header_len as u32).to_le_bytes());
+        db_bytes[itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET] = itunesdb::HandleableHashingScheme::Hash58 as u8;
+        db_bytes[database_hash_constants::DATABASE_OBJECT_DATABASE_ID_OFFSET] = 0x42;
+        db_bytes.extend_from_slice(b"mhsd and everything below it");
+
+        return db_bytes;
+    }
+
+    #[test]
+    fn hmac_sha1_matches_rfc_2202() {
+        // Test case 1 of RFC 2202
+        let hmac = compute_hmac_sha1(&[0x0B; 20], &[b"Hi ", b"There"]);
+
+        assert_eq!(
+            hmac,
+            vec![
+                0xB6, 0x17, 0x31, 0x86, 0x55, 0x05, 0x72, 0x64, 0xE2, 0x8B, 0xC0, 0xB6, 0xFB, 0x37, 0x8C, 0x8E, 0xF1, 0x46,
+                0xBE, 0x00
+            ]
+        );
+    }
+
+    #[test]
+    fn hash58_verifies_only_for_its_guid_and_contents() {
+        let hash58_tables = build_test_tables();
+        let mut db_bytes = build_test_database();
+
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(false));
+
+        write_database_hash_with_tables(&mut db_bytes, TEST_FIREWIRE_GUID, &hash58_tables).unwrap();
+
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(true));
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, "0x000a27001234abcd", &hash58_tables), Ok(true));
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, "000A27001234ABCE", &hash58_tables), Ok(false));
+
+        // The database ID isn't hashed, the children are
+        db_bytes[database_hash_constants::DATABASE_OBJECT_DATABASE_ID_OFFSET] = 0x43;
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(true));
+
+        let last_byte_idx = db_bytes.len() - 1;
+        db_bytes[last_byte_idx] ^= 1;
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(false));
+    }
+
+    #[test]
+    fn only_hash58_databases_are_signed() {
+        let hash58_tables = build_test_tables();
+        let mut db_bytes = build_test_database();
+
+        for (hashing_scheme, hashing_scheme_name) in [
+            (itunesdb::HandleableHashingScheme::None, "None"),
+            (itunesdb::HandleableHashingScheme::Hash72, "hash72"),
+            (itunesdb::HandleableHashingScheme::HashAB, "hashAB"),
+        ] {
+            db_bytes[itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET] = hashing_scheme as u8;
+            let unsigned_db_bytes = db_bytes.clone();
+
+            let signing_error = write_database_hash_with_tables(&mut db_bytes, TEST_FIREWIRE_GUID, &hash58_tables).unwrap_err();
+            assert!(signing_error.contains(hashing_scheme_name), "{}", signing_error);
+            assert_eq!(db_bytes, unsigned_db_bytes);
+        }
+
+        db_bytes[itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET] = itunesdb::HandleableHashingScheme::Hash58 as u8;
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(false));
+
+        write_database_hash_with_tables(&mut db_bytes, TEST_FIREWIRE_GUID, &hash58_tables).unwrap();
+        assert_eq!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables), Ok(true));
+
+        // Signing again gives the same hash, the old one isn't part of it
+        let first_signature = db_bytes.clone();
+        write_database_hash_with_tables(&mut db_bytes, TEST_FIREWIRE_GUID, &hash58_tables).unwrap();
+        assert_eq!(db_bytes, first_signature);
+    }
+
+    #[test]
+    fn hash58_needs_a_signable_database_and_a_guid() {
+        let hash58_tables = build_test_tables();
+        let mut db_bytes = build_test_database();
+
+        assert!(verify_database_hash_with_tables(&db_bytes, "not a GUID", &hash58_tables).is_err());
+        assert!(verify_database_hash_with_tables(&db_bytes[0..0x60], TEST_FIREWIRE_GUID, &hash58_tables).is_err());
+        assert!(verify_database_hash_with_tables(b"mhsd", TEST_FIREWIRE_GUID, &hash58_tables).is_err());
+
+        db_bytes[itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET] = itunesdb::HandleableHashingScheme::Hash72 as u8;
+        assert!(verify_database_hash_with_tables(&db_bytes, TEST_FIREWIRE_GUID, &hash58_tables).is_err());
+    }
+
+    #[test]
+    fn hash58_tables_are_read_from_c_source() {
+        let table_values = (0..=255u32).map(|table_value| format!("0x{:02X}", table_value)).collect::<Vec<String>>().join(", ");
+
+        let c_source = format!(
+            "static const guint8 invTable[256] = {{ {0} }};\nstatic const guint8 table1[256] = {{ {0} }};\n\
+             static const guint8 table2[256] = {{ {0} }};\nstatic const guint8 fixed[18] = {{ {1} }};\n\
+             y[i*4] = table1[hi] * 0xB5 - 3;\n",
+            table_values,
+            (0..18).map(|fixed_value| fixed_value.to_string()).collect::<Vec<String>>().join(", ")
+        );
+
+        let hash58_tables = read_hash58_tables(&c_source).unwrap();
+
+        assert_eq!(hash58_tables.table1[0xAB], 0xAB);
+        assert_eq!(hash58_tables.fixed[17], 17);
+
+        // A table cut short isn't used
+        assert!(read_hash58_tables(&c_source.replace(", 0xFF }", " }")).is_none());
+    }
+}
@@ -0,0 +1,310 @@
+/**
+ * File: device_menu.rs
+ *
+ * Rebuilds the menu structure the iPod itself would show (Playlists, Artists, Albums, Genres
+ * and Podcasts) out of everything that was parsed from the iTunesDB.
+ *
+ * The snapshot is "time-boxed": smart playlists are evaluated as of the newest track in the
+ * library rather than as of right now, so the output approximates what the iPod showed
+ * right after its last sync.
+ *
+ */
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::helpers::helpers;
+use crate::itunesdb;
+use crate::smart_playlist;
+
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+const UNKNOWN_ALBUM: &str = "Unknown Album";
+const UNKNOWN_GENRE: &str = "Unknown Genre";
+
+#[derive(Serialize)]
+pub struct MenuTrack {
+    pub track_id: u32,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+}
+
+#[derive(Serialize)]
+pub struct MenuPlaylist {
+    pub playlist_name: String,
+    pub is_smart_playlist: bool,
+    pub membership_source: String,
+    pub tracks: Vec<MenuTrack>,
+}
+
+#[derive(Serialize)]
+pub struct MenuAlbum {
+    pub album_name: String,
+    pub tracks: Vec<MenuTrack>,
+}
+
+#[derive(Serialize)]
+pub struct MenuArtist {
+    pub artist_name: String,
+    pub albums: Vec<MenuAlbum>,
+}
+
+#[derive(Serialize)]
+pub struct MenuGenre {
+    pub genre_name: String,
+    pub artists: Vec<MenuArtist>,
+}
+
+#[derive(Serialize)]
+pub struct MenuPodcastShow {
+    pub show_name: String,
+    pub episodes: Vec<MenuTrack>,
+}
+
+#[derive(Serialize)]
+pub struct DeviceMenuSnapshot {
+    /// The master playlist is named after the iPod
+    pub device_name: String,
+    pub snapshot_timestamp: chrono::DateTime<chrono::Utc>,
+    pub playlists: Vec<MenuPlaylist>,
+    pub artists: Vec<MenuArtist>,
+    pub albums: Vec<MenuAlbum>,
+    pub genres: Vec<MenuGenre>,
+    pub podcasts: Vec<MenuPodcastShow>,
+}
+
+fn get_name_or_placeholder(name: &str, placeholder: &str) -> String {
+    if name.trim().is_empty() {
+        return placeholder.to_string();
+    }
+
+    return name.to_string();
+}
+
+/// The iPod sorts its menus alphabetically, ignoring case
+fn get_menu_sort_key(name: &str) -> String {
+    return name.to_lowercase();
+}
+
+fn build_menu_track(track: &dyn smart_playlist::SmartPlaylistTrack) -> MenuTrack {
+    return MenuTrack {
+        track_id: track.get_track_id(),
+        title: track
+            .get_string_field(smart_playlist::SmartPlaylistRuleField::SongName as u32)
+            .unwrap_or_default(),
+        artist: track
+            .get_string_field(smart_playlist::SmartPlaylistRuleField::Artist as u32)
+            .unwrap_or_default(),
+        album: track
+            .get_string_field(smart_playlist::SmartPlaylistRuleField::Album as u32)
+            .unwrap_or_default(),
+    };
+}
+
+fn build_menu_tracks(
+    track_ids: &[u32],
+    library: &[&dyn smart_playlist::SmartPlaylistTrack],
+) -> Vec<MenuTrack> {
+    let mut menu_tracks: Vec<MenuTrack> = Vec::new();
+
+    for track_id in track_ids.iter() {
+        if let Some(track) = library.iter().find(|track| track.get_track_id() == *track_id) {
+            menu_tracks.push(build_menu_track(*track));
+        }
+    }
+
+    return menu_tracks;
+}
+
+/// Albums are keyed by their sort key, holding on to the first spelling seen for display
+fn build_menu_albums(songs: &[&itunesdb::Song]) -> Vec<MenuAlbum> {
+    let mut albums: BTreeMap<String, (String, Vec<&itunesdb::Song>)> = BTreeMap::new();
+
+    for song in songs.iter() {
+        let album_name = get_name_or_placeholder(&song.song_album, UNKNOWN_ALBUM);
+
+        albums
+            .entry(get_menu_sort_key(&album_name))
+            .or_insert_with(|| (album_name, Vec::new()))
+            .1
+            .push(song);
+    }
+
+    let mut menu_albums: Vec<MenuAlbum> = Vec::new();
+
+    for (_, (album_name, album_songs)) in albums.into_iter() {
+        let mut tracks: Vec<MenuTrack> = Vec::new();
+
+        for song in album_songs.iter() {
+            tracks.push(build_menu_track(*song));
+        }
+
+        menu_albums.push(MenuAlbum {
+            album_name,
+            tracks,
+        });
+    }
+
+    return menu_albums;
+}
+
+fn build_menu_artists(songs: &[&itunesdb::Song]) -> Vec<MenuArtist> {
+    let mut artists: BTreeMap<String, (String, Vec<&itunesdb::Song>)> = BTreeMap::new();
+
+    for song in songs.iter() {
+        let artist_name = get_name_or_placeholder(&song.song_artist, UNKNOWN_ARTIST);
+
+        artists
+            .entry(get_menu_sort_key(&artist_name))
+            .or_insert_with(|| (artist_name, Vec::new()))
+            .1
+            .push(song);
+    }
+
+    let mut menu_artists: Vec<MenuArtist> = Vec::new();
+
+    for (_, (artist_name, artist_songs)) in artists.into_iter() {
+        menu_artists.push(MenuArtist {
+            artist_name,
+            albums: build_menu_albums(&artist_songs),
+        });
+    }
+
+    return menu_artists;
+}
+
+fn build_menu_genres(songs: &[&itunesdb::Song]) -> Vec<MenuGenre> {
+    let mut genres: BTreeMap<String, (String, Vec<&itunesdb::Song>)> = BTreeMap::new();
+
+    for song in songs.iter() {
+        let genre_name = get_name_or_placeholder(&song.song_genre, UNKNOWN_GENRE);
+
+        genres
+            .entry(get_menu_sort_key(&genre_name))
+            .or_insert_with(|| (genre_name, Vec::new()))
+            .1
+            .push(song);
+    }
+
+    let mut menu_genres: Vec<MenuGenre> = Vec::new();
+
+    for (_, (genre_name, genre_songs)) in genres.into_iter() {
+        menu_genres.push(MenuGenre {
+            genre_name,
+            artists: build_menu_artists(&genre_songs),
+        });
+    }
+
+    return menu_genres;
+}
+
+/// Uses the show groups from the Podcast List if there are any,
+/// otherwise groups the episodes by their publisher
+fn build_menu_podcasts(
+    podcasts: &[itunesdb::Podcast],
+    podcast_groups: &[itunesdb::PodcastGroup],
+    library: &[&dyn smart_playlist::SmartPlaylistTrack],
+) -> Vec<MenuPodcastShow> {
+    let mut menu_podcasts: Vec<MenuPodcastShow> = Vec::new();
+
+    if !podcast_groups.is_empty() {
+        for podcast_group in podcast_groups.iter() {
+            menu_podcasts.push(MenuPodcastShow {
+                show_name: podcast_group.group_name.clone(),
+                episodes: build_menu_tracks(&podcast_group.track_ids, library),
+            });
+        }
+
+        return menu_podcasts;
+    }
+
+    let mut shows: BTreeMap<String, (String, Vec<u32>)> = BTreeMap::new();
+
+    for podcast in podcasts.iter() {
+        let show_name = get_name_or_placeholder(&podcast.podcast_publisher, UNKNOWN_ARTIST);
+
+        shows
+            .entry(get_menu_sort_key(&show_name))
+            .or_insert_with(|| (show_name, Vec::new()))
+            .1
+            .push(podcast.track_id);
+    }
+
+    for (_, (show_name, track_ids)) in shows.into_iter() {
+        menu_podcasts.push(MenuPodcastShow {
+            show_name,
+            episodes: build_menu_tracks(&track_ids, library),
+        });
+    }
+
+    return menu_podcasts;
+}
+
+pub fn build_device_menu_snapshot(
+    songs: &[itunesdb::Song],
+    podcasts: &[itunesdb::Podcast],
+    playlists: &[itunesdb::Playlist],
+    podcast_groups: &[itunesdb::PodcastGroup],
+    library: &[&dyn smart_playlist::SmartPlaylistTrack],
+) -> DeviceMenuSnapshot {
+    // Pin the snapshot to the last time something was added to the library
+    let snapshot_timestamp = songs
+        .iter()
+        .filter_map(|song| song.song_added_to_library_ts)
+        .max()
+        .unwrap_or_else(chrono::Utc::now);
+
+    let reference_timestamp_mac = helpers::get_mac_timestamp_from_datetime(snapshot_timestamp);
+
+    let mut device_name = "".to_string();
+    let mut menu_playlists: Vec<MenuPlaylist> = Vec::new();
+
+    for playlist in playlists.iter() {
+        if playlist.is_master_playlist {
+            device_name = playlist.playlist_name.clone();
+            continue;
+        }
+
+        let mut track_ids = &playlist.stored_track_ids;
+        let mut membership_source = "Stored playlist items".to_string();
+        let evaluated_track_ids: Option<Vec<u32>>;
+
+        if let (Some(preferences), Some(smart_playlist_rules)) = (
+            &playlist.smart_playlist_preferences,
+            &playlist.smart_playlist_rules,
+        ) {
+            evaluated_track_ids = smart_playlist::evaluate_smart_playlist(
+                preferences,
+                smart_playlist_rules,
+                library,
+                reference_timestamp_mac,
+            );
+        } else {
+            evaluated_track_ids = None;
+        }
+
+        if let Some(evaluated_track_ids) = evaluated_track_ids.as_ref() {
+            track_ids = evaluated_track_ids;
+            membership_source = "Evaluated from smart playlist rules".to_string();
+        }
+
+        menu_playlists.push(MenuPlaylist {
+            playlist_name: playlist.playlist_name.clone(),
+            is_smart_playlist: playlist.is_smart_playlist,
+            membership_source,
+            tracks: build_menu_tracks(track_ids, library),
+        });
+    }
+
+    let song_refs: Vec<&itunesdb::Song> = songs.iter().collect();
+
+    return DeviceMenuSnapshot {
+        device_name,
+        snapshot_timestamp,
+        playlists: menu_playlists,
+        artists: build_menu_artists(&song_refs),
+        albums: build_menu_albums(&song_refs),
+        genres: build_menu_genres(&song_refs),
+        podcasts: build_menu_podcasts(podcasts, podcast_groups, library),
+    };
+}
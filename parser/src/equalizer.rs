@@ -1,10 +1,17 @@
 
+use serde::Serialize;
+
+#[derive(Serialize)]
 pub struct EqualizerPreset {
+    /// What a song's EQ setting refers to, e.g. "#!#100#!#" is the first preset
+    pub equalizer_preset_id: u32,
     pub equalizer_preset_name: String,
     pub equalizer_preset_name_len: usize,
     pub equalizer_preamp_value_raw: i32,
     pub equalizer_preamp_value_db: f32,
     pub equalizer_num_of_bands: u8,
+    /// The iTunes bands, from 32 Hz to 16 kHz
+    pub equalizer_bands: Vec<EqualizerFrequencySetting>,
 }
 
 impl Default for EqualizerPreset {
@@ -12,15 +19,18 @@ impl Default for EqualizerPreset {
     fn default() -> EqualizerPreset {
 
         return EqualizerPreset {
+            equalizer_preset_id: 0,
             equalizer_preset_name: String::new(),
             equalizer_preset_name_len: 0,
             equalizer_preamp_value_raw: 0,
             equalizer_preamp_value_db: 0.0,
             equalizer_num_of_bands: 0,
+            equalizer_bands: Vec::new(),
         };
     }
  }
 
+#[derive(Serialize)]
 pub struct EqualizerFrequencySetting {
     pub real_frequency_hz: u32,
     pub display_frequency: String,
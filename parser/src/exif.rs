@@ -0,0 +1,231 @@
+/**
+ * File: exif.rs
+ *
+ * Writes the dates from an Image Item (mhii) into exported photos as EXIF, so photo managers
+ * sort them by when they were taken rather than when they were recovered.
+ * The EXIF block is a small TIFF structure: IFD0 only points to the Exif IFD, which holds
+ * DateTimeOriginal and DateTimeDigitized. JPEGs get it as an APP1 segment, PNGs as an eXIf chunk.
+ *
+ */
+const EXIF_IDENTIFIER: &[u8] = b"Exif\0\0";
+
+const TIFF_HEADER_LENGTH: usize = 8;
+const IFD_ENTRY_LENGTH: usize = 12;
+
+const TIFF_TYPE_ASCII: u16 = 2;
+const TIFF_TYPE_LONG: u16 = 4;
+
+const EXIF_IFD_POINTER_TAG: u16 = 0x8769;
+const DATE_TIME_ORIGINAL_TAG: u16 = 0x9003;
+const DATE_TIME_DIGITIZED_TAG: u16 = 0x9004;
+
+/// "YYYY:MM:DD HH:MM:SS" and its NUL terminator
+const EXIF_DATE_TIME_LENGTH: usize = 20;
+
+const JPEG_START_OF_IMAGE: [u8; 2] = [0xFF, 0xD8];
+const JPEG_APP0_MARKER: u8 = 0xE0;
+const JPEG_APP1_MARKER: u8 = 0xE1;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+const PNG_EXIF_CHUNK_TYPE: &[u8] = b"eXIf";
+
+/// EXIF has no time zone. The Mac timestamps are the camera's wall clock time, which is what EXIF expects
+fn format_exif_date_time(timestamp: chrono::DateTime<chrono::Utc>) -> Vec<u8> {
+    let mut exif_date_time = timestamp.format("%Y:%m:%d %H:%M:%S").to_string().into_bytes();
+    exif_date_time.resize(EXIF_DATE_TIME_LENGTH, 0);
+
+    return exif_date_time;
+}
+
+fn push_ifd_entry(exif_block: &mut Vec<u8>, tag: u16, tiff_type: u16, count: u32, value_or_offset: u32) {
+    exif_block.extend_from_slice(&tag.to_le_bytes());
+    exif_block.extend_from_slice(&tiff_type.to_le_bytes());
+    exif_block.extend_from_slice(&count.to_le_bytes());
+    exif_block.extend_from_slice(&value_or_offset.to_le_bytes());
+}
+
+/// Returns the TIFF structure (little-endian) with whichever dates are set,
+/// or `None` if neither of them is
+fn build_exif_block(
+    original_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+    digitized_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+) -> Option<Vec<u8>> {
+    let exif_dates: Vec<(u16, Vec<u8>)> = [
+        (DATE_TIME_ORIGINAL_TAG, original_date_ts),
+        (DATE_TIME_DIGITIZED_TAG, digitized_date_ts),
+    ]
+    .into_iter()
+    .filter_map(|(tag, timestamp)| Some((tag, format_exif_date_time(timestamp?))))
+    .collect();
+
+    if exif_dates.is_empty() {
+        return None;
+    }
+
+    // Each IFD is its entry count, its entries, and the offset of the next IFD (0 for none)
+    let ifd0_length = 2 + IFD_ENTRY_LENGTH + 4;
+    let exif_ifd_offset = TIFF_HEADER_LENGTH + ifd0_length;
+    let exif_ifd_length = 2 + (exif_dates.len() * IFD_ENTRY_LENGTH) + 4;
+    let mut exif_date_offset = exif_ifd_offset + exif_ifd_length;
+
+    let mut exif_block: Vec<u8> = Vec::new();
+
+    // "II" for little-endian, then the TIFF magic number and the offset of IFD0
+    exif_block.extend_from_slice(b"II");
+    exif_block.extend_from_slice(&42u16.to_le_bytes());
+    exif_block.extend_from_slice(&(TIFF_HEADER_LENGTH as u32).to_le_bytes());
+
+    exif_block.extend_from_slice(&1u16.to_le_bytes());
+    push_ifd_entry(&mut exif_block, EXIF_IFD_POINTER_TAG, TIFF_TYPE_LONG, 1, exif_ifd_offset as u32);
+    exif_block.extend_from_slice(&0u32.to_le_bytes());
+
+    // The dates don't fit in the entries, so they come after the IFD
+    exif_block.extend_from_slice(&(exif_dates.len() as u16).to_le_bytes());
+
+    for (tag, _) in exif_dates.iter() {
+        push_ifd_entry(
+            &mut exif_block,
+            *tag,
+            TIFF_TYPE_ASCII,
+            EXIF_DATE_TIME_LENGTH as u32,
+            exif_date_offset as u32,
+        );
+        exif_date_offset += EXIF_DATE_TIME_LENGTH;
+    }
+
+    exif_block.extend_from_slice(&0u32.to_le_bytes());
+
+    for (_, exif_date_time) in exif_dates.iter() {
+        exif_block.extend_from_slice(exif_date_time);
+    }
+
+    return Some(exif_block);
+}
+
+/// The APP1 segment goes right after the JFIF (APP0) segment if there is one, otherwise right after
+/// the start of the image. Returns `None` if it already has EXIF, or isn't a JPEG
+fn add_exif_to_jpeg(jpeg_file_as_bytes: &[u8], exif_block: &[u8]) -> Option<Vec<u8>> {
+    if !jpeg_file_as_bytes.starts_with(&JPEG_START_OF_IMAGE) {
+        return None;
+    }
+
+    let mut insert_idx = JPEG_START_OF_IMAGE.len();
+    let mut segment_idx = insert_idx;
+
+    // Only the segments before the image data can hold metadata
+    while segment_idx + 4 <= jpeg_file_as_bytes.len() && jpeg_file_as_bytes[segment_idx] == 0xFF {
+        let segment_marker = jpeg_file_as_bytes[segment_idx + 1];
+
+        if !(JPEG_APP0_MARKER..=0xEF).contains(&segment_marker) {
+            break;
+        }
+
+        // The segment length counts itself, but not the marker
+        let segment_len = u16::from_be_bytes([
+            jpeg_file_as_bytes[segment_idx + 2],
+            jpeg_file_as_bytes[segment_idx + 3],
+        ]) as usize;
+
+        if segment_marker == JPEG_APP1_MARKER
+            && jpeg_file_as_bytes[segment_idx + 4..].starts_with(EXIF_IDENTIFIER)
+        {
+            return None;
+        }
+
+        if segment_marker == JPEG_APP0_MARKER && segment_idx == insert_idx {
+            insert_idx = segment_idx + 2 + segment_len;
+        }
+
+        segment_idx += 2 + segment_len;
+    }
+
+    let app1_segment_len = (2 + EXIF_IDENTIFIER.len() + exif_block.len()) as u16;
+
+    let mut jpeg_with_exif: Vec<u8> = Vec::with_capacity(jpeg_file_as_bytes.len() + app1_segment_len as usize + 2);
+    jpeg_with_exif.extend_from_slice(&jpeg_file_as_bytes[..insert_idx]);
+    jpeg_with_exif.extend_from_slice(&[0xFF, JPEG_APP1_MARKER]);
+    jpeg_with_exif.extend_from_slice(&app1_segment_len.to_be_bytes());
+    jpeg_with_exif.extend_from_slice(EXIF_IDENTIFIER);
+    jpeg_with_exif.extend_from_slice(exif_block);
+    jpeg_with_exif.extend_from_slice(&jpeg_file_as_bytes[insert_idx..]);
+
+    return Some(jpeg_with_exif);
+}
+
+/// The eXIf chunk goes right after the IHDR chunk, which is always the first one.
+/// Returns `None` if it already has EXIF, or isn't a PNG
+fn add_exif_to_png(png_file_as_bytes: &[u8], exif_block: &[u8]) -> Option<Vec<u8>> {
+    if !png_file_as_bytes.starts_with(&PNG_SIGNATURE) {
+        return None;
+    }
+
+    let mut chunk_idx = PNG_SIGNATURE.len();
+    let mut insert_idx = 0;
+
+    // Each chunk is its data length, its type, its data and a CRC
+    while chunk_idx + 8 <= png_file_as_bytes.len() {
+        let chunk_data_len = u32::from_be_bytes(png_file_as_bytes[chunk_idx..chunk_idx + 4].try_into().unwrap()) as usize;
+        let chunk_type = &png_file_as_bytes[chunk_idx + 4..chunk_idx + 8];
+
+        if chunk_type == PNG_EXIF_CHUNK_TYPE {
+            return None;
+        }
+
+        chunk_idx += 12 + chunk_data_len;
+
+        if insert_idx == 0 {
+            insert_idx = chunk_idx;
+        }
+    }
+
+    if insert_idx == 0 || insert_idx > png_file_as_bytes.len() {
+        return None;
+    }
+
+    let mut exif_chunk_crc = crc32fast::Hasher::new();
+    exif_chunk_crc.update(PNG_EXIF_CHUNK_TYPE);
+    exif_chunk_crc.update(exif_block);
+
+    let mut png_with_exif: Vec<u8> = Vec::with_capacity(png_file_as_bytes.len() + exif_block.len() + 12);
+    png_with_exif.extend_from_slice(&png_file_as_bytes[..insert_idx]);
+    png_with_exif.extend_from_slice(&(exif_block.len() as u32).to_be_bytes());
+    png_with_exif.extend_from_slice(PNG_EXIF_CHUNK_TYPE);
+    png_with_exif.extend_from_slice(exif_block);
+    png_with_exif.extend_from_slice(&exif_chunk_crc.finalize().to_be_bytes());
+    png_with_exif.extend_from_slice(&png_file_as_bytes[insert_idx..]);
+
+    return Some(png_with_exif);
+}
+
+/// Rewrites the JPEG or PNG file with the dates added. Files that already have EXIF
+/// (e.g. originals straight from a camera) are left as they are.
+/// Returns whether the dates were written
+pub fn write_exif_dates(
+    photo_file_path: &std::path::Path,
+    original_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+    digitized_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+) -> bool {
+    let exif_block = match build_exif_block(original_date_ts, digitized_date_ts) {
+        Some(exif_block) => exif_block,
+        None => return false,
+    };
+
+    let photo_file_as_bytes = match std::fs::read(photo_file_path) {
+        Ok(photo_file_as_bytes) => photo_file_as_bytes,
+        Err(_) => return false,
+    };
+
+    let photo_with_exif = match add_exif_to_jpeg(&photo_file_as_bytes, &exif_block)
+        .or_else(|| add_exif_to_png(&photo_file_as_bytes, &exif_block))
+    {
+        Some(photo_with_exif) => photo_with_exif,
+        None => return false,
+    };
+
+    if let Err(write_error) = std::fs::write(photo_file_path, photo_with_exif) {
+        eprintln!("Can't write EXIF dates to '{}': {}", photo_file_path.display(), write_error);
+        return false;
+    }
+
+    return true;
+}
@@ -118,6 +118,11 @@ pub fn get_slice_as_le_u64(
     ));
 }
 
+/// Single-byte settings are on when set to 1
+pub fn get_byte_flag(array_idx: usize, file_as_array: &[u8], file_offset: usize) -> bool {
+    return file_as_array[array_idx + file_offset] == 1;
+}
+
 pub fn get_slice_as_mac_timestamp(
     array_idx: usize,
     file_as_array: &[u8],
@@ -146,6 +151,23 @@ pub fn return_utf16_from_utf8(utf8_bytes: &[u8]) -> Vec<u16> {
     return arr_elements_pairwise_combined;
 }
 
+/// Decodes a UTF-16 (little-endian) string straight from its raw bytes, without panicking on bad input.
+/// Unpaired surrogates become the replacement character and a trailing odd byte is dropped;
+/// in either case the returned flag is `false`, so the caller can warn about it
+pub fn decode_utf16_le_lossy(le_bytes: &[u8]) -> (String, bool) {
+    let utf16_units: Vec<u16> = le_bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+
+    let is_even_length = le_bytes.len().is_multiple_of(2);
+
+    match String::from_utf16(&utf16_units) {
+        Ok(decoded_str) => return (decoded_str, is_even_length),
+        Err(_) => return (String::from_utf16_lossy(&utf16_units), false),
+    }
+}
+
 /// Apple (Mac OS X, iOS, iPadOS, et al) timestamps start on Jan 1 1904, whereas Linux timestamps
 /// (which is what Rust's `chrono` library uses) start at Jan 1 1970,
 /// hence this difference
@@ -237,10 +259,345 @@ pub fn convert_bytes_to_human_readable_size(num_bytes: u64) -> String {
     return human_readable_size;
 }
 
-/// Initialize an object to write to a CSV file, given a CSV filename
-pub fn init_csv_writer(filename: &str) -> csv::Writer<std::fs::File> {
-    let csv_writer = csv::Writer::from_path(filename)
-        .expect(&format!("Can't initialize CSV file '{}'", &filename));
+/// How the CSV files are written. Some Excel locales split comma-delimited files on commas in titles,
+/// so the delimiter can be changed (e.g. to a tab or semicolon) and every field quoted
+pub struct CsvFormat {
+    pub delimiter: u8,
+    pub quote_all: bool,
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        return CsvFormat {
+            delimiter: b',',
+            quote_all: false,
+        };
+    }
+}
+
+/// Set once in main from the command line, before any CSV file is written
+static CSV_FORMAT: std::sync::OnceLock<CsvFormat> = std::sync::OnceLock::new();
+
+/// Every CSV file is written through this, so it can be compressed, see `create_output_file`
+pub type CsvWriter = csv::Writer<Box<dyn std::io::Write>>;
+
+/// Large libraries make for large exports, which are often archived for good, so they can be
+/// written compressed. The file extension gets a ".gz" or ".zst" added
+pub enum OutputCompression {
+    Gzip,
+    Zstd,
+}
+
+/// Set once in main from the command line, before any export is written. Uncompressed if never set
+static OUTPUT_COMPRESSION: std::sync::OnceLock<OutputCompression> = std::sync::OnceLock::new();
+
+pub fn set_output_compression(output_compression: OutputCompression) {
+    if OUTPUT_COMPRESSION.set(output_compression).is_err() {
+        eprintln!("Output compression was already set, keeping the first one");
+    }
+}
+
+/// The name the export is actually written to, e.g. "music.csv.zst" for "music.csv" with zstd
+pub fn get_output_filename(filename: &str) -> String {
+    return match OUTPUT_COMPRESSION.get() {
+        Some(OutputCompression::Gzip) => format!("{}.gz", filename),
+        Some(OutputCompression::Zstd) => format!("{}.zst", filename),
+        None => filename.to_string(),
+    };
+}
+
+/// Creates an export file, compressed as picked on the command line. The compressed stream is
+/// finished when the writer is dropped
+pub fn create_output_file(filename: &str) -> Box<dyn std::io::Write> {
+    let output_filename = get_output_filename(filename);
+
+    let output_file = std::fs::File::create(&output_filename)
+        .unwrap_or_else(|_| panic!("Can't create output file '{}'", output_filename));
+
+    return match OUTPUT_COMPRESSION.get() {
+        Some(OutputCompression::Gzip) => Box::new(flate2::write::GzEncoder::new(output_file, flate2::Compression::default())),
+        Some(OutputCompression::Zstd) => Box::new(
+            zstd::stream::write::Encoder::new(output_file, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .unwrap_or_else(|_| panic!("Can't start compressing '{}'", output_filename))
+                .auto_finish(),
+        ),
+        None => Box::new(output_file),
+    };
+}
+
+/// Like `std::fs::write`, for exports
+pub fn write_output_file(filename: &str, contents: &[u8]) {
+    let mut output_file = create_output_file(filename);
+
+    std::io::Write::write_all(&mut output_file, contents)
+        .unwrap_or_else(|_| panic!("Can't write '{}'", get_output_filename(filename)));
+
+    std::io::Write::flush(&mut output_file)
+        .unwrap_or_else(|_| panic!("Can't write '{}'", get_output_filename(filename)));
+}
+
+/// For exports that can only be written to a plain file (e.g. SQLite databases): compresses the file
+/// as picked on the command line, if at all, and removes the uncompressed one
+#[cfg(feature = "sqlite")]
+pub fn compress_output_file(filename: &str) {
+    if OUTPUT_COMPRESSION.get().is_none() {
+        return;
+    }
+
+    let file_contents = std::fs::read(filename)
+        .expect(&format!("Can't read '{}' to compress it", filename));
+
+    write_output_file(filename, &file_contents);
+
+    std::fs::remove_file(filename)
+        .expect(&format!("Can't remove '{}' after compressing it", filename));
+}
+
+pub fn set_csv_format(csv_format: CsvFormat) {
+    if CSV_FORMAT.set(csv_format).is_err() {
+        eprintln!("CSV format was already set, keeping the first one");
+    }
+}
+
+/// Initialize an object to write to a CSV file, given a CSV filename, in the format picked on the command line
+pub fn init_csv_writer(filename: &str) -> CsvWriter {
+    return match CSV_FORMAT.get() {
+        Some(csv_format) => init_csv_writer_with_format(filename, csv_format),
+        None => init_csv_writer_with_format(filename, &CsvFormat::default()),
+    };
+}
+
+/// For files whose format is fixed by whatever reads them, regardless of the command line
+pub fn init_csv_writer_with_format(filename: &str, csv_format: &CsvFormat) -> CsvWriter {
+    let quote_style = if csv_format.quote_all {
+        csv::QuoteStyle::Always
+    } else {
+        csv::QuoteStyle::Necessary
+    };
+
+    let csv_writer = csv::WriterBuilder::new()
+        .delimiter(csv_format.delimiter)
+        .quote_style(quote_style)
+        .from_writer(create_output_file(filename));
 
     return csv_writer;
 }
+
+/// The positions of the columns picked by `selected_columns`, a comma-separated list of header names
+/// (case-insensitive) in the order they should be written. Every column, in the usual order, if nothing was picked.
+/// Unknown names are skipped with a warning
+pub fn get_selected_column_idxs(headers: &[&str], selected_columns: Option<&str>, csv_filename: &str) -> Vec<usize> {
+    let selected_columns = match selected_columns {
+        Some(selected_columns) => selected_columns,
+        None => return (0..headers.len()).collect(),
+    };
+
+    let mut column_idxs: Vec<usize> = Vec::new();
+
+    for column_name in selected_columns.split(',').map(|column_name| column_name.trim()) {
+        if column_name.is_empty() {
+            continue;
+        }
+
+        match headers.iter().position(|header| header.eq_ignore_ascii_case(column_name)) {
+            Some(column_idx) => column_idxs.push(column_idx),
+            None => eprintln!("{} has no column '{}', skipping it", csv_filename, column_name),
+        }
+    }
+
+    if column_idxs.is_empty() {
+        eprintln!("None of the columns picked for {} exist, writing all of them", csv_filename);
+        return (0..headers.len()).collect();
+    }
+
+    return column_idxs;
+}
+
+/// The fields of a CSV record at `column_idxs`, see `get_selected_column_idxs`
+pub fn get_selected_columns<'a, T>(record: &'a [T], column_idxs: &[usize]) -> Vec<&'a T> {
+    return column_idxs.iter().map(|column_idx| &record[*column_idx]).collect();
+}
+
+/// Serializes the given records into a pretty-printed JSON file
+pub fn write_json_file<T: serde::Serialize>(filename: &str, records: &T) {
+    let records_json = serde_json::to_string_pretty(records)
+        .unwrap_or_else(|_| panic!("Error serializing records for '{}'", filename));
+
+    let mut records_json_file = create_output_file(filename);
+
+    std::io::Write::write_all(&mut records_json_file, records_json.as_bytes())
+        .unwrap_or_else(|_| panic!("Error writing JSON file '{}'", filename));
+}
+
+/// Serializes the given records as newline-delimited JSON (one compact JSON object per line),
+/// which tools like `jq` can read as they come in
+pub fn write_ndjson<T: serde::Serialize, W: std::io::Write>(ndjson_writer: W, records: &[T]) {
+    let mut ndjson_writer = std::io::BufWriter::new(ndjson_writer);
+
+    for record in records.iter() {
+        serde_json::to_writer(&mut ndjson_writer, record).expect("Error serializing record to NDJSON");
+
+        std::io::Write::write_all(&mut ndjson_writer, b"\n").expect("Error writing NDJSON");
+    }
+
+    std::io::Write::flush(&mut ndjson_writer).expect("Error writing NDJSON");
+}
+
+pub fn write_yaml_file<T: serde::Serialize>(filename: &str, records: &T) {
+    let records_yaml = serde_yaml::to_string(records)
+        .unwrap_or_else(|_| panic!("Error serializing records for '{}'", filename));
+
+    let mut records_yaml_file = create_output_file(filename);
+
+    std::io::Write::write_all(&mut records_yaml_file, records_yaml.as_bytes())
+        .unwrap_or_else(|_| panic!("Error writing YAML file '{}'", filename));
+}
+
+/// Maps with field names (rather than arrays), so the file can be read without knowing the model
+pub fn write_messagepack_file<T: serde::Serialize>(filename: &str, records: &T) {
+    let records_messagepack = rmp_serde::to_vec_named(records)
+        .unwrap_or_else(|_| panic!("Error serializing records for '{}'", filename));
+
+    write_output_file(filename, &records_messagepack);
+}
+
+pub fn build_be_u64_from_bytes(bytes: &[u8]) -> u64 {
+    let mut number: u64 = 0;
+    const RADIX: u64 = 256;
+
+    for (idx, item) in bytes.iter().rev().enumerate() {
+        let summand: u64 = RADIX
+            .checked_pow(idx as u32)
+            .unwrap_or_else(|| panic!("Can't raise {} to power {}", RADIX, idx));
+
+        number += (summand) * (*item as u64);
+    }
+
+    return number;
+}
+
+pub fn get_slice_as_be_u32(
+    array_idx: usize,
+    file_as_array: &[u8],
+    file_offset: usize,
+    slice_len: usize,
+) -> u32 {
+    if slice_len > 4 {
+        panic!("Can't create u32 out of this large of a slice");
+    }
+
+    return build_be_u32_from_bytes(&get_slice_from_offset_with_len(
+        array_idx,
+        file_as_array,
+        file_offset,
+        slice_len,
+    ));
+}
+
+pub fn get_slice_as_be_u64(
+    array_idx: usize,
+    file_as_array: &[u8],
+    file_offset: usize,
+    slice_len: usize,
+) -> u64 {
+    if slice_len > 8 {
+        panic!("Can't create u64 out of this large of a slice");
+    }
+
+    return build_be_u64_from_bytes(&get_slice_from_offset_with_len(
+        array_idx,
+        file_as_array,
+        file_offset,
+        slice_len,
+    ));
+}
+
+/// Same as `return_utf16_from_utf8`, but for strings stored big-endian (e.g. Smart Playlist rules)
+pub fn return_utf16_from_be_bytes(be_bytes: &[u8]) -> Vec<u16> {
+    let mut arr_elements_pairwise_combined: Vec<u16> = vec![];
+
+    for i in (0..be_bytes.len()).step_by(2) {
+        let u16_elem: u16 = ((be_bytes[i] as u16) << 8) | be_bytes[i + 1] as u16;
+        arr_elements_pairwise_combined.push(u16_elem);
+    }
+
+    return arr_elements_pairwise_combined;
+}
+
+/// The opposite of `get_timestamp_as_mac`: converts a UTC timestamp into a Mac epoch time
+pub fn get_mac_timestamp_from_datetime(timestamp: chrono::DateTime<chrono::Utc>) -> u64 {
+    return (timestamp.timestamp() + MAC_TO_LINUX_EPOCH_CONVERSION) as u64;
+}
+
+const MP4_ATOM_HEADER_LEN: usize = 8;
+
+/// Looks for the child atom with the given type between `start` and `end` of an MP4 file,
+/// returning where its contents start and end
+pub fn find_mp4_atom(
+    file_as_bytes: &[u8],
+    start: usize,
+    end: usize,
+    atom_type: &[u8],
+) -> Option<(usize, usize)> {
+    let mut idx = start;
+
+    while idx + MP4_ATOM_HEADER_LEN <= end {
+        let atom_len = build_be_u32_from_bytes(&file_as_bytes[idx..idx + 4]) as usize;
+        let curr_atom_type = &file_as_bytes[idx + 4..idx + MP4_ATOM_HEADER_LEN];
+
+        // A 0 length means "until the end of the file", 1 means a 64-bit length follows.
+        // Neither are used for the (small) metadata atoms this is used to find
+        if atom_len < MP4_ATOM_HEADER_LEN || idx + atom_len > end {
+            return None;
+        }
+
+        if curr_atom_type == atom_type {
+            return Some((idx + MP4_ATOM_HEADER_LEN, idx + atom_len));
+        }
+
+        idx += atom_len;
+    }
+
+    return None;
+}
+
+/// Hashes a file without reading all of it into memory (videos can be several GB), as lowercase hex.
+/// `checksum_algorithm` is "md5" or "sha1". Returns `None` if the file can't be read
+pub fn compute_file_checksum(file_path: &std::path::Path, checksum_algorithm: &str) -> Option<String> {
+    use md5::Digest;
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(file_path).ok()?;
+    let mut read_buffer = vec![0u8; 1 << 16];
+
+    let mut md5_hasher = md5::Md5::new();
+    let mut sha1_hasher = sha1::Sha1::new();
+
+    loop {
+        let num_bytes_read = file.read(&mut read_buffer).ok()?;
+
+        if num_bytes_read == 0 {
+            break;
+        }
+
+        if checksum_algorithm == "sha1" {
+            sha1_hasher.update(&read_buffer[..num_bytes_read]);
+        } else {
+            md5_hasher.update(&read_buffer[..num_bytes_read]);
+        }
+    }
+
+    let checksum_bytes: Vec<u8> = if checksum_algorithm == "sha1" {
+        sha1_hasher.finalize().to_vec()
+    } else {
+        md5_hasher.finalize().to_vec()
+    };
+
+    let mut checksum = String::new();
+
+    for checksum_byte in checksum_bytes.iter() {
+        write!(checksum, "{:02x}", checksum_byte).unwrap();
+    }
+
+    return Some(checksum);
+}
@@ -55,3 +55,94 @@ pub fn get_canonical_path(itunesdb_format_path: String) -> String {
 
     return str::replace(&string_to_sanitize, ITUNESDB_DIRECTORY_SEPARATOR, "/");
 }
+
+/// Voice memos are named after when the recording started, e.g. "Recordings/20071224 183015.wav"
+pub fn get_voice_memo_timestamp_from_path(
+    canonical_path: &str,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let file_name = canonical_path.rsplit('/').next()?;
+    let file_stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+
+    let recorded_timestamp =
+        chrono::NaiveDateTime::parse_from_str(file_stem, "%Y%m%d %H%M%S").ok()?;
+
+    return Some(chrono::DateTime::from_naive_utc_and_offset(
+        recorded_timestamp,
+        chrono::Utc,
+    ));
+}
+
+/// Older databases (1st - 4th gen) leave the filetype empty, but the
+/// file on the iPod still has its original extension, e.g. "iPod_Control/Music/F00/ABCD.mp3"
+/// Returns it in uppercase, to match how the filetype field is decoded
+pub fn get_file_extension_from_path(canonical_path: &str) -> Option<String> {
+    let file_name = canonical_path.rsplit('/').next()?;
+    let (file_stem, file_extension) = file_name.rsplit_once('.')?;
+
+    if file_stem.is_empty() || file_extension.is_empty() {
+        return None;
+    }
+
+    return Some(file_extension.to_uppercase());
+}
+
+/// Whether a field is inside a header that's `header_len` bytes long. Older iTunes versions write
+/// shorter track item headers (e.g. 0x9C or 0xF4 bytes), and past their end are the first data object's bytes
+pub fn is_field_in_header(header_len: u32, field_offset: usize, field_len: usize) -> bool {
+    return field_offset + field_len <= header_len as usize;
+}
+
+/// `None` if the header at `header_idx` ends before the field
+pub fn get_header_field_as_le_u32(
+    header_idx: usize,
+    itunesdb_file_as_bytes: &[u8],
+    header_len: u32,
+    field_offset: usize,
+    field_len: usize,
+) -> Option<u32> {
+    if !is_field_in_header(header_len, field_offset, field_len) {
+        return None;
+    }
+
+    return Some(crate::helpers::helpers::get_slice_as_le_u32(
+        header_idx,
+        itunesdb_file_as_bytes,
+        field_offset,
+        field_len,
+    ));
+}
+
+/// `false` if the header at `header_idx` ends before the flag
+pub fn get_header_byte_flag(
+    header_idx: usize,
+    itunesdb_file_as_bytes: &[u8],
+    header_len: u32,
+    field_offset: usize,
+) -> bool {
+    return is_field_in_header(header_len, field_offset, 1)
+        && crate::helpers::helpers::get_byte_flag(header_idx, itunesdb_file_as_bytes, field_offset);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_fields_past_a_short_header_are_not_read() {
+        // A 0x9C byte track item header, followed by a data object whose bytes would be misread
+        let mut itunesdb_file_as_bytes: Vec<u8> = vec![0; 0x9C];
+        itunesdb_file_as_bytes.extend(vec![0xAB; 0x200]);
+
+        assert_eq!(get_header_field_as_le_u32(0, &itunesdb_file_as_bytes, 0x9C, 352, 4), None);
+        assert_eq!(get_header_field_as_le_u32(0, &itunesdb_file_as_bytes, 0x164, 352, 4), Some(0xABABABAB));
+        assert_eq!(get_header_field_as_le_u32(0, &itunesdb_file_as_bytes, 0x9C, 128, 4), Some(0));
+
+        assert!(!get_header_byte_flag(0, &itunesdb_file_as_bytes, 0x9C, 0x9C));
+
+        itunesdb_file_as_bytes[0xA5] = 1;
+        assert!(get_header_byte_flag(0, &itunesdb_file_as_bytes, 0xF4, 0xA5));
+
+        assert!(is_field_in_header(0x9C, 0x9B, 1));
+        assert!(!is_field_in_header(0x9C, 164, 1));
+    }
+}
@@ -0,0 +1,265 @@
+/**
+ * File: plist_helpers.rs
+ *
+ * Just enough of an XML property list reader for the files the iPod writes itself (e.g. SysInfoExtended),
+ * and a writer for the ones exported (e.g. the iTunes Library).
+ * Binary plists aren't supported. Nothing the parser needs is a <real> or <data>, so those are read as `Other`.
+ *
+ */
+
+pub enum PlistValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Date(chrono::DateTime<chrono::Utc>),
+    Array(Vec<PlistValue>),
+    /// Keys stay in file order
+    Dict(Vec<(String, PlistValue)>),
+    Other,
+}
+
+impl PlistValue {
+    /// Returns `None` if this isn't a dict, or it doesn't have that key
+    pub fn get(&self, key: &str) -> Option<&PlistValue> {
+        if let PlistValue::Dict(dict_entries) = self {
+            return dict_entries
+                .iter()
+                .find(|(dict_key, _)| dict_key == key)
+                .map(|(_, dict_value)| dict_value);
+        }
+
+        return None;
+    }
+
+    pub fn as_string(&self) -> Option<String> {
+        if let PlistValue::String(string_value) = self {
+            return Some(string_value.to_string());
+        }
+
+        return None;
+    }
+
+    pub fn as_integer(&self) -> Option<i64> {
+        if let PlistValue::Integer(integer_value) = self {
+            return Some(*integer_value);
+        }
+
+        return None;
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<PlistValue>> {
+        if let PlistValue::Array(array_values) = self {
+            return Some(array_values);
+        }
+
+        return None;
+    }
+}
+
+fn decode_xml_entities(xml_text: &str) -> String {
+    return xml_text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+}
+
+/// Splits the XML into tags (without their angle brackets) and the text between them.
+/// The XML declaration, doctype and comments are dropped
+fn get_xml_tokens(plist_xml: &str) -> Vec<(bool, String)> {
+    let mut xml_tokens: Vec<(bool, String)> = Vec::new();
+    let mut remaining_xml = plist_xml;
+
+    while let Some(tag_start_idx) = remaining_xml.find('<') {
+        let text_before_tag = remaining_xml[..tag_start_idx].trim();
+
+        if !text_before_tag.is_empty() {
+            xml_tokens.push((false, decode_xml_entities(text_before_tag)));
+        }
+
+        let tag_end_idx = match remaining_xml[tag_start_idx..].find('>') {
+            Some(tag_end_idx) => tag_start_idx + tag_end_idx,
+            None => break,
+        };
+
+        let xml_tag = &remaining_xml[tag_start_idx + 1..tag_end_idx];
+
+        if !xml_tag.starts_with('?') && !xml_tag.starts_with('!') {
+            xml_tokens.push((true, xml_tag.trim().to_string()));
+        }
+
+        remaining_xml = &remaining_xml[tag_end_idx + 1..];
+    }
+
+    return xml_tokens;
+}
+
+/// Reads the value starting at `token_idx`, and moves `token_idx` past it
+fn read_plist_value(xml_tokens: &[(bool, String)], token_idx: &mut usize) -> Option<PlistValue> {
+    let (is_tag, xml_tag) = xml_tokens.get(*token_idx)?;
+
+    if !is_tag {
+        return None;
+    }
+
+    *token_idx += 1;
+
+    // Empty elements, e.g. <true/> or <string/>
+    if let Some(xml_tag_name) = xml_tag.strip_suffix('/') {
+        return Some(match xml_tag_name.trim() {
+            "string" => PlistValue::String("".to_string()),
+            "array" => PlistValue::Array(Vec::new()),
+            "dict" => PlistValue::Dict(Vec::new()),
+            "true" => PlistValue::Boolean(true),
+            "false" => PlistValue::Boolean(false),
+            _ => PlistValue::Other,
+        });
+    }
+
+    // <plist> has attributes
+    let xml_tag_name = xml_tag.split_whitespace().next().unwrap_or("");
+    let closing_tag = format!("/{}", xml_tag_name);
+
+    let plist_value = match xml_tag_name {
+        "plist" => {
+            let plist_value = read_plist_value(xml_tokens, token_idx)?;
+            *token_idx += 1;
+            return Some(plist_value);
+        }
+        "dict" => {
+            let mut dict_entries: Vec<(String, PlistValue)> = Vec::new();
+
+            while matches!(xml_tokens.get(*token_idx), Some((true, xml_tag)) if xml_tag == "key") {
+                let dict_key = match xml_tokens.get(*token_idx + 1)? {
+                    (false, dict_key) => {
+                        *token_idx += 3;
+                        dict_key.to_string()
+                    }
+                    // <key></key>
+                    (true, _) => {
+                        *token_idx += 2;
+                        "".to_string()
+                    }
+                };
+
+                dict_entries.push((dict_key, read_plist_value(xml_tokens, token_idx)?));
+            }
+
+            PlistValue::Dict(dict_entries)
+        }
+        "array" => {
+            let mut array_values: Vec<PlistValue> = Vec::new();
+
+            while xml_tokens.get(*token_idx)?.1 != closing_tag {
+                array_values.push(read_plist_value(xml_tokens, token_idx)?);
+            }
+
+            PlistValue::Array(array_values)
+        }
+        _ => {
+            let element_text = match xml_tokens.get(*token_idx)? {
+                (false, element_text) => {
+                    *token_idx += 1;
+                    element_text.to_string()
+                }
+                (true, _) => "".to_string(),
+            };
+
+            match xml_tag_name {
+                "string" => PlistValue::String(element_text),
+                "integer" => PlistValue::Integer(element_text.parse::<i64>().ok()?),
+                "date" => chrono::DateTime::parse_from_rfc3339(&element_text)
+                    .map_or(PlistValue::Other, |date_value| PlistValue::Date(date_value.with_timezone(&chrono::Utc))),
+                _ => PlistValue::Other,
+            }
+        }
+    };
+
+    // Past the closing tag
+    if xml_tokens.get(*token_idx)?.1 != closing_tag {
+        return None;
+    }
+
+    *token_idx += 1;
+
+    return Some(plist_value);
+}
+
+/// Returns `None` if it isn't a well-formed XML plist
+pub fn parse_xml_plist(plist_xml: &str) -> Option<PlistValue> {
+    let xml_tokens = get_xml_tokens(plist_xml);
+    let mut token_idx = 0;
+
+    return read_plist_value(&xml_tokens, &mut token_idx);
+}
+
+fn encode_xml_entities(xml_text: &str) -> String {
+    return xml_text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+}
+
+/// Appends the value, indented with tabs like iTunes does
+fn write_plist_value(plist_value: &PlistValue, indent_level: usize, plist_xml: &mut String) {
+    let indent = "\t".repeat(indent_level);
+
+    match plist_value {
+        PlistValue::String(string_value) => {
+            plist_xml.push_str(&format!("{}<string>{}</string>\n", indent, encode_xml_entities(string_value)));
+        }
+        PlistValue::Integer(integer_value) => {
+            plist_xml.push_str(&format!("{}<integer>{}</integer>\n", indent, integer_value));
+        }
+        PlistValue::Boolean(boolean_value) => {
+            plist_xml.push_str(&format!("{}<{}/>\n", indent, if *boolean_value { "true" } else { "false" }));
+        }
+        PlistValue::Date(date_value) => {
+            plist_xml.push_str(&format!("{}<date>{}</date>\n", indent, date_value.format("%Y-%m-%dT%H:%M:%SZ")));
+        }
+        PlistValue::Array(array_values) => {
+            plist_xml.push_str(&format!("{}<array>\n", indent));
+
+            for array_value in array_values.iter() {
+                write_plist_value(array_value, indent_level + 1, plist_xml);
+            }
+
+            plist_xml.push_str(&format!("{}</array>\n", indent));
+        }
+        PlistValue::Dict(dict_entries) => {
+            plist_xml.push_str(&format!("{}<dict>\n", indent));
+
+            for (dict_key, dict_value) in dict_entries.iter() {
+                plist_xml.push_str(&format!("{}\t<key>{}</key>", indent, encode_xml_entities(dict_key)));
+
+                // iTunes keeps scalar values on the same line as their key
+                if matches!(dict_value, PlistValue::Array(_) | PlistValue::Dict(_)) {
+                    plist_xml.push('\n');
+                    write_plist_value(dict_value, indent_level + 1, plist_xml);
+                } else {
+                    write_plist_value(dict_value, 0, plist_xml);
+                }
+            }
+
+            plist_xml.push_str(&format!("{}</dict>\n", indent));
+        }
+        // Can't be written back, since it wasn't read
+        PlistValue::Other => {}
+    }
+}
+
+/// Returns the whole XML document, declaration and doctype included
+pub fn write_xml_plist(plist_value: &PlistValue) -> String {
+    let mut plist_xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <!DOCTYPE plist PUBLIC \"-//Apple Computer//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+        <plist version=\"1.0\">\n",
+    );
+
+    write_plist_value(plist_value, 0, &mut plist_xml);
+
+    plist_xml.push_str("</plist>\n");
+
+    return plist_xml;
+}
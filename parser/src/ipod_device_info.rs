@@ -0,0 +1,522 @@
+/**
+ * File: ipod_device_info.rs
+ *
+ * Everything the parser learns about the iPod itself (rather than about the media on it),
+ * gathered in one place so it can be exported alongside the tracks.
+ *
+ */
+use serde::Serialize;
+
+use crate::itunesprefs;
+use crate::preferences;
+
+pub const UNKNOWN_LANGUAGE: &str = "unknown";
+
+#[derive(Serialize)]
+pub struct IpodDeviceInfo {
+    /// As named in iTunes, from the DeviceInfo file
+    pub ipod_name: String,
+    /// Who and which computer it was last synced with, if iTunes wrote them down
+    pub user_name: String,
+    pub host_computer_name: String,
+    /// ISO 639-1 code of the iTunes interface language the database was written with
+    pub language_code: String,
+    pub language_name: String,
+    pub database_version: u32,
+    pub itunes_version: String,
+    pub hashing_scheme: String,
+    /// What the iPod was doing when it was last used. Only the iPod shuffle saves this
+    pub playback_state: Option<PlaybackState>,
+    /// How the iPod shuffle is set to play
+    pub playback_settings: Option<PlaybackSettings>,
+    /// Settings chosen in iTunes (iTunesPrefs) and on the iPod (Preferences)
+    pub itunes_prefs: Option<itunesprefs::ItunesPrefs>,
+    pub preferences: Option<preferences::DevicePreferences>,
+    /// What the iPod says about itself, if its SysInfo file is at hand
+    pub sysinfo: Option<SysInfo>,
+    pub model: Option<IpodModel>,
+    /// What the model was identified from: "Model number" or "Serial number"
+    pub model_source: String,
+}
+
+/// From iPod_Control/Device/SysInfo. Unlike the database, this comes from the iPod, not from iTunes
+#[derive(Serialize)]
+pub struct SysInfo {
+    /// As hex, without the "0x"
+    pub firewire_guid: String,
+    /// e.g. "A446", see `decode_model_number`
+    pub model_number: String,
+    pub serial_number: String,
+    pub firmware_version: String,
+    pub board_name: String,
+    pub board_revision: String,
+    /// Only listed in SysInfoExtended
+    pub artwork_formats: Vec<ArtworkFormat>,
+}
+
+/// One of the sizes the iPod wants album art, photos or chapter images in
+#[derive(Serialize)]
+pub struct ArtworkFormat {
+    /// "Album art", "Photo" or "Chapter art"
+    pub usage: String,
+    /// Same as the correlation ID in the ArtworkDB/Photo Database
+    pub format_id: u32,
+    pub width: u32,
+    pub height: u32,
+    /// e.g. "L565" for little-endian RGB565
+    pub pixel_format: String,
+}
+
+impl Default for SysInfo {
+    fn default() -> SysInfo {
+        return SysInfo {
+            firewire_guid: "".to_string(),
+            model_number: "".to_string(),
+            serial_number: "".to_string(),
+            firmware_version: "".to_string(),
+            board_name: "".to_string(),
+            board_revision: "".to_string(),
+            artwork_formats: Vec::new(),
+        };
+    }
+}
+
+/// From the iPod shuffle's iTunesPState file, which it rewrites whenever playback stops
+#[derive(Serialize)]
+pub struct PlaybackState {
+    /// As set with the volume buttons, not a percentage
+    pub volume_raw: u32,
+    /// Position of the current song in the iTunesSD (the songs' `track_index`)
+    pub current_track_index: u32,
+    /// Relative to the iPod's root, e.g. "iPod_Control/Music/F00/XZYL.m4a", if the iTunesSD is at hand
+    pub current_song_filename: Option<String>,
+    pub playback_position_ms: u32,
+    pub is_shuffle_enabled: bool,
+    /// Where the current song is in the shuffled order
+    pub shuffle_position: u32,
+}
+
+/// Only what the iTunesSD and iTunesPState record. Whether Sound Check is on, and shuffling by album
+/// (rather than by song), aren't stored anywhere documented
+#[derive(Serialize)]
+pub struct PlaybackSettings {
+    /// "Songs" or "Off", from the shuffle switch. Not known for the iTunesSD version 3
+    pub shuffle_mode: Option<String>,
+    /// Only in the iTunesSD version 3. 0 means there's no limit
+    pub max_volume_raw: Option<u8>,
+    pub is_voiceover_enabled: Option<bool>,
+}
+
+impl Default for PlaybackSettings {
+    fn default() -> PlaybackSettings {
+        return PlaybackSettings {
+            shuffle_mode: None,
+            max_volume_raw: None,
+            is_voiceover_enabled: None,
+        };
+    }
+}
+
+impl Default for IpodDeviceInfo {
+    fn default() -> IpodDeviceInfo {
+        return IpodDeviceInfo {
+            ipod_name: "".to_string(),
+            user_name: "".to_string(),
+            host_computer_name: "".to_string(),
+            language_code: UNKNOWN_LANGUAGE.to_string(),
+            language_name: UNKNOWN_LANGUAGE.to_string(),
+            database_version: 0,
+            itunes_version: "".to_string(),
+            hashing_scheme: "".to_string(),
+            playback_state: None,
+            playback_settings: None,
+            itunes_prefs: None,
+            preferences: None,
+            sysinfo: None,
+            model: None,
+            model_source: "".to_string(),
+        };
+    }
+}
+
+impl IpodDeviceInfo {
+    /// Identifies the model from the last 3 characters of the serial number alone.
+    /// Everything else is left as default, as is the model if the serial number isn't a known one
+    pub fn from_serial(serial_number: &str) -> IpodDeviceInfo {
+        let mut ipod_device_info = IpodDeviceInfo::default();
+
+        if let Some(ipod_model) = decode_serial_number(serial_number) {
+            ipod_device_info.model = Some(ipod_model.clone());
+            ipod_device_info.model_source = "Serial number".to_string();
+        }
+
+        return ipod_device_info;
+    }
+
+    /// The model number is exact, so it wins over the serial number, which confirms it.
+    /// Returns a warning if the two point to different models
+    pub fn set_sysinfo(&mut self, sysinfo: SysInfo) -> Option<String> {
+        let model_from_serial = IpodDeviceInfo::from_serial(&sysinfo.serial_number);
+        let mut model_mismatch_warning: Option<String> = None;
+
+        if let Some(ipod_model) = decode_model_number(&sysinfo.model_number) {
+            if let Some(serial_ipod_model) = model_from_serial.model.as_ref() {
+                if serial_ipod_model.model_number != ipod_model.model_number {
+                    model_mismatch_warning = Some(format!(
+                        "Serial number {} belongs to an {} ({}), but the iPod says it's an {} ({})",
+                        sysinfo.serial_number,
+                        serial_ipod_model.get_description(),
+                        serial_ipod_model.model_number,
+                        ipod_model.get_description(),
+                        ipod_model.model_number
+                    ));
+                }
+            }
+
+            self.model = Some(ipod_model.clone());
+            self.model_source = "Model number".to_string();
+        } else if model_from_serial.model.is_some() {
+            self.model = model_from_serial.model;
+            self.model_source = model_from_serial.model_source;
+        }
+
+        self.sysinfo = Some(sysinfo);
+
+        return model_mismatch_warning;
+    }
+}
+
+/// The database only stores the two-letter language code, e.g. "en".
+/// Returns the (code, language name), or `None` if the bytes aren't a known ISO 639-1 code
+pub fn decode_database_language(db_language_raw: &[u8]) -> Option<(String, String)> {
+    if !db_language_raw.iter().all(|byte| byte.is_ascii_lowercase()) {
+        return None;
+    }
+
+    let db_language_code = std::str::from_utf8(db_language_raw).ok()?;
+
+    let db_language = isolang::Language::from_639_1(db_language_code)?;
+
+    return Some((db_language_code.to_string(), db_language.to_name().to_string()));
+}
+
+/// One iPod model, as sold. Several model numbers can share a family and generation
+#[derive(Serialize, Clone)]
+pub struct IpodModel {
+    /// Without its first letter, e.g. "A446"
+    pub model_number: &'static str,
+    pub family: &'static str,
+    pub generation: &'static str,
+    pub capacity: &'static str,
+    pub color: &'static str,
+}
+
+impl IpodModel {
+    /// e.g. "iPod video (5.5th gen) 30GB Black"
+    pub fn get_description(&self) -> String {
+        return format!("{} ({}) {} {}", self.family, self.generation, self.capacity, self.color);
+    }
+}
+
+/// Model numbers, without their first letter (e.g. "M" for retail, "P" for personalized),
+/// as in libgpod's ipod_info_table
+const IPOD_MODELS: [IpodModel; 148] = [
+    IpodModel { model_number: "8513", family: "iPod", generation: "1st gen", capacity: "5GB", color: "White" },
+    IpodModel { model_number: "8541", family: "iPod", generation: "1st gen", capacity: "5GB", color: "White" },
+    IpodModel { model_number: "8697", family: "iPod", generation: "1st gen", capacity: "5GB", color: "White" },
+    IpodModel { model_number: "8709", family: "iPod", generation: "1st gen", capacity: "10GB", color: "White" },
+    IpodModel { model_number: "8737", family: "iPod", generation: "2nd gen", capacity: "10GB", color: "White" },
+    IpodModel { model_number: "8740", family: "iPod", generation: "2nd gen", capacity: "10GB", color: "White" },
+    IpodModel { model_number: "8738", family: "iPod", generation: "2nd gen", capacity: "20GB", color: "White" },
+    IpodModel { model_number: "8741", family: "iPod", generation: "2nd gen", capacity: "20GB", color: "White" },
+    IpodModel { model_number: "8976", family: "iPod", generation: "3rd gen", capacity: "10GB", color: "White" },
+    IpodModel { model_number: "8946", family: "iPod", generation: "3rd gen", capacity: "15GB", color: "White" },
+    IpodModel { model_number: "9460", family: "iPod", generation: "3rd gen", capacity: "15GB", color: "White" },
+    IpodModel { model_number: "9244", family: "iPod", generation: "3rd gen", capacity: "20GB", color: "White" },
+    IpodModel { model_number: "8948", family: "iPod", generation: "3rd gen", capacity: "30GB", color: "White" },
+    IpodModel { model_number: "9245", family: "iPod", generation: "3rd gen", capacity: "40GB", color: "White" },
+    IpodModel { model_number: "9282", family: "iPod", generation: "4th gen", capacity: "20GB", color: "White" },
+    IpodModel { model_number: "9787", family: "iPod U2", generation: "4th gen", capacity: "25GB", color: "Black" },
+    IpodModel { model_number: "9268", family: "iPod", generation: "4th gen", capacity: "40GB", color: "White" },
+    IpodModel { model_number: "A079", family: "iPod photo", generation: "4th gen", capacity: "20GB", color: "White" },
+    IpodModel { model_number: "A127", family: "iPod U2", generation: "4th gen", capacity: "20GB", color: "Black" },
+    IpodModel { model_number: "9829", family: "iPod photo", generation: "4th gen", capacity: "30GB", color: "White" },
+    IpodModel { model_number: "9585", family: "iPod photo", generation: "4th gen", capacity: "40GB", color: "White" },
+    IpodModel { model_number: "9830", family: "iPod photo", generation: "4th gen", capacity: "60GB", color: "White" },
+    IpodModel { model_number: "9160", family: "iPod mini", generation: "1st gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "9436", family: "iPod mini", generation: "1st gen", capacity: "4GB", color: "Blue" },
+    IpodModel { model_number: "9435", family: "iPod mini", generation: "1st gen", capacity: "4GB", color: "Pink" },
+    IpodModel { model_number: "9434", family: "iPod mini", generation: "1st gen", capacity: "4GB", color: "Green" },
+    IpodModel { model_number: "9437", family: "iPod mini", generation: "1st gen", capacity: "4GB", color: "Gold" },
+    IpodModel { model_number: "9800", family: "iPod mini", generation: "2nd gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "9802", family: "iPod mini", generation: "2nd gen", capacity: "4GB", color: "Blue" },
+    IpodModel { model_number: "9804", family: "iPod mini", generation: "2nd gen", capacity: "4GB", color: "Pink" },
+    IpodModel { model_number: "9806", family: "iPod mini", generation: "2nd gen", capacity: "4GB", color: "Green" },
+    IpodModel { model_number: "9801", family: "iPod mini", generation: "2nd gen", capacity: "6GB", color: "Silver" },
+    IpodModel { model_number: "9803", family: "iPod mini", generation: "2nd gen", capacity: "6GB", color: "Blue" },
+    IpodModel { model_number: "9805", family: "iPod mini", generation: "2nd gen", capacity: "6GB", color: "Pink" },
+    IpodModel { model_number: "9807", family: "iPod mini", generation: "2nd gen", capacity: "6GB", color: "Green" },
+    IpodModel { model_number: "9724", family: "iPod shuffle", generation: "1st gen", capacity: "512MB", color: "White" },
+    IpodModel { model_number: "9725", family: "iPod shuffle", generation: "1st gen", capacity: "1GB", color: "White" },
+    IpodModel { model_number: "A564", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Silver" },
+    IpodModel { model_number: "A947", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Pink" },
+    IpodModel { model_number: "A949", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Blue" },
+    IpodModel { model_number: "A951", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Green" },
+    IpodModel { model_number: "A953", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Orange" },
+    IpodModel { model_number: "B225", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Silver" },
+    IpodModel { model_number: "B227", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Blue" },
+    IpodModel { model_number: "B229", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Green" },
+    IpodModel { model_number: "B231", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Red" },
+    IpodModel { model_number: "B233", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Purple" },
+    IpodModel { model_number: "C167", family: "iPod shuffle", generation: "2nd gen", capacity: "1GB", color: "Gold" },
+    IpodModel { model_number: "B518", family: "iPod shuffle", generation: "2nd gen", capacity: "2GB", color: "Silver" },
+    IpodModel { model_number: "B520", family: "iPod shuffle", generation: "2nd gen", capacity: "2GB", color: "Blue" },
+    IpodModel { model_number: "B522", family: "iPod shuffle", generation: "2nd gen", capacity: "2GB", color: "Green" },
+    IpodModel { model_number: "B524", family: "iPod shuffle", generation: "2nd gen", capacity: "2GB", color: "Red" },
+    IpodModel { model_number: "B526", family: "iPod shuffle", generation: "2nd gen", capacity: "2GB", color: "Purple" },
+    IpodModel { model_number: "C306", family: "iPod shuffle", generation: "3rd gen", capacity: "2GB", color: "Silver" },
+    IpodModel { model_number: "C323", family: "iPod shuffle", generation: "3rd gen", capacity: "2GB", color: "Black" },
+    IpodModel { model_number: "C381", family: "iPod shuffle", generation: "3rd gen", capacity: "2GB", color: "Green" },
+    IpodModel { model_number: "C384", family: "iPod shuffle", generation: "3rd gen", capacity: "2GB", color: "Blue" },
+    IpodModel { model_number: "C387", family: "iPod shuffle", generation: "3rd gen", capacity: "2GB", color: "Pink" },
+    IpodModel { model_number: "B867", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "C164", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Black" },
+    IpodModel { model_number: "C303", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Stainless steel" },
+    IpodModel { model_number: "C307", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Green" },
+    IpodModel { model_number: "C328", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Blue" },
+    IpodModel { model_number: "C331", family: "iPod shuffle", generation: "3rd gen", capacity: "4GB", color: "Pink" },
+    IpodModel { model_number: "C584", family: "iPod shuffle", generation: "4th gen", capacity: "2GB", color: "Silver" },
+    IpodModel { model_number: "C585", family: "iPod shuffle", generation: "4th gen", capacity: "2GB", color: "Pink" },
+    IpodModel { model_number: "C749", family: "iPod shuffle", generation: "4th gen", capacity: "2GB", color: "Orange" },
+    IpodModel { model_number: "C750", family: "iPod shuffle", generation: "4th gen", capacity: "2GB", color: "Green" },
+    IpodModel { model_number: "C751", family: "iPod shuffle", generation: "4th gen", capacity: "2GB", color: "Blue" },
+    IpodModel { model_number: "A350", family: "iPod nano", generation: "1st gen", capacity: "1GB", color: "White" },
+    IpodModel { model_number: "A352", family: "iPod nano", generation: "1st gen", capacity: "1GB", color: "Black" },
+    IpodModel { model_number: "A004", family: "iPod nano", generation: "1st gen", capacity: "2GB", color: "White" },
+    IpodModel { model_number: "A099", family: "iPod nano", generation: "1st gen", capacity: "2GB", color: "Black" },
+    IpodModel { model_number: "A005", family: "iPod nano", generation: "1st gen", capacity: "4GB", color: "White" },
+    IpodModel { model_number: "A107", family: "iPod nano", generation: "1st gen", capacity: "4GB", color: "Black" },
+    IpodModel { model_number: "A477", family: "iPod nano", generation: "2nd gen", capacity: "2GB", color: "Silver" },
+    IpodModel { model_number: "A426", family: "iPod nano", generation: "2nd gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "A428", family: "iPod nano", generation: "2nd gen", capacity: "4GB", color: "Blue" },
+    IpodModel { model_number: "A487", family: "iPod nano", generation: "2nd gen", capacity: "4GB", color: "Green" },
+    IpodModel { model_number: "A489", family: "iPod nano", generation: "2nd gen", capacity: "4GB", color: "Pink" },
+    IpodModel { model_number: "A725", family: "iPod nano", generation: "2nd gen", capacity: "4GB", color: "Red" },
+    IpodModel { model_number: "A497", family: "iPod nano", generation: "2nd gen", capacity: "8GB", color: "Black" },
+    IpodModel { model_number: "A726", family: "iPod nano", generation: "2nd gen", capacity: "8GB", color: "Red" },
+    IpodModel { model_number: "A978", family: "iPod nano", generation: "3rd gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "A980", family: "iPod nano", generation: "3rd gen", capacity: "8GB", color: "Silver" },
+    IpodModel { model_number: "B249", family: "iPod nano", generation: "3rd gen", capacity: "8GB", color: "Blue" },
+    IpodModel { model_number: "B253", family: "iPod nano", generation: "3rd gen", capacity: "8GB", color: "Green" },
+    IpodModel { model_number: "B257", family: "iPod nano", generation: "3rd gen", capacity: "8GB", color: "Red" },
+    IpodModel { model_number: "B261", family: "iPod nano", generation: "3rd gen", capacity: "8GB", color: "Black" },
+    IpodModel { model_number: "B480", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Silver" },
+    IpodModel { model_number: "B651", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Blue" },
+    IpodModel { model_number: "B654", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Pink" },
+    IpodModel { model_number: "B657", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Purple" },
+    IpodModel { model_number: "B660", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Orange" },
+    IpodModel { model_number: "B663", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Green" },
+    IpodModel { model_number: "B666", family: "iPod nano", generation: "4th gen", capacity: "4GB", color: "Yellow" },
+    IpodModel { model_number: "B598", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Silver" },
+    IpodModel { model_number: "B732", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Blue" },
+    IpodModel { model_number: "B735", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Pink" },
+    IpodModel { model_number: "B739", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Purple" },
+    IpodModel { model_number: "B742", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Orange" },
+    IpodModel { model_number: "B745", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Green" },
+    IpodModel { model_number: "B748", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Yellow" },
+    IpodModel { model_number: "B751", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Red" },
+    IpodModel { model_number: "B754", family: "iPod nano", generation: "4th gen", capacity: "8GB", color: "Black" },
+    IpodModel { model_number: "B903", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Silver" },
+    IpodModel { model_number: "B905", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Blue" },
+    IpodModel { model_number: "B907", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Pink" },
+    IpodModel { model_number: "B909", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Purple" },
+    IpodModel { model_number: "B911", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Orange" },
+    IpodModel { model_number: "B913", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Green" },
+    IpodModel { model_number: "B915", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Yellow" },
+    IpodModel { model_number: "B917", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Red" },
+    IpodModel { model_number: "B918", family: "iPod nano", generation: "4th gen", capacity: "16GB", color: "Black" },
+    IpodModel { model_number: "C027", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Silver" },
+    IpodModel { model_number: "C031", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Black" },
+    IpodModel { model_number: "C034", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Purple" },
+    IpodModel { model_number: "C037", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Blue" },
+    IpodModel { model_number: "C040", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Green" },
+    IpodModel { model_number: "C043", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Yellow" },
+    IpodModel { model_number: "C046", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Orange" },
+    IpodModel { model_number: "C049", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Red" },
+    IpodModel { model_number: "C050", family: "iPod nano", generation: "5th gen", capacity: "8GB", color: "Pink" },
+    IpodModel { model_number: "C060", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Silver" },
+    IpodModel { model_number: "C062", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Black" },
+    IpodModel { model_number: "C064", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Purple" },
+    IpodModel { model_number: "C066", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Blue" },
+    IpodModel { model_number: "C068", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Green" },
+    IpodModel { model_number: "C070", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Yellow" },
+    IpodModel { model_number: "C072", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Orange" },
+    IpodModel { model_number: "C074", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Red" },
+    IpodModel { model_number: "C075", family: "iPod nano", generation: "5th gen", capacity: "16GB", color: "Pink" },
+    IpodModel { model_number: "A002", family: "iPod video", generation: "5th gen", capacity: "30GB", color: "White" },
+    IpodModel { model_number: "A146", family: "iPod video", generation: "5th gen", capacity: "30GB", color: "Black" },
+    IpodModel { model_number: "A003", family: "iPod video", generation: "5th gen", capacity: "60GB", color: "White" },
+    IpodModel { model_number: "A147", family: "iPod video", generation: "5th gen", capacity: "60GB", color: "Black" },
+    IpodModel { model_number: "A444", family: "iPod video", generation: "5.5th gen", capacity: "30GB", color: "White" },
+    IpodModel { model_number: "A446", family: "iPod video", generation: "5.5th gen", capacity: "30GB", color: "Black" },
+    IpodModel { model_number: "A448", family: "iPod video", generation: "5.5th gen", capacity: "80GB", color: "White" },
+    IpodModel { model_number: "A450", family: "iPod video", generation: "5.5th gen", capacity: "80GB", color: "Black" },
+    IpodModel { model_number: "B029", family: "iPod classic", generation: "6th gen", capacity: "80GB", color: "Silver" },
+    IpodModel { model_number: "B147", family: "iPod classic", generation: "6th gen", capacity: "80GB", color: "Black" },
+    IpodModel { model_number: "B145", family: "iPod classic", generation: "6th gen", capacity: "160GB", color: "Silver" },
+    IpodModel { model_number: "B150", family: "iPod classic", generation: "6th gen", capacity: "160GB", color: "Black" },
+    IpodModel { model_number: "B562", family: "iPod classic", generation: "6.5th gen", capacity: "120GB", color: "Silver" },
+    IpodModel { model_number: "B565", family: "iPod classic", generation: "6.5th gen", capacity: "120GB", color: "Black" },
+    IpodModel { model_number: "C293", family: "iPod classic", generation: "7th gen", capacity: "160GB", color: "Silver" },
+    IpodModel { model_number: "C297", family: "iPod classic", generation: "7th gen", capacity: "160GB", color: "Black" },
+];
+
+/// The last 3 characters of a serial number say which model it is, and map to its model number,
+/// as in libgpod's serial_to_model_mapping. Not every model has a known suffix
+const SERIAL_NUMBER_SUFFIXES: [(&str, &str); 177] = [
+    ("LG6", "8541"), ("NAM", "8541"), ("MJ2", "8541"), ("ML1", "8709"), ("MME", "8709"), ("MMB", "8737"),
+    ("MMC", "8738"), ("NGE", "8740"), ("NGH", "8740"), ("MMF", "8741"), ("NRH", "8976"), ("NLW", "8946"),
+    ("QQF", "9460"), ("PQ5", "9244"), ("PNT", "9244"), ("NLY", "8948"), ("NM7", "8948"), ("PNU", "9245"),
+    ("PS9", "9282"), ("Q8U", "9282"), ("V9V", "9787"), ("S2X", "9787"), ("PQ7", "9268"), ("TDU", "A079"),
+    ("TDS", "A079"), ("TM2", "A127"), ("SAY", "9829"), ("R5Q", "9585"), ("SAZ", "9830"), ("SB1", "9830"),
+    ("PFW", "9160"), ("PRC", "9160"), ("QKL", "9436"), ("QKQ", "9436"), ("QKK", "9435"), ("QKP", "9435"),
+    ("QKJ", "9434"), ("QKN", "9434"), ("QKM", "9437"), ("QKR", "9437"), ("S41", "9800"), ("S4C", "9800"),
+    ("S43", "9802"), ("S45", "9804"), ("S47", "9806"), ("S4J", "9806"), ("S42", "9801"), ("S44", "9803"),
+    ("S48", "9807"), ("RS9", "9724"), ("QGV", "9724"), ("TSX", "9724"), ("PFV", "9724"), ("R80", "9724"),
+    ("RSA", "9725"), ("TSY", "9725"), ("C60", "9725"), ("UNA", "A350"), ("UNB", "A352"), ("SZB", "A004"),
+    ("SZV", "A004"), ("SZW", "A004"), ("TJT", "A099"), ("TJU", "A099"), ("SZC", "A005"), ("SZT", "A005"),
+    ("TK2", "A107"), ("TK3", "A107"), ("SZ9", "A002"), ("TXK", "A146"), ("TXM", "A146"), ("SZA", "A003"),
+    ("SZU", "A003"), ("TXL", "A147"), ("TXN", "A147"), ("V9K", "A444"), ("V9L", "A444"), ("V9M", "A446"),
+    ("V9N", "A446"), ("V9P", "A448"), ("V9Q", "A448"), ("V9R", "A450"), ("V9S", "A450"), ("Y5N", "B029"),
+    ("YMV", "B147"), ("YMU", "B145"), ("YMX", "B150"), ("9ZS", "C293"), ("VQ5", "A477"), ("VQ6", "A477"),
+    ("V8T", "A426"), ("V8U", "A426"), ("V8W", "A428"), ("V8X", "A428"), ("VQH", "A487"), ("VQJ", "A487"),
+    ("VQK", "A489"), ("VKL", "A489"), ("WL2", "A725"), ("WL3", "A725"), ("X9A", "A726"), ("X9B", "A726"),
+    ("VQT", "A497"), ("VQU", "A497"), ("Y0P", "A978"), ("Y0R", "A980"), ("YXR", "B249"), ("YXT", "B253"),
+    ("YXV", "B257"), ("YXX", "B261"), ("1P1", "B480"), ("37H", "B654"), ("37K", "B657"), ("37L", "B660"),
+    ("37P", "B663"), ("37Q", "B666"), ("2ME", "B598"), ("3QS", "B732"), ("3QT", "B735"), ("3QU", "B739"),
+    ("3QW", "B742"), ("3QX", "B745"), ("3QY", "B748"), ("3QZ", "B751"), ("3R0", "B754"), ("5B7", "B903"),
+    ("5B8", "B905"), ("5B9", "B907"), ("5BA", "B909"), ("5BB", "B911"), ("5BC", "B913"), ("5BD", "B915"),
+    ("5BE", "B917"), ("5BF", "B918"), ("71V", "C027"), ("71Y", "C031"), ("721", "C034"), ("726", "C037"),
+    ("72A", "C040"), ("72F", "C046"), ("72K", "C049"), ("72L", "C050"), ("72Q", "C060"), ("72R", "C062"),
+    ("72S", "C064"), ("72X", "C066"), ("734", "C068"), ("738", "C070"), ("739", "C072"), ("73A", "C074"),
+    ("73B", "C075"), ("XQ5", "A947"), ("XQS", "A947"), ("XQV", "A949"), ("XQX", "A949"), ("XQY", "A951"),
+    ("XR1", "A953"), ("8CQ", "C167"), ("4NZ", "B867"), ("891", "C164"), ("A1S", "C306"), ("A78", "C323"),
+    ("ALB", "C381"), ("ALD", "C384"), ("ALG", "C387"), ("A1L", "C303"), ("A1U", "C307"), ("A7B", "C328"),
+    ("A7D", "C331"), ("CMJ", "C584"), ("CMK", "C585"), ("FDM", "C749"), ("FDN", "C750"), ("FDP", "C751"),
+    ("2C5", "B562"), ("2C7", "B565"), ("9ZU", "C297"),
+];
+
+/// SysInfo stores e.g. "xA446" or "MA446". Returns "A446"
+pub fn get_model_number(model_number_raw: &str) -> String {
+    let model_number_raw = model_number_raw.trim();
+
+    if model_number_raw.len() == 5 && model_number_raw.is_ascii() {
+        return model_number_raw[1..].to_uppercase();
+    }
+
+    return model_number_raw.to_uppercase();
+}
+
+/// Returns `None` for model numbers that aren't in the table
+pub fn decode_model_number(model_number: &str) -> Option<&'static IpodModel> {
+    return IPOD_MODELS
+        .iter()
+        .find(|ipod_model| ipod_model.model_number == model_number);
+}
+
+/// Returns `None` for serial numbers whose model isn't in the table
+pub fn decode_serial_number(serial_number: &str) -> Option<&'static IpodModel> {
+    let serial_number = serial_number.trim().to_uppercase();
+
+    if serial_number.len() < 3 || !serial_number.is_ascii() {
+        return None;
+    }
+
+    let serial_number_suffix = &serial_number[serial_number.len() - 3..];
+
+    let (_, model_number) = SERIAL_NUMBER_SUFFIXES
+        .iter()
+        .find(|(model_suffix, _)| *model_suffix == serial_number_suffix)?;
+
+    return decode_model_number(model_number);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serial_numbers_are_decoded_by_their_last_3_characters() {
+        let ipod_model = decode_serial_number("8K0251T0V9M").unwrap();
+        assert_eq!(ipod_model.model_number, "A446");
+        assert_eq!(ipod_model.get_description(), "iPod video (5.5th gen) 30GB Black");
+
+        assert_eq!(decode_serial_number(" 8k02519zu\n").unwrap().model_number, "C297");
+        assert_eq!(decode_serial_number("YM8282C72R").unwrap().get_description(), "iPod nano (5th gen) 16GB Black");
+        assert_eq!(decode_serial_number("CC7012FDP").unwrap().get_description(), "iPod shuffle (4th gen) 2GB Blue");
+        assert_eq!(decode_serial_number("2C7").unwrap().get_description(), "iPod classic (6.5th gen) 120GB Black");
+
+        assert!(decode_serial_number("8K0251T0ZZZ").is_none());
+        assert!(decode_serial_number("9Z").is_none());
+        assert!(decode_serial_number("").is_none());
+    }
+
+    #[test]
+    fn model_numbers_lose_their_first_letter() {
+        assert_eq!(get_model_number("xA446"), "A446");
+        assert_eq!(get_model_number("MA446"), "A446");
+        assert_eq!(get_model_number(" pc297 "), "C297");
+        assert_eq!(get_model_number("A446"), "A446");
+
+        assert_eq!(decode_model_number(&get_model_number("xA446")).unwrap().family, "iPod video");
+        assert!(decode_model_number("Z999").is_none());
+    }
+
+    #[test]
+    fn every_serial_number_suffix_maps_to_a_model() {
+        for (serial_number_suffix, model_number) in SERIAL_NUMBER_SUFFIXES.iter() {
+            assert!(decode_model_number(model_number).is_some(), "{} -> {}", serial_number_suffix, model_number);
+        }
+    }
+
+    #[test]
+    fn sysinfo_model_number_wins_over_the_serial_number() {
+        let mut ipod_device_info = IpodDeviceInfo::default();
+
+        let model_mismatch_warning = ipod_device_info.set_sysinfo(SysInfo {
+            model_number: "A446".to_string(),
+            serial_number: "8K02519ZU".to_string(),
+            ..Default::default()
+        });
+
+        assert_eq!(ipod_device_info.model.as_ref().unwrap().model_number, "A446");
+        assert_eq!(ipod_device_info.model_source, "Model number");
+        assert_eq!(
+            model_mismatch_warning.unwrap(),
+            "Serial number 8K02519ZU belongs to an iPod classic (7th gen) 160GB Black (C297), but the iPod says it's an iPod video (5.5th gen) 30GB Black (A446)"
+        );
+
+        let mut ipod_device_info = IpodDeviceInfo::default();
+
+        let model_mismatch_warning = ipod_device_info.set_sysinfo(SysInfo {
+            model_number: "C297".to_string(),
+            serial_number: "8K02519ZU".to_string(),
+            ..Default::default()
+        });
+
+        assert!(model_mismatch_warning.is_none());
+        assert_eq!(ipod_device_info.model.as_ref().unwrap().color, "Black");
+
+        // Without a known model number, the serial number is used
+        let mut ipod_device_info = IpodDeviceInfo::default();
+
+        let model_mismatch_warning = ipod_device_info.set_sysinfo(SysInfo {
+            serial_number: "8K02519ZU".to_string(),
+            ..Default::default()
+        });
+
+        assert!(model_mismatch_warning.is_none());
+        assert_eq!(ipod_device_info.model.as_ref().unwrap().model_number, "C297");
+        assert_eq!(ipod_device_info.model_source, "Serial number");
+    }
+}
@@ -0,0 +1,377 @@
+/**
+ * File: ithmb.rs
+ *
+ * Thumbnails (and the photos as the iPod shows them) aren't stored as image files, but as raw bitmaps
+ * one after another in the .ithmb files, at the offset given by their Image Name (mhni).
+ * Most of them are RGB565, except the ones meant for TV out, which are UYVY. The correlation ID
+ * of the Image Name says which format (size, pixel format and row alignment) the bitmap is in.
+ *
+ */
+use crate::helpers::helpers;
+use crate::photo_database;
+
+/// Every format uses 2 bytes per pixel
+const BYTES_PER_PIXEL: usize = 2;
+
+pub enum PixelFormat {
+    /// 5 bits of red, 6 of green and 5 of blue in a little-endian u16
+    Rgb565Le,
+    /// Same, but big-endian (1st gen iPod nano)
+    Rgb565Be,
+    /// Two pixels in 4 bytes (U, Y0, V, Y1), with the even lines stored before the odd ones
+    UyvyInterlaced,
+}
+
+/// How a given correlation ID is stored. The correlation ID is also in the .ithmb filename, e.g. "F1028_1.ithmb"
+pub struct ThumbnailFormat {
+    pub correlation_id: u32,
+    pub pixel_format: PixelFormat,
+    /// The stored bitmap, which can be bigger than the image itself (see the Image Name's padding)
+    pub bitmap_width: usize,
+    pub bitmap_height: usize,
+    /// Rows are padded up to a multiple of this many bytes
+    pub row_alignment_bytes: usize,
+    pub description: &'static str,
+}
+
+/// From the formats iTunes writes for each model. The iPod photo's full screen format (1013)
+/// is stored rotated and isn't in here
+const THUMBNAIL_FORMATS: [ThumbnailFormat; 20] = [
+    // iPod photo/color
+    ThumbnailFormat { correlation_id: 1009, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 42, bitmap_height: 30, row_alignment_bytes: 2, description: "iPod photo: photo list" },
+    ThumbnailFormat { correlation_id: 1015, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 130, bitmap_height: 88, row_alignment_bytes: 2, description: "iPod photo/video: photo preview" },
+    ThumbnailFormat { correlation_id: 1016, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 140, bitmap_height: 140, row_alignment_bytes: 2, description: "iPod photo: album art (large)" },
+    ThumbnailFormat { correlation_id: 1017, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 56, bitmap_height: 56, row_alignment_bytes: 2, description: "iPod photo: album art (small)" },
+    ThumbnailFormat { correlation_id: 1019, pixel_format: PixelFormat::UyvyInterlaced, bitmap_width: 720, bitmap_height: 480, row_alignment_bytes: 2, description: "iPod photo/video: TV out" },
+    // iPod nano (1st and 2nd gen)
+    ThumbnailFormat { correlation_id: 1023, pixel_format: PixelFormat::Rgb565Be, bitmap_width: 176, bitmap_height: 132, row_alignment_bytes: 2, description: "iPod nano: full screen photo" },
+    ThumbnailFormat { correlation_id: 1027, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 100, bitmap_height: 100, row_alignment_bytes: 2, description: "iPod nano: album art (large)" },
+    ThumbnailFormat { correlation_id: 1031, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 42, bitmap_height: 42, row_alignment_bytes: 2, description: "iPod nano: album art (small)" },
+    ThumbnailFormat { correlation_id: 1032, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 42, bitmap_height: 37, row_alignment_bytes: 2, description: "iPod nano: photo list" },
+    // iPod video
+    ThumbnailFormat { correlation_id: 1024, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 320, bitmap_height: 240, row_alignment_bytes: 2, description: "iPod video: full screen photo" },
+    ThumbnailFormat { correlation_id: 1028, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 100, bitmap_height: 100, row_alignment_bytes: 2, description: "iPod video: album art (small)" },
+    ThumbnailFormat { correlation_id: 1029, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 200, bitmap_height: 200, row_alignment_bytes: 2, description: "iPod video: album art (large)" },
+    ThumbnailFormat { correlation_id: 1036, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 50, bitmap_height: 41, row_alignment_bytes: 2, description: "iPod video: photo list" },
+    // iPod classic and nano (3rd gen)
+    ThumbnailFormat { correlation_id: 1055, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 128, bitmap_height: 128, row_alignment_bytes: 2, description: "iPod classic: album art (list)" },
+    ThumbnailFormat { correlation_id: 1060, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 320, bitmap_height: 320, row_alignment_bytes: 2, description: "iPod classic: album art (Cover Flow)" },
+    ThumbnailFormat { correlation_id: 1061, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 56, bitmap_height: 56, row_alignment_bytes: 2, description: "iPod classic: album art (small)" },
+    // Later models, whose rows are aligned to 16 bytes
+    ThumbnailFormat { correlation_id: 3004, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 55, bitmap_height: 55, row_alignment_bytes: 16, description: "Photo list (small)" },
+    ThumbnailFormat { correlation_id: 3008, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 640, bitmap_height: 480, row_alignment_bytes: 16, description: "Full screen photo" },
+    ThumbnailFormat { correlation_id: 3009, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 160, bitmap_height: 120, row_alignment_bytes: 16, description: "Photo preview" },
+    ThumbnailFormat { correlation_id: 3011, pixel_format: PixelFormat::Rgb565Le, bitmap_width: 79, bitmap_height: 79, row_alignment_bytes: 16, description: "Photo list (large)" },
+];
+
+pub fn get_thumbnail_format(correlation_id: u32) -> Option<&'static ThumbnailFormat> {
+    return THUMBNAIL_FORMATS
+        .iter()
+        .find(|thumbnail_format| thumbnail_format.correlation_id == correlation_id);
+}
+
+fn get_row_len(bitmap_width: usize, row_alignment_bytes: usize) -> usize {
+    return (bitmap_width * BYTES_PER_PIXEL).div_ceil(row_alignment_bytes) * row_alignment_bytes;
+}
+
+/// For correlation IDs that aren't in the table (or whose size doesn't match it), the bitmap is assumed
+/// to be as wide as the image plus its padding, with rows aligned to 2, 4, 8 or 16 bytes,
+/// whichever makes the size add up first. Returns the (width, height, row length)
+fn guess_bitmap_layout(thumbnail: &photo_database::Thumbnail) -> Option<(usize, usize, usize)> {
+    let bitmap_width = (thumbnail.width as i32 + thumbnail.horizontal_padding as i32).max(0) as usize;
+    let image_size_bytes = thumbnail.image_size_bytes as usize;
+
+    for row_alignment_bytes in [2, 4, 8, 16] {
+        let row_len = get_row_len(bitmap_width, row_alignment_bytes);
+
+        if (row_len > 0) && (image_size_bytes.is_multiple_of(row_len)) {
+            return Some((bitmap_width, image_size_bytes / row_len, row_len));
+        }
+    }
+
+    return None;
+}
+
+/// Returns the width, height and RGB pixels of the stored bitmap, padding included.
+/// Returns `None` if the thumbnail isn't in `ithmb_file_as_bytes`, or its size doesn't add up
+pub fn decode_thumbnail(
+    ithmb_file_as_bytes: &[u8],
+    thumbnail: &photo_database::Thumbnail,
+) -> Option<(u32, u32, Vec<u8>)> {
+    let bitmap_start = thumbnail.ithmb_offset as usize;
+    let bitmap_end = bitmap_start + thumbnail.image_size_bytes as usize;
+
+    if bitmap_end > ithmb_file_as_bytes.len() {
+        return None;
+    }
+
+    let thumbnail_format = get_thumbnail_format(thumbnail.correlation_id);
+
+    let (bitmap_width, bitmap_height, row_len) = match thumbnail_format {
+        Some(thumbnail_format)
+            if get_row_len(thumbnail_format.bitmap_width, thumbnail_format.row_alignment_bytes)
+                * thumbnail_format.bitmap_height
+                == thumbnail.image_size_bytes as usize =>
+        {
+            (
+                thumbnail_format.bitmap_width,
+                thumbnail_format.bitmap_height,
+                get_row_len(thumbnail_format.bitmap_width, thumbnail_format.row_alignment_bytes),
+            )
+        }
+        _ => guess_bitmap_layout(thumbnail)?,
+    };
+
+    let bitmap = &ithmb_file_as_bytes[bitmap_start..bitmap_end];
+
+    let rgb_pixels = match thumbnail_format.map(|thumbnail_format| &thumbnail_format.pixel_format) {
+        Some(PixelFormat::UyvyInterlaced) => {
+            decode_uyvy_interlaced(bitmap, bitmap_width, bitmap_height, row_len)
+        }
+        Some(PixelFormat::Rgb565Be) => decode_rgb565(bitmap, bitmap_width, row_len, false),
+        _ => decode_rgb565(bitmap, bitmap_width, row_len, true),
+    };
+
+    return Some((bitmap_width as u32, bitmap_height as u32, rgb_pixels));
+}
+
+/// Any bytes past `bitmap_width` pixels in a row are just there for alignment
+fn decode_rgb565(bitmap: &[u8], bitmap_width: usize, row_len: usize, is_little_endian: bool) -> Vec<u8> {
+    let mut rgb_pixels: Vec<u8> = Vec::with_capacity((bitmap.len() / BYTES_PER_PIXEL) * 3);
+
+    for row in bitmap.chunks_exact(row_len) {
+        for pixel in row[..bitmap_width * BYTES_PER_PIXEL].chunks_exact(BYTES_PER_PIXEL) {
+            let pixel_value = if is_little_endian {
+                helpers::build_le_u16_from_bytes(pixel)
+            } else {
+                helpers::build_be_u32_from_bytes(pixel) as u16
+            };
+
+            let red = ((pixel_value >> 11) & 0x1F) as u8;
+            let green = ((pixel_value >> 5) & 0x3F) as u8;
+            let blue = (pixel_value & 0x1F) as u8;
+
+            // Repeat the top bits in the bottom ones, so white stays white
+            rgb_pixels.push((red << 3) | (red >> 2));
+            rgb_pixels.push((green << 2) | (green >> 4));
+            rgb_pixels.push((blue << 3) | (blue >> 2));
+        }
+    }
+
+    return rgb_pixels;
+}
+
+fn decode_uyvy_interlaced(
+    bitmap: &[u8],
+    bitmap_width: usize,
+    bitmap_height: usize,
+    row_len: usize,
+) -> Vec<u8> {
+    let mut rgb_pixels: Vec<u8> = vec![0; bitmap_width * bitmap_height * 3];
+    let num_even_rows = bitmap_height.div_ceil(2);
+
+    for stored_row_num in 0..bitmap_height {
+        // The first half of the bitmap holds lines 0, 2, 4..., the second half 1, 3, 5...
+        let row_num = if stored_row_num < num_even_rows {
+            stored_row_num * 2
+        } else {
+            ((stored_row_num - num_even_rows) * 2) + 1
+        };
+
+        let stored_row = &bitmap[stored_row_num * row_len..(stored_row_num * row_len) + (bitmap_width * BYTES_PER_PIXEL)];
+
+        for (pixel_pair_num, pixel_pair) in stored_row.chunks_exact(4).enumerate() {
+            let (u, y0, v, y1) = (pixel_pair[0], pixel_pair[1], pixel_pair[2], pixel_pair[3]);
+
+            for (pixel_in_pair, y) in [y0, y1].iter().enumerate() {
+                let rgb_idx = ((row_num * bitmap_width) + (pixel_pair_num * 2) + pixel_in_pair) * 3;
+
+                rgb_pixels[rgb_idx..rgb_idx + 3].copy_from_slice(&convert_yuv_to_rgb(*y, u, v));
+            }
+        }
+    }
+
+    return rgb_pixels;
+}
+
+/// ITU-R BT.601, which is what the iPod's TV out uses
+fn convert_yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let y = y as f32;
+    let u = (u as f32) - 128.0;
+    let v = (v as f32) - 128.0;
+
+    let red = y + (1.402 * v);
+    let green = y - (0.344136 * u) - (0.714136 * v);
+    let blue = y + (1.772 * u);
+
+    return [
+        red.round().clamp(0.0, 255.0) as u8,
+        green.round().clamp(0.0, 255.0) as u8,
+        blue.round().clamp(0.0, 255.0) as u8,
+    ];
+}
+
+/// The image format (PNG or JPEG) is picked from the file extension. Returns whether the file was written
+pub fn write_thumbnail_file(
+    thumbnail_file_path: &std::path::Path,
+    bitmap_width: u32,
+    bitmap_height: u32,
+    rgb_pixels: Vec<u8>,
+) -> bool {
+    let thumbnail_image = match image::RgbImage::from_raw(bitmap_width, bitmap_height, rgb_pixels) {
+        Some(thumbnail_image) => thumbnail_image,
+        None => {
+            eprintln!("Bitmap for '{}' is smaller than its dimensions", thumbnail_file_path.display());
+            return false;
+        }
+    };
+
+    if let Err(save_error) = thumbnail_image.save(thumbnail_file_path) {
+        eprintln!("Can't write thumbnail '{}': {}", thumbnail_file_path.display(), save_error);
+        return false;
+    }
+
+    return true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A made-up picture with a different color in every pixel
+    fn get_test_pixel(x: usize, y: usize) -> [u8; 3] {
+        return [(x * 4 % 256) as u8, (y * 4 % 256) as u8, ((x + y) % 256) as u8];
+    }
+
+    fn encode_rgb565(rgb_pixel: [u8; 3]) -> u16 {
+        return ((rgb_pixel[0] as u16 >> 3) << 11) | ((rgb_pixel[1] as u16 >> 2) << 5) | (rgb_pixel[2] as u16 >> 3);
+    }
+
+    /// What a pixel comes back as after the 5/6/5 bits
+    fn quantize_rgb565(rgb_pixel: [u8; 3]) -> [u8; 3] {
+        let (red, green, blue) = (rgb_pixel[0] >> 3, rgb_pixel[1] >> 2, rgb_pixel[2] >> 3);
+
+        return [(red << 3) | (red >> 2), (green << 2) | (green >> 4), (blue << 3) | (blue >> 2)];
+    }
+
+    /// The bitmap of a format from the table, as the iPod stores it, and the thumbnail pointing at it
+    fn build_rgb565_bitmap(correlation_id: u32, is_little_endian: bool) -> (Vec<u8>, photo_database::Thumbnail) {
+        let thumbnail_format = get_thumbnail_format(correlation_id).unwrap();
+        let row_len = get_row_len(thumbnail_format.bitmap_width, thumbnail_format.row_alignment_bytes);
+
+        let mut bitmap: Vec<u8> = vec![0; row_len * thumbnail_format.bitmap_height];
+
+        for y in 0..thumbnail_format.bitmap_height {
+            for x in 0..thumbnail_format.bitmap_width {
+                let pixel_value = encode_rgb565(get_test_pixel(x, y));
+                let pixel_idx = (y * row_len) + (x * BYTES_PER_PIXEL);
+
+                bitmap[pixel_idx..pixel_idx + BYTES_PER_PIXEL].copy_from_slice(&if is_little_endian {
+                    pixel_value.to_le_bytes()
+                } else {
+                    pixel_value.to_be_bytes()
+                });
+            }
+        }
+
+        let thumbnail = photo_database::Thumbnail {
+            correlation_id,
+            image_size_bytes: bitmap.len() as u32,
+            width: thumbnail_format.bitmap_width as u16,
+            height: thumbnail_format.bitmap_height as u16,
+            ..Default::default()
+        };
+
+        return (bitmap, thumbnail);
+    }
+
+    fn assert_rgb565_round_trip(correlation_id: u32, is_little_endian: bool) {
+        let (bitmap, thumbnail) = build_rgb565_bitmap(correlation_id, is_little_endian);
+
+        let (bitmap_width, bitmap_height, rgb_pixels) = decode_thumbnail(&bitmap, &thumbnail).unwrap();
+
+        assert_eq!((bitmap_width, bitmap_height), (thumbnail.width as u32, thumbnail.height as u32));
+
+        for y in 0..bitmap_height as usize {
+            for x in 0..bitmap_width as usize {
+                let rgb_idx = ((y * bitmap_width as usize) + x) * 3;
+
+                assert_eq!(rgb_pixels[rgb_idx..rgb_idx + 3], quantize_rgb565(get_test_pixel(x, y)), "pixel ({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn rgb565_little_endian_round_trips() {
+        // iPod video album art, rows without padding
+        assert_rgb565_round_trip(1028, true);
+        // Rows padded from 110 to 112 bytes
+        assert_rgb565_round_trip(3004, true);
+    }
+
+    #[test]
+    fn rgb565_big_endian_round_trips() {
+        // 1st gen iPod nano full screen photo
+        assert_rgb565_round_trip(1023, false);
+    }
+
+    #[test]
+    fn uyvy_interlaced_round_trips() {
+        let thumbnail_format = get_thumbnail_format(1019).unwrap();
+        let (bitmap_width, bitmap_height) = (thumbnail_format.bitmap_width, thumbnail_format.bitmap_height);
+        let row_len = get_row_len(bitmap_width, thumbnail_format.row_alignment_bytes);
+        let num_even_rows = bitmap_height.div_ceil(2);
+
+        let mut bitmap: Vec<u8> = vec![0; row_len * bitmap_height];
+
+        // Grey lines as light as their line number, so lines in the wrong place stand out,
+        // except for the first two pixels, which are red
+        for row_num in 0..bitmap_height {
+            let stored_row_num = if row_num % 2 == 0 { row_num / 2 } else { num_even_rows + (row_num / 2) };
+            let stored_row = &mut bitmap[stored_row_num * row_len..(stored_row_num + 1) * row_len];
+
+            for pixel_pair in stored_row[..bitmap_width * BYTES_PER_PIXEL].chunks_exact_mut(4) {
+                pixel_pair.copy_from_slice(&[128, row_num as u8, 128, row_num as u8]);
+            }
+        }
+
+        bitmap[0..4].copy_from_slice(&[85, 76, 255, 76]);
+
+        let thumbnail = photo_database::Thumbnail {
+            correlation_id: 1019,
+            image_size_bytes: bitmap.len() as u32,
+            width: bitmap_width as u16,
+            height: bitmap_height as u16,
+            ..Default::default()
+        };
+
+        let (_, _, rgb_pixels) = decode_thumbnail(&bitmap, &thumbnail).unwrap();
+
+        assert_eq!(rgb_pixels[0..6], [254, 0, 0, 254, 0, 0]);
+
+        for row_num in 0..bitmap_height {
+            let rgb_idx = ((row_num * bitmap_width) + 2) * 3;
+
+            assert_eq!(rgb_pixels[rgb_idx..rgb_idx + 3], [row_num as u8; 3], "line {}", row_num);
+        }
+    }
+
+    #[test]
+    fn decoded_thumbnails_are_written_as_png() {
+        let (bitmap, thumbnail) = build_rgb565_bitmap(1031, true);
+        let (bitmap_width, bitmap_height, rgb_pixels) = decode_thumbnail(&bitmap, &thumbnail).unwrap();
+
+        let thumbnail_file_path = std::env::temp_dir().join(format!("ithmb_test_{}.png", std::process::id()));
+
+        assert!(write_thumbnail_file(&thumbnail_file_path, bitmap_width, bitmap_height, rgb_pixels.clone()));
+
+        let written_image = image::open(&thumbnail_file_path).unwrap().to_rgb8();
+        std::fs::remove_file(&thumbnail_file_path).unwrap();
+
+        assert_eq!(written_image.dimensions(), (bitmap_width, bitmap_height));
+        assert_eq!(written_image.into_raw(), rgb_pixels);
+
+        // A bitmap that's too short for its dimensions isn't written
+        assert!(!write_thumbnail_file(&thumbnail_file_path, bitmap_width, bitmap_height + 1, bitmap));
+    }
+}
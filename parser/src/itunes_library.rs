@@ -0,0 +1,479 @@
+/**
+ * File: itunes_library.rs
+ *
+ * iTunes on the computer exports its library as "iTunes Music Library.xml". Comparing it with the
+ * iPod's own database shows what the last sync left out, and which stats haven't made it back yet.
+ * The iPod's database can also be written out in the same format, so that its tracks and playlists
+ * can be imported into iTunes, Music or anything else that reads that file.
+ *
+ */
+use serde::Serialize;
+
+use std::collections::HashMap;
+
+use crate::constants::itunes_library_constants;
+use crate::helpers::helpers;
+use crate::helpers::plist_helpers::PlistValue;
+use crate::itunesdb;
+use crate::smart_playlist;
+
+pub struct LibraryTrack {
+    pub track_id: u32,
+    /// Same as the dbid in the iTunesDB, 0 if the track doesn't have one
+    pub persistent_id: u64,
+    pub name: String,
+    pub artist: String,
+    pub album: String,
+    pub play_count: u32,
+    /// 0 to 100. Ratings iTunes computed from the album rating are left out (0)
+    pub rating_raw: u8,
+    /// Podcasts and videos are kept out of the comparison, the iPod has them outside of its songs
+    pub is_music: bool,
+}
+
+#[derive(Serialize)]
+pub struct LibraryDifference {
+    /// The dbid on the iPod, or the persistent ID in the library for tracks that are only there
+    pub dbid: u64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// "Only on device", "Only in library", "Play count" or "Rating"
+    pub difference: String,
+    pub device_value: String,
+    pub library_value: String,
+}
+
+/// Used when the persistent IDs don't match, e.g. for an iPod last synced with another computer
+fn get_track_match_key(title: &str, artist: &str, album: &str) -> (String, String, String) {
+    return (title.trim().to_lowercase(), artist.trim().to_lowercase(), album.trim().to_lowercase());
+}
+
+/// Matches the songs with the library tracks by persistent ID first, and then by title, artist and album.
+/// The songs' play counts and ratings should already include the Play Counts file, if there was one
+pub fn compare_songs_with_library(
+    songs: &[itunesdb::Song],
+    library_tracks: &[LibraryTrack],
+) -> Vec<LibraryDifference> {
+    let mut library_differences: Vec<LibraryDifference> = Vec::new();
+
+    let mut is_library_track_matched: Vec<bool> = vec![false; library_tracks.len()];
+
+    let library_track_idx_by_persistent_id: HashMap<u64, usize> = library_tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, library_track)| library_track.persistent_id != 0)
+        .map(|(library_track_idx, library_track)| (library_track.persistent_id, library_track_idx))
+        .collect();
+
+    let mut library_track_idxs_by_match_key: HashMap<(String, String, String), Vec<usize>> = HashMap::new();
+
+    for (library_track_idx, library_track) in library_tracks.iter().enumerate() {
+        library_track_idxs_by_match_key
+            .entry(get_track_match_key(&library_track.name, &library_track.artist, &library_track.album))
+            .or_default()
+            .push(library_track_idx);
+    }
+
+    let mut unmatched_songs: Vec<&itunesdb::Song> = Vec::new();
+    let mut matched_pairs: Vec<(&itunesdb::Song, &LibraryTrack)> = Vec::new();
+
+    for song in songs.iter() {
+        match library_track_idx_by_persistent_id.get(&song.dbid) {
+            Some(library_track_idx) => {
+                is_library_track_matched[*library_track_idx] = true;
+                matched_pairs.push((song, &library_tracks[*library_track_idx]));
+            }
+            None => unmatched_songs.push(song),
+        }
+    }
+
+    for song in unmatched_songs.iter() {
+        let song_match_key = get_track_match_key(&song.song_title, &song.song_artist, &song.song_album);
+
+        let library_track_idx = library_track_idxs_by_match_key
+            .get(&song_match_key)
+            .and_then(|library_track_idxs| {
+                library_track_idxs
+                    .iter()
+                    .find(|library_track_idx| !is_library_track_matched[**library_track_idx])
+                    .copied()
+            });
+
+        match library_track_idx {
+            Some(library_track_idx) => {
+                is_library_track_matched[library_track_idx] = true;
+                matched_pairs.push((song, &library_tracks[library_track_idx]));
+            }
+            None => {
+                library_differences.push(LibraryDifference {
+                    dbid: song.dbid,
+                    title: song.song_title.to_string(),
+                    artist: song.song_artist.to_string(),
+                    album: song.song_album.to_string(),
+                    difference: "Only on device".to_string(),
+                    device_value: "".to_string(),
+                    library_value: "".to_string(),
+                });
+            }
+        }
+    }
+
+    for (song, library_track) in matched_pairs.iter() {
+        if song.num_plays != library_track.play_count {
+            library_differences.push(LibraryDifference {
+                dbid: song.dbid,
+                title: song.song_title.to_string(),
+                artist: song.song_artist.to_string(),
+                album: song.song_album.to_string(),
+                difference: "Play count".to_string(),
+                device_value: song.num_plays.to_string(),
+                library_value: library_track.play_count.to_string(),
+            });
+        }
+
+        if song.song_rating_raw != library_track.rating_raw {
+            library_differences.push(LibraryDifference {
+                dbid: song.dbid,
+                title: song.song_title.to_string(),
+                artist: song.song_artist.to_string(),
+                album: song.song_album.to_string(),
+                difference: "Rating".to_string(),
+                device_value: song.song_rating_raw.to_string(),
+                library_value: library_track.rating_raw.to_string(),
+            });
+        }
+    }
+
+    for (library_track_idx, library_track) in library_tracks.iter().enumerate() {
+        if is_library_track_matched[library_track_idx] || !library_track.is_music {
+            continue;
+        }
+
+        library_differences.push(LibraryDifference {
+            dbid: library_track.persistent_id,
+            title: library_track.name.to_string(),
+            artist: library_track.artist.to_string(),
+            album: library_track.album.to_string(),
+            difference: "Only in library".to_string(),
+            device_value: "".to_string(),
+            library_value: format!("Track ID {}", library_track.track_id),
+        });
+    }
+
+    return library_differences;
+}
+
+/// A track of any media type, with what only the concrete types know
+struct ExportedTrack<'a> {
+    dbid: u64,
+    media_kind: &'a str,
+    track: &'a dyn smart_playlist::SmartPlaylistTrack,
+    /// Songs have more fields than the smart playlist rules can ask for
+    song: Option<&'a itunesdb::Song>,
+}
+
+/// iTunes writes the persistent IDs as 16 uppercase hex digits
+fn get_persistent_id_hex(persistent_id: u64) -> String {
+    return format!("{:016X}", persistent_id);
+}
+
+/// As a file:// URL, percent-encoded like iTunes does (e.g. "file:///Volumes/iPod/iPod_Control/Music/F12/ABCD.mp3").
+/// Windows paths get a leading slash, e.g. "file:///E:/iPod_Control/..."
+fn get_file_url(file_path: &std::path::Path) -> String {
+    let file_path = file_path.display().to_string().replace('\\', "/");
+
+    let mut file_url = String::from("file://");
+
+    if !file_path.starts_with('/') {
+        file_url.push('/');
+    }
+
+    for file_path_byte in file_path.bytes() {
+        if file_path_byte.is_ascii_alphanumeric() || b"/-_.~:".contains(&file_path_byte) {
+            file_url.push(file_path_byte as char);
+        } else {
+            file_url.push_str(&format!("%{:02X}", file_path_byte));
+        }
+    }
+
+    return file_url;
+}
+
+/// Tracks' flags by media kind, so iTunes puts them in the right part of its library
+fn get_media_kind_keys(media_kind: &str) -> Vec<&'static str> {
+    if media_kind == "Podcast" {
+        return vec![itunes_library_constants::ITUNES_LIBRARY_PODCAST_KEY];
+    } else if media_kind == "Video Podcast" {
+        return vec![
+            itunes_library_constants::ITUNES_LIBRARY_PODCAST_KEY,
+            itunes_library_constants::ITUNES_LIBRARY_HAS_VIDEO_KEY,
+        ];
+    } else if media_kind == "Video" {
+        return vec![
+            itunes_library_constants::ITUNES_LIBRARY_MOVIE_KEY,
+            itunes_library_constants::ITUNES_LIBRARY_HAS_VIDEO_KEY,
+        ];
+    } else if media_kind == "Music Video" {
+        return vec![
+            itunes_library_constants::ITUNES_LIBRARY_MUSIC_VIDEO_KEY,
+            itunes_library_constants::ITUNES_LIBRARY_HAS_VIDEO_KEY,
+        ];
+    } else if media_kind.starts_with("TV") {
+        return vec![
+            itunes_library_constants::ITUNES_LIBRARY_TV_SHOW_KEY,
+            itunes_library_constants::ITUNES_LIBRARY_HAS_VIDEO_KEY,
+        ];
+    }
+
+    return Vec::new();
+}
+
+fn get_track_dict(exported_track: &ExportedTrack, track_location: Option<String>) -> PlistValue {
+    let mut track_dict: Vec<(String, PlistValue)> = Vec::new();
+
+    // iTunes leaves keys out rather than writing empty strings or zeros
+    let push_string = |track_dict: &mut Vec<(String, PlistValue)>, key: &str, value: &str| {
+        if !value.is_empty() {
+            track_dict.push((key.to_string(), PlistValue::String(value.to_string())));
+        }
+    };
+
+    let push_integer = |track_dict: &mut Vec<(String, PlistValue)>, key: &str, value: u64| {
+        if value != 0 {
+            track_dict.push((key.to_string(), PlistValue::Integer(value as i64)));
+        }
+    };
+
+    let get_string = |rule_field: smart_playlist::SmartPlaylistRuleField| -> String {
+        return exported_track.track.get_string_field(rule_field as u32).unwrap_or_default();
+    };
+
+    let get_numeric = |rule_field: smart_playlist::SmartPlaylistRuleField| -> u64 {
+        return exported_track.track.get_numeric_field(rule_field as u32).unwrap_or(0);
+    };
+
+    track_dict.push((
+        itunes_library_constants::ITUNES_LIBRARY_TRACK_ID_KEY.to_string(),
+        PlistValue::Integer(exported_track.track.get_track_id() as i64),
+    ));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_NAME_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::SongName));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_ARTIST_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Artist));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_ALBUM_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Album));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_GENRE_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Genre));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_COMPOSER_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Composer));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_GROUPING_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Grouping));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_COMMENTS_KEY, &get_string(smart_playlist::SmartPlaylistRuleField::Comment));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SIZE_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Size));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_TOTAL_TIME_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Time));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_TRACK_NUMBER_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::TrackNumber));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_DISC_NUMBER_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::DiscNumber));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_YEAR_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Year));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_BPM_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Bpm));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_BIT_RATE_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Bitrate));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SAMPLE_RATE_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::SampleRate));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_PLAY_COUNT_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::PlayCount));
+    push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_RATING_KEY, get_numeric(smart_playlist::SmartPlaylistRuleField::Rating));
+
+    let date_added_epoch = get_numeric(smart_playlist::SmartPlaylistRuleField::DateAdded);
+
+    if date_added_epoch != 0 {
+        track_dict.push((
+            itunes_library_constants::ITUNES_LIBRARY_DATE_ADDED_KEY.to_string(),
+            PlistValue::Date(helpers::get_timestamp_as_mac(date_added_epoch)),
+        ));
+    }
+
+    if let Some(song) = exported_track.song {
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_ALBUM_ARTIST_KEY, &song.song_album_artist);
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SORT_NAME_KEY, &song.song_sort_title);
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SORT_ARTIST_KEY, &song.song_sort_artist);
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SORT_ALBUM_KEY, &song.song_sort_album);
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SORT_ALBUM_ARTIST_KEY, &song.song_sort_album_artist);
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SORT_COMPOSER_KEY, &song.song_sort_composer);
+        push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_TRACK_COUNT_KEY, song.num_tracks_in_album as u64);
+        push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_DISC_COUNT_KEY, song.num_discs as u64);
+        push_integer(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_SKIP_COUNT_KEY, song.num_skips as u64);
+
+        if song.song_modified_epoch != 0 {
+            track_dict.push((
+                itunes_library_constants::ITUNES_LIBRARY_DATE_MODIFIED_KEY.to_string(),
+                PlistValue::Date(helpers::get_timestamp_as_mac(song.song_modified_epoch)),
+            ));
+        }
+
+        if let Some(last_played_at) = song.last_played_at {
+            track_dict.push((itunes_library_constants::ITUNES_LIBRARY_PLAY_DATE_UTC_KEY.to_string(), PlistValue::Date(last_played_at)));
+        }
+
+        if let Some(last_skipped_at) = song.last_skipped_at {
+            track_dict.push((itunes_library_constants::ITUNES_LIBRARY_SKIP_DATE_KEY.to_string(), PlistValue::Date(last_skipped_at)));
+        }
+
+        if song.is_compilation {
+            track_dict.push((itunes_library_constants::ITUNES_LIBRARY_COMPILATION_KEY.to_string(), PlistValue::Boolean(true)));
+        }
+    }
+
+    for media_kind_key in get_media_kind_keys(exported_track.media_kind) {
+        track_dict.push((media_kind_key.to_string(), PlistValue::Boolean(true)));
+    }
+
+    track_dict.push((
+        itunes_library_constants::ITUNES_LIBRARY_PERSISTENT_ID_KEY.to_string(),
+        PlistValue::String(get_persistent_id_hex(exported_track.dbid)),
+    ));
+    push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_TRACK_TYPE_KEY, "File");
+
+    if let Some(track_location) = track_location {
+        push_string(&mut track_dict, itunes_library_constants::ITUNES_LIBRARY_LOCATION_KEY, &track_location);
+    }
+
+    return PlistValue::Dict(track_dict);
+}
+
+/// Builds an iTunes Library plist of the tracks of every media type and the playlists (with their
+/// current contents, so smart playlists as evaluated). The track and playlist persistent IDs are the
+/// iPod's DBIDs and persistent playlist IDs, which iTunes would recognise as its own.
+/// `track_filenames` are relative to the iPod's root, the locations are only written if the mount point was given
+pub fn build_itunes_library_plist(
+    library_tracks: &itunesdb::LibraryTracks,
+    playlists: &[itunesdb::Playlist],
+    track_filenames: &HashMap<u32, String>,
+    ipod_mount_point: Option<&str>,
+) -> PlistValue {
+    let itunesdb::LibraryTracks {
+        songs,
+        podcasts,
+        videos,
+        audiobooks,
+        itunesu_items,
+        ringtones,
+        voice_memos,
+    } = *library_tracks;
+
+    let mut exported_tracks: Vec<ExportedTrack> = Vec::new();
+
+    for song in songs.iter() {
+        exported_tracks.push(ExportedTrack { dbid: song.dbid, media_kind: &song.media_kind, track: song, song: Some(song) });
+    }
+
+    for podcast in podcasts.iter() {
+        exported_tracks.push(ExportedTrack { dbid: podcast.dbid, media_kind: &podcast.media_kind, track: podcast, song: None });
+    }
+
+    for video in videos.iter() {
+        exported_tracks.push(ExportedTrack { dbid: video.track.dbid, media_kind: &video.track.media_kind, track: &video.track, song: None });
+    }
+
+    for audiobook in audiobooks.iter() {
+        exported_tracks.push(ExportedTrack { dbid: audiobook.track.dbid, media_kind: &audiobook.track.media_kind, track: &audiobook.track, song: None });
+    }
+
+    for itunesu_item in itunesu_items.iter() {
+        exported_tracks.push(ExportedTrack { dbid: itunesu_item.track.dbid, media_kind: &itunesu_item.track.media_kind, track: &itunesu_item.track, song: None });
+    }
+
+    for ringtone in ringtones.iter() {
+        exported_tracks.push(ExportedTrack { dbid: ringtone.track.dbid, media_kind: &ringtone.track.media_kind, track: &ringtone.track, song: None });
+    }
+
+    for voice_memo in voice_memos.iter() {
+        exported_tracks.push(ExportedTrack { dbid: voice_memo.track.dbid, media_kind: &voice_memo.track.media_kind, track: &voice_memo.track, song: None });
+    }
+
+    // iTunes wants absolute locations, so relative mount points (e.g. ".") are resolved first
+    let ipod_mount_path = ipod_mount_point.map(|ipod_mount_point| {
+        std::fs::canonicalize(ipod_mount_point).unwrap_or(std::path::PathBuf::from(ipod_mount_point))
+    });
+
+    let mut track_dicts: Vec<(String, PlistValue)> = Vec::new();
+
+    for exported_track in exported_tracks.iter() {
+        let track_id = exported_track.track.get_track_id();
+
+        let track_location = match (&ipod_mount_path, track_filenames.get(&track_id)) {
+            (Some(ipod_mount_path), Some(track_filename)) => Some(get_file_url(&ipod_mount_path.join(track_filename))),
+            _ => None,
+        };
+
+        track_dicts.push((track_id.to_string(), get_track_dict(exported_track, track_location)));
+    }
+
+    // iTunes numbers its playlists after its tracks, from the same counter
+    let first_playlist_id = exported_tracks
+        .iter()
+        .map(|exported_track| exported_track.track.get_track_id())
+        .max()
+        .unwrap_or(0) as i64
+        + 1;
+
+    let mut playlist_dicts: Vec<PlistValue> = Vec::new();
+
+    // The library playlist comes first, which the iPod's master playlist should already be
+    let mut sorted_playlists: Vec<&itunesdb::Playlist> = playlists.iter().collect();
+    sorted_playlists.sort_by_key(|playlist| !playlist.is_master_playlist);
+
+    for (playlist_id, playlist) in (first_playlist_id..).zip(sorted_playlists) {
+        let mut playlist_dict: Vec<(String, PlistValue)> = Vec::new();
+
+        playlist_dict.push((
+            itunes_library_constants::ITUNES_LIBRARY_NAME_KEY.to_string(),
+            PlistValue::String(playlist.playlist_name.to_string()),
+        ));
+
+        if playlist.is_master_playlist {
+            playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_MASTER_KEY.to_string(), PlistValue::Boolean(true)));
+            playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_VISIBLE_KEY.to_string(), PlistValue::Boolean(false)));
+        }
+
+        playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_PLAYLIST_ID_KEY.to_string(), PlistValue::Integer(playlist_id)));
+
+        playlist_dict.push((
+            itunes_library_constants::ITUNES_LIBRARY_PLAYLIST_PERSISTENT_ID_KEY.to_string(),
+            PlistValue::String(get_persistent_id_hex(playlist.persistent_playlist_id)),
+        ));
+
+        if playlist.is_podcast_playlist {
+            playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_PODCASTS_KEY.to_string(), PlistValue::Boolean(true)));
+        }
+
+        playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_ALL_ITEMS_KEY.to_string(), PlistValue::Boolean(true)));
+
+        // iTunes leaves the key out of empty playlists
+        if !playlist.track_ids.is_empty() {
+            let playlist_items = playlist
+                .track_ids
+                .iter()
+                .map(|track_id| {
+                    PlistValue::Dict(vec![(
+                        itunes_library_constants::ITUNES_LIBRARY_TRACK_ID_KEY.to_string(),
+                        PlistValue::Integer(*track_id as i64),
+                    )])
+                })
+                .collect();
+
+            playlist_dict.push((itunes_library_constants::ITUNES_LIBRARY_PLAYLIST_ITEMS_KEY.to_string(), PlistValue::Array(playlist_items)));
+        }
+
+        playlist_dicts.push(PlistValue::Dict(playlist_dict));
+    }
+
+    let mut itunes_library: Vec<(String, PlistValue)> = vec![
+        (itunes_library_constants::ITUNES_LIBRARY_MAJOR_VERSION_KEY.to_string(), PlistValue::Integer(1)),
+        (itunes_library_constants::ITUNES_LIBRARY_MINOR_VERSION_KEY.to_string(), PlistValue::Integer(1)),
+        (itunes_library_constants::ITUNES_LIBRARY_DATE_KEY.to_string(), PlistValue::Date(chrono::Utc::now())),
+    ];
+
+    if let Some(ipod_mount_path) = ipod_mount_path.as_ref() {
+        itunes_library.push((
+            itunes_library_constants::ITUNES_LIBRARY_MUSIC_FOLDER_KEY.to_string(),
+            PlistValue::String(get_file_url(&ipod_mount_path.join("iPod_Control").join("Music")) + "/"),
+        ));
+    }
+
+    itunes_library.push((itunes_library_constants::ITUNES_LIBRARY_TRACKS_KEY.to_string(), PlistValue::Dict(track_dicts)));
+    itunes_library.push((itunes_library_constants::ITUNES_LIBRARY_PLAYLISTS_KEY.to_string(), PlistValue::Array(playlist_dicts)));
+
+    return PlistValue::Dict(itunes_library);
+}
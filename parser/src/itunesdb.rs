@@ -10,18 +10,62 @@
 
  use crate::helpers::helpers;
  use crate::helpers::itunesdb_helpers;
+ use crate::ipod_device_info;
+ use crate::library_stats;
+ use crate::smart_playlist;
 
- use serde::Serialize;
+ use schemars::JsonSchema;
+ use serde::Deserialize;
+use serde::Serialize;
 
  
- #[derive(Serialize)]
+ /// A chapter of a podcast episode or audiobook
+ #[derive(Serialize, Deserialize, JsonSchema)]
+ pub struct Chapter {
+    pub start_ms: u32,
+    pub title: String,
+ }
+
+ #[derive(Serialize, Deserialize, JsonSchema)]
   pub struct Podcast {
     pub podcast_title : String,
     pub podcast_publisher : String,
     pub podcast_genre : String,
     pub podcast_file_type : String,
     pub podcast_subtitle : String,
-    pub podcast_description : String
+    pub podcast_description : String,
+    pub podcast_grouping : String,
+    pub podcast_keywords : String,
+    /// The show the episode is grouped under in the Podcast List dataset, if any
+    pub podcast_show : String,
+    /// 1-based position of the episode in its show, in the order the iPod lists them.
+    /// Podcasts don't use the TV season/episode numbers of the track item
+    pub episode_order : Option<u32>,
+    /// Where playback resumes from, aka the "bookmark time"
+    pub resume_position_ms : u32,
+    pub remember_playback_position : bool,
+    pub skip_when_shuffling : bool,
+    /// "explicit", "clean" or "none"
+    pub explicitness : String,
+    /// When the episode was published, if the feed said so
+    pub release_date : Option<chrono::DateTime<chrono::Utc>>,
+    pub duration_s : u32,
+    /// See `Song::file_size_bytes`
+    pub file_size_bytes : u64,
+    pub file_size_friendly : String,
+    pub file_size_is_estimated : bool,
+    pub is_played : bool,
+    /// iTunes still shows the blue "new" bullet next to the episode. Never set for a played episode
+    pub is_new : bool,
+    /// Where the episode itself was downloaded from
+    pub enclosure_url : String,
+    /// The podcast's feed, to resubscribe with
+    pub rss_url : String,
+    pub track_id : u32,
+    pub dbid : u64,
+    pub media_type_raw : u32,
+    pub media_kind : String,
+    pub chapters : Vec<Chapter>
  }
 
  impl Default for Podcast {
@@ -34,34 +78,509 @@
             podcast_genre: "".to_string(),
             podcast_file_type: "".to_string(),
             podcast_subtitle: "".to_string(),
-            podcast_description: "".to_string()
+            podcast_description: "".to_string(),
+            podcast_grouping: "".to_string(),
+            podcast_keywords: "".to_string(),
+            podcast_show: "".to_string(),
+            episode_order: None,
+            resume_position_ms: 0,
+            remember_playback_position: false,
+            skip_when_shuffling: false,
+            explicitness: "none".to_string(),
+            release_date: None,
+            duration_s: 0,
+            file_size_bytes: 0,
+            file_size_friendly: "".to_string(),
+            file_size_is_estimated: false,
+            is_played: false,
+            is_new: false,
+            enclosure_url: "".to_string(),
+            rss_url: "".to_string(),
+            track_id: 0,
+            dbid: 0,
+            media_type_raw: 0,
+            media_kind: "".to_string(),
+            chapters: Vec::new()
+        };
+    }
+ }
+
+ impl Podcast {
+
+    /// For episodes whose media type says "audio", but which the Podcast List dataset files under a show
+    pub fn from_song(song: Song) -> Podcast {
+        return Podcast {
+            podcast_title: song.song_title,
+            podcast_publisher: song.song_artist,
+            podcast_genre: song.song_genre,
+            podcast_description: song.song_description,
+            podcast_grouping: song.song_grouping,
+            podcast_keywords: song.song_keywords,
+            remember_playback_position: song.remember_playback_position,
+            skip_when_shuffling: song.skip_when_shuffling,
+            explicitness: song.explicitness,
+            release_date: song.song_released_ts,
+            duration_s: song.song_duration_s,
+            file_size_bytes: song.file_size_bytes,
+            file_size_friendly: song.file_size_friendly,
+            file_size_is_estimated: song.file_size_is_estimated,
+            is_played: song.num_plays > 0,
+            resume_position_ms: song.resume_position_ms,
+            track_id: song.track_id,
+            dbid: song.dbid,
+            media_type_raw: song.media_type_raw,
+            media_kind: song.media_kind,
+            ..Podcast::default()
+        };
+    }
+ }
+
+ /// Movies, music videos and TV shows. The mhit doesn't carry the frame dimensions or codec,
+ /// those are only filled in from the video file, if the iPod's mount point is known.
+ #[derive(Serialize)]
+ pub struct Video {
+    #[serde(flatten)]
+    pub track: TrackCore,
+    pub video_kind: String,
+    /// Only set for videos from a TV series
+    pub tv_show: String,
+    pub tv_episode_id: String,
+    pub tv_network: String,
+    pub tv_season_number: u32,
+    pub tv_episode_number: u32,
+    /// "MPEG-4" or "H.264", see `video_properties`
+    pub video_codec: String,
+    pub video_width: Option<u16>,
+    pub video_height: Option<u16>,
+ }
+
+ impl Default for Video {
+
+    fn default() -> Video {
+
+        return Video {
+            track: TrackCore::default(),
+            video_kind: "".to_string(),
+            tv_show: "".to_string(),
+            tv_episode_id: "".to_string(),
+            tv_network: "".to_string(),
+            tv_season_number: 0,
+            tv_episode_number: 0,
+            video_codec: "".to_string(),
+            video_width: None,
+            video_height: None,
         };
     }
  }
 
+ impl Video {
+
+    /// Episodes of a TV series, as opposed to movies and music videos
+    pub fn is_tv_show(&self) -> bool {
+        return self.track.media_kind.starts_with("TV");
+    }
+ }
+
+ /// Recordings made on the iPod itself (with a microphone accessory).
+ /// They're stored under "Recordings/", named after when the recording started
  #[derive(Serialize)]
+ pub struct VoiceMemo {
+    #[serde(flatten)]
+    pub track: TrackCore,
+    /// Taken from the filename, this is the device's local time
+    pub recorded_ts: Option<chrono::DateTime<chrono::Utc>>,
+ }
+
+ impl VoiceMemo {
+
+    /// Voice memos are parsed as songs, since they look the same until the file location shows up
+    pub fn from_song(song: Song) -> VoiceMemo {
+        return VoiceMemo {
+            recorded_ts: itunesdb_helpers::get_voice_memo_timestamp_from_path(&song.song_filename),
+            track: TrackCore {
+                title: song.song_title,
+                genre: song.song_genre,
+                file_extension: song.file_extension,
+                file_size_bytes: song.file_size_bytes,
+                file_size_friendly: song.file_size_friendly,
+                file_size_is_estimated: song.file_size_is_estimated,
+                duration_s: song.song_duration_s,
+                duration_friendly: song.song_duration_friendly,
+                num_plays: song.num_plays,
+                added_to_library_epoch: song.song_added_to_library_epoch,
+                added_to_library_ts: song.song_added_to_library_ts,
+                filename: song.song_filename,
+                track_id: song.track_id,
+                dbid: song.dbid,
+                media_type_raw: song.media_type_raw,
+                media_kind: "Voice Memo".to_string(),
+                ..TrackCore::default()
+            },
+        };
+    }
+ }
+
+ /// What every track item carries, whatever its media type. Everything but songs and
+ /// podcasts, whose exports have their own (versioned) columns, is built around it
+ #[derive(Serialize)]
+ pub struct TrackCore {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub genre: String,
+    pub grouping: String,
+    pub category: String,
+    pub description: String,
+    pub file_extension: String,
+    /// See `Song::file_size_bytes`
+    pub file_size_bytes: u64,
+    pub file_size_friendly: String,
+    pub file_size_is_estimated: bool,
+    pub duration_s: u32,
+    pub duration_friendly: String,
+    pub num_plays: u32,
+    /// The epoch is a Mac timestamp, 0 when not set
+    pub added_to_library_epoch: u64,
+    pub added_to_library_ts: Option<chrono::DateTime<chrono::Utc>>,
+    /// Where playback resumes from, aka the "bookmark time"
+    pub resume_position_ms: u32,
+    pub remember_playback_position: bool,
+    pub skip_when_shuffling: bool,
+    pub filename: String,
+    pub track_id: u32,
+    pub dbid: u64,
+    pub media_type_raw: u32,
+    pub media_kind: String,
+ }
+
+ impl Default for TrackCore {
+
+    fn default() -> TrackCore {
+
+        return TrackCore {
+            title: "".to_string(),
+            artist: "".to_string(),
+            album: "".to_string(),
+            genre: "".to_string(),
+            grouping: "".to_string(),
+            category: "".to_string(),
+            description: "".to_string(),
+            file_extension: "".to_string(),
+            file_size_bytes: 0,
+            file_size_friendly: "".to_string(),
+            file_size_is_estimated: false,
+            duration_s: 0,
+            duration_friendly: "".to_string(),
+            num_plays: 0,
+            added_to_library_epoch: 0,
+            added_to_library_ts: None,
+            resume_position_ms: 0,
+            remember_playback_position: false,
+            skip_when_shuffling: false,
+            filename: "".to_string(),
+            track_id: 0,
+            dbid: 0,
+            media_type_raw: 0,
+            media_kind: "".to_string(),
+        };
+    }
+ }
+
+ impl TrackCore {
+
+    pub fn set_duration(&mut self, duration_raw: u32) {
+        self.duration_s = super::itunesdb::decode_raw_track_length_to_s(duration_raw);
+
+        self.duration_friendly =
+            helpers::convert_seconds_to_human_readable_duration(self.duration_s);
+    }
+
+    pub fn set_filesize(&mut self, file_size_bytes: u64) {
+        self.file_size_bytes = file_size_bytes;
+        self.file_size_friendly =
+            helpers::convert_bytes_to_human_readable_size(file_size_bytes);
+    }
+
+    pub fn set_added_timestamp(&mut self, added_to_library_epoch: u64) {
+        self.added_to_library_epoch = added_to_library_epoch;
+        self.added_to_library_ts =
+            Some(helpers::get_timestamp_as_mac(added_to_library_epoch));
+    }
+
+    pub fn set_filename(&mut self, filename_raw: String) {
+        self.filename = itunesdb_helpers::get_canonical_path(filename_raw)
+    }
+ }
+
+ /// Reads the fields shared by every media type out of the track item starting at `track_item_idx`.
+ /// The media kind and file extension are passed in, since the parser may have remapped them.
+ /// Fields past the end of the track item's header are left at their defaults
+ pub fn read_track_core(
+    track_item_idx: usize,
+    file_as_bytes: &[u8],
+    track_item_header_len: u32,
+    media_kind: &str,
+    file_extension: &str,
+ ) -> TrackCore {
+    let mut track = TrackCore {
+        file_extension: file_extension.to_string(),
+        num_plays: helpers::get_slice_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_PLAY_COUNT_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_PLAY_COUNT_LEN,
+        ),
+        resume_position_ms: helpers::get_slice_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_LEN,
+        ),
+        remember_playback_position: itunesdb_helpers::get_header_byte_flag(
+            track_item_idx,
+            file_as_bytes,
+            track_item_header_len,
+            itunesdb_constants::TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET,
+        ),
+        skip_when_shuffling: itunesdb_helpers::get_header_byte_flag(
+            track_item_idx,
+            file_as_bytes,
+            track_item_header_len,
+            itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET,
+        ),
+        track_id: helpers::get_slice_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_UNIQUE_ID_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_UNIQUE_ID_LEN,
+        ),
+        dbid: helpers::get_slice_as_le_u64(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_DBID_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_DBID_LEN,
+        ),
+        media_type_raw: itunesdb_helpers::get_header_field_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            track_item_header_len,
+            itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_LEN,
+        )
+        .unwrap_or(itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_AUDIO),
+        media_kind: media_kind.to_string(),
+        ..TrackCore::default()
+    };
+
+    let (file_size_bytes, file_size_is_estimated) = read_track_file_size(track_item_idx, file_as_bytes);
+
+    track.set_filesize(file_size_bytes);
+    track.file_size_is_estimated = file_size_is_estimated;
+
+    track.set_duration(helpers::get_slice_as_le_u32(
+        track_item_idx,
+        file_as_bytes,
+        itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_OFFSET,
+        itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_LEN,
+    ));
+
+    let added_to_library_epoch = helpers::get_slice_as_le_u32(
+        track_item_idx,
+        file_as_bytes,
+        itunesdb_constants::TRACK_ITEM_TRACK_ADDED_TIMESTAMP_OFFSET,
+        itunesdb_constants::TRACK_ITEM_TRACK_ADDED_TIMESTAMP_LEN,
+    );
+
+    if added_to_library_epoch > 0 {
+        track.set_added_timestamp(added_to_library_epoch as u64);
+    }
+
+    return track;
+ }
+
+ #[derive(Serialize)]
+ pub struct Ringtone {
+    #[serde(flatten)]
+    pub track: TrackCore,
+ }
+
+ impl Default for Ringtone {
+
+    fn default() -> Ringtone {
+
+        return Ringtone {
+            track: TrackCore::default(),
+        };
+    }
+ }
+
+ /// iTunes U lectures. The course is stored as the album, and the
+ /// institution providing it as the artist
+ #[derive(Serialize)]
+ pub struct ITunesUItem {
+    #[serde(flatten)]
+    pub track: TrackCore,
+ }
+
+ impl Default for ITunesUItem {
+
+    fn default() -> ITunesUItem {
+
+        return ITunesUItem {
+            track: TrackCore::default(),
+        };
+    }
+ }
+
+ #[derive(Serialize)]
+ pub struct Audiobook {
+    /// The author is stored as the artist
+    #[serde(flatten)]
+    pub track: TrackCore,
+    /// DRM-protected, see `is_protected_file_extension`
+    pub is_protected: bool,
+    pub chapters: Vec<Chapter>,
+ }
+
+ impl Default for Audiobook {
+
+    fn default() -> Audiobook {
+
+        return Audiobook {
+            track: TrackCore::default(),
+            is_protected: false,
+            chapters: Vec::new(),
+        };
+    }
+ }
+
+ #[derive(Serialize, Deserialize, JsonSchema)]
  pub struct Song {
     pub file_extension: String,
     pub bitrate_kbps: u32,
     pub sample_rate_hz: u32,
-    pub file_size_bytes: u32, // iPod file systems use FAT
+    /// The track item only has room for 32 bits, but Mac-formatted (HFS+) iPods can hold files of 4GB or more.
+    /// Those sizes wrap around, and are unwrapped with a guess at the real size from the bitrate and duration
+    /// (see `get_unwrapped_file_size`), so they're only as good as that guess
+    pub file_size_bytes: u64,
     pub file_size_friendly: String,
+    /// The stored size wrapped around, `file_size_bytes` is an estimate
+    pub file_size_is_estimated: bool,
     pub song_duration_s: u32,
     pub song_duration_friendly: String,
     pub num_plays: u32,
     pub song_rating_raw: u8,
+    /// The "application rating", i.e. what iTunes had for the song before the last sync
+    pub song_previous_rating_raw: u8,
+    /// "iTunes", "iPod" or empty if the song was never rated
+    pub rating_source: String,
+    /// The epochs are Mac timestamps, 0 when not set
     pub song_added_to_library_epoch: u64,
-    pub song_added_to_library_ts: chrono::DateTime<chrono::Utc>,
+    pub song_added_to_library_ts: Option<chrono::DateTime<chrono::Utc>>,
+    pub song_modified_epoch: u64,
+    pub song_modified_ts: Option<chrono::DateTime<chrono::Utc>>,
+    /// When the song was published on the iTunes Store
+    pub song_released_epoch: u64,
+    pub song_released_ts: Option<chrono::DateTime<chrono::Utc>>,
     pub song_year: u16,
+    /// 0 when not set
+    pub track_number: u32,
+    pub num_tracks_in_album: u32,
+    pub disc_number: u32,
+    pub num_discs: u32,
     pub song_title: String,
     pub song_artist: String,
     pub song_composer: String,
     pub song_album: String,
     pub song_genre: String,
     pub song_comment: String, 
+    /// What compilations should be grouped by, since every track can have a different artist
+    pub song_album_artist: String,
+    pub song_sort_title: String,
+    pub song_sort_artist: String,
+    pub song_sort_album: String,
+    pub song_sort_composer: String,
+    pub song_sort_album_artist: String,
+    pub song_grouping: String,
+    pub song_description: String,
+    pub song_keywords: String,
+    pub has_artwork: bool,
+    pub artwork_count: u32,
+    pub artwork_size_bytes: u32,
+    /// Matches the image ID in the ArtworkDB
+    pub artwork_id: u32,
+    /// Where playback starts/stops, if the song was cropped in iTunes ("Options" tab)
+    pub start_time_ms: u32,
+    pub stop_time_ms: u32,
+    /// Between -255 (-100%) and +255 (+100%)
+    pub volume_adjustment: i32,
+    /// Not set if the song was never played (on the iPod or in iTunes)
+    pub last_played_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub num_skips: u32,
+    pub is_compilation: bool,
+    pub gapless_enabled: bool,
+    /// # of silent samples at the start/end of the track, only set for gapless tracks
+    pub pregap_samples: u32,
+    pub postgap_samples: u32,
+    /// Total # of samples in the track, also only set for gapless tracks
+    pub num_samples: u64,
+    pub bpm: u32,
+    /// As stored, e.g. "#!#107#!#"
+    pub equalizer_preset: String,
+    /// Refers to a preset in the iTunes EQ Presets file (see equalizer.rs)
+    pub equalizer_preset_id: Option<u32>,
+    /// Only known if the EQ Presets file was read from the iPod's mount point
+    pub equalizer_preset_name: String,
+    /// Not set if the song was never skipped
+    pub last_skipped_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Where playback resumes from, aka the "bookmark time"
+    pub resume_position_ms: u32,
+    /// Position of the track item in the Track List, which is how the Play Counts file refers to it
+    pub track_index: u32,
     /// As far as I can tell from looking at the output, this field
-    /// is always the last one to get populated
+    /// is always the last one to get populated.
+    /// Relative to the iPod's root, e.g. "iPod_Control/Music/F12/ABCD.mp3"
     pub song_filename: String,
+    /// As stored, e.g. ":iPod_Control:Music:F12:ABCD.mp3"
+    pub song_filename_raw: String,
+    /// Only set when the iPod's mount point is given
+    pub song_absolute_path: Option<String>,
+    /// The "unique ID" of the track, which is what playlist items refer to
+    pub track_id: u32,
+    /// The 64-bit "dbid", which stays the same across syncs and is what other files
+    /// (e.g. the ArtworkDB) refer to. Use this to join records across exports
+    pub dbid: u64,
+    pub media_type_raw: u32,
+    /// The decoded media type, e.g. "Audio", "Podcast" or "Music Video"
+    pub media_kind: String,
+    /// The checkbox next to the song in iTunes. Unchecked songs are left out of syncs
+    /// when "Sync only checked songs" is turned on
+    pub is_checked: bool,
+    pub remember_playback_position: bool,
+    pub skip_when_shuffling: bool,
+    /// "explicit", "clean" or "none"
+    pub explicitness: String,
+    pub has_lyrics: bool,
+    pub sound_check_raw: u32,
+    pub sound_check_gain_db: Option<f64>,
+    /// e.g. "-9.16 dB", empty if the track has no Sound Check value
+    pub replaygain_track_gain: String,
+    /// Read from the audio file itself, only when the iPod's mount point is given
+    pub lyrics: Option<String>,
+    /// The iTunes Store (or Audible) account a DRM-protected song was bought with, 0 for everything else
+    pub apple_user_id: u32,
+    /// DRM-protected, see `is_protected_file_extension`
+    pub is_protected: bool,
+    /// Called "unk14/1", see `decode_track_audio_type`
+    pub audio_type_raw: u32,
+    /// See `decode_track_codec`. Replaced by what the audio file says, if the iPod's mount point is known
+    pub codec: String,
+    /// Only known for sure for WAV and AIFF files, or once the audio file has been read
+    pub is_lossless: bool,
+    /// Not stored in the iTunesDB, only read from lossless audio files
+    pub bit_depth: Option<u16>,
+    /// MD5 or SHA-1 of the audio file, only computed when asked for (see `track_files::TrackFile`)
+    pub file_checksum: Option<String>,
 }
 
 impl Default for Song {
@@ -77,20 +596,83 @@ impl Default for Song {
             sample_rate_hz: 0,
             file_size_bytes: 0,
             file_size_friendly: "".to_string(),
+            file_size_is_estimated: false,
             song_duration_s: 0,
             song_duration_friendly: "".to_string(),
             num_plays: 0,
             song_rating_raw: 0,
+            song_previous_rating_raw: 0,
+            rating_source: "".to_string(),
             song_added_to_library_epoch: 0,
-            song_added_to_library_ts: helpers::get_timestamp_as_mac(0),
+            song_added_to_library_ts: None,
+            song_modified_epoch: 0,
+            song_modified_ts: None,
+            song_released_epoch: 0,
+            song_released_ts: None,
             song_year: 0,
+            track_number: 0,
+            num_tracks_in_album: 0,
+            disc_number: 0,
+            num_discs: 0,
             song_title: "".to_string(),
             song_artist: "".to_string(),
             song_composer: "".to_string(),
             song_album: "".to_string(),
             song_genre: "".to_string(),
             song_comment: "".to_string(),
+            song_album_artist: "".to_string(),
+            song_sort_title: "".to_string(),
+            song_sort_artist: "".to_string(),
+            song_sort_album: "".to_string(),
+            song_sort_composer: "".to_string(),
+            song_sort_album_artist: "".to_string(),
+            song_grouping: "".to_string(),
+            song_description: "".to_string(),
+            song_keywords: "".to_string(),
+            has_artwork: false,
+            artwork_count: 0,
+            artwork_size_bytes: 0,
+            artwork_id: 0,
+            start_time_ms: 0,
+            stop_time_ms: 0,
+            volume_adjustment: 0,
+            last_played_at: None,
+            num_skips: 0,
+            is_compilation: false,
+            gapless_enabled: false,
+            pregap_samples: 0,
+            postgap_samples: 0,
+            num_samples: 0,
+            bpm: 0,
+            equalizer_preset: "".to_string(),
+            equalizer_preset_id: None,
+            equalizer_preset_name: "".to_string(),
+            last_skipped_at: None,
+            resume_position_ms: 0,
+            track_index: 0,
             song_filename: "".to_string(),
+            song_filename_raw: "".to_string(),
+            song_absolute_path: None,
+            track_id: 0,
+            dbid: 0,
+            media_type_raw: 0,
+            media_kind: "".to_string(),
+            is_checked: true,
+            remember_playback_position: false,
+            skip_when_shuffling: false,
+            explicitness: "none".to_string(),
+            has_lyrics: false,
+            sound_check_raw: 0,
+            sound_check_gain_db: None,
+            replaygain_track_gain: "".to_string(),
+            lyrics: None,
+            apple_user_id: 0,
+            is_protected: false,
+            audio_type_raw: 0,
+            codec: "".to_string(),
+            is_lossless: false,
+            bit_depth: None,
+            file_checksum: None,
         };
     }
 }
@@ -105,22 +687,49 @@ impl Song {
             helpers::convert_seconds_to_human_readable_duration(self.song_duration_s);
     }
 
-    pub fn set_song_filesize(&mut self, file_size_bytes: u32) {
+    pub fn set_song_filesize(&mut self, file_size_bytes: u64) {
         self.file_size_bytes = file_size_bytes;
         self.file_size_friendly =
-            helpers::convert_bytes_to_human_readable_size(file_size_bytes as u64);
+            helpers::convert_bytes_to_human_readable_size(file_size_bytes);
     }
 
     pub fn set_song_added_timestamp(&mut self, added_to_library_epoch: u64) {
         self.song_added_to_library_epoch = added_to_library_epoch;
         self.song_added_to_library_ts =
-            helpers::get_timestamp_as_mac(added_to_library_epoch);
+            Some(helpers::get_timestamp_as_mac(added_to_library_epoch));
+    }
+
+    pub fn set_song_modified_timestamp(&mut self, modified_epoch: u64) {
+        self.song_modified_epoch = modified_epoch;
+        self.song_modified_ts = Some(helpers::get_timestamp_as_mac(modified_epoch));
+    }
+
+    pub fn set_song_released_timestamp(&mut self, released_epoch: u64) {
+        self.song_released_epoch = released_epoch;
+        self.song_released_ts = Some(helpers::get_timestamp_as_mac(released_epoch));
     }
 
     pub fn set_song_filename(&mut self, song_filename_raw: String) {
+        self.song_filename_raw = song_filename_raw.to_string();
         self.song_filename = itunesdb_helpers::get_canonical_path(song_filename_raw)
     }
 
+    pub fn set_song_absolute_path(&mut self, ipod_mount_point: &str) {
+        self.song_absolute_path = Some(
+            std::path::Path::new(ipod_mount_point)
+                .join(&self.song_filename)
+                .display()
+                .to_string(),
+        );
+    }
+
+    /// Not every iPod flags recordings with the voice memo media type,
+    /// but they always end up in the same folder, and iTunes gives them the same genre
+    pub fn is_voice_memo(&self) -> bool {
+        return self.song_filename.to_lowercase().starts_with("recordings/")
+            || (self.song_genre == "Voice Memo");
+    }
+
     /// This function determines whether there's enough metadata for the song to be added.
     /// Because an iPod can have songs from different sources (eg you can upload your own MP3 songs to your device)
     /// the level of metadata present can vary. At a minimum, a song is considered valid if it has:
@@ -138,6 +747,376 @@ impl Song {
 }
 
 
+impl smart_playlist::SmartPlaylistTrack for Song {
+    fn get_track_id(&self) -> u32 {
+        return self.track_id;
+    }
+
+    fn get_string_field(&self, rule_field: u32) -> Option<String> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::SongName as u32 {
+            return Some(self.song_title.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Album as u32 {
+            return Some(self.song_album.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Artist as u32 {
+            return Some(self.song_artist.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Genre as u32 {
+            return Some(self.song_genre.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Comment as u32 {
+            return Some(self.song_comment.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Composer as u32 {
+            return Some(self.song_composer.to_string());
+        }
+
+        return None;
+    }
+
+    fn get_numeric_field(&self, rule_field: u32) -> Option<u64> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::Bitrate as u32 {
+            return Some(self.bitrate_kbps as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::SampleRate as u32 {
+            return Some(self.sample_rate_hz as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Year as u32 {
+            return Some(self.song_year as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Size as u32 {
+            return Some(self.file_size_bytes);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Time as u32 {
+            return Some((self.song_duration_s as u64) * 1000);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::DateAdded as u32 {
+            return Some(self.song_added_to_library_epoch);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::PlayCount as u32 {
+            return Some(self.num_plays as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Rating as u32 {
+            return Some(self.song_rating_raw as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::VideoKind as u32 {
+            return Some(self.media_type_raw as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Bpm as u32 {
+            return Some(self.bpm as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::TrackNumber as u32 {
+            return Some(self.track_number as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::DiscNumber as u32 {
+            return Some(self.disc_number as u64);
+        }
+
+        return None;
+    }
+
+    fn get_checked_state(&self) -> Option<bool> {
+        return Some(self.is_checked);
+    }
+}
+
+impl smart_playlist::SmartPlaylistTrack for TrackCore {
+    fn get_track_id(&self) -> u32 {
+        return self.track_id;
+    }
+
+    fn get_string_field(&self, rule_field: u32) -> Option<String> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::SongName as u32 {
+            return Some(self.title.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Album as u32 {
+            return Some(self.album.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Artist as u32 {
+            return Some(self.artist.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Genre as u32 {
+            return Some(self.genre.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Grouping as u32 {
+            return Some(self.grouping.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Category as u32 {
+            return Some(self.category.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Description as u32 {
+            return Some(self.description.to_string());
+        }
+
+        return None;
+    }
+
+    fn get_numeric_field(&self, rule_field: u32) -> Option<u64> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::Size as u32 {
+            return Some(self.file_size_bytes);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Time as u32 {
+            return Some((self.duration_s as u64) * 1000);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::DateAdded as u32 {
+            return Some(self.added_to_library_epoch);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::PlayCount as u32 {
+            return Some(self.num_plays as u64);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::VideoKind as u32 {
+            return Some(self.media_type_raw as u64);
+        }
+
+        return None;
+    }
+}
+
+impl smart_playlist::SmartPlaylistTrack for Podcast {
+    fn get_track_id(&self) -> u32 {
+        return self.track_id;
+    }
+
+    fn get_string_field(&self, rule_field: u32) -> Option<String> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::SongName as u32 {
+            return Some(self.podcast_title.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Artist as u32 {
+            return Some(self.podcast_publisher.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Genre as u32 {
+            return Some(self.podcast_genre.to_string());
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Description as u32 {
+            return Some(self.podcast_description.to_string());
+        }
+
+        return None;
+    }
+
+    fn get_numeric_field(&self, rule_field: u32) -> Option<u64> {
+        if rule_field == smart_playlist::SmartPlaylistRuleField::Size as u32 {
+            return Some(self.file_size_bytes);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::Time as u32 {
+            return Some((self.duration_s as u64) * 1000);
+        } else if rule_field == smart_playlist::SmartPlaylistRuleField::VideoKind as u32 {
+            return Some(self.media_type_raw as u64);
+        }
+
+        return None;
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct Playlist {
+    pub playlist_name: String,
+    /// The master playlist holds every track, and is named after the iPod itself
+    pub is_master_playlist: bool,
+    /// Shown as "Podcasts" in the Music menu, rather than under "Playlists"
+    pub is_podcast_playlist: bool,
+    pub is_smart_playlist: bool,
+    /// Made by Genius from a seed track, see `decode_playlist_kind`
+    pub is_genius_playlist: bool,
+    /// Exported as its name, e.g. "Smart", so it stays a string in the schema
+    #[schemars(with = "String")]
+    pub playlist_kind: PlaylistKind,
+    pub persistent_playlist_id: u64,
+    pub created_ts: chrono::DateTime<chrono::Utc>,
+    pub sort_order_raw: u32,
+    pub sort_order: String,
+    pub smart_playlist_preferences: Option<smart_playlist::SmartPlaylistPreferences>,
+    pub smart_playlist_rules: Option<smart_playlist::SmartPlaylistRules>,
+    /// The track IDs listed in the playlist items, as stored in the database
+    pub stored_track_ids: Vec<u32>,
+    /// The actual playlist contents. For smart playlists, this comes from evaluating the rules
+    pub track_ids: Vec<u32>,
+    pub membership_source: String,
+    /// How iTunes displays the playlist. The iPod itself ignores this
+    pub column_settings: Option<PlaylistColumnSettings>,
+}
+
+impl Default for Playlist {
+    fn default() -> Playlist {
+        return Playlist {
+            playlist_name: "".to_string(),
+            is_master_playlist: false,
+            is_podcast_playlist: false,
+            is_smart_playlist: false,
+            is_genius_playlist: false,
+            playlist_kind: PlaylistKind::Regular,
+            persistent_playlist_id: 0,
+            created_ts: chrono::DateTime::<chrono::Utc>::default(),
+            sort_order_raw: 0,
+            sort_order: "".to_string(),
+            smart_playlist_preferences: None,
+            smart_playlist_rules: None,
+            stored_track_ids: Vec::new(),
+            track_ids: Vec::new(),
+            membership_source: "Stored playlist items".to_string(),
+            column_settings: None,
+        };
+    }
+}
+
+/// When the iPod syncs, iTunes copies the On-The-Go playlists built on the device into the library
+/// as "On-The-Go 1", "On-The-Go 2" etc. (just "On-The-Go" on older versions). Their headers don't
+/// have a flag setting them apart, so the name is all there is to go on
+pub fn is_on_the_go_playlist_name(playlist_name: &str) -> bool {
+    let name_suffix = match playlist_name.strip_prefix("On-The-Go") {
+        Some(name_suffix) => name_suffix,
+        None => return false,
+    };
+
+    if name_suffix.is_empty() {
+        return true;
+    }
+
+    return name_suffix
+        .strip_prefix(' ')
+        .is_some_and(|playlist_number| {
+            !playlist_number.is_empty() && playlist_number.chars().all(|c| c.is_ascii_digit())
+        });
+}
+
+/// What a playlist is for, so the ones iTunes or the iPod made can be told apart from the user's own,
+/// e.g. to leave them out when moving the playlists to another library
+#[derive(Serialize, Deserialize, JsonSchema, Clone, PartialEq, Debug)]
+pub enum PlaylistKind {
+    Master,
+    Podcasts,
+    /// iTunesSD only, the iPod shuffle's audiobooks playlist
+    Audiobooks,
+    Smart,
+    Genius,
+    OnTheGo,
+    Regular,
+    /// iTunesSD only, a playlist type that isn't documented
+    Unknown,
+}
+
+impl std::fmt::Display for PlaylistKind {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let playlist_kind_name = match self {
+            PlaylistKind::Master => "Master",
+            PlaylistKind::Podcasts => "Podcasts",
+            PlaylistKind::Audiobooks => "Audiobooks",
+            PlaylistKind::Smart => "Smart",
+            PlaylistKind::Genius => "Genius",
+            PlaylistKind::OnTheGo => "OnTheGo",
+            PlaylistKind::Regular => "Regular",
+            PlaylistKind::Unknown => "Unknown",
+        };
+
+        return write!(formatter, "{}", playlist_kind_name);
+    }
+}
+
+pub fn decode_playlist_kind(playlist: &Playlist) -> PlaylistKind {
+    if playlist.is_master_playlist {
+        return PlaylistKind::Master;
+    } else if playlist.is_podcast_playlist {
+        return PlaylistKind::Podcasts;
+    } else if playlist.is_smart_playlist {
+        return PlaylistKind::Smart;
+    } else if playlist.is_genius_playlist {
+        return PlaylistKind::Genius;
+    } else if is_on_the_go_playlist_name(&playlist.playlist_name) {
+        return PlaylistKind::OnTheGo;
+    }
+
+    return PlaylistKind::Regular;
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct PlaylistColumn {
+    pub column_id: u16,
+    pub column_name: String,
+    pub width_px: u16,
+    pub is_sort_reversed: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct PlaylistColumnSettings {
+    /// Which column the playlist is sorted by
+    pub sort_type: u32,
+    /// Left to right, as shown in iTunes
+    pub columns: Vec<PlaylistColumn>,
+}
+
+/// A podcast (show), as grouped in the Podcast List. This is the submenu
+/// the episodes appear under in the iPod's "Podcasts" menu
+#[derive(Serialize)]
+pub struct PodcastGroup {
+    pub group_name: String,
+    pub group_id: u32,
+    pub track_ids: Vec<u32>,
+}
+
+/// Everything read from the iTunesDB, for the exports that take the database as a whole (e.g. MessagePack).
+/// Serialized with the same field names as the JSON files
+#[derive(Serialize)]
+pub struct ParsedDatabase<'a> {
+    /// Of the songs, podcasts and playlists, see export_schema_constants.rs
+    pub schema_version: &'static str,
+    pub device_info: &'a ipod_device_info::IpodDeviceInfo,
+    pub library_stats: &'a library_stats::LibraryStats,
+    pub songs: &'a [Song],
+    pub podcasts: &'a [Podcast],
+    pub videos: &'a [Video],
+    pub audiobooks: &'a [Audiobook],
+    pub itunesu_items: &'a [ITunesUItem],
+    pub ringtones: &'a [Ringtone],
+    pub voice_memos: &'a [VoiceMemo],
+    pub playlists: &'a [Playlist],
+    pub podcast_groups: &'a [PodcastGroup],
+    pub artwork: &'a [TrackArtwork],
+}
+
+/// Cover art of a single track, of any media type. The image itself lives in the ArtworkDB,
+/// the iTunesDB only keeps track of its ID and size
+#[derive(Serialize)]
+pub struct TrackArtwork {
+    pub track_id: u32,
+    pub dbid: u64,
+    pub media_kind: String,
+    /// Filled in once the track's data objects have been parsed
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub artwork_count: u32,
+    pub artwork_size_bytes: u32,
+    /// Matches the image ID in the ArtworkDB. 0 if the track item header ends before it (shorter than 0x164 bytes)
+    pub artwork_id: u32,
+    /// Whether the ArtworkDB has cover art for the track's dbid. `None` if the ArtworkDB wasn't read
+    pub is_on_device: Option<bool>,
+    /// Each size the cover art is stored in on the iPod
+    pub thumbnails: Vec<ArtworkThumbnail>,
+}
+
+/// Where one size of a track's cover art is, from the ArtworkDB
+#[derive(Serialize)]
+pub struct ArtworkThumbnail {
+    /// Format of the thumbnail, see ithmb.rs
+    pub correlation_id: u32,
+    pub width: u16,
+    pub height: u16,
+    /// As stored, e.g. ":F1028_1.ithmb"
+    pub ithmb_filename: String,
+    pub ithmb_offset: u32,
+    pub image_size_bytes: u32,
+}
+
+impl Default for TrackArtwork {
+    fn default() -> TrackArtwork {
+        return TrackArtwork {
+            track_id: 0,
+            dbid: 0,
+            media_kind: "".to_string(),
+            title: "".to_string(),
+            artist: "".to_string(),
+            album: "".to_string(),
+            artwork_count: 0,
+            artwork_size_bytes: 0,
+            artwork_id: 0,
+            is_on_device: None,
+            thumbnails: Vec::new(),
+        };
+    }
+}
+
+/// Every kind of track parsed from the database, for what goes through all of them
+pub struct LibraryTracks<'a> {
+    pub songs: &'a [Song],
+    pub podcasts: &'a [Podcast],
+    pub videos: &'a [Video],
+    pub audiobooks: &'a [Audiobook],
+    pub itunesu_items: &'a [ITunesUItem],
+    pub ringtones: &'a [Ringtone],
+    pub voice_memos: &'a [VoiceMemo],
+}
+
+/// The different DataSet (mhsd) types
+pub enum HandleableDatasetType {
+    Tracks = 1,
+    Playlists = 2,
+    /// Same playlists as the Playlist List, but with podcasts grouped together
+    Podcasts = 3,
+    Albums = 4,
+    SmartPlaylists = 5,
+    Artists = 8,
+}
 
 pub fn parse_version_number(version_number: u32) -> String {
     let itunes_version: String;
@@ -174,6 +1153,18 @@ pub fn parse_version_number(version_number: u32) -> String {
         itunes_version = "Tunes 7.3.1 - 7.3.2".to_string();
     } else if version_number == 0x19 {
         itunes_version = "iTunes 7.4".to_string();
+    } else if version_number == 0x1A {
+        itunes_version = "iTunes 7.5".to_string();
+    } else if version_number == 0x1B {
+        itunes_version = "iTunes 7.6".to_string();
+    } else if version_number == 0x1C {
+        itunes_version = "iTunes 7.7".to_string();
+    } else if (0x1D..0x30).contains(&version_number) {
+        itunes_version = "iTunes 8".to_string();
+    } else if version_number >= 0x30 {
+        // iTunes 9 and everything since (including Finder/Music syncs) keep bumping the version,
+        // without any changes to the parts of the format that are parsed here
+        itunes_version = format!("iTunes 9 or later ({:#x})", version_number);
     } else {
         itunes_version = format!("N/A ({})", version_number);
     }
@@ -181,23 +1172,68 @@ pub fn parse_version_number(version_number: u32) -> String {
     return itunes_version;
 }
 
-pub fn track_has_artwork(track_artwork_setting: &[u8]) -> bool {
-    return track_artwork_setting[0] == 0x01;
+#[derive(PartialEq)]
+pub enum HandleableHashingScheme {
+    None = 0,
+    /// iPod Classic and Nano 3G: HMAC-SHA1 keyed on the device's FireWire GUID
+    Hash58 = 1,
+    /// Nano 4G: AES-based, keyed on the device
+    Hash72 = 2,
+    /// Nano 6G
+    HashAB = 3,
+}
+
+pub fn decode_hashing_scheme(hashing_scheme_raw: u32) -> (String, Option<HandleableHashingScheme>) {
+    if hashing_scheme_raw == HandleableHashingScheme::None as u32 {
+        return ("None".to_string(), Some(HandleableHashingScheme::None));
+    } else if hashing_scheme_raw == HandleableHashingScheme::Hash58 as u32 {
+        return ("hash58".to_string(), Some(HandleableHashingScheme::Hash58));
+    } else if hashing_scheme_raw == HandleableHashingScheme::Hash72 as u32 {
+        return ("hash72".to_string(), Some(HandleableHashingScheme::Hash72));
+    } else if hashing_scheme_raw == HandleableHashingScheme::HashAB as u32 {
+        return ("hashAB".to_string(), Some(HandleableHashingScheme::HashAB));
+    }
+
+    return (format!("Unknown ({})", hashing_scheme_raw), None);
+}
+
+/// Returns the (offset, length) of the hash that goes with the hashing scheme, within the database object
+pub fn get_database_hash_location(hashing_scheme: &HandleableHashingScheme) -> Option<(usize, usize)> {
+    if *hashing_scheme == HandleableHashingScheme::Hash58 {
+        return Some((
+            itunesdb_constants::DATABASE_OBJECT_HASH58_OFFSET,
+            itunesdb_constants::DATABASE_OBJECT_HASH58_LEN,
+        ));
+    } else if *hashing_scheme == HandleableHashingScheme::Hash72 {
+        return Some((
+            itunesdb_constants::DATABASE_OBJECT_HASH72_OFFSET,
+            itunesdb_constants::DATABASE_OBJECT_HASH72_LEN,
+        ));
+    } else if *hashing_scheme == HandleableHashingScheme::HashAB {
+        return Some((
+            itunesdb_constants::DATABASE_OBJECT_HASHAB_OFFSET,
+            itunesdb_constants::DATABASE_OBJECT_HASHAB_LEN,
+        ));
+    }
+
+    return None;
 }
 
 pub fn parse_dataset_type(dataset_type_raw: u32) -> String {
     let dataset_type: String;
 
-    if dataset_type_raw == 1 {
+    if dataset_type_raw == HandleableDatasetType::Tracks as u32 {
         dataset_type = "Track List".to_string();
-    } else if dataset_type_raw == 2 {
+    } else if dataset_type_raw == HandleableDatasetType::Playlists as u32 {
         dataset_type = "Playlist List".to_string();
-    } else if dataset_type_raw == 3 {
+    } else if dataset_type_raw == HandleableDatasetType::Podcasts as u32 {
         dataset_type = "Podcast List".to_string();
-    } else if dataset_type_raw == 4 {
+    } else if dataset_type_raw == HandleableDatasetType::Albums as u32 {
         dataset_type = "Album List".to_string();
-    } else if dataset_type_raw == 5 {
+    } else if dataset_type_raw == HandleableDatasetType::SmartPlaylists as u32 {
         dataset_type = "New Playlist List (smart playlists)".to_string();
+    } else if dataset_type_raw == HandleableDatasetType::Artists as u32 {
+        dataset_type = "Artist List".to_string();
     } else {
         dataset_type = format!("N/A ({})", dataset_type_raw);
     }
@@ -279,6 +1315,48 @@ pub fn decode_track_samplerate_to_hz(track_samplerate_raw: u32) -> u32 {
     return track_samplerate_raw / 65536;
 }
 
+/// When iTunes and the iPod disagree on a rating during a sync, the iPod's rating wins
+/// and the iTunes one is kept as the "application rating". So if the two differ,
+/// the rating was changed on the device.
+pub fn decode_rating_source(rating_raw: u8, application_rating_raw: u8) -> String {
+    if rating_raw == 0 && application_rating_raw == 0 {
+        return "".to_string();
+    } else if rating_raw == application_rating_raw {
+        return "iTunes".to_string();
+    }
+
+    return "iPod".to_string();
+}
+
+/// Sound Check values are stored as 1000 * 10^(-gain / 10), so a value of 1000 means no adjustment.
+/// Returns the gain in dB, or `None` if the track was never analysed
+pub fn decode_sound_check_gain_db(sound_check_raw: u32) -> Option<f64> {
+    if sound_check_raw == 0 {
+        return None;
+    }
+
+    return Some(-10.0 * (sound_check_raw as f64 / 1000.0).log10());
+}
+
+/// Formatted like a REPLAYGAIN_TRACK_GAIN tag, e.g. "-9.16 dB".
+/// Both describe how much to turn the track down (or up), so the Sound Check gain carries over as-is
+pub fn get_replaygain_track_gain(sound_check_gain_db: Option<f64>) -> String {
+    match sound_check_gain_db {
+        Some(gain_db) => return format!("{:.2} dB", gain_db),
+        None => return "".to_string(),
+    }
+}
+
+pub fn decode_explicitness(explicit_flag_raw: u8) -> String {
+    if explicit_flag_raw == 1 {
+        return "explicit".to_string();
+    } else if explicit_flag_raw == 2 {
+        return "clean".to_string();
+    }
+
+    return "none".to_string();
+}
+
 pub fn decode_track_audio_type(track_type_unk14_1: u32) -> String {
     let suspected_track_type: String;
 
@@ -317,6 +1395,12 @@ pub enum HandleableMediaType {
     Podcast = 2,
     Television = 3,
     UNKNOWN = 4,
+    /// Movies and music videos
+    Video = 5,
+    Audiobook = 6,
+    ITunesU = 7,
+    Ringtone = 8,
+    VoiceMemo = 9,
 }
 
 pub fn decode_track_media_type(track_media_type_raw: &[u8]) -> (String, HandleableMediaType) {
@@ -325,13 +1409,28 @@ pub fn decode_track_media_type(track_media_type_raw: &[u8]) -> (String, Handleab
 
     let conditional_byte = track_media_type_raw[0];
 
-    if conditional_byte == 0x00 {
+    // iTunes U is flagged in the third byte (0x200000), on top of the audio/video bits
+    if track_media_type_raw.len() > 2 && (track_media_type_raw[2] & 0x20) != 0 {
+        media_type_name = "iTunes U".to_string();
+        media_type = HandleableMediaType::ITunesU;
+    }
+    // Voice memos are flagged in the third byte too (0x100000)
+    else if track_media_type_raw.len() > 2 && (track_media_type_raw[2] & 0x10) != 0 {
+        media_type_name = "Voice Memo".to_string();
+        media_type = HandleableMediaType::VoiceMemo;
+    }
+    // Ringtones are flagged in the second byte (0x4000)
+    else if track_media_type_raw.len() > 1 && (track_media_type_raw[1] & 0x40) != 0 {
+        media_type_name = "Ringtone".to_string();
+        media_type = HandleableMediaType::Ringtone;
+    } else if conditional_byte == 0x00 {
         media_type_name = "Audio/Video".to_string();
     } else if conditional_byte == 0x01 {
         media_type_name = "Audio".to_string();
         media_type = HandleableMediaType::SongLike;
     } else if conditional_byte == 0x02 {
         media_type_name = "Video".to_string();
+        media_type = HandleableMediaType::Video;
     } else if conditional_byte == 0x04 {
         media_type_name = "Podcast".to_string();
         media_type = HandleableMediaType::Podcast;
@@ -340,11 +1439,12 @@ pub fn decode_track_media_type(track_media_type_raw: &[u8]) -> (String, Handleab
         media_type = HandleableMediaType::Podcast;
     } else if conditional_byte == 0x08 {
         media_type_name = "Audiobook".to_string();
-        media_type = HandleableMediaType::SongLike;
+        media_type = HandleableMediaType::Audiobook;
     } else if conditional_byte == 0x20
     /* 32d */
     {
         media_type_name = "Music Video".to_string();
+        media_type = HandleableMediaType::Video;
     } else if conditional_byte == 0x40
     /* 64d */
     {
@@ -363,8 +1463,104 @@ pub fn decode_track_media_type(track_media_type_raw: &[u8]) -> (String, Handleab
     return (media_type_name, media_type);
 }
 
+/// The track item only has room for a 32-bit file size, so anything of 4GB or more
+/// (which only really happens with movies) wraps around. Bitrate and duration give a rough idea
+/// of the real size, which is enough to tell how many times it wrapped.
+pub fn get_unwrapped_file_size(file_size_raw: u32, bitrate_kbps: u32, duration_ms: u32) -> u64 {
+    let estimated_file_size = (bitrate_kbps as u64) * (duration_ms as u64) / 8;
+    // Rounded, as the estimate can be off in either direction
+    let num_wraps =
+        (estimated_file_size.saturating_sub(file_size_raw as u64) + (1u64 << 31)) / (1u64 << 32);
+
+    return (num_wraps << 32) + (file_size_raw as u64);
+}
+
+/// The file size of the track item starting at `track_item_idx`, for every media type, and whether
+/// it's an estimate, i.e. the stored size wrapped around and was unwrapped with `get_unwrapped_file_size`
+pub fn read_track_file_size(track_item_idx: usize, file_as_bytes: &[u8]) -> (u64, bool) {
+    let file_size_raw = helpers::get_slice_as_le_u32(
+        track_item_idx,
+        file_as_bytes,
+        itunesdb_constants::TRACK_ITEM_TRACK_FILE_SIZE_BYTES_OFFSET,
+        itunesdb_constants::TRACK_ITEM_TRACK_FILE_SIZE_BYTES_LEN,
+    );
+
+    let file_size_bytes = get_unwrapped_file_size(
+        file_size_raw,
+        helpers::get_slice_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_BITRATE_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_BITRATE_LEN,
+        ),
+        helpers::get_slice_as_le_u32(
+            track_item_idx,
+            file_as_bytes,
+            itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_OFFSET,
+            itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_LEN,
+        ),
+    );
+
+    return (file_size_bytes, file_size_bytes != file_size_raw as u64);
+}
+
+/// Ringtones bought from the iTunes Store (or made with GarageBand) are .m4r files
+pub fn is_ringtone_file_extension(file_extension: &str) -> bool {
+    return file_extension.to_lowercase() == "m4r";
+}
+
+/// Audible (.aa) and AAC audiobook (.m4b) files are audiobooks, whatever the media type says
+pub fn is_audiobook_file_extension(file_extension: &str) -> bool {
+    let file_extension_lowercase = file_extension.to_lowercase();
+
+    return (file_extension_lowercase == "aa") || (file_extension_lowercase == "m4b");
+}
+
+/// FairPlay-protected iTunes Store purchases are .m4p files, and Audible audiobooks (.aa) have DRM of their own.
+/// Neither will play without authorizing the account they were bought with
+pub fn is_protected_file_extension(file_extension: &str) -> bool {
+    let file_extension_lowercase = file_extension.to_lowercase();
+
+    return (file_extension_lowercase == "m4p") || (file_extension_lowercase == "aa");
+}
+
+pub fn is_lossless_file_extension(file_extension: &str) -> bool {
+    let file_extension_lowercase = file_extension.to_lowercase();
+
+    return (file_extension_lowercase == "wav")
+        || (file_extension_lowercase == "aif")
+        || (file_extension_lowercase == "aiff");
+}
+
+/// AAC and Apple Lossless files are both "M4A" as far as the iTunesDB is concerned.
+/// Only AAC files have been seen with the AAC audio type, so anything else could be either
+pub fn decode_track_codec(file_extension: &str, audio_type_raw: u32) -> String {
+    let file_extension_lowercase = file_extension.to_lowercase();
+
+    if file_extension_lowercase == "mp3" {
+        return "MP3".to_string();
+    } else if file_extension_lowercase == "wav" {
+        return "PCM (WAV)".to_string();
+    } else if (file_extension_lowercase == "aif") || (file_extension_lowercase == "aiff") {
+        return "PCM (AIFF)".to_string();
+    } else if file_extension_lowercase == "aa" {
+        return "Audible".to_string();
+    } else if (file_extension_lowercase == "m4a")
+        || (file_extension_lowercase == "m4p")
+        || (file_extension_lowercase == "m4b")
+    {
+        if audio_type_raw == 0x0033 {
+            return "AAC".to_string();
+        }
+
+        return "AAC or Apple Lossless".to_string();
+    }
+
+    return "".to_string();
+}
+
 pub fn decode_playlist_sort_order(playlist_sort_order_raw: u32) -> String {
-    let mut playlist_sort_order: String = "Playlist sort order: ".to_string();
+    let mut playlist_sort_order: String = String::new();
 
     if playlist_sort_order_raw == 1 {
         playlist_sort_order.push_str("Manual (user sorted)");
@@ -442,15 +1638,39 @@ pub enum HandleableDataObjectType {
     Artist = 4,
     Genre = 5,
     FileType = 6,
+    EqualizerSetting = 7,
     Comment = 8,
+    Category = 9,
     Composer = 12,
+    Grouping = 13,
     PodcastDescription = 14,
     PodcastEnclosureURL = 15,
     Podcast_RSS_URL = 16,
+    ChapterData = 17,
+    Subtitle = 18,
+    TVShow = 19,
+    TVEpisodeID = 20,
+    TVNetwork = 21,
+    AlbumArtist = 22,
+    SortArtist = 23,
+    Keywords = 24,
+    SortTitle = 27,
+    SortAlbum = 28,
+    SortAlbumArtist = 29,
+    SortComposer = 30,
+    SmartPlaylistData = 50,
+    SmartPlaylistRules = 51,
+    /// Either the playlist's column definitions or a playlist item's position, see `PLAYLIST_COLUMNS_TOTAL_LEN`
+    PlaylistColumns = 100,
+    /// Every playlist of iTunes 8+ has one, only the Genius flag of the playlist makes it a Genius playlist
+    GeniusPlaylistData = 102,
 }
 
+/// Types 15 and 16 (podcast URLs) are strings too, but aren't stored in the usual string format.
+/// Type 17 is the (binary) chapter data, and type 26 is unknown
 pub fn is_data_object_type_string(data_object_raw: u32) -> bool {
-    return data_object_raw < 15;
+    return (data_object_raw < 15)
+        || ((18..=31).contains(&data_object_raw) && (data_object_raw != 26));
 }
 
 pub fn decode_podcast_urls(mhod_start_idx: usize, file_as_bytes: &[u8]) -> String {
@@ -481,101 +1701,363 @@ pub fn decode_podcast_urls(mhod_start_idx: usize, file_as_bytes: &[u8]) -> Strin
     return podcast_url.to_string();
 }
 
-pub fn decode_data_object_type(data_object_type_raw: u32) -> String {
-    let mut data_object_type: String = String::new();
-
-    if data_object_type_raw == 1 {
-        data_object_type = "Song title".to_string();
-    } else if data_object_type_raw == 2 {
-        data_object_type = "File location".to_string();
-    } else if data_object_type_raw == 3 {
-        data_object_type = "Album".to_string();
-    } else if data_object_type_raw == 4 {
-        data_object_type = "Artist".to_string();
-    } else if data_object_type_raw == 5 {
-        data_object_type = "Genre".to_string();
-    } else if data_object_type_raw == 6 {
-        data_object_type = "Filetype".to_string();
-    } else if data_object_type_raw == 7 {
-        data_object_type = "EQ Setting".to_string();
-    } else if data_object_type_raw == 8 {
-        data_object_type = "Comment".to_string();
-    } else if data_object_type_raw == 9 {
-        data_object_type = "Podcast Category".to_string();
-    } else if data_object_type_raw == 12 {
-        data_object_type = "Composer".to_string();
-    } else if data_object_type_raw == 13 {
-        data_object_type = "Grouping".to_string();
-    } else if data_object_type_raw == 14 {
-        data_object_type = "Description text (?)".to_string();
-    } else if data_object_type_raw == 15 {
-        data_object_type = "Podcast Enclosure URL".to_string();
-    } else if data_object_type_raw == 16 {
-        data_object_type = "Podcdast RSS URL".to_string();
-    } else if data_object_type_raw == 17 {
-        data_object_type = "Chapter data (?)".to_string();
-    } else if data_object_type_raw == 18 {
-        data_object_type = "Subtitle".to_string();
-    } else if data_object_type_raw == 19 {
-        data_object_type = "Television Show".to_string();
-    } else if data_object_type_raw == 20 {
-        data_object_type = "Episode #".to_string();
-    } else if data_object_type_raw == 21 {
-        data_object_type = "TV Show network".to_string();
-    } else if data_object_type_raw == 22 {
-        data_object_type = "Album Artist".to_string();
-    } else if data_object_type_raw == 23 {
-        data_object_type = "Artist name".to_string();
-    } else if data_object_type_raw == 24 {
-        data_object_type = "Track keywords (?)".to_string();
-    } else if data_object_type_raw == 25 {
-        data_object_type = "TV Show locale".to_string();
-    } else if data_object_type_raw == 27 {
-        data_object_type = "Title (for sorting)".to_string();
-    } else if data_object_type_raw == 28 {
-        data_object_type = "Album (for sorting)".to_string();
-    } else if data_object_type_raw == 29 {
-        data_object_type = "Album artist (for sorting)".to_string();
-    } else if data_object_type_raw == 30 {
-        data_object_type = "Composer (for sorting)".to_string();
-    } else if data_object_type_raw == 31 {
-        data_object_type = "Television Show (for sorting)".to_string();
-    } else if data_object_type_raw == 32 {
-        data_object_type = format!(
-            "Unknown video track field (#{}), iTunes 7.1+",
-            data_object_type_raw
-        );
-    } else if data_object_type_raw == 50 {
-        data_object_type = "Smart Playlist data".to_string();
-    } else if data_object_type_raw == 51 {
-        data_object_type = "Smart Playlist rules".to_string();
-    } else if data_object_type_raw == 52 {
-        data_object_type = "Library Playlist index".to_string();
-    } else if data_object_type_raw == 53 {
-        data_object_type = format!("Unknown type (#{}), iTunes7.2+", data_object_type_raw);
-    } else if data_object_type_raw == 100 {
-        data_object_type = format!(
-            "Indeterminate field (#{}), either column sizing or order indicator",
-            data_object_type_raw
-        );
-    } else if data_object_type_raw == 200 {
-        data_object_type = "Album (from Album List, iTunes 7.1+ only)".to_string();
-    }
-    // TODO what is the difference between this and the next entry (202) ???
-    else if data_object_type_raw == 201 {
-        data_object_type = "Artist (in Album List, iTunes 7.1)".to_string();
-    } else if data_object_type_raw == 202 {
-        data_object_type = "Artist (for sorting in Album List) - iTunes 7.1+ only".to_string();
-    } else if data_object_type_raw == 203 {
-        data_object_type = "Podcast URL (in Album List, iTunes 7.1)".to_string();
-    } else if data_object_type_raw == 204 {
-        data_object_type = "TV Show (in Album List)".to_string();
-    } else {
-        eprintln!(
-            "Unable to decode Data Object with type #{}",
-            data_object_type_raw
+/// The EQ setting data object holds the preset number between "#!#" markers, e.g. "#!#107#!#"
+pub fn decode_equalizer_setting(equalizer_setting: &str) -> Option<u32> {
+    return equalizer_setting
+        .trim_start_matches("#!#")
+        .trim_end_matches("#!#")
+        .parse::<u32>()
+        .ok();
+}
+
+pub fn decode_playlist_column_id(column_id: u16) -> String {
+    let column_name = match column_id {
+        0x01 => "Position",
+        0x02 => "Name",
+        0x03 => "Album",
+        0x04 => "Artist",
+        0x05 => "Bit Rate",
+        0x06 => "Sample Rate",
+        0x07 => "Year",
+        0x08 => "Genre",
+        0x09 => "Kind",
+        0x0A => "Date Modified",
+        0x0B => "Track Number",
+        0x0C => "Size",
+        0x0D => "Time",
+        0x0E => "Comment",
+        0x10 => "Date Added",
+        0x11 => "Equalizer",
+        0x12 => "Composer",
+        0x14 => "Play Count",
+        0x15 => "Last Played",
+        0x16 => "Disc Number",
+        0x17 => "My Rating",
+        0x19 => "Date Released",
+        0x1A => "BPM",
+        0x1C => "Grouping",
+        0x1E => "Category",
+        0x1F => "Description",
+        0x21 => "Show",
+        0x22 => "Season",
+        0x23 => "Episode Number",
+        _ => return format!("Unknown ({})", column_id),
+    };
+
+    return column_name.to_string();
+}
+
+/// Column definitions past the end of the data object are ignored
+pub fn decode_playlist_column_settings(
+    mhod_start_idx: usize,
+    file_as_bytes: &[u8],
+) -> PlaylistColumnSettings {
+    let mut column_settings = PlaylistColumnSettings {
+        sort_type: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            itunesdb_constants::PLAYLIST_COLUMNS_SORT_TYPE_OFFSET,
+            itunesdb_constants::PLAYLIST_COLUMNS_SORT_TYPE_LEN,
+        ),
+        columns: Vec::new(),
+    };
+
+    let num_columns = helpers::get_slice_as_le_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        itunesdb_constants::PLAYLIST_COLUMNS_NUM_COLUMNS_OFFSET,
+        itunesdb_constants::PLAYLIST_COLUMNS_NUM_COLUMNS_LEN,
+    ) as usize;
+
+    let max_num_columns = (itunesdb_constants::PLAYLIST_COLUMNS_TOTAL_LEN
+        - itunesdb_constants::PLAYLIST_COLUMNS_FIRST_COLUMN_OFFSET)
+        / itunesdb_constants::PLAYLIST_COLUMNS_COLUMN_DEFINITION_LEN;
+
+    for column_num in 0..std::cmp::min(num_columns, max_num_columns) {
+        let column_idx = mhod_start_idx
+            + itunesdb_constants::PLAYLIST_COLUMNS_FIRST_COLUMN_OFFSET
+            + column_num * itunesdb_constants::PLAYLIST_COLUMNS_COLUMN_DEFINITION_LEN;
+
+        let column_id = helpers::get_slice_as_le_u32(
+            column_idx,
+            file_as_bytes,
+            itunesdb_constants::PLAYLIST_COLUMN_ID_OFFSET,
+            itunesdb_constants::PLAYLIST_COLUMN_ID_LEN,
+        ) as u16;
+
+        column_settings.columns.push(PlaylistColumn {
+            column_id,
+            column_name: decode_playlist_column_id(column_id),
+            width_px: helpers::get_slice_as_le_u32(
+                column_idx,
+                file_as_bytes,
+                itunesdb_constants::PLAYLIST_COLUMN_WIDTH_OFFSET,
+                itunesdb_constants::PLAYLIST_COLUMN_WIDTH_LEN,
+            ) as u16,
+            is_sort_reversed: helpers::get_slice_as_le_u32(
+                column_idx,
+                file_as_bytes,
+                itunesdb_constants::PLAYLIST_COLUMN_SORT_DIRECTION_OFFSET,
+                itunesdb_constants::PLAYLIST_COLUMN_SORT_DIRECTION_LEN,
+            ) == 1,
+        });
+    }
+
+    return column_settings;
+}
+
+/// Returns an empty list if the chapter data doesn't look like what libgpod/iTunes write
+pub fn decode_chapter_data(mhod_start_idx: usize, file_as_bytes: &[u8]) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+
+    let header_len_offset = 4;
+    let element_header_length = helpers::get_slice_as_le_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        header_len_offset,
+        itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE,
+    ) as usize;
+    let total_length = helpers::get_slice_as_le_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_OFFSET,
+        itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_LEN,
+    ) as usize;
+
+    let chapter_data_end = std::cmp::min(mhod_start_idx + total_length, file_as_bytes.len());
+    let container_atom_idx =
+        mhod_start_idx + element_header_length + itunesdb_constants::CHAPTER_DATA_UNKNOWN_LEN;
+
+    if container_atom_idx + itunesdb_constants::CHAPTER_DATA_ATOM_HEADER_LEN > chapter_data_end {
+        return chapters;
+    }
+
+    if helpers::get_slice_from_offset_with_len(
+        container_atom_idx,
+        file_as_bytes,
+        itunesdb_constants::CHAPTER_DATA_ATOM_NAME_OFFSET,
+        itunesdb_constants::CHAPTER_DATA_ATOM_NAME_LEN,
+    ) != itunesdb_constants::CHAPTER_DATA_CONTAINER_ATOM_KEY.as_bytes()
+    {
+        return chapters;
+    }
+
+    let mut atom_idx = container_atom_idx + itunesdb_constants::CHAPTER_DATA_ATOM_HEADER_LEN;
+
+    while atom_idx + itunesdb_constants::CHAPTER_DATA_ATOM_HEADER_LEN <= chapter_data_end {
+        let atom_size = helpers::get_slice_as_be_u32(
+            atom_idx,
+            file_as_bytes,
+            itunesdb_constants::CHAPTER_DATA_ATOM_SIZE_OFFSET,
+            itunesdb_constants::CHAPTER_DATA_ATOM_SIZE_LEN,
+        ) as usize;
+
+        // Malformed atom, bail out rather than looping forever
+        if atom_size < itunesdb_constants::CHAPTER_DATA_ATOM_HEADER_LEN {
+            break;
+        }
+
+        let atom_name = helpers::get_slice_from_offset_with_len(
+            atom_idx,
+            file_as_bytes,
+            itunesdb_constants::CHAPTER_DATA_ATOM_NAME_OFFSET,
+            itunesdb_constants::CHAPTER_DATA_ATOM_NAME_LEN,
         );
+
+        if atom_name == itunesdb_constants::CHAPTER_DATA_CHAPTER_ATOM_KEY.as_bytes() {
+            let mut chapter = Chapter {
+                start_ms: helpers::get_slice_as_be_u32(
+                    atom_idx,
+                    file_as_bytes,
+                    itunesdb_constants::CHAPTER_DATA_CHAPTER_START_MILLISECONDS_OFFSET,
+                    itunesdb_constants::CHAPTER_DATA_CHAPTER_START_MILLISECONDS_LEN,
+                ),
+                title: "".to_string(),
+            };
+
+            let name_atom_idx = atom_idx + itunesdb_constants::CHAPTER_DATA_ATOM_HEADER_LEN;
+            let name_string_idx = name_atom_idx + itunesdb_constants::CHAPTER_DATA_NAME_STRING_OFFSET;
+
+            if (name_string_idx <= chapter_data_end)
+                && (helpers::get_slice_from_offset_with_len(
+                    name_atom_idx,
+                    file_as_bytes,
+                    itunesdb_constants::CHAPTER_DATA_ATOM_NAME_OFFSET,
+                    itunesdb_constants::CHAPTER_DATA_ATOM_NAME_LEN,
+                ) == itunesdb_constants::CHAPTER_DATA_NAME_ATOM_KEY.as_bytes())
+            {
+                let name_string_len = helpers::get_slice_as_be_u32(
+                    name_atom_idx,
+                    file_as_bytes,
+                    itunesdb_constants::CHAPTER_DATA_NAME_STRING_LENGTH_OFFSET,
+                    itunesdb_constants::CHAPTER_DATA_NAME_STRING_LENGTH_LEN,
+                ) as usize;
+                let name_string_end = name_string_idx + (name_string_len * 2);
+
+                if name_string_end <= chapter_data_end {
+                    chapter.title = String::from_utf16_lossy(&helpers::return_utf16_from_be_bytes(
+                        &file_as_bytes[name_string_idx..name_string_end],
+                    ));
+                }
+            }
+
+            chapters.push(chapter);
+        }
+
+        atom_idx += atom_size;
+    }
+
+    return chapters;
+}
+
+/// Every documented data object (mhod) type. `HandleableDataObjectType` only lists the ones
+/// the parser attaches to a record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataObjectType {
+    Title,
+    FileLocation,
+    Album,
+    Artist,
+    Genre,
+    FileType,
+    EqualizerSetting,
+    Comment,
+    Category,
+    Composer,
+    Grouping,
+    Description,
+    PodcastEnclosureURL,
+    PodcastRSSURL,
+    ChapterData,
+    Subtitle,
+    TVShow,
+    TVEpisodeID,
+    TVNetwork,
+    AlbumArtist,
+    SortArtist,
+    Keywords,
+    TVShowLocale,
+    SortTitle,
+    SortAlbum,
+    SortAlbumArtist,
+    SortComposer,
+    SortTVShow,
+    VideoTrackData,
+    SmartPlaylistData,
+    SmartPlaylistRules,
+    LibraryPlaylistIndex,
+    LibraryPlaylistJumpTable,
+    PlaylistColumns,
+    GeniusPlaylistData,
+    AlbumListAlbum,
+    AlbumListArtist,
+    AlbumListSortArtist,
+    AlbumListPodcastURL,
+    AlbumListTVShow,
+    Unknown(u32),
+}
+
+impl std::fmt::Display for DataObjectType {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let data_object_type_name = match self {
+            DataObjectType::Title => "Song title",
+            DataObjectType::FileLocation => "File location",
+            DataObjectType::Album => "Album",
+            DataObjectType::Artist => "Artist",
+            DataObjectType::Genre => "Genre",
+            DataObjectType::FileType => "Filetype",
+            DataObjectType::EqualizerSetting => "EQ Setting",
+            DataObjectType::Comment => "Comment",
+            DataObjectType::Category => "Podcast Category",
+            DataObjectType::Composer => "Composer",
+            DataObjectType::Grouping => "Grouping",
+            DataObjectType::Description => "Description text",
+            DataObjectType::PodcastEnclosureURL => "Podcast Enclosure URL",
+            DataObjectType::PodcastRSSURL => "Podcast RSS URL",
+            DataObjectType::ChapterData => "Chapter data",
+            DataObjectType::Subtitle => "Subtitle",
+            DataObjectType::TVShow => "Television Show",
+            DataObjectType::TVEpisodeID => "Episode #",
+            DataObjectType::TVNetwork => "TV Show network",
+            DataObjectType::AlbumArtist => "Album Artist",
+            DataObjectType::SortArtist => "Artist (for sorting)",
+            DataObjectType::Keywords => "Track keywords",
+            DataObjectType::TVShowLocale => "TV Show locale",
+            DataObjectType::SortTitle => "Title (for sorting)",
+            DataObjectType::SortAlbum => "Album (for sorting)",
+            DataObjectType::SortAlbumArtist => "Album artist (for sorting)",
+            DataObjectType::SortComposer => "Composer (for sorting)",
+            DataObjectType::SortTVShow => "Television Show (for sorting)",
+            DataObjectType::VideoTrackData => "Unknown video track field, iTunes 7.1+",
+            DataObjectType::SmartPlaylistData => "Smart Playlist data",
+            DataObjectType::SmartPlaylistRules => "Smart Playlist rules",
+            DataObjectType::LibraryPlaylistIndex => "Library Playlist index",
+            DataObjectType::LibraryPlaylistJumpTable => "Library Playlist jump table, iTunes 7.2+",
+            DataObjectType::PlaylistColumns => {
+                "Playlist column definitions or playlist item position"
+            }
+            DataObjectType::GeniusPlaylistData => "Genius playlist data, iTunes 8+",
+            DataObjectType::AlbumListAlbum => "Album (from Album List, iTunes 7.1+ only)",
+            DataObjectType::AlbumListArtist => "Artist (in Album List, iTunes 7.1)",
+            DataObjectType::AlbumListSortArtist => {
+                "Artist (for sorting in Album List) - iTunes 7.1+ only"
+            }
+            DataObjectType::AlbumListPodcastURL => "Podcast URL (in Album List, iTunes 7.1)",
+            DataObjectType::AlbumListTVShow => "TV Show (in Album List)",
+            DataObjectType::Unknown(data_object_type_raw) => {
+                return write!(formatter, "Unknown type (#{})", data_object_type_raw);
+            }
+        };
+
+        return write!(formatter, "{}", data_object_type_name);
     }
+}
+
+pub fn decode_data_object_type(data_object_type_raw: u32) -> DataObjectType {
+    let data_object_type = match data_object_type_raw {
+        1 => DataObjectType::Title,
+        2 => DataObjectType::FileLocation,
+        3 => DataObjectType::Album,
+        4 => DataObjectType::Artist,
+        5 => DataObjectType::Genre,
+        6 => DataObjectType::FileType,
+        7 => DataObjectType::EqualizerSetting,
+        8 => DataObjectType::Comment,
+        9 => DataObjectType::Category,
+        12 => DataObjectType::Composer,
+        13 => DataObjectType::Grouping,
+        14 => DataObjectType::Description,
+        15 => DataObjectType::PodcastEnclosureURL,
+        16 => DataObjectType::PodcastRSSURL,
+        17 => DataObjectType::ChapterData,
+        18 => DataObjectType::Subtitle,
+        19 => DataObjectType::TVShow,
+        20 => DataObjectType::TVEpisodeID,
+        21 => DataObjectType::TVNetwork,
+        22 => DataObjectType::AlbumArtist,
+        23 => DataObjectType::SortArtist,
+        24 => DataObjectType::Keywords,
+        25 => DataObjectType::TVShowLocale,
+        27 => DataObjectType::SortTitle,
+        28 => DataObjectType::SortAlbum,
+        29 => DataObjectType::SortAlbumArtist,
+        30 => DataObjectType::SortComposer,
+        31 => DataObjectType::SortTVShow,
+        32 => DataObjectType::VideoTrackData,
+        50 => DataObjectType::SmartPlaylistData,
+        51 => DataObjectType::SmartPlaylistRules,
+        52 => DataObjectType::LibraryPlaylistIndex,
+        53 => DataObjectType::LibraryPlaylistJumpTable,
+        100 => DataObjectType::PlaylistColumns,
+        102 => DataObjectType::GeniusPlaylistData,
+        200 => DataObjectType::AlbumListAlbum,
+        201 => DataObjectType::AlbumListArtist,
+        202 => DataObjectType::AlbumListSortArtist,
+        203 => DataObjectType::AlbumListPodcastURL,
+        204 => DataObjectType::AlbumListTVShow,
+        // Reported once per type by the caller, databases can have thousands of them
+        _ => DataObjectType::Unknown(data_object_type_raw),
+    };
 
     return data_object_type;
 }
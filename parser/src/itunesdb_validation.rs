@@ -0,0 +1,723 @@
+/**
+ * File: itunesdb_validation.rs
+ *
+ * Other tools write the iTunesDB too: libgpod (and gtkpod, Rhythmbox or Amarok on top of it), or
+ * scripts fixing up a database by hand. This parser only looks for chunk keys, so it reads
+ * databases that libgpod, which follows every chunk's lengths, gives up on. Walking the chunks the
+ * same way libgpod does shows where such a database doesn't hold together.
+ *
+ */
+use serde::Serialize;
+
+use crate::constants::itunesdb_constants;
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+#[derive(Serialize)]
+pub struct ValidationIssue {
+    /// Where the chunk starts in the file
+    pub offset: usize,
+    /// The chunk's key, e.g. "mhit"
+    pub chunk: String,
+    /// "Unexpected chunk", "Truncated chunk", "Length out of bounds", "Wrong header length",
+    /// "Wrong file length", "Wrong child count", "Missing dataset", "Duplicate dataset", "Dataset order"
+    /// or "Master playlist not first"
+    pub category: String,
+    pub message: String,
+}
+
+struct ChunkHeader {
+    key: String,
+    header_len: u32,
+    /// Lists (mhlt, mhlp, mhla, mhli) have the number of items here instead
+    total_len_or_num_items: u32,
+}
+
+/// What `validate_chunk` looks for
+struct ExpectedChunk<'a> {
+    key: &'a str,
+    /// The header length libgpod and iTunes write, if there's only one
+    header_len: Option<u32>,
+    is_list: bool,
+}
+
+/// A list and its items. Field offsets and lengths are in the item's header
+struct ListLayout<'a> {
+    list_key: &'a str,
+    item_key: &'a str,
+    num_data_objects_field: (usize, usize),
+    /// Playlists only
+    num_playlist_items_field: Option<(usize, usize)>,
+    is_master_playlist_first: bool,
+}
+
+fn add_issue(
+    validation_issues: &mut Vec<ValidationIssue>,
+    offset: usize,
+    chunk: &str,
+    category: &str,
+    message: String,
+) {
+    validation_issues.push(ValidationIssue {
+        offset,
+        chunk: chunk.to_string(),
+        category: category.to_string(),
+        message,
+    });
+}
+
+/// `None` if there's no room for a header before `parent_end`
+fn read_chunk_header(itunesdb_file_as_bytes: &[u8], chunk_idx: usize, parent_end: usize) -> Option<ChunkHeader> {
+    if chunk_idx + itunesdb_constants::CHUNK_MIN_HEADER_LEN > parent_end {
+        return None;
+    }
+
+    return Some(ChunkHeader {
+        // Whatever is there instead of a key can be any bytes, keep them readable in the report
+        key: itunesdb_file_as_bytes[chunk_idx..chunk_idx + itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE]
+            .iter()
+            .map(|key_byte| if key_byte.is_ascii_graphic() { *key_byte as char } else { '.' })
+            .collect(),
+        header_len: helpers::get_slice_as_le_u32(
+            chunk_idx,
+            itunesdb_file_as_bytes,
+            itunesdb_constants::CHUNK_HEADER_LEN_OFFSET,
+            itunesdb_constants::CHUNK_HEADER_LEN_LEN,
+        ),
+        total_len_or_num_items: helpers::get_slice_as_le_u32(
+            chunk_idx,
+            itunesdb_file_as_bytes,
+            itunesdb_constants::CHUNK_TOTAL_LEN_OFFSET,
+            itunesdb_constants::CHUNK_TOTAL_LEN_LEN,
+        ),
+    });
+}
+
+/// `None` if the field isn't inside the chunk's header, e.g. in a header cut short by another tool
+fn get_header_field(
+    itunesdb_file_as_bytes: &[u8],
+    chunk_idx: usize,
+    chunk_header: &ChunkHeader,
+    field_offset: usize,
+    field_len: usize,
+) -> Option<u32> {
+    if field_offset + field_len > chunk_header.header_len as usize {
+        return None;
+    }
+
+    return Some(helpers::get_slice_as_le_u32(chunk_idx, itunesdb_file_as_bytes, field_offset, field_len));
+}
+
+/// Checks the chunk at `chunk_idx` is the expected chunk and fits inside its parent.
+/// Returns `None` if it isn't, since none of what follows it can be found then
+fn validate_chunk(
+    itunesdb_file_as_bytes: &[u8],
+    chunk_idx: usize,
+    parent_end: usize,
+    parent_key: &str,
+    expected_chunk: &ExpectedChunk,
+    validation_issues: &mut Vec<ValidationIssue>,
+) -> Option<ChunkHeader> {
+    let expected_key = expected_chunk.key;
+
+    let chunk_header = match read_chunk_header(itunesdb_file_as_bytes, chunk_idx, parent_end) {
+        Some(chunk_header) => chunk_header,
+        None => {
+            add_issue(
+                validation_issues,
+                chunk_idx,
+                parent_key,
+                "Truncated chunk",
+                format!(
+                    "{} bytes left at the end of the '{}', too few for a '{}'",
+                    parent_end - chunk_idx,
+                    parent_key,
+                    expected_key
+                ),
+            );
+            return None;
+        }
+    };
+
+    if chunk_header.key != expected_key {
+        add_issue(
+            validation_issues,
+            chunk_idx,
+            &chunk_header.key,
+            "Unexpected chunk",
+            format!(
+                "Found a '{}' where a '{}' was expected, the rest of the '{}' was skipped",
+                chunk_header.key, expected_key, parent_key
+            ),
+        );
+        return None;
+    }
+
+    let chunk_len = if expected_chunk.is_list {
+        chunk_header.header_len
+    } else {
+        chunk_header.total_len_or_num_items
+    };
+
+    if (chunk_header.header_len as usize) < itunesdb_constants::CHUNK_MIN_HEADER_LEN
+        || chunk_header.header_len > chunk_len
+    {
+        add_issue(
+            validation_issues,
+            chunk_idx,
+            &chunk_header.key,
+            "Wrong header length",
+            format!(
+                "Header length of {} bytes doesn't fit a total length of {} bytes",
+                chunk_header.header_len, chunk_len
+            ),
+        );
+        return None;
+    }
+
+    if chunk_idx + chunk_len as usize > parent_end {
+        add_issue(
+            validation_issues,
+            chunk_idx,
+            &chunk_header.key,
+            "Length out of bounds",
+            format!(
+                "Ends {} bytes past the end of its '{}'",
+                chunk_idx + chunk_len as usize - parent_end,
+                parent_key
+            ),
+        );
+        return None;
+    }
+
+    if let Some(expected_header_len) = expected_chunk.header_len {
+        if chunk_header.header_len != expected_header_len {
+            add_issue(
+                validation_issues,
+                chunk_idx,
+                &chunk_header.key,
+                "Wrong header length",
+                format!(
+                    "Header length is {:#X}, libgpod and iTunes write {:#X}",
+                    chunk_header.header_len, expected_header_len
+                ),
+            );
+        }
+    }
+
+    return Some(chunk_header);
+}
+
+/// Compares what the header declares with what was actually found
+fn validate_child_count(
+    itunesdb_file_as_bytes: &[u8],
+    chunk_idx: usize,
+    chunk_header: &ChunkHeader,
+    num_children_field: (usize, usize),
+    num_children_found: u32,
+    children_name: &str,
+    validation_issues: &mut Vec<ValidationIssue>,
+) {
+    if let Some(num_children_declared) =
+        get_header_field(itunesdb_file_as_bytes, chunk_idx, chunk_header, num_children_field.0, num_children_field.1)
+    {
+        if num_children_declared != num_children_found {
+            add_issue(
+                validation_issues,
+                chunk_idx,
+                &chunk_header.key,
+                "Wrong child count",
+                format!(
+                    "Declares {} {}, has {}",
+                    num_children_declared, children_name, num_children_found
+                ),
+            );
+        }
+    }
+}
+
+/// Walks the data objects of a track item, playlist, playlist item, album item or artist item.
+/// Playlists hold their playlist items too, which are counted separately
+fn validate_item(
+    itunesdb_file_as_bytes: &[u8],
+    item_idx: usize,
+    item_header: &ChunkHeader,
+    num_data_objects_field: (usize, usize),
+    num_playlist_items_field: Option<(usize, usize)>,
+    validation_issues: &mut Vec<ValidationIssue>,
+) {
+    let item_end = item_idx + item_header.total_len_or_num_items as usize;
+
+    let mut num_data_objects_found: u32 = 0;
+    let mut num_playlist_items_found: u32 = 0;
+
+    let mut child_idx = item_idx + item_header.header_len as usize;
+
+    while child_idx < item_end {
+        let is_playlist_item = num_playlist_items_field.is_some()
+            && itunesdb_file_as_bytes[child_idx..]
+                .starts_with(itunesdb_constants::PLAYLIST_ITEM_KEY.as_bytes());
+
+        let child_header = if is_playlist_item {
+            validate_chunk(
+                itunesdb_file_as_bytes,
+                child_idx,
+                item_end,
+                &item_header.key,
+                &ExpectedChunk {
+                    key: itunesdb_constants::PLAYLIST_ITEM_KEY,
+                    header_len: Some(itunesdb_constants::PLAYLIST_ITEM_LIBGPOD_HEADER_LEN),
+                    is_list: false,
+                },
+                validation_issues,
+            )
+        } else {
+            validate_chunk(
+                itunesdb_file_as_bytes,
+                child_idx,
+                item_end,
+                &item_header.key,
+                &ExpectedChunk {
+                    key: itunesdb_constants::DATA_OBJECT_KEY,
+                    header_len: Some(itunesdb_constants::DATA_OBJECT_LIBGPOD_HEADER_LEN),
+                    is_list: false,
+                },
+                validation_issues,
+            )
+        };
+
+        let child_header = match child_header {
+            Some(child_header) => child_header,
+            None => break,
+        };
+
+        if is_playlist_item {
+            validate_item(
+                itunesdb_file_as_bytes,
+                child_idx,
+                &child_header,
+                (
+                    itunesdb_constants::PLAYLIST_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                    itunesdb_constants::PLAYLIST_ITEM_NUM_DATA_OBJECTS_LEN,
+                ),
+                None,
+                validation_issues,
+            );
+
+            num_playlist_items_found += 1;
+        } else {
+            num_data_objects_found += 1;
+        }
+
+        child_idx += child_header.total_len_or_num_items as usize;
+    }
+
+    validate_child_count(
+        itunesdb_file_as_bytes,
+        item_idx,
+        item_header,
+        num_data_objects_field,
+        num_data_objects_found,
+        "data objects",
+        validation_issues,
+    );
+
+    if let Some(num_playlist_items_field) = num_playlist_items_field {
+        validate_child_count(
+            itunesdb_file_as_bytes,
+            item_idx,
+            item_header,
+            num_playlist_items_field,
+            num_playlist_items_found,
+            "playlist items",
+            validation_issues,
+        );
+    }
+}
+
+/// A dataset holds a single list, whose items take up the rest of the dataset.
+/// In the Playlist List (and Podcast List) the first playlist has to be the master playlist
+fn validate_list(
+    itunesdb_file_as_bytes: &[u8],
+    list_idx: usize,
+    dataset_end: usize,
+    list_layout: &ListLayout,
+    validation_issues: &mut Vec<ValidationIssue>,
+) {
+    let list_key = list_layout.list_key;
+    let item_key = list_layout.item_key;
+
+    let list_header = match validate_chunk(
+        itunesdb_file_as_bytes,
+        list_idx,
+        dataset_end,
+        itunesdb_constants::DATASET_KEY,
+        &ExpectedChunk {
+            key: list_key,
+            header_len: Some(itunesdb_constants::LIST_LIBGPOD_HEADER_LEN),
+            is_list: true,
+        },
+        validation_issues,
+    ) {
+        Some(list_header) => list_header,
+        None => return,
+    };
+
+    let mut num_items_found: u32 = 0;
+    let mut item_idx = list_idx + list_header.header_len as usize;
+
+    while item_idx < dataset_end {
+        let item_header = match validate_chunk(
+            itunesdb_file_as_bytes,
+            item_idx,
+            dataset_end,
+            list_key,
+            &ExpectedChunk {
+                key: item_key,
+                header_len: None,
+                is_list: false,
+            },
+            validation_issues,
+        ) {
+            Some(item_header) => item_header,
+            None => break,
+        };
+
+        if list_layout.is_master_playlist_first && num_items_found == 0 {
+            let is_master_playlist = get_header_field(
+                itunesdb_file_as_bytes,
+                item_idx,
+                &item_header,
+                itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_OFFSET,
+                itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_LEN,
+            ) == Some(1);
+
+            if !is_master_playlist {
+                add_issue(
+                    validation_issues,
+                    item_idx,
+                    &item_header.key,
+                    "Master playlist not first",
+                    "The first playlist isn't the master (Library) playlist".to_string(),
+                );
+            }
+        }
+
+        validate_item(
+            itunesdb_file_as_bytes,
+            item_idx,
+            &item_header,
+            list_layout.num_data_objects_field,
+            list_layout.num_playlist_items_field,
+            validation_issues,
+        );
+
+        num_items_found += 1;
+        item_idx += item_header.total_len_or_num_items as usize;
+    }
+
+    if list_header.total_len_or_num_items != num_items_found {
+        add_issue(
+            validation_issues,
+            list_idx,
+            list_key,
+            "Wrong child count",
+            format!(
+                "Declares {} '{}' items, has {}",
+                list_header.total_len_or_num_items, item_key, num_items_found
+            ),
+        );
+    }
+}
+
+/// libgpod looks the datasets up by type, and only reads the first of each. The iPod itself
+/// expects the Track List before any playlists, and the Podcast List before the Playlist List
+fn validate_dataset_order(dataset_types: &[(usize, u32)], validation_issues: &mut Vec<ValidationIssue>) {
+    for (dataset_pos, (dataset_idx, dataset_type)) in dataset_types.iter().enumerate() {
+        if dataset_types[..dataset_pos]
+            .iter()
+            .any(|(_, prev_dataset_type)| prev_dataset_type == dataset_type)
+        {
+            add_issue(
+                validation_issues,
+                *dataset_idx,
+                itunesdb_constants::DATASET_KEY,
+                "Duplicate dataset",
+                format!(
+                    "Another {}, only the first one is read",
+                    itunesdb::parse_dataset_type(*dataset_type)
+                ),
+            );
+        }
+    }
+
+    let get_first_dataset = |dataset_type: u32| -> Option<(usize, usize)> {
+        return dataset_types
+            .iter()
+            .position(|(_, found_dataset_type)| *found_dataset_type == dataset_type)
+            .map(|dataset_pos| (dataset_pos, dataset_types[dataset_pos].0));
+    };
+
+    let track_list = get_first_dataset(itunesdb::HandleableDatasetType::Tracks as u32);
+    let playlist_list = get_first_dataset(itunesdb::HandleableDatasetType::Playlists as u32);
+    let podcast_list = get_first_dataset(itunesdb::HandleableDatasetType::Podcasts as u32);
+
+    if track_list.is_none() {
+        add_issue(
+            validation_issues,
+            0,
+            itunesdb_constants::DATABASE_OBJECT_KEY,
+            "Missing dataset",
+            "No Track List (dataset type 1), libgpod can't read the database without one".to_string(),
+        );
+    }
+
+    if playlist_list.is_none() && podcast_list.is_none() {
+        add_issue(
+            validation_issues,
+            0,
+            itunesdb_constants::DATABASE_OBJECT_KEY,
+            "Missing dataset",
+            "No Playlist List (dataset type 2 or 3), so there's no master playlist".to_string(),
+        );
+    }
+
+    if let Some((track_list_pos, track_list_idx)) = track_list {
+        for (playlists_pos, playlists_type) in [
+            (playlist_list, itunesdb::HandleableDatasetType::Playlists as u32),
+            (podcast_list, itunesdb::HandleableDatasetType::Podcasts as u32),
+        ] {
+            if let Some((playlists_pos, _)) = playlists_pos {
+                if playlists_pos < track_list_pos {
+                    add_issue(
+                        validation_issues,
+                        track_list_idx,
+                        itunesdb_constants::DATASET_KEY,
+                        "Dataset order",
+                        format!(
+                            "The Track List comes after the {}, it should come before any playlists",
+                            itunesdb::parse_dataset_type(playlists_type)
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    if let (Some((playlist_list_pos, _)), Some((podcast_list_pos, podcast_list_idx))) = (playlist_list, podcast_list) {
+        if podcast_list_pos > playlist_list_pos {
+            add_issue(
+                validation_issues,
+                podcast_list_idx,
+                itunesdb_constants::DATASET_KEY,
+                "Dataset order",
+                "The Podcast List comes after the Playlist List, the iPod won't show podcasts unless it comes before".to_string(),
+            );
+        }
+    }
+}
+
+/// Follows every chunk's header and total length from the database object down to the data objects,
+/// the way libgpod reads the database. Datasets of types libgpod doesn't know are skipped, like it does
+pub fn validate_itunesdb(itunesdb_file_as_bytes: &[u8]) -> Vec<ValidationIssue> {
+    let mut validation_issues: Vec<ValidationIssue> = Vec::new();
+
+    let database_header = match read_chunk_header(itunesdb_file_as_bytes, 0, itunesdb_file_as_bytes.len()) {
+        Some(database_header) if database_header.key == itunesdb_constants::DATABASE_OBJECT_KEY => database_header,
+        _ => {
+            add_issue(
+                &mut validation_issues,
+                0,
+                "",
+                "Unexpected chunk",
+                "The file doesn't start with a database object ('mhbd')".to_string(),
+            );
+            return validation_issues;
+        }
+    };
+
+    if database_header.header_len as usize > itunesdb_file_as_bytes.len() {
+        add_issue(
+            &mut validation_issues,
+            0,
+            &database_header.key,
+            "Length out of bounds",
+            format!(
+                "Header length of {} bytes, the file is only {} bytes",
+                database_header.header_len,
+                itunesdb_file_as_bytes.len()
+            ),
+        );
+        return validation_issues;
+    }
+
+    if database_header.header_len < itunesdb_constants::DATABASE_OBJECT_MIN_HEADER_LEN {
+        add_issue(
+            &mut validation_issues,
+            0,
+            &database_header.key,
+            "Wrong header length",
+            format!(
+                "Header length of {} bytes, the oldest databases already have {}",
+                database_header.header_len,
+                itunesdb_constants::DATABASE_OBJECT_MIN_HEADER_LEN
+            ),
+        );
+        return validation_issues;
+    }
+
+    if database_header.total_len_or_num_items as usize != itunesdb_file_as_bytes.len() {
+        add_issue(
+            &mut validation_issues,
+            0,
+            &database_header.key,
+            "Wrong file length",
+            format!(
+                "Declares a total length of {} bytes, the file is {} bytes",
+                database_header.total_len_or_num_items,
+                itunesdb_file_as_bytes.len()
+            ),
+        );
+    }
+
+    let database_end = std::cmp::min(
+        database_header.total_len_or_num_items as usize,
+        itunesdb_file_as_bytes.len(),
+    );
+
+    // Where each dataset starts, and its type
+    let mut dataset_types: Vec<(usize, u32)> = Vec::new();
+
+    let mut dataset_idx = database_header.header_len as usize;
+
+    while dataset_idx < database_end {
+        let dataset_header = match validate_chunk(
+            itunesdb_file_as_bytes,
+            dataset_idx,
+            database_end,
+            itunesdb_constants::DATABASE_OBJECT_KEY,
+            &ExpectedChunk {
+                key: itunesdb_constants::DATASET_KEY,
+                header_len: Some(itunesdb_constants::DATASET_LIBGPOD_HEADER_LEN),
+                is_list: false,
+            },
+            &mut validation_issues,
+        ) {
+            Some(dataset_header) => dataset_header,
+            None => break,
+        };
+
+        // Only the first byte, like the parser
+        let dataset_type = if itunesdb_constants::DATASET_TYPE_OFFSET + itunesdb_constants::DATASET_TYPE_LEN
+            <= dataset_header.header_len as usize
+        {
+            helpers::get_slice_from_offset_with_len(
+                dataset_idx,
+                itunesdb_file_as_bytes,
+                itunesdb_constants::DATASET_TYPE_OFFSET,
+                itunesdb_constants::DATASET_TYPE_LEN,
+            )[0] as u32
+        } else {
+            0
+        };
+
+        let list_idx = dataset_idx + dataset_header.header_len as usize;
+        let dataset_end = dataset_idx + dataset_header.total_len_or_num_items as usize;
+
+        if dataset_type == itunesdb::HandleableDatasetType::Tracks as u32 {
+            validate_list(
+                itunesdb_file_as_bytes,
+                list_idx,
+                dataset_end,
+                &ListLayout {
+                    list_key: itunesdb_constants::TRACKLIST_KEY,
+                    item_key: itunesdb_constants::TRACK_ITEM_KEY,
+                    num_data_objects_field: (
+                        itunesdb_constants::TRACK_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_NUM_DATA_OBJECTS_LEN,
+                    ),
+                    num_playlist_items_field: None,
+                    is_master_playlist_first: false,
+                },
+                &mut validation_issues,
+            );
+        } else if dataset_type == itunesdb::HandleableDatasetType::Playlists as u32
+            || dataset_type == itunesdb::HandleableDatasetType::Podcasts as u32
+            || dataset_type == itunesdb::HandleableDatasetType::SmartPlaylists as u32
+        {
+            validate_list(
+                itunesdb_file_as_bytes,
+                list_idx,
+                dataset_end,
+                &ListLayout {
+                    list_key: itunesdb_constants::PLAYLIST_LIST_KEY,
+                    item_key: itunesdb_constants::PLAYLIST_KEY,
+                    num_data_objects_field: (
+                        itunesdb_constants::PLAYLIST_NUM_DATA_OBJECTS_OFFSET,
+                        itunesdb_constants::PLAYLIST_NUM_DATA_OBJECTS_LEN,
+                    ),
+                    num_playlist_items_field: Some((
+                        itunesdb_constants::PLAYLIST_NUM_PLAYLIST_ITEMS_OFFSET,
+                        itunesdb_constants::PLAYLIST_NUM_PLAYLIST_ITEMS_LEN,
+                    )),
+                    is_master_playlist_first: dataset_type != itunesdb::HandleableDatasetType::SmartPlaylists as u32,
+                },
+                &mut validation_issues,
+            );
+        } else if dataset_type == itunesdb::HandleableDatasetType::Albums as u32 {
+            validate_list(
+                itunesdb_file_as_bytes,
+                list_idx,
+                dataset_end,
+                &ListLayout {
+                    list_key: itunesdb_constants::ALBUM_LIST_KEY,
+                    item_key: itunesdb_constants::ALBUM_ITEM_KEY,
+                    num_data_objects_field: (
+                        itunesdb_constants::ALBUM_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                        itunesdb_constants::ALBUM_ITEM_NUM_DATA_OBJECTS_LEN,
+                    ),
+                    num_playlist_items_field: None,
+                    is_master_playlist_first: false,
+                },
+                &mut validation_issues,
+            );
+        } else if dataset_type == itunesdb::HandleableDatasetType::Artists as u32 {
+            validate_list(
+                itunesdb_file_as_bytes,
+                list_idx,
+                dataset_end,
+                &ListLayout {
+                    list_key: itunesdb_constants::ARTIST_LIST_KEY,
+                    item_key: itunesdb_constants::ARTIST_ITEM_KEY,
+                    num_data_objects_field: (
+                        itunesdb_constants::ARTIST_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                        itunesdb_constants::ARTIST_ITEM_NUM_DATA_OBJECTS_LEN,
+                    ),
+                    num_playlist_items_field: None,
+                    is_master_playlist_first: false,
+                },
+                &mut validation_issues,
+            );
+        }
+
+        dataset_types.push((dataset_idx, dataset_type));
+        dataset_idx = dataset_end;
+    }
+
+    validate_child_count(
+        itunesdb_file_as_bytes,
+        0,
+        &database_header,
+        (
+            itunesdb_constants::DATABASE_OBJECT_NUM_DATASETS_OFFSET,
+            itunesdb_constants::DATABASE_OBJECT_NUM_DATASETS_LEN,
+        ),
+        dataset_types.len() as u32,
+        "datasets",
+        &mut validation_issues,
+    );
+
+    validate_dataset_order(&dataset_types, &mut validation_issues);
+
+    return validation_issues;
+}
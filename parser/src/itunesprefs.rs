@@ -2,9 +2,31 @@
 /**
  * File: itunesprefs.rs
  *
- * Provides functionality around working with the Photo Database internals file. Analogue of 'itunesdb.rs'
+ * Provides functionality around working with the iTunesPrefs file. Analogue of 'itunesdb.rs'
  * 
  */
+use serde::Serialize;
+
+/// The iPod's settings in iTunes, from iPod_Control/iTunes/iTunesPrefs
+#[derive(Serialize)]
+pub struct ItunesPrefs {
+    pub is_ipod_set_up: bool,
+    pub auto_open_itunes: bool,
+    /// "MANUAL" means the user chose to manually manage music
+    pub song_sync_mode: String,
+    pub podcast_sync_mode: String,
+    pub sync_selection: String,
+    pub only_update_checked_songs: bool,
+    /// Whether the iPod can be used as an external disk
+    pub disk_use_enabled: bool,
+    pub show_artwork: bool,
+}
+
+impl ItunesPrefs {
+    pub fn is_music_manually_managed(&self) -> bool {
+        return self.song_sync_mode == SyncSetting::MANUAL.to_string();
+    }
+}
 
  #[derive(Debug)] 
  pub enum SyncSetting {
@@ -86,10 +108,10 @@ impl std::fmt::Display for SyncSelection{
 
  pub fn disk_use_enabled(disk_use_setting_raw : u32) -> bool {
 
-    if disk_use_setting_raw == 01 {
+    if disk_use_setting_raw == 1 {
         return true;
     }
-    else if disk_use_setting_raw == 1 {
+    else if disk_use_setting_raw == 0 {
         return false;
     }
     else {
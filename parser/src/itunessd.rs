@@ -4,6 +4,8 @@
  * 
  * 
  */
+use crate::constants::itunessd_constants;
+use crate::itunesdb;
 
  #[derive(Debug)] 
  pub enum iTunesSDFileType
@@ -29,4 +31,40 @@ pub fn decode_itunessd_file_type(file_type: u32) -> iTunesSDFileType
         0x04 => iTunesSDFileType::WAV,
         _ => panic!("Unknown iTunesSD file type: {}", file_type)
     }
-}
\ No newline at end of file
+}
+
+/// Same names as `itunesdb::decode_track_codec`, so the exports line up
+pub fn decode_itunessd_codec(file_type: &iTunesSDFileType) -> String
+{
+    match file_type
+    {
+        iTunesSDFileType::MP3 => "MP3".to_string(),
+        iTunesSDFileType::AAC => "AAC".to_string(),
+        iTunesSDFileType::WAV => "PCM (WAV)".to_string()
+    }
+}
+
+/// The iTunesDB stores the volume adjustment between -255 and +255 (see `Song`),
+/// the iTunesSD as a percentage between -100 and +100
+pub fn decode_itunessd_volume_adjustment(volume_raw: u32) -> i32
+{
+    let volume_percent = if (volume_raw & itunessd_constants::ITUNESSD_VOLUME_SIGN_BIT) != 0 {
+        (volume_raw as i32) - ((itunessd_constants::ITUNESSD_VOLUME_SIGN_BIT as i32) << 1)
+    } else {
+        volume_raw as i32
+    };
+
+    return (volume_percent * 255) / 100;
+}
+
+pub fn decode_itunessd_playlist_type(playlist_type_raw: u32) -> itunesdb::PlaylistKind
+{
+    match playlist_type_raw
+    {
+        1 => itunesdb::PlaylistKind::Master,
+        2 => itunesdb::PlaylistKind::Regular,
+        3 => itunesdb::PlaylistKind::Podcasts,
+        4 => itunesdb::PlaylistKind::Audiobooks,
+        _ => itunesdb::PlaylistKind::Unknown
+    }
+}
@@ -0,0 +1,368 @@
+/**
+ * File: json_schema.rs
+ *
+ * The JSON Schema (draft 2020-12) of the songs, podcasts and playlists in the JSON exports, so pipelines
+ * that ingest them can validate the files, or generate types from the schema instead of from a sample file.
+ * The field descriptions are the doc comments of the structs in itunesdb.rs.
+ *
+ * Every record carries the `schema_version` it was written with, and the layout is held to the
+ * compatibility policy in export_schema_constants.rs, so tools built on these files don't silently break.
+ *
+ */
+use serde::Serialize;
+
+use crate::constants::export_schema_constants;
+use crate::itunesdb;
+
+/// The record types which get a `schema_version` field
+const VERSIONED_RECORD_TYPES: [&str; 3] = ["Song", "Podcast", "Playlist"];
+
+/// A record as written to the exports: the record's own fields, preceded by `schema_version`
+#[derive(Serialize)]
+pub struct VersionedRecord<'a, T: Serialize> {
+    pub schema_version: &'static str,
+    #[serde(flatten)]
+    pub record: &'a T,
+}
+
+pub fn get_versioned_records<T: Serialize>(records: &[T]) -> Vec<VersionedRecord<'_, T>> {
+    return records
+        .iter()
+        .map(|record| VersionedRecord {
+            schema_version: export_schema_constants::EXPORT_SCHEMA_VERSION,
+            record,
+        })
+        .collect();
+}
+
+/// One schema for the three files, with the record types under `$defs` (`Song`, `Podcast`, `Playlist`, ...)
+/// and a property per file, each an array of its record type
+pub fn build_json_schema() -> serde_json::Value {
+    let mut schema_generator = schemars::generate::SchemaSettings::draft2020_12().into_generator();
+
+    let song_schema = schema_generator.subschema_for::<itunesdb::Song>();
+    let podcast_schema = schema_generator.subschema_for::<itunesdb::Podcast>();
+    let playlist_schema = schema_generator.subschema_for::<itunesdb::Playlist>();
+
+    let mut record_schemas = schema_generator.take_definitions(true);
+
+    for record_type in VERSIONED_RECORD_TYPES.iter() {
+        if let Some(serde_json::Value::Object(record_schema)) = record_schemas.get_mut(*record_type) {
+            if let Some(serde_json::Value::Object(record_properties)) = record_schema.get_mut("properties") {
+                record_properties.insert(
+                    "schema_version".to_string(),
+                    serde_json::json!({
+                        "description": "Version of the export schema the record was written with, \"major.minor\"",
+                        "type": "string",
+                        "const": export_schema_constants::EXPORT_SCHEMA_VERSION,
+                    }),
+                );
+            }
+
+            if let Some(serde_json::Value::Array(required_fields)) = record_schema.get_mut("required") {
+                required_fields.insert(0, serde_json::Value::from("schema_version"));
+            }
+        }
+    }
+
+    return serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "iTunesDB exports",
+        "description": format!(
+            "The records in music.json, podcasts.json and playlists.json. Each file is an array of them. Schema version {}",
+            export_schema_constants::EXPORT_SCHEMA_VERSION
+        ),
+        "type": "object",
+        "properties": {
+            "music.json": {
+                "type": "array",
+                "items": song_schema,
+            },
+            "podcasts.json": {
+                "type": "array",
+                "items": podcast_schema,
+            },
+            "playlists.json": {
+                "type": "array",
+                "items": playlist_schema,
+            },
+        },
+        "$defs": record_schemas,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::smart_playlist;
+
+    /// A record as it's written to the JSON exports, `schema_version` included
+    fn get_exported_record<T: Serialize>(record: &T) -> serde_json::Value {
+        return serde_json::to_value(get_versioned_records(std::slice::from_ref(record))).unwrap()[0].clone();
+    }
+
+    /// The JSON type of a field, e.g. "string", "integer|null", "array<Chapter>" or "SmartPlaylistRules|null"
+    fn get_property_type(property_schema: &serde_json::Value) -> String {
+        if let Some(ref_path) = property_schema.get("$ref").and_then(|ref_path| ref_path.as_str()) {
+            return ref_path.rsplit('/').next().unwrap_or_default().to_string();
+        }
+
+        if let Some(any_of) = property_schema.get("anyOf").and_then(|any_of| any_of.as_array()) {
+            return any_of.iter().map(get_property_type).collect::<Vec<String>>().join("|");
+        }
+
+        return match property_schema.get("type") {
+            Some(serde_json::Value::Array(property_types)) => property_types
+                .iter()
+                .filter_map(|property_type| property_type.as_str())
+                .collect::<Vec<&str>>()
+                .join("|"),
+            Some(serde_json::Value::String(property_type)) if property_type == "array" => format!(
+                "array<{}>",
+                property_schema.get("items").map(get_property_type).unwrap_or_default()
+            ),
+            Some(serde_json::Value::String(property_type)) => property_type.to_string(),
+            _ => "any".to_string(),
+        };
+    }
+
+    /// Whether the records still follow the compatibility policy, see export_schema_constants.rs:
+    /// no field of `EXPORT_SCHEMA_FIELDS` was removed, renamed or given another type, every new field is listed there,
+    /// and the ones added in a minor version are optional. Prints whatever breaks it
+    fn is_schema_compatible() -> bool {
+        let json_schema = build_json_schema();
+        let mut is_compatible = true;
+
+        for (record_type, record_fields) in export_schema_constants::EXPORT_SCHEMA_FIELDS.iter() {
+            let record_schema = &json_schema["$defs"][*record_type];
+
+            let record_properties = match record_schema.get("properties").and_then(|properties| properties.as_object()) {
+                Some(record_properties) => record_properties,
+                None => {
+                    eprintln!("Export schema: {} was removed or renamed, that needs a new major version", record_type);
+                    is_compatible = false;
+                    continue;
+                }
+            };
+
+            let required_fields: Vec<&str> = record_schema
+                .get("required")
+                .and_then(|required_fields| required_fields.as_array())
+                .map(|required_fields| required_fields.iter().filter_map(|field_name| field_name.as_str()).collect())
+                .unwrap_or_default();
+
+            for (field_name, field_type, added_in_version) in record_fields.iter() {
+                match record_properties.get(*field_name) {
+                    Some(property_schema) => {
+                        let property_type = get_property_type(property_schema);
+
+                        if property_type != *field_type {
+                            eprintln!(
+                                "Export schema: {}.{} changed from {} to {}, that needs a new major version",
+                                record_type, field_name, field_type, property_type
+                            );
+                            is_compatible = false;
+                        }
+
+                        if !added_in_version.ends_with(".0") && required_fields.contains(field_name) {
+                            eprintln!(
+                                "Export schema: {}.{} was added in {}, it needs #[serde(default)] to be optional",
+                                record_type, field_name, added_in_version
+                            );
+                            is_compatible = false;
+                        }
+                    }
+                    None => {
+                        eprintln!(
+                            "Export schema: {}.{} was removed or renamed, that needs a new major version",
+                            record_type, field_name
+                        );
+                        is_compatible = false;
+                    }
+                }
+            }
+
+            for field_name in record_properties.keys() {
+                if !record_fields.iter().any(|(listed_field_name, _, _)| listed_field_name == field_name) {
+                    eprintln!(
+                        "Export schema: {}.{} is new, add it to EXPORT_SCHEMA_FIELDS and bump the minor version of EXPORT_SCHEMA_VERSION",
+                        record_type, field_name
+                    );
+                    is_compatible = false;
+                }
+            }
+        }
+
+        if let Some(record_schemas) = json_schema["$defs"].as_object() {
+            for record_type in record_schemas.keys() {
+                if !export_schema_constants::EXPORT_SCHEMA_FIELDS
+                    .iter()
+                    .any(|(listed_record_type, _)| listed_record_type == record_type)
+                {
+                    eprintln!(
+                        "Export schema: {} is new, add its fields to EXPORT_SCHEMA_FIELDS and bump the minor version of EXPORT_SCHEMA_VERSION",
+                        record_type
+                    );
+                    is_compatible = false;
+                }
+            }
+        }
+
+        return is_compatible;
+    }
+
+    fn get_record_fields(record_type: &str) -> &'static [export_schema_constants::ExportSchemaField] {
+        return export_schema_constants::EXPORT_SCHEMA_FIELDS
+            .iter()
+            .find(|(listed_record_type, _)| *listed_record_type == record_type)
+            .map(|(_, record_fields)| *record_fields)
+            .unwrap();
+    }
+
+    fn build_test_song() -> itunesdb::Song {
+        return itunesdb::Song {
+            song_title: "Song 2".to_string(),
+            song_artist: "Blur".to_string(),
+            song_added_to_library_ts: chrono::DateTime::from_timestamp(1_000_000_000, 0),
+            file_size_bytes: 5_000_000_000,
+            file_size_is_estimated: true,
+            ..Default::default()
+        };
+    }
+
+    fn build_test_podcast() -> itunesdb::Podcast {
+        return itunesdb::Podcast {
+            podcast_title: "Episode 1".to_string(),
+            chapters: vec![itunesdb::Chapter {
+                start_ms: 1000,
+                title: "Intro".to_string(),
+            }],
+            ..Default::default()
+        };
+    }
+
+    fn build_test_playlist() -> itunesdb::Playlist {
+        return itunesdb::Playlist {
+            playlist_name: "Britpop".to_string(),
+            is_smart_playlist: true,
+            playlist_kind: itunesdb::PlaylistKind::Smart,
+            smart_playlist_rules: Some(smart_playlist::SmartPlaylistRules {
+                match_any: false,
+                rules: vec![smart_playlist::SmartPlaylistRule {
+                    field: smart_playlist::SmartPlaylistRuleField::Artist as u32,
+                    field_name: "Artist".to_string(),
+                    action: 0x01000002,
+                    action_name: "contains".to_string(),
+                    string_value: Some("Blur".to_string()),
+                    from_value: 0,
+                    from_date: 0,
+                    from_units: 0,
+                    to_value: 0,
+                    to_date: 0,
+                    to_units: 0,
+                }],
+            }),
+            column_settings: Some(itunesdb::PlaylistColumnSettings {
+                sort_type: 3,
+                columns: vec![itunesdb::PlaylistColumn {
+                    column_id: 2,
+                    column_name: "Name".to_string(),
+                    width_px: 200,
+                    is_sort_reversed: false,
+                }],
+            }),
+            track_ids: vec![1, 2, 3],
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn exported_records_follow_the_compatibility_policy() {
+        assert!(is_schema_compatible());
+    }
+
+    #[test]
+    fn exported_records_read_back_the_same() {
+        let exported_song = get_exported_record(&build_test_song());
+        let read_song: itunesdb::Song = serde_json::from_value(exported_song.clone()).unwrap();
+        assert_eq!(get_exported_record(&read_song), exported_song);
+
+        let exported_podcast = get_exported_record(&build_test_podcast());
+        let read_podcast: itunesdb::Podcast = serde_json::from_value(exported_podcast.clone()).unwrap();
+        assert_eq!(get_exported_record(&read_podcast), exported_podcast);
+
+        let exported_playlist = get_exported_record(&build_test_playlist());
+        let read_playlist: itunesdb::Playlist = serde_json::from_value(exported_playlist.clone()).unwrap();
+        assert_eq!(get_exported_record(&read_playlist), exported_playlist);
+        assert_eq!(read_playlist.playlist_kind, itunesdb::PlaylistKind::Smart);
+    }
+
+    #[test]
+    fn fields_added_in_a_minor_version_are_optional() {
+        let mut exported_records = [
+            ("Song", get_exported_record(&build_test_song())),
+            ("Podcast", get_exported_record(&build_test_podcast())),
+            ("Playlist", get_exported_record(&build_test_playlist())),
+        ];
+
+        // What a tool written against the .0 of the major version would have in its files
+        for (record_type, exported_record) in exported_records.iter_mut() {
+            for (field_name, _, added_in_version) in get_record_fields(record_type).iter() {
+                if !added_in_version.ends_with(".0") {
+                    exported_record.as_object_mut().unwrap().remove(*field_name).unwrap();
+                }
+            }
+        }
+
+        let read_song: itunesdb::Song = serde_json::from_value(exported_records[0].1.clone()).unwrap();
+        assert_eq!(read_song.song_title, "Song 2");
+
+        let read_podcast: itunesdb::Podcast = serde_json::from_value(exported_records[1].1.clone()).unwrap();
+        assert_eq!(read_podcast.podcast_title, "Episode 1");
+
+        let read_playlist: itunesdb::Playlist = serde_json::from_value(exported_records[2].1.clone()).unwrap();
+        assert_eq!(read_playlist.playlist_name, "Britpop");
+
+        // The left out fields read back as their defaults
+        for (record_type, read_record, default_record) in [
+            ("Song", get_exported_record(&read_song), get_exported_record(&itunesdb::Song::default())),
+            ("Podcast", get_exported_record(&read_podcast), get_exported_record(&itunesdb::Podcast::default())),
+            ("Playlist", get_exported_record(&read_playlist), get_exported_record(&itunesdb::Playlist::default())),
+        ] {
+            for (field_name, _, added_in_version) in get_record_fields(record_type).iter() {
+                if !added_in_version.ends_with(".0") {
+                    assert_eq!(read_record[*field_name], default_record[*field_name], "{}.{}", record_type, field_name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn renamed_fields_break_reading() {
+        for (record_type, exported_record) in [
+            ("Song", get_exported_record(&build_test_song())),
+            ("Podcast", get_exported_record(&build_test_podcast())),
+            ("Playlist", get_exported_record(&build_test_playlist())),
+        ] {
+            for (field_name, field_type, added_in_version) in get_record_fields(record_type).iter() {
+                // `schema_version` is only written, and optional fields are read as their default anyway
+                if *field_name == "schema_version" || !added_in_version.ends_with(".0") || field_type.ends_with("|null") {
+                    continue;
+                }
+
+                let mut renamed_record = exported_record.clone();
+                let field_value = renamed_record.as_object_mut().unwrap().remove(*field_name).unwrap();
+                renamed_record[format!("{}_renamed", field_name)] = field_value;
+
+                let is_read = match record_type {
+                    "Song" => serde_json::from_value::<itunesdb::Song>(renamed_record).is_ok(),
+                    "Podcast" => serde_json::from_value::<itunesdb::Podcast>(renamed_record).is_ok(),
+                    _ => serde_json::from_value::<itunesdb::Playlist>(renamed_record).is_ok(),
+                };
+
+                assert!(!is_read, "{}.{} can be renamed without breaking older files", record_type, field_name);
+            }
+        }
+    }
+}
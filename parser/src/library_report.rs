@@ -0,0 +1,519 @@
+/**
+ * File: library_report.rs
+ *
+ * A recovered library is easier to look through in a browser than in a folder of CSV files.
+ * The report is a single HTML file (styles and scripts included, nothing is loaded from elsewhere),
+ * with a few numbers about the library up top and sortable tables of the songs, podcasts and playlists.
+ * The same numbers also go into a short Markdown summary, to paste into an issue or keep next to an archive.
+ *
+ */
+use std::collections::HashMap;
+
+use crate::helpers::helpers;
+use crate::ipod_device_info;
+use crate::itunesdb;
+use crate::parse_report;
+use crate::smart_playlist;
+
+/// How many artists the report lists, by number of songs
+const NUM_TOP_ARTISTS: usize = 10;
+
+pub struct ArtistSummary {
+    pub artist: String,
+    pub num_songs: u32,
+    pub num_plays: u32,
+}
+
+/// The numbers about the library as a whole. Totals are over every media type
+pub struct LibrarySummary {
+    /// The model, or the name given to the iPod if the model isn't known
+    pub device_description: String,
+    pub num_songs: usize,
+    pub num_podcasts: usize,
+    pub num_videos: usize,
+    pub num_audiobooks: usize,
+    pub num_playlists: usize,
+    /// Smart playlists that match nothing included
+    pub num_empty_playlists: usize,
+    pub num_tracks: usize,
+    pub total_duration_s: u64,
+    pub total_size_bytes: u64,
+    pub total_num_plays: u64,
+    pub top_artists: Vec<ArtistSummary>,
+    /// When the first and the last of the songs were added to the library, if any were dated
+    pub first_song_added_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_song_added_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub fn build_library_summary(
+    library_tracks: &itunesdb::LibraryTracks,
+    playlists: &[itunesdb::Playlist],
+    ipod_device_info: &ipod_device_info::IpodDeviceInfo,
+) -> LibrarySummary {
+    let itunesdb::LibraryTracks {
+        songs,
+        podcasts,
+        videos,
+        audiobooks,
+        itunesu_items,
+        ringtones,
+        voice_memos,
+    } = *library_tracks;
+
+    let mut tracks: Vec<&dyn smart_playlist::SmartPlaylistTrack> = Vec::new();
+
+    tracks.extend(songs.iter().map(|song| song as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(podcasts.iter().map(|podcast| podcast as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(videos.iter().map(|video| &video.track as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(audiobooks.iter().map(|audiobook| &audiobook.track as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(itunesu_items.iter().map(|itunesu_item| &itunesu_item.track as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(ringtones.iter().map(|ringtone| &ringtone.track as &dyn smart_playlist::SmartPlaylistTrack));
+    tracks.extend(voice_memos.iter().map(|voice_memo| &voice_memo.track as &dyn smart_playlist::SmartPlaylistTrack));
+
+    let get_total = |rule_field: smart_playlist::SmartPlaylistRuleField| -> u64 {
+        let rule_field = rule_field as u32;
+
+        return tracks
+            .iter()
+            .filter_map(|track| track.get_numeric_field(rule_field))
+            .sum();
+    };
+
+    let mut artist_summaries: HashMap<String, ArtistSummary> = HashMap::new();
+
+    for song in songs.iter().filter(|song| !song.song_artist.is_empty()) {
+        let artist_summary = artist_summaries
+            .entry(song.song_artist.to_lowercase())
+            .or_insert_with(|| ArtistSummary { artist: song.song_artist.to_string(), num_songs: 0, num_plays: 0 });
+
+        artist_summary.num_songs += 1;
+        artist_summary.num_plays += song.num_plays;
+    }
+
+    let mut top_artists: Vec<ArtistSummary> = artist_summaries.into_values().collect();
+
+    top_artists.sort_by(|artist_summary, other_artist_summary| {
+        other_artist_summary
+            .num_songs
+            .cmp(&artist_summary.num_songs)
+            .then(other_artist_summary.num_plays.cmp(&artist_summary.num_plays))
+            .then(artist_summary.artist.cmp(&other_artist_summary.artist))
+    });
+    top_artists.truncate(NUM_TOP_ARTISTS);
+
+    let device_description = match ipod_device_info.model.as_ref() {
+        Some(ipod_model) => ipod_model.get_description(),
+        None if !ipod_device_info.ipod_name.is_empty() => ipod_device_info.ipod_name.to_string(),
+        None => "Unknown iPod".to_string(),
+    };
+
+    return LibrarySummary {
+        device_description,
+        num_songs: songs.len(),
+        num_podcasts: podcasts.len(),
+        num_videos: videos.len(),
+        num_audiobooks: audiobooks.len(),
+        num_playlists: playlists.len(),
+        num_empty_playlists: playlists.iter().filter(|playlist| playlist.track_ids.is_empty()).count(),
+        num_tracks: tracks.len(),
+        // Time is in milliseconds
+        total_duration_s: get_total(smart_playlist::SmartPlaylistRuleField::Time) / 1000,
+        total_size_bytes: get_total(smart_playlist::SmartPlaylistRuleField::Size),
+        total_num_plays: get_total(smart_playlist::SmartPlaylistRuleField::PlayCount),
+        top_artists,
+        first_song_added_at: songs.iter().filter_map(|song| song.song_added_to_library_ts).min(),
+        last_song_added_at: songs.iter().filter_map(|song| song.song_added_to_library_ts).max(),
+    };
+}
+
+/// "1.2 GB" for a whole library, where `convert_bytes_to_human_readable_size` would stop at MB
+pub fn get_library_size_friendly(size_bytes: u64) -> String {
+    const ONE_GB_AS_BYTES: f64 = 1000000000_f64;
+
+    if size_bytes as f64 >= ONE_GB_AS_BYTES {
+        return format!("{:.2} GB", size_bytes as f64 / ONE_GB_AS_BYTES);
+    }
+
+    return helpers::convert_bytes_to_human_readable_size(size_bytes);
+}
+
+/// "2 days, 3 hours" rather than "51 hours, ...", seconds left out
+pub fn get_library_duration_friendly(duration_s: u64) -> String {
+    let num_days = duration_s / 86400;
+    let num_hours = (duration_s % 86400) / 3600;
+    let num_minutes = (duration_s % 3600) / 60;
+
+    if num_days > 0 {
+        return format!("{} day{}, {} hour{}", num_days, if num_days == 1 { "" } else { "s" }, num_hours, if num_hours == 1 { "" } else { "s" });
+    } else if num_hours > 0 {
+        return format!("{} hour{}, {} minute{}", num_hours, if num_hours == 1 { "" } else { "s" }, num_minutes, if num_minutes == 1 { "" } else { "s" });
+    }
+
+    return format!("{} minute{}", num_minutes, if num_minutes == 1 { "" } else { "s" });
+}
+
+fn encode_html_entities(html_text: &str) -> String {
+    return html_text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+}
+
+/// What's shown, and what the column is sorted by if that's not the text itself (e.g. seconds for "3:25")
+struct HtmlCell {
+    text: String,
+    sort_key: Option<String>,
+}
+
+fn get_text_cell(text: &str) -> HtmlCell {
+    return HtmlCell { text: text.to_string(), sort_key: None };
+}
+
+fn get_number_cell(number: u64) -> HtmlCell {
+    return HtmlCell { text: number.to_string(), sort_key: Some(number.to_string()) };
+}
+
+/// As "m:ss", or "h:mm:ss" for anything an hour or longer
+fn get_duration_cell(duration_s: u32) -> HtmlCell {
+    let duration_text = if duration_s >= 3600 {
+        format!("{}:{:02}:{:02}", duration_s / 3600, (duration_s % 3600) / 60, duration_s % 60)
+    } else {
+        format!("{}:{:02}", duration_s / 60, duration_s % 60)
+    };
+
+    return HtmlCell { text: duration_text, sort_key: Some(duration_s.to_string()) };
+}
+
+/// Sorts as text, since the format puts the biggest unit first
+fn get_date_cell(timestamp: Option<chrono::DateTime<chrono::Utc>>) -> HtmlCell {
+    return get_text_cell(
+        &timestamp
+            .map(|timestamp| timestamp.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default(),
+    );
+}
+
+fn write_html_table(report_html: &mut String, table_title: &str, headers: &[&str], rows: Vec<Vec<HtmlCell>>) {
+    report_html.push_str(&format!("<h2>{} <span class=\"count\">({})</span></h2>\n", encode_html_entities(table_title), rows.len()));
+
+    if rows.is_empty() {
+        report_html.push_str("<p class=\"empty\">None on this iPod.</p>\n");
+        return;
+    }
+
+    report_html.push_str("<table class=\"sortable\">\n<thead><tr>");
+
+    for header in headers.iter() {
+        report_html.push_str(&format!("<th>{}</th>", encode_html_entities(header)));
+    }
+
+    report_html.push_str("</tr></thead>\n<tbody>\n");
+
+    for row_cells in rows.iter() {
+        report_html.push_str("<tr>");
+
+        for row_cell in row_cells.iter() {
+            match row_cell.sort_key.as_ref() {
+                Some(sort_key) => report_html.push_str(&format!(
+                    "<td data-sort=\"{}\" class=\"number\">{}</td>",
+                    encode_html_entities(sort_key),
+                    encode_html_entities(&row_cell.text)
+                )),
+                None => report_html.push_str(&format!("<td>{}</td>", encode_html_entities(&row_cell.text))),
+            }
+        }
+
+        report_html.push_str("</tr>\n");
+    }
+
+    report_html.push_str("</tbody>\n</table>\n");
+}
+
+const REPORT_STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.generated { color: #777; margin-top: 0; }
+.cards { display: flex; flex-wrap: wrap; gap: 1em; margin: 1.5em 0; }
+.card { border: 1px solid #ddd; border-radius: 8px; padding: 0.8em 1.2em; min-width: 10em; }
+.card .label { color: #777; font-size: 0.85em; }
+.card .value { font-size: 1.4em; font-weight: 600; }
+.count { color: #777; font-weight: normal; }
+table { border-collapse: collapse; margin-bottom: 2em; font-size: 0.9em; }
+th, td { border-bottom: 1px solid #eee; padding: 0.3em 0.8em; text-align: left; }
+th { cursor: pointer; user-select: none; background: #f6f6f6; position: sticky; top: 0; }
+th.asc::after { content: ' \\25B2'; }
+th.desc::after { content: ' \\25BC'; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+.empty { color: #777; }
+";
+
+/// Clicking a header sorts its table by that column, clicking again reverses it.
+/// Columns with a data-sort key sort as numbers
+const REPORT_SCRIPT: &str = "
+document.querySelectorAll('table.sortable th').forEach(function (header) {
+  header.addEventListener('click', function () {
+    var table = header.closest('table');
+    var tbody = table.tBodies[0];
+    var columnIdx = header.cellIndex;
+    var isAscending = !header.classList.contains('asc');
+
+    table.querySelectorAll('th').forEach(function (otherHeader) {
+      otherHeader.classList.remove('asc', 'desc');
+    });
+    header.classList.add(isAscending ? 'asc' : 'desc');
+
+    var getSortKey = function (row) {
+      var cell = row.cells[columnIdx];
+      return cell.dataset.sort !== undefined ? parseFloat(cell.dataset.sort) : cell.textContent.toLowerCase();
+    };
+
+    Array.from(tbody.rows)
+      .sort(function (row, otherRow) {
+        var sortKey = getSortKey(row);
+        var otherSortKey = getSortKey(otherRow);
+        var comparison = sortKey < otherSortKey ? -1 : sortKey > otherSortKey ? 1 : 0;
+        return isAscending ? comparison : -comparison;
+      })
+      .forEach(function (row) {
+        tbody.appendChild(row);
+      });
+  });
+});
+";
+
+/// The whole report, as a standalone HTML document
+pub fn build_html_report(
+    library_summary: &LibrarySummary,
+    songs: &[itunesdb::Song],
+    podcasts: &[itunesdb::Podcast],
+    playlists: &[itunesdb::Playlist],
+) -> String {
+    let mut report_html = String::new();
+
+    report_html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    report_html.push_str(&format!(
+        "<title>{} - iPod library</title>\n<style>{}</style>\n</head>\n<body>\n",
+        encode_html_entities(&library_summary.device_description),
+        REPORT_STYLE
+    ));
+
+    report_html.push_str(&format!("<h1>{}</h1>\n", encode_html_entities(&library_summary.device_description)));
+    report_html.push_str(&format!(
+        "<p class=\"generated\">Generated on {} by itunesdb_parser</p>\n",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let summary_cards: Vec<(&str, String)> = vec![
+        ("Tracks", library_summary.num_tracks.to_string()),
+        ("Total duration", get_library_duration_friendly(library_summary.total_duration_s)),
+        ("Total size", get_library_size_friendly(library_summary.total_size_bytes)),
+        ("Songs", library_summary.num_songs.to_string()),
+        ("Podcasts", library_summary.num_podcasts.to_string()),
+        ("Videos", library_summary.num_videos.to_string()),
+        ("Audiobooks", library_summary.num_audiobooks.to_string()),
+        ("Playlists", library_summary.num_playlists.to_string()),
+        ("Plays", library_summary.total_num_plays.to_string()),
+    ];
+
+    report_html.push_str("<div class=\"cards\">\n");
+
+    for (card_label, card_value) in summary_cards.iter() {
+        report_html.push_str(&format!(
+            "<div class=\"card\"><div class=\"label\">{}</div><div class=\"value\">{}</div></div>\n",
+            card_label,
+            encode_html_entities(card_value)
+        ));
+    }
+
+    report_html.push_str("</div>\n");
+
+    write_html_table(
+        &mut report_html,
+        "Top artists",
+        &["Artist", "Songs", "Plays"],
+        library_summary
+            .top_artists
+            .iter()
+            .map(|artist_summary| {
+                vec![
+                    get_text_cell(&artist_summary.artist),
+                    get_number_cell(artist_summary.num_songs as u64),
+                    get_number_cell(artist_summary.num_plays as u64),
+                ]
+            })
+            .collect(),
+    );
+
+    write_html_table(
+        &mut report_html,
+        "Songs",
+        &["Title", "Artist", "Album", "Genre", "Year", "Time", "Plays", "Rating", "Added"],
+        songs
+            .iter()
+            .map(|song| {
+                let num_stars = (song.song_rating_raw / 20) as usize;
+
+                vec![
+                    get_text_cell(&song.song_title),
+                    get_text_cell(&song.song_artist),
+                    get_text_cell(&song.song_album),
+                    get_text_cell(&song.song_genre),
+                    HtmlCell {
+                        text: if song.song_year == 0 { "".to_string() } else { song.song_year.to_string() },
+                        sort_key: Some(song.song_year.to_string()),
+                    },
+                    get_duration_cell(song.song_duration_s),
+                    get_number_cell(song.num_plays as u64),
+                    HtmlCell { text: "\u{2605}".repeat(num_stars), sort_key: Some(song.song_rating_raw.to_string()) },
+                    get_date_cell(song.song_added_to_library_ts),
+                ]
+            })
+            .collect(),
+    );
+
+    write_html_table(
+        &mut report_html,
+        "Podcasts",
+        &["Episode", "Show", "Released", "Time", "Played"],
+        podcasts
+            .iter()
+            .map(|podcast| {
+                vec![
+                    get_text_cell(&podcast.podcast_title),
+                    get_text_cell(&podcast.podcast_show),
+                    get_date_cell(podcast.release_date),
+                    get_duration_cell(podcast.duration_s),
+                    get_text_cell(if podcast.is_played { "Yes" } else { "No" }),
+                ]
+            })
+            .collect(),
+    );
+
+    write_html_table(
+        &mut report_html,
+        "Playlists",
+        &["Name", "Kind", "Tracks", "Created"],
+        playlists
+            .iter()
+            .map(|playlist| {
+                vec![
+                    get_text_cell(&playlist.playlist_name),
+                    get_text_cell(&playlist.playlist_kind.to_string()),
+                    get_number_cell(playlist.track_ids.len() as u64),
+                    get_date_cell(Some(playlist.created_ts)),
+                ]
+            })
+            .collect(),
+    );
+
+    report_html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", REPORT_SCRIPT));
+
+    return report_html;
+}
+
+/// Pipes would end the Markdown table cell
+fn encode_markdown_cell(markdown_text: &str) -> String {
+    return markdown_text.replace('|', "\\|").replace('\n', " ");
+}
+
+/// The device, the size of the library, a few notable numbers and what the parser had to work around,
+/// as a Markdown document short enough to paste into an issue
+pub fn build_markdown_summary(
+    library_summary: &LibrarySummary,
+    ipod_device_info: &ipod_device_info::IpodDeviceInfo,
+    parse_report: &parse_report::ParseReport,
+) -> String {
+    let mut summary_markdown = String::new();
+
+    summary_markdown.push_str(&format!("# {}\n\n", library_summary.device_description));
+    summary_markdown.push_str(&format!(
+        "Generated on {} by itunesdb_parser.\n\n",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")
+    ));
+
+    let sysinfo = ipod_device_info.sysinfo.as_ref();
+
+    let device_fields: Vec<(&str, String)> = vec![
+        ("iPod name", ipod_device_info.ipod_name.to_string()),
+        ("Model number", sysinfo.map(|sysinfo| sysinfo.model_number.to_string()).unwrap_or_default()),
+        ("Serial number", sysinfo.map(|sysinfo| sysinfo.serial_number.to_string()).unwrap_or_default()),
+        ("Firmware version", sysinfo.map(|sysinfo| sysinfo.firmware_version.to_string()).unwrap_or_default()),
+        ("Database version", ipod_device_info.database_version.to_string()),
+        ("iTunes version", ipod_device_info.itunes_version.to_string()),
+        ("Language", ipod_device_info.language_name.to_string()),
+        ("Last synced by", ipod_device_info.user_name.to_string()),
+        ("Last synced with", ipod_device_info.host_computer_name.to_string()),
+    ];
+
+    summary_markdown.push_str("## Device\n\n| | |\n|---|---|\n");
+
+    // Whatever wasn't read (e.g. no mount point, so no SysInfo) is left out
+    for (field_name, field_value) in device_fields.iter().filter(|(_, field_value)| !field_value.is_empty()) {
+        summary_markdown.push_str(&format!("| {} | {} |\n", field_name, encode_markdown_cell(field_value)));
+    }
+
+    summary_markdown.push_str("\n## Library\n\n| | |\n|---|---:|\n");
+
+    let library_fields: Vec<(&str, String)> = vec![
+        ("Tracks", library_summary.num_tracks.to_string()),
+        ("Songs", library_summary.num_songs.to_string()),
+        ("Podcasts", library_summary.num_podcasts.to_string()),
+        ("Videos", library_summary.num_videos.to_string()),
+        ("Audiobooks", library_summary.num_audiobooks.to_string()),
+        ("Playlists", library_summary.num_playlists.to_string()),
+        ("Total duration", get_library_duration_friendly(library_summary.total_duration_s)),
+        ("Total size", get_library_size_friendly(library_summary.total_size_bytes)),
+        ("Plays", library_summary.total_num_plays.to_string()),
+    ];
+
+    for (field_name, field_value) in library_fields.iter() {
+        summary_markdown.push_str(&format!("| {} | {} |\n", field_name, field_value));
+    }
+
+    summary_markdown.push_str("\n## Notable\n\n");
+
+    if let (Some(first_song_added_at), Some(last_song_added_at)) =
+        (library_summary.first_song_added_at, library_summary.last_song_added_at)
+    {
+        summary_markdown.push_str(&format!(
+            "- Songs added between {} and {}\n",
+            first_song_added_at.format("%Y-%m-%d"),
+            last_song_added_at.format("%Y-%m-%d")
+        ));
+    }
+
+    if !library_summary.top_artists.is_empty() {
+        summary_markdown.push_str(&format!(
+            "- Top artists: {}\n",
+            library_summary
+                .top_artists
+                .iter()
+                .take(5)
+                .map(|artist_summary| format!("{} ({} songs)", artist_summary.artist, artist_summary.num_songs))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+
+    if library_summary.num_empty_playlists > 0 {
+        summary_markdown.push_str(&format!(
+            "- {} of the {} playlists are empty\n",
+            library_summary.num_empty_playlists, library_summary.num_playlists
+        ));
+    }
+
+    summary_markdown.push_str("\n## Warnings\n\n");
+
+    if parse_report.is_empty() {
+        summary_markdown.push_str("Nothing had to be skipped or guessed.\n");
+    } else {
+        for (category, num_entries) in parse_report.get_num_entries_per_category().iter() {
+            summary_markdown.push_str(&format!("- {} x {}\n", num_entries, category));
+        }
+
+        summary_markdown.push_str("\nSee `parse_report.json` for every entry.\n");
+    }
+
+    return summary_markdown;
+}
@@ -0,0 +1,69 @@
+/**
+ * File: library_stats.rs
+ *
+ * The Track List, Playlist List and Album List headers each declare how many records follow
+ * them. Comparing those counts with what was actually parsed shows whether records were
+ * dropped (or invented) along the way.
+ *
+ */
+use serde::Serialize;
+
+use crate::parse_report;
+
+#[derive(Serialize)]
+pub struct LibraryStats {
+    /// From the Track List (mhlt) header. `None` if the database doesn't have that list
+    pub declared_num_tracks: Option<u32>,
+    /// Track items (mhit) in the Track List, of every media type. Hidden ones count too, like in the header
+    pub parsed_num_tracks: u32,
+    /// From the Playlist List and Smart Playlist List (mhlp) headers.
+    /// The Podcast List repeats the same playlists, so it isn't counted
+    pub declared_num_playlists: Option<u32>,
+    pub parsed_num_playlists: u32,
+    /// From the Album List (mhla) header
+    pub declared_num_albums: Option<u32>,
+    pub parsed_num_albums: u32,
+    pub has_mismatches: bool,
+}
+
+impl Default for LibraryStats {
+    fn default() -> LibraryStats {
+        return LibraryStats {
+            declared_num_tracks: None,
+            parsed_num_tracks: 0,
+            declared_num_playlists: None,
+            parsed_num_playlists: 0,
+            declared_num_albums: None,
+            parsed_num_albums: 0,
+            has_mismatches: false,
+        };
+    }
+}
+
+impl LibraryStats {
+    /// Adds a parse report entry for every count that doesn't match its header
+    pub fn cross_check(&mut self, parse_report: &mut parse_report::ParseReport) {
+        let counts = [
+            ("tracks", self.declared_num_tracks, self.parsed_num_tracks),
+            ("playlists", self.declared_num_playlists, self.parsed_num_playlists),
+            ("albums", self.declared_num_albums, self.parsed_num_albums),
+        ];
+
+        for (record_name, declared_num_records, parsed_num_records) in counts.iter() {
+            if let Some(declared_num_records) = declared_num_records {
+                if declared_num_records != parsed_num_records {
+                    self.has_mismatches = true;
+
+                    parse_report.add_entry(
+                        "Library count mismatch",
+                        None,
+                        format!(
+                            "Header declares {} {}, but {} were parsed",
+                            declared_num_records, record_name, parsed_num_records
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
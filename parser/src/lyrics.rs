@@ -0,0 +1,202 @@
+/**
+ * File: lyrics.rs
+ *
+ * The iTunesDB only records whether a track has lyrics, the lyrics themselves are stored
+ * in the audio file's tags: an ID3v2 USLT frame for MP3s, or the ©lyr atom for AAC/ALAC files.
+ *
+ */
+use crate::helpers::helpers;
+
+const ID3_HEADER_LEN: usize = 10;
+const ID3_EXTENDED_HEADER_FLAG: u8 = 0x40;
+const ID3_UNSYNCHRONISATION_FLAG: u8 = 0x80;
+
+/// The "meta" atom has a 4-byte version/flags field before its children
+const MP4_META_ATOM_VERSION_LEN: usize = 4;
+/// "data" atoms start with a 4-byte type and a 4-byte locale, the value comes after
+const MP4_DATA_ATOM_PREAMBLE_LEN: usize = 8;
+
+/// Returns `None` if the file can't be read, or if it has no (supported) lyrics tag
+pub fn read_lyrics_from_audio_file(audio_file_path: &std::path::Path) -> Option<String> {
+    let audio_file_as_bytes = std::fs::read(audio_file_path).ok()?;
+
+    if audio_file_as_bytes.starts_with(b"ID3") {
+        return read_id3_lyrics(&audio_file_as_bytes);
+    }
+
+    return read_mp4_lyrics(&audio_file_as_bytes);
+}
+
+/// ID3v2.4 (and every tag size) uses "syncsafe" integers, where only the low 7 bits of each byte count
+fn get_syncsafe_u32(bytes: &[u8]) -> usize {
+    return bytes
+        .iter()
+        .fold(0, |value, byte| (value << 7) | (*byte as usize & 0x7F));
+}
+
+fn get_be_uint(bytes: &[u8]) -> usize {
+    return bytes.iter().fold(0, |value, byte| (value << 8) | *byte as usize);
+}
+
+fn read_id3_lyrics(file_as_bytes: &[u8]) -> Option<String> {
+    if file_as_bytes.len() < ID3_HEADER_LEN {
+        return None;
+    }
+
+    let id3_major_version = file_as_bytes[3];
+    let id3_flags = file_as_bytes[5];
+    let id3_tag_len = get_syncsafe_u32(&file_as_bytes[6..10]);
+
+    // Unsynchronised tags would have to be re-synchronised first, iTunes never writes them
+    if id3_flags & ID3_UNSYNCHRONISATION_FLAG != 0 {
+        return None;
+    }
+
+    let id3_tag_end = std::cmp::min(ID3_HEADER_LEN + id3_tag_len, file_as_bytes.len());
+    let mut idx = ID3_HEADER_LEN;
+
+    if id3_flags & ID3_EXTENDED_HEADER_FLAG != 0 && idx + 4 <= id3_tag_end {
+        let extended_header_len = get_be_uint(&file_as_bytes[idx..idx + 4]);
+
+        // v2.3 doesn't count the size field itself, v2.4 does
+        if id3_major_version == 3 {
+            idx += extended_header_len + 4;
+        } else {
+            idx += get_syncsafe_u32(&file_as_bytes[idx..idx + 4]);
+        }
+    }
+
+    // ID3v2.2 has 3 character frame IDs and 3 byte sizes, without any frame flags
+    let (frame_id_len, frame_header_len, lyrics_frame_id): (usize, usize, &[u8]) =
+        if id3_major_version == 2 {
+            (3, 6, b"ULT")
+        } else {
+            (4, 10, b"USLT")
+        };
+
+    while idx + frame_header_len <= id3_tag_end {
+        let frame_id = &file_as_bytes[idx..idx + frame_id_len];
+
+        // The rest of the tag is padding
+        if frame_id[0] == 0 {
+            break;
+        }
+
+        let frame_size_bytes = &file_as_bytes[idx + frame_id_len..idx + frame_id_len * 2];
+
+        let frame_len = if id3_major_version == 4 {
+            get_syncsafe_u32(frame_size_bytes)
+        } else {
+            get_be_uint(frame_size_bytes)
+        };
+
+        let frame_start = idx + frame_header_len;
+        let frame_end = frame_start + frame_len;
+
+        if frame_end > id3_tag_end {
+            break;
+        }
+
+        if frame_id == lyrics_frame_id {
+            return decode_id3_lyrics_frame(&file_as_bytes[frame_start..frame_end]);
+        }
+
+        idx = frame_end;
+    }
+
+    return None;
+}
+
+/// A USLT frame is: text encoding (1 byte), language (3 bytes), a terminated
+/// content descriptor, then the lyrics themselves
+fn decode_id3_lyrics_frame(frame_bytes: &[u8]) -> Option<String> {
+    if frame_bytes.len() < 4 {
+        return None;
+    }
+
+    let text_encoding = frame_bytes[0];
+    let text_bytes = &frame_bytes[4..];
+
+    // Latin-1 and UTF-8 strings end with a single 0 byte, UTF-16 strings with two
+    let lyrics_bytes: &[u8] = if text_encoding == 1 || text_encoding == 2 {
+        let descriptor_end = text_bytes
+            .chunks_exact(2)
+            .position(|pair| pair == [0, 0])
+            .map_or(text_bytes.len(), |pair_idx| pair_idx * 2 + 2);
+
+        &text_bytes[descriptor_end..]
+    } else {
+        let descriptor_end = text_bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .map_or(text_bytes.len(), |byte_idx| byte_idx + 1);
+
+        &text_bytes[descriptor_end..]
+    };
+
+    let lyrics: String;
+
+    if text_encoding == 0 {
+        lyrics = lyrics_bytes.iter().map(|byte| *byte as char).collect();
+    } else if text_encoding == 1 {
+        // UTF-16 with a byte order mark
+        if lyrics_bytes.starts_with(&[0xFE, 0xFF]) {
+            lyrics = decode_utf16_be_lossy(&lyrics_bytes[2..]);
+        } else if lyrics_bytes.starts_with(&[0xFF, 0xFE]) {
+            lyrics = helpers::decode_utf16_le_lossy(&lyrics_bytes[2..]).0;
+        } else {
+            lyrics = helpers::decode_utf16_le_lossy(lyrics_bytes).0;
+        }
+    } else if text_encoding == 2 {
+        lyrics = decode_utf16_be_lossy(lyrics_bytes);
+    } else {
+        lyrics = String::from_utf8_lossy(lyrics_bytes).to_string();
+    }
+
+    let lyrics = lyrics.trim_end_matches('\0').to_string();
+
+    if lyrics.trim().is_empty() {
+        return None;
+    }
+
+    return Some(lyrics);
+}
+
+fn decode_utf16_be_lossy(be_bytes: &[u8]) -> String {
+    let utf16_units: Vec<u16> = be_bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+
+    return String::from_utf16_lossy(&utf16_units);
+}
+
+/// The lyrics live at moov > udta > meta > ilst > ©lyr > data
+fn read_mp4_lyrics(file_as_bytes: &[u8]) -> Option<String> {
+    let (moov_start, moov_end) = helpers::find_mp4_atom(file_as_bytes, 0, file_as_bytes.len(), b"moov")?;
+    let (udta_start, udta_end) = helpers::find_mp4_atom(file_as_bytes, moov_start, moov_end, b"udta")?;
+    let (meta_start, meta_end) = helpers::find_mp4_atom(file_as_bytes, udta_start, udta_end, b"meta")?;
+    let (ilst_start, ilst_end) = helpers::find_mp4_atom(
+        file_as_bytes,
+        meta_start + MP4_META_ATOM_VERSION_LEN,
+        meta_end,
+        b"ilst",
+    )?;
+    let (lyrics_start, lyrics_end) =
+        helpers::find_mp4_atom(file_as_bytes, ilst_start, ilst_end, b"\xA9lyr")?;
+    let (data_start, data_end) = helpers::find_mp4_atom(file_as_bytes, lyrics_start, lyrics_end, b"data")?;
+
+    if data_start + MP4_DATA_ATOM_PREAMBLE_LEN > data_end {
+        return None;
+    }
+
+    let lyrics =
+        String::from_utf8_lossy(&file_as_bytes[data_start + MP4_DATA_ATOM_PREAMBLE_LEN..data_end])
+            .to_string();
+
+    if lyrics.trim().is_empty() {
+        return None;
+    }
+
+    return Some(lyrics);
+}
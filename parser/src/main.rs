@@ -1,53 +1,122 @@
+// Explicit `return`s, and a blank line after each file's header comment, are this codebase's style
+#![allow(clippy::needless_return, clippy::empty_line_after_doc_comments)]
+
 /// Top-level declaration of modules, see:
 /// https://stackoverflow.com/questions/46829539
 /// https://stackoverflow.com/questions/58935890
 
 mod constants {
+    pub mod database_hash_constants;
     pub mod deviceinfo_constants;
     pub mod equalizer_constants;
+    pub mod export_schema_constants;
+    pub mod itunes_library_constants;
     pub mod itunesdb_constants;
     pub mod itunesprefs_constants;
+    pub mod itunespstate_constants;
     pub mod itunessd_constants;
+    pub mod itunesstats_constants;
+    pub mod otgplaylist_constants;
     pub mod photo_database_constants;
     pub mod photofolderalbums_constants;
     pub mod playcounts_constants;
     pub mod preferences_constants;
+    pub mod smart_playlist_constants;
+    pub mod sysinfo_constants;
 }
 
 mod helpers {
     pub mod helpers;
     pub mod itunesdb_helpers;
+    pub mod plist_helpers;
 }
 
 mod parsers {
     pub mod deviceinfo_parser;
     pub mod equalizer_parser;
+    pub mod itunes_library_parser;
     pub mod itunesdb_parser;
+    pub mod itunesdb_validation_parser;
+    pub mod itunespstate_parser;
     pub mod itunessd_parser;
+    pub mod itunesstats_parser;
+    #[cfg(feature = "medialibrary")]
+    pub mod medialibrary_parser;
+    pub mod otgplaylist_parser;
     pub mod photo_type_parser;
     pub mod playcounts_parser;
     pub mod preferences_parser;
+    pub mod sysinfo_parser;
+    pub mod sysinfoextended_parser;
 }
 
 mod itunesdb;
 mod itunesprefs;
 mod photo_database;
+mod ithmb;
+mod exif;
 mod preferences;
 mod itunessd;
 mod equalizer;
+mod smart_playlist;
+mod device_menu;
+mod library_stats;
+mod parse_report;
+mod ipod_device_info;
+mod lyrics;
+mod audio_properties;
+mod video_properties;
+mod playcounts;
+mod itunes_library;
+mod orphaned_files;
+mod track_files;
+mod itunesdb_validation;
+mod library_report;
+mod scrobbles;
+mod json_schema;
+mod playlist_items;
+mod albums;
+mod artists;
+mod database_hash;
+#[cfg(feature = "sqlite")]
+mod sqlite_export;
+#[cfg(feature = "arrow")]
+mod arrow_export;
+#[cfg(feature = "xlsx")]
+mod xlsx_export;
 
 use std::io::Read;
 
 fn main() {
     // add a check for the number of arguments
-    let args: Vec<String> = std::env::args().collect();
+    // Options (e.g. "--checksum=md5") can go anywhere, everything else is positional.
+    // The exceptions are "--output -" and "--compress zstd", whose values are the next argument
+    let all_args: Vec<String> = std::env::args().collect();
+    let args: Vec<String> = all_args
+        .iter()
+        .enumerate()
+        .filter(|(arg_idx, arg)| {
+            !arg.starts_with("--") && !(*arg_idx > 0 && (all_args[arg_idx - 1] == "--output" || all_args[arg_idx - 1] == "--compress"))
+        })
+        .map(|(_, arg)| arg.clone())
+        .collect();
     
+    // Doesn't need a database: prints the JSON Schema of the JSON exports and exits
+    if all_args.iter().any(|arg| arg == "--json-schema") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json_schema::build_json_schema()).expect("Can't serialize the JSON Schema")
+        );
+        return;
+    }
+
     if args.len() < 3 {
-        panic!("Usage: {} <iTunes DB filename> <type> [format=csv|json]", args[0]);
+        panic!("Usage: {} <iTunes DB filename> <type> [format=csv|json|yaml|msgpack|sqlite|feather|xlsx] [iPod mount point] [iTunes Library.xml] [--checksum=md5|sha1] [--music-columns=...] [--podcast-columns=...] [--delimiter=tab|semicolon|pipe] [--quote-all] [--output -|<file> [--export=songs|podcasts|...]] [--compress gzip|zstd] [--hash58-tables=<itdb_hash58.c>] [--firewire-guid=...] [--thumbnail-format=png|jpeg] | --json-schema", args[0]);
     }
 
-    let itunesdb_filename: String = std::env::args()
-        .nth(1)
+    let itunesdb_filename: String = args
+        .get(1)
+        .cloned()
         .expect("Missing first parameter: iTunes DB filename");
 
     let itunesdb_file_path = std::path::Path::new(&itunesdb_filename);
@@ -73,6 +142,34 @@ fn main() {
         match args[3].to_lowercase().as_str() {
             "json" => "json",
             "csv" => "csv",
+            // iTunesDB only: songs, podcasts and playlists as YAML, everything else as JSON
+            "yaml" => "yaml",
+            // iTunesDB only: the CSV exports, plus the whole database as MessagePack
+            "msgpack" => "msgpack",
+            // iTunesDB only: the CSV exports, plus a SQLite database with all of it
+            #[cfg(feature = "sqlite")]
+            "sqlite" => "sqlite",
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => {
+                eprintln!("SQLite export isn't built in, rebuild with `cargo build --features sqlite`. Using 'csv'");
+                "csv"
+            }
+            // iTunesDB only: the CSV exports, plus the tracks as a Feather (Arrow) file
+            #[cfg(feature = "arrow")]
+            "feather" => "feather",
+            #[cfg(not(feature = "arrow"))]
+            "feather" => {
+                eprintln!("Feather export isn't built in, rebuild with `cargo build --features arrow`. Using 'csv'");
+                "csv"
+            }
+            // iTunesDB only: the CSV exports, plus the main ones as an Excel workbook
+            #[cfg(feature = "xlsx")]
+            "xlsx" => "xlsx",
+            #[cfg(not(feature = "xlsx"))]
+            "xlsx" => {
+                eprintln!("XLSX export isn't built in, rebuild with `cargo build --features xlsx`. Using 'csv'");
+                "csv"
+            }
             _ => {
                 eprintln!("Invalid format specified. Using default 'csv'");
                 "csv"
@@ -82,6 +179,80 @@ fn main() {
         "csv"
     };
 
+    // Only needed to read things the database doesn't store itself, e.g. lyrics
+    let ipod_mount_point: Option<String> = args.get(4).cloned();
+
+    // iTunesDB only: the computer's library to compare the iPod with
+    let itunes_library_filename: Option<String> = args.get(5).cloned();
+
+    // iTunesDB only: hash every track's file on the iPod (needs the mount point)
+    let checksum_algorithm: Option<String> = match std::env::args().find_map(|arg| arg.strip_prefix("--checksum=").map(|checksum_algorithm| checksum_algorithm.to_lowercase())) {
+        Some(checksum_algorithm) if checksum_algorithm == "md5" || checksum_algorithm == "sha1" => Some(checksum_algorithm),
+        Some(checksum_algorithm) => {
+            eprintln!("Unsupported checksum '{}', use 'md5' or 'sha1'. No checksums will be computed", checksum_algorithm);
+            None
+        }
+        None => None,
+    };
+
+    // iTunesDB only: which columns go into music.csv and podcasts.csv, and in what order, e.g. "Artist,Song Title,Play count"
+    let music_columns: Option<String> = std::env::args().find_map(|arg| arg.strip_prefix("--music-columns=").map(|music_columns| music_columns.to_string()));
+    let podcast_columns: Option<String> = std::env::args().find_map(|arg| arg.strip_prefix("--podcast-columns=").map(|podcast_columns| podcast_columns.to_string()));
+
+    // iTunesDB only: write a single export as NDJSON to stdout ("-") or a file, instead of all the usual files
+    let output_path: Option<String> = match all_args.iter().position(|arg| arg == "--output") {
+        Some(output_arg_idx) => all_args.get(output_arg_idx + 1).cloned(),
+        None => all_args.iter().find_map(|arg| arg.strip_prefix("--output=").map(|output_path| output_path.to_string())),
+    };
+
+    let export_name: Option<String> = all_args.iter().find_map(|arg| arg.strip_prefix("--export=").map(|export_name| export_name.to_lowercase()));
+
+    // Every CSV file: "--delimiter=tab" (or semicolon, pipe, or any single character) and "--quote-all"
+    let csv_delimiter: u8 = match std::env::args().find_map(|arg| arg.strip_prefix("--delimiter=").map(|csv_delimiter| csv_delimiter.to_string())) {
+        Some(csv_delimiter) => match csv_delimiter.to_lowercase().as_str() {
+            "tab" | "\\t" => b'\t',
+            "semicolon" => b';',
+            "pipe" => b'|',
+            "comma" => b',',
+            _ if csv_delimiter.len() == 1 => csv_delimiter.as_bytes()[0],
+            _ => {
+                eprintln!("Unsupported delimiter '{}', use 'tab', 'semicolon', 'pipe' or a single character. Using commas", csv_delimiter);
+                b','
+            }
+        },
+        None => b',',
+    };
+
+    helpers::helpers::set_csv_format(helpers::helpers::CsvFormat {
+        delimiter: csv_delimiter,
+        quote_all: std::env::args().any(|arg| arg == "--quote-all"),
+    });
+
+    // Every export file: "--compress gzip" or "--compress zstd" (or "--compress=zstd")
+    let output_compression: Option<String> = match all_args.iter().position(|arg| arg == "--compress") {
+        Some(compress_arg_idx) => all_args.get(compress_arg_idx + 1).map(|output_compression| output_compression.to_lowercase()),
+        None => all_args.iter().find_map(|arg| arg.strip_prefix("--compress=").map(|output_compression| output_compression.to_lowercase())),
+    };
+
+    match output_compression.as_deref() {
+        Some("gzip") | Some("gz") => helpers::helpers::set_output_compression(helpers::helpers::OutputCompression::Gzip),
+        Some("zstd") | Some("zst") => helpers::helpers::set_output_compression(helpers::helpers::OutputCompression::Zstd),
+        Some(output_compression) => {
+            eprintln!("Unsupported compression '{}', use 'gzip' or 'zstd'. Exports won't be compressed", output_compression);
+        }
+        None => {}
+    }
+
+    // iTunesDB and signing only: libgpod's itdb_hash58.c, whose tables are needed to check or write the database's hash58
+    if let Some(hash58_tables_filename) = std::env::args().find_map(|arg| arg.strip_prefix("--hash58-tables=").map(|hash58_tables_filename| hash58_tables_filename.to_string())) {
+        match std::fs::read_to_string(&hash58_tables_filename).ok().as_deref().and_then(database_hash::read_hash58_tables) {
+            Some(hash58_tables) => database_hash::set_hash58_tables(hash58_tables),
+            None => {
+                eprintln!("Can't read the hash58 tables from '{}', it should be libgpod's src/itdb_hash58.c. Database signatures won't be verified", hash58_tables_filename);
+            }
+        }
+    }
+
     let mut itunesdb_file_as_bytes = Vec::new();
 
     // https://stackoverflow.com/questions/47660946/why-does-a-file-need-to-be-mutable-to-call-readread-to-string
@@ -91,27 +262,73 @@ fn main() {
         .read_to_end(&mut itunesdb_file_as_bytes)
         .unwrap();
 
-    let itunesdb_file_type: String = std::env::args()
-        .nth(2)
+    let itunesdb_file_type: String = args
+        .get(2)
+        .cloned()
         .expect("Missing second parameter: iTunes DB file type");
 
     let desired_report_csv_filename = itunesdb_filename.to_string() + ".csv";
 
     assert!(desired_report_csv_filename != itunesdb_filename);
 
+    // Photo types only: the thumbnails are written as PNG, or as JPEG with "--thumbnail-format=jpeg"
+    let thumbnail_format = match std::env::args().find_map(|arg| arg.strip_prefix("--thumbnail-format=").map(|thumbnail_format| thumbnail_format.to_lowercase())).as_deref() {
+        Some("jpeg") | Some("jpg") => "jpg",
+        Some("png") | None => "png",
+        Some(thumbnail_format) => {
+            eprintln!("Unsupported thumbnail format '{}', use 'png' or 'jpeg'. Using PNG", thumbnail_format);
+            "png"
+        }
+    };
+
     if itunesdb_file_type == "photo" {
         let photos_csv_writer = helpers::helpers::init_csv_writer(&desired_report_csv_filename);
+
         parsers::photo_type_parser::parse_photo_type_file(
             itunesdb_file_as_bytes,
             photos_csv_writer,
+            itunesdb_file_path.parent(),
+            thumbnail_format,
+        );
+    } else if itunesdb_file_type == "export-photos" {
+        parsers::photo_type_parser::export_photo_albums(
+            itunesdb_file_as_bytes,
+            output_format,
+            itunesdb_file_path.parent(),
+            thumbnail_format,
         );
     } else if itunesdb_file_type == "itunes" {
-        parsers::itunesdb_parser::parse_itunesdb_file(itunesdb_file_as_bytes, output_format.to_string());
+        parsers::itunesdb_parser::parse_itunesdb_file(
+            itunesdb_file_as_bytes,
+            output_format.to_string(),
+            parsers::itunesdb_parser::ItunesDbParseOptions {
+                ipod_mount_point,
+                itunes_library_filename,
+                checksum_algorithm,
+                music_columns,
+                podcast_columns,
+                output_path,
+                export_name,
+            },
+        );
+    } else if itunesdb_file_type == "validate" {
+        parsers::itunesdb_validation_parser::validate_itunesdb_file(itunesdb_file_as_bytes, output_format.to_string());
+    } else if itunesdb_file_type == "medialibrary" {
+        #[cfg(feature = "medialibrary")]
+        parsers::medialibrary_parser::parse_media_library_file(&itunesdb_filename, output_format.to_string());
+
+        #[cfg(not(feature = "medialibrary"))]
+        eprintln!("MediaLibrary.sqlitedb support isn't built in, rebuild with `cargo build --features medialibrary`");
     } else if itunesdb_file_type == "itprefs" {
         parsers::preferences_parser::parse_itunes_prefs_file(itunesdb_file_as_bytes);
     } else if itunesdb_file_type == "playcounts" {
         let playcounts_csv_writer = helpers::helpers::init_csv_writer(&desired_report_csv_filename);
         parsers::playcounts_parser::parse_playcounts(itunesdb_file_as_bytes, playcounts_csv_writer);
+    } else if itunesdb_file_type == "reset-playcounts" {
+        parsers::playcounts_parser::reset_playcounts_file(itunesdb_file_as_bytes, &(itunesdb_filename.to_string() + ".reset"));
+    } else if itunesdb_file_type == "sign" {
+        let firewire_guid: Option<String> = std::env::args().find_map(|arg| arg.strip_prefix("--firewire-guid=").map(|firewire_guid| firewire_guid.to_string()));
+        database_hash::sign_itunesdb_file(itunesdb_file_as_bytes, firewire_guid, ipod_mount_point, &(itunesdb_filename.to_string() + ".signed"));
     } else if itunesdb_file_type == "pfalbums" {
         parsers::photo_type_parser::parse_photofolder_albums_file(itunesdb_file_as_bytes);
     } else if itunesdb_file_type == "preferences" {
@@ -122,7 +339,16 @@ fn main() {
         let equalizer_csv_writer = helpers::helpers::init_csv_writer(&desired_report_csv_filename);
         parsers::equalizer_parser::parse_equalizer_file(itunesdb_file_as_bytes, equalizer_csv_writer);
     } else if itunesdb_file_type == "itunessd" {
-        parsers::itunessd_parser::parse_itunessd_file(itunesdb_file_as_bytes);
+        parsers::itunessd_parser::parse_itunessd_file(itunesdb_file_as_bytes, output_format.to_string(), ipod_mount_point);
+    } else if itunesdb_file_type == "itunesstats" {
+        let itunesstats_csv_writer = helpers::helpers::init_csv_writer(&desired_report_csv_filename);
+        parsers::itunesstats_parser::parse_itunesstats(itunesdb_file_as_bytes, itunesstats_csv_writer);
+    } else if itunesdb_file_type == "itunespstate" {
+        parsers::itunespstate_parser::parse_itunespstate_file(itunesdb_file_as_bytes);
+    } else if itunesdb_file_type == "sysinfo" {
+        parsers::sysinfo_parser::parse_sysinfo_file(itunesdb_file_as_bytes);
+    } else if itunesdb_file_type == "sysinfoextended" {
+        parsers::sysinfoextended_parser::parse_sysinfo_extended_file(itunesdb_file_as_bytes);
     } else {
         println!(
             "'{}' is not a supported iTunesDB file type!",
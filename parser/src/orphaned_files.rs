@@ -0,0 +1,99 @@
+/**
+ * File: orphaned_files.rs
+ *
+ * Audio files can stay on the iPod after the database has lost track of them, e.g. after an
+ * interrupted sync or a database rebuilt from scratch. iTunes and the iPod never show them,
+ * but they're still in the "iPod_Control/Music/Fxx" folders, and can be copied off.
+ *
+ */
+use serde::Serialize;
+
+use std::collections::HashSet;
+
+use crate::helpers::helpers;
+use crate::helpers::itunesdb_helpers;
+
+#[derive(Serialize)]
+pub struct OrphanedFile {
+    /// Relative to the iPod's root, e.g. "iPod_Control/Music/F12/ABCD.mp3"
+    pub filename: String,
+    pub absolute_path: String,
+    pub file_extension: String,
+    pub file_size_bytes: u64,
+    pub file_size_friendly: String,
+}
+
+/// iTunes names the folders F00 to F49 (more on some iPods), anything else in "Music" isn't its own
+fn is_music_folder_name(folder_name: &str) -> bool {
+    return folder_name.len() > 1
+        && folder_name.starts_with('F')
+        && folder_name[1..].chars().all(|folder_name_char| folder_name_char.is_ascii_digit());
+}
+
+/// Lists the files in the music folders that no track item's file location points to.
+/// `referenced_file_locations` are relative to the iPod's root, like `Song::song_filename`.
+/// The iPod's filesystem isn't case sensitive, so neither is the comparison
+pub fn find_orphaned_files(ipod_mount_point: &str, referenced_file_locations: &[String]) -> Vec<OrphanedFile> {
+    let mut orphaned_files: Vec<OrphanedFile> = Vec::new();
+
+    let referenced_file_locations: HashSet<String> = referenced_file_locations
+        .iter()
+        .map(|file_location| file_location.trim_start_matches('/').to_lowercase())
+        .collect();
+
+    let music_folder_path = std::path::Path::new(ipod_mount_point).join("iPod_Control").join("Music");
+
+    let mut music_folder_names: Vec<String> = match std::fs::read_dir(&music_folder_path) {
+        Ok(music_folder_entries) => music_folder_entries
+            .filter_map(|music_folder_entry| music_folder_entry.ok())
+            .filter(|music_folder_entry| music_folder_entry.path().is_dir())
+            .map(|music_folder_entry| music_folder_entry.file_name().to_string_lossy().to_string())
+            .filter(|music_folder_name| is_music_folder_name(music_folder_name))
+            .collect(),
+        Err(_) => {
+            eprintln!("Can't list '{}', no orphaned files were looked for", music_folder_path.display());
+            return orphaned_files;
+        }
+    };
+
+    music_folder_names.sort();
+
+    for music_folder_name in music_folder_names.iter() {
+        let mut music_filenames: Vec<(String, u64)> = std::fs::read_dir(music_folder_path.join(music_folder_name))
+            .map(|music_file_entries| {
+                music_file_entries
+                    .filter_map(|music_file_entry| music_file_entry.ok())
+                    .filter(|music_file_entry| music_file_entry.path().is_file())
+                    .map(|music_file_entry| {
+                        (
+                            music_file_entry.file_name().to_string_lossy().to_string(),
+                            music_file_entry.metadata().map_or(0, |metadata| metadata.len()),
+                        )
+                    })
+                    // Mac-formatted iPods get "._" resource fork files next to the real ones
+                    .filter(|(music_filename, _)| !music_filename.starts_with('.'))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        music_filenames.sort();
+
+        for (music_filename, file_size_bytes) in music_filenames {
+            let filename = format!("iPod_Control/Music/{}/{}", music_folder_name, music_filename);
+
+            if referenced_file_locations.contains(&filename.to_lowercase()) {
+                continue;
+            }
+
+            orphaned_files.push(OrphanedFile {
+                absolute_path: music_folder_path.join(music_folder_name).join(&music_filename).display().to_string(),
+                file_extension: itunesdb_helpers::get_file_extension_from_path(&filename).unwrap_or_default(),
+                file_size_bytes,
+                file_size_friendly: helpers::convert_bytes_to_human_readable_size(file_size_bytes),
+                filename,
+            });
+        }
+    }
+
+    return orphaned_files;
+}
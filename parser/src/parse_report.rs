@@ -0,0 +1,67 @@
+/**
+ * File: parse_report.rs
+ *
+ * Keeps track of everything the parser had to skip, guess or work around while going
+ * through a database, so whoever reads the exports knows which values to trust.
+ *
+ */
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ParseReportEntry {
+    pub category: String,
+    /// Only set when the entry is about a specific track
+    pub track_id: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct ParseReport {
+    pub entries: Vec<ParseReportEntry>,
+}
+
+impl Default for ParseReport {
+    fn default() -> ParseReport {
+        return ParseReport {
+            entries: Vec::new(),
+        };
+    }
+}
+
+impl ParseReport {
+    pub fn add_entry(&mut self, category: &str, track_id: Option<u32>, message: String) {
+        self.entries.push(ParseReportEntry {
+            category: category.to_string(),
+            track_id,
+            message,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    /// By category name, in alphabetical order
+    pub fn get_num_entries_per_category(&self) -> BTreeMap<&str, usize> {
+        let mut num_entries_per_category: BTreeMap<&str, usize> = BTreeMap::new();
+
+        for entry in self.entries.iter() {
+            *num_entries_per_category
+                .entry(entry.category.as_str())
+                .or_insert(0) += 1;
+        }
+
+        return num_entries_per_category;
+    }
+
+    /// Prints how many entries there are of each category, rather than every single one
+    pub fn print_summary(&self) {
+        let num_entries_per_category = self.get_num_entries_per_category();
+
+        for (category, num_entries) in num_entries_per_category.iter() {
+            println!("Parse report: {} x '{}'", num_entries, category);
+        }
+    }
+}
@@ -1,35 +1,52 @@
 use crate::constants::deviceinfo_constants;
 use crate::helpers::helpers;
+use crate::ipod_device_info;
 
-pub fn parse_device_info_file(deviceinfo_file_as_bytes: Vec<u8>) {
+/// Returns `None` if the length doesn't fit in the string's block
+fn read_deviceinfo_string(deviceinfo_file_as_bytes: &[u8], string_offset: usize) -> Option<String> {
+    let string_length = helpers::build_le_u16_from_bytes(
+        &deviceinfo_file_as_bytes[string_offset..string_offset + deviceinfo_constants::DEVICEINFO_STRING_LENGTH_LEN],
+    ) as usize;
+
+    // factor of 2 to account for UTF-16 encoding (2 bytes per character)
+    if string_length * 2 > deviceinfo_constants::DEVICEINFO_MAX_STRING_LENGTH {
+        eprintln!("Invalid DeviceInfo string length value of '{}' at offset {}", string_length, string_offset);
+        return None;
+    }
+
+    let string_start = string_offset + deviceinfo_constants::DEVICEINFO_STRING_LENGTH_LEN;
+    let string_raw_bytes = &deviceinfo_file_as_bytes[string_start..string_start + (string_length * 2)];
+
+    return Some(String::from_utf16_lossy(&helpers::return_utf16_from_utf8(string_raw_bytes)));
+}
+
+/// Fills in the iPod, user and host computer names. Returns `false` (leaving them empty)
+/// if the file isn't the right size
+pub fn read_device_info(deviceinfo_file_as_bytes: &[u8], ipod_device_info: &mut ipod_device_info::IpodDeviceInfo) -> bool {
     if deviceinfo_file_as_bytes.len() != deviceinfo_constants::DEVICEINFO_FILE_SIZE {
-        panic!(
+        eprintln!(
             "Invalid DeviceInfo file size! Expected: {} | Got: {}",
             deviceinfo_constants::DEVICEINFO_FILE_SIZE,
             deviceinfo_file_as_bytes.len()
         );
+        return false;
     }
 
-    let ipod_name_length_raw = &deviceinfo_file_as_bytes[0..2];
-
-    let ipod_name_length = helpers::build_le_u16_from_bytes(ipod_name_length_raw) as usize;
+    ipod_device_info.ipod_name = read_deviceinfo_string(deviceinfo_file_as_bytes, deviceinfo_constants::DEVICEINFO_IPOD_NAME_OFFSET).unwrap_or_default();
+    ipod_device_info.user_name = read_deviceinfo_string(deviceinfo_file_as_bytes, deviceinfo_constants::DEVICEINFO_USER_NAME_OFFSET).unwrap_or_default();
+    ipod_device_info.host_computer_name = read_deviceinfo_string(deviceinfo_file_as_bytes, deviceinfo_constants::DEVICEINFO_HOST_NAME_OFFSET).unwrap_or_default();
 
-    // The strings are formatted using UTF-16 so this byte value must be a multiple of 2
-    if ipod_name_length % 2 != 0
-        || ipod_name_length > deviceinfo_constants::DEVICEINFO_MAX_STRING_LENGTH
-    {
-        panic!("Invalid iPod Name length value of '{}'", ipod_name_length);
-    }
+    return true;
+}
 
-    println!("iPod Name Length: {}", ipod_name_length);
+pub fn parse_device_info_file(deviceinfo_file_as_bytes: Vec<u8>) {
+    let mut ipod_device_info = ipod_device_info::IpodDeviceInfo::default();
 
-    // factor of 2 to account for UTF-16 encoding (2 bytes per character),
-    // and the +2 to account for the length bytes.
-    // no need to use helper method here because there's no index variable
-    let ipod_name_raw_bytes = &deviceinfo_file_as_bytes[2..(ipod_name_length * 2 + 2)];
+    if !read_device_info(&deviceinfo_file_as_bytes, &mut ipod_device_info) {
+        panic!("Can't read DeviceInfo file");
+    }
 
-    println!(
-        "iPod Name: {:?}",
-        String::from_utf16(&helpers::return_utf16_from_utf8(ipod_name_raw_bytes)).unwrap()
-    );
+    println!("iPod Name: {:?}", ipod_device_info.ipod_name);
+    println!("User Name: {:?}", ipod_device_info.user_name);
+    println!("Host Computer Name: {:?}", ipod_device_info.host_computer_name);
 }
@@ -4,7 +4,9 @@ use crate::constants::itunesdb_constants;
 use crate::helpers::helpers;
 use crate::equalizer;
 
-pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj: csv::Writer<std::fs::File>) {
+/// The presets are in the order songs refer to them, see `EQUALIZER_FIRST_PRESET_ID`
+pub fn read_equalizer_presets(equalizer_file_as_bytes: &[u8]) -> Vec<equalizer::EqualizerPreset> {
+    let mut equalizer_presets: Vec<equalizer::EqualizerPreset> = Vec::new();
     let mut idx: usize = 0;
 
     while idx < (equalizer_file_as_bytes.len() - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE) {
@@ -21,7 +23,9 @@ pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj
                 equalizer_constants::EQUALIZER_NUMBER_OF_PRESETS_LEN,
             );
 
-            println!("Equalizer file has {} presets", num_presets);
+            if num_presets == 0 {
+                eprintln!("Equalizer file has no presets");
+            }
 
             let preset_child_size = helpers::get_slice_as_le_u32(
                 idx,
@@ -33,12 +37,13 @@ pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj
             if preset_child_size != equalizer_constants::EQUALIZER_PRESET_CHILDSIZE_VALUE as u32 {
                 panic!("Invalid preset child size value of '{}'", preset_child_size);
             }
-
-            println!("==========");
         } else if equalizer_type_heading
             == equalizer_constants::EQUALIZER_PRESET_PRESET_OBJECT_KEY.as_bytes()
         {
-            let mut curr_equalizer_preset = equalizer::EqualizerPreset::default();
+            let mut curr_equalizer_preset = equalizer::EqualizerPreset {
+                equalizer_preset_id: equalizer_constants::EQUALIZER_FIRST_PRESET_ID + equalizer_presets.len() as u32,
+                ..Default::default()
+            };
 
             let preset_name_length = helpers::build_le_u16_from_bytes(
                 &equalizer_file_as_bytes[idx
@@ -55,8 +60,6 @@ pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj
 
             curr_equalizer_preset.equalizer_preset_name_len = preset_name_length;
 
-            println!("Preset Name Length: {}", preset_name_length);
-
             // Factor of 2 to account for UTF-16 encoding (2 bytes per character)
             let preset_name_raw_bytes = helpers::get_slice_from_offset_with_len(
                 idx,
@@ -68,10 +71,6 @@ pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj
             let preset_name_str = String::from_utf16(&helpers::return_utf16_from_utf8(&preset_name_raw_bytes))
             .unwrap();
 
-            println!(
-                "Preset Name: {:?}", preset_name_str
-            );
-
             curr_equalizer_preset.equalizer_preset_name = preset_name_str;
 
             let preamp_value_raw = helpers::get_slice_as_le_u32(
@@ -178,13 +177,54 @@ pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, mut csv_writer_obj
                 //println!("[DSP] Band Value: {}", band_value);
             }
 
-            csv_writer_obj.write_record(&["Preset Name", "Preset Name length", "Preamp Value (Raw)", "Preamp Value (dB)", "Band #1 Frequency (Hz)", "Band #1 Frequency display", "Band #1 Value (Raw)", "Band #1 Value (dB)", "Band #2 Frequency (Hz)", "Band #2 Frequency display", "Band #2 Value (Raw)", "Band #2 Value (dB)", "Band #3 Frequency (Hz)", "Band #3 Frequency display", "Band #3 Value (Raw)", "Band #3 Value (dB)", "Band #4 Frequency (Hz)", "Band #4 Frequency display", "Band #4 Value (Raw)", "Band #4 Value (dB)", "Band #5 Frequency (Hz)", "Band #5 Frequency display", "Band #5 Value (Raw)", "Band #5 Value (dB)", "Band #6 Frequency (Hz)", "Band #6 Frequency display", "Band #6 Value (Raw)", "Band #6 Value (dB)", "Band #7 Frequency (Hz)", "Band #7 Frequency display", "Band #7 Value (Raw)", "Band #7 Value (dB)", "Band #8 Frequency (Hz)", "Band #8 Frequency display", "Band #8 Value (Raw)", "Band #8 Value (dB)", "Band #9 Frequency (Hz)", "Band #9 Frequency display", "Band #9 Value (Raw)", "Band #9 Value (dB)", "Band #10 Frequency (Hz)", "Band #10 Frequency display", "Band #10 Value (Raw)", "Band #10 Value (dB)"]).unwrap();
-
-            csv_writer_obj.write_record(&[format!("'{}'", curr_equalizer_preset.equalizer_preset_name), format!("{}", curr_equalizer_preset.equalizer_preset_name_len), format!("{}", curr_equalizer_preset.equalizer_preamp_value_raw), format!("{}", curr_equalizer_preset.equalizer_preamp_value_db), format!("{}", curr_equalizer_frequency_settings[0].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[0].display_frequency), format!("{}", curr_equalizer_frequency_settings[0].gain_raw), format!("{}", curr_equalizer_frequency_settings[0].gain_db), format!("{}", curr_equalizer_frequency_settings[1].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[1].display_frequency), format!("{}", curr_equalizer_frequency_settings[1].gain_raw), format!("{}", curr_equalizer_frequency_settings[1].gain_db), format!("{}", curr_equalizer_frequency_settings[2].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[2].display_frequency), format!("{}", curr_equalizer_frequency_settings[2].gain_raw), format!("{}", curr_equalizer_frequency_settings[2].gain_db), format!("{}", curr_equalizer_frequency_settings[3].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[3].display_frequency), format!("{}", curr_equalizer_frequency_settings[3].gain_raw), format!("{}", curr_equalizer_frequency_settings[3].gain_db), format!("{}", curr_equalizer_frequency_settings[4].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[4].display_frequency), format!("{}", curr_equalizer_frequency_settings[4].gain_raw), format!("{}", curr_equalizer_frequency_settings[4].gain_db), format!("{}", curr_equalizer_frequency_settings[5].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[5].display_frequency), format!("{}", curr_equalizer_frequency_settings[5].gain_raw), format!("{}", curr_equalizer_frequency_settings[5].gain_db), format!("{}", curr_equalizer_frequency_settings[6].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[6].display_frequency), format!("{}", curr_equalizer_frequency_settings[6].gain_raw), format!("{}", curr_equalizer_frequency_settings[6].gain_db), format!("{}", curr_equalizer_frequency_settings[7].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[7].display_frequency), format!("{}", curr_equalizer_frequency_settings[7].gain_raw), format!("{}", curr_equalizer_frequency_settings[7].gain_db), format!("{}", curr_equalizer_frequency_settings[8].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[8].display_frequency), format!("{}", curr_equalizer_frequency_settings[8].gain_raw), format!("{}", curr_equalizer_frequency_settings[8].gain_db), format!("{}", curr_equalizer_frequency_settings[9].real_frequency_hz), format!("{}", curr_equalizer_frequency_settings[9].display_frequency), format!("{}", curr_equalizer_frequency_settings[9].gain_raw), format!("{}", curr_equalizer_frequency_settings[9].gain_db)]).unwrap();
+            curr_equalizer_preset.equalizer_bands = curr_equalizer_frequency_settings;
 
-            println!("-----------");
+            equalizer_presets.push(curr_equalizer_preset);
         }
 
         idx += itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE
     }
+    return equalizer_presets;
+}
+
+pub fn write_equalizer_presets_csv(equalizer_presets: &[equalizer::EqualizerPreset], mut csv_writer_obj: helpers::CsvWriter) {
+    let mut csv_header: Vec<String> = vec!["Preset ID".to_string(), "Preset Name".to_string(), "Preset Name length".to_string(), "Preamp Value (Raw)".to_string(), "Preamp Value (dB)".to_string()];
+
+    for band_idx in 1..=equalizer_constants::EQUALIZER_NUM_OF_ITUNES_BANDS_EXPECTED_VALUE {
+        csv_header.push(format!("Band #{} Frequency (Hz)", band_idx));
+        csv_header.push(format!("Band #{} Frequency display", band_idx));
+        csv_header.push(format!("Band #{} Value (Raw)", band_idx));
+        csv_header.push(format!("Band #{} Value (dB)", band_idx));
+    }
+
+    csv_writer_obj.write_record(&csv_header).unwrap();
+
+    for equalizer_preset in equalizer_presets.iter() {
+        let mut csv_row: Vec<String> = vec![format!("{}", equalizer_preset.equalizer_preset_id), format!("'{}'", equalizer_preset.equalizer_preset_name), format!("{}", equalizer_preset.equalizer_preset_name_len), format!("{}", equalizer_preset.equalizer_preamp_value_raw), format!("{}", equalizer_preset.equalizer_preamp_value_db)];
+
+        for equalizer_band in equalizer_preset.equalizer_bands.iter() {
+            csv_row.push(format!("{}", equalizer_band.real_frequency_hz));
+            csv_row.push(equalizer_band.display_frequency.to_string());
+            csv_row.push(format!("{}", equalizer_band.gain_raw));
+            csv_row.push(format!("{}", equalizer_band.gain_db));
+        }
+
+        csv_writer_obj.write_record(&csv_row).unwrap();
+    }
+}
+
+pub fn parse_equalizer_file(equalizer_file_as_bytes: Vec<u8>, csv_writer_obj: helpers::CsvWriter) {
+    let equalizer_presets = read_equalizer_presets(&equalizer_file_as_bytes);
+
+    println!("Equalizer file has {} presets", equalizer_presets.len());
+    println!("==========");
+
+    for equalizer_preset in equalizer_presets.iter() {
+        println!("Preset Name Length: {}", equalizer_preset.equalizer_preset_name_len);
+        println!("Preset Name: {:?}", equalizer_preset.equalizer_preset_name);
+        println!("Preset ID: {}", equalizer_preset.equalizer_preset_id);
+        println!("-----------");
+    }
+
+    write_equalizer_presets_csv(&equalizer_presets, csv_writer_obj);
 }
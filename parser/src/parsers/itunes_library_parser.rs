@@ -0,0 +1,51 @@
+use crate::constants::itunes_library_constants;
+
+use crate::helpers::plist_helpers;
+use crate::itunes_library;
+
+/// Returns `None` if it isn't an XML plist with a "Tracks" dict
+pub fn read_itunes_library(itunes_library_file_as_bytes: &[u8]) -> Option<Vec<itunes_library::LibraryTrack>> {
+
+    let itunes_library = match plist_helpers::parse_xml_plist(&String::from_utf8_lossy(itunes_library_file_as_bytes)) {
+        Some(itunes_library) => itunes_library,
+        None => {
+            eprintln!("iTunes Library isn't an XML plist, it was skipped");
+            return None;
+        }
+    };
+
+    let track_dicts = match itunes_library.get(itunes_library_constants::ITUNES_LIBRARY_TRACKS_KEY) {
+        Some(plist_helpers::PlistValue::Dict(track_dicts)) => track_dicts,
+        _ => {
+            eprintln!("iTunes Library has no tracks, it was skipped");
+            return None;
+        }
+    };
+
+    let get_string = |track_dict: &plist_helpers::PlistValue, key: &str| -> String {
+        return track_dict.get(key).and_then(|value| value.as_string()).unwrap_or_default();
+    };
+
+    let get_integer = |track_dict: &plist_helpers::PlistValue, key: &str| -> i64 {
+        return track_dict.get(key).and_then(|value| value.as_integer()).unwrap_or(0);
+    };
+
+    let mut library_tracks: Vec<itunes_library::LibraryTrack> = Vec::new();
+
+    for (_, track_dict) in track_dicts.iter() {
+        let is_rating_computed = track_dict.get(itunes_library_constants::ITUNES_LIBRARY_RATING_COMPUTED_KEY).is_some();
+
+        library_tracks.push(itunes_library::LibraryTrack {
+            track_id: get_integer(track_dict, itunes_library_constants::ITUNES_LIBRARY_TRACK_ID_KEY) as u32,
+            persistent_id: u64::from_str_radix(&get_string(track_dict, itunes_library_constants::ITUNES_LIBRARY_PERSISTENT_ID_KEY), 16).unwrap_or(0),
+            name: get_string(track_dict, itunes_library_constants::ITUNES_LIBRARY_NAME_KEY),
+            artist: get_string(track_dict, itunes_library_constants::ITUNES_LIBRARY_ARTIST_KEY),
+            album: get_string(track_dict, itunes_library_constants::ITUNES_LIBRARY_ALBUM_KEY),
+            play_count: get_integer(track_dict, itunes_library_constants::ITUNES_LIBRARY_PLAY_COUNT_KEY) as u32,
+            rating_raw: if is_rating_computed { 0 } else { get_integer(track_dict, itunes_library_constants::ITUNES_LIBRARY_RATING_KEY) as u8 },
+            is_music: !itunes_library_constants::ITUNES_LIBRARY_NON_MUSIC_KEYS.iter().any(|non_music_key| track_dict.get(non_music_key).is_some()),
+        });
+    }
+
+    return Some(library_tracks);
+}
@@ -1,22 +1,136 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fmt::Write;
-use std::fs::File;
 use std::io;
 
+#[cfg(feature = "arrow")]
+use crate::arrow_export;
+use crate::albums;
+use crate::artists;
+use crate::audio_properties;
+use crate::constants::deviceinfo_constants;
+use crate::constants::equalizer_constants;
+use crate::constants::export_schema_constants;
 use crate::constants::itunesdb_constants;
+use crate::constants::itunesprefs_constants;
+use crate::constants::otgplaylist_constants;
+use crate::constants::preferences_constants;
+use crate::database_hash;
+use crate::device_menu;
+use crate::equalizer;
+use crate::ipod_device_info;
+use crate::itunes_library;
+use crate::json_schema;
+use crate::library_report;
+use crate::library_stats;
+use crate::lyrics;
+use crate::orphaned_files;
+use crate::parse_report;
+use crate::parsers::deviceinfo_parser;
+use crate::parsers::equalizer_parser;
+use crate::parsers::itunes_library_parser;
+use crate::parsers::otgplaylist_parser;
+use crate::parsers::photo_type_parser;
+use crate::parsers::playcounts_parser;
+use crate::parsers::preferences_parser;
+use crate::parsers::sysinfo_parser;
+use crate::playcounts;
+use crate::playlist_items;
+use crate::scrobbles;
 use crate::itunesdb;
+use crate::smart_playlist;
+#[cfg(feature = "sqlite")]
+use crate::sqlite_export;
+use crate::track_files;
+use crate::video_properties;
+#[cfg(feature = "xlsx")]
+use crate::xlsx_export;
 
 use crate::helpers::helpers;
 use crate::helpers::itunesdb_helpers;
+use crate::helpers::plist_helpers;
+
+/// The command line options of the "itunes" type, besides the output format
+pub struct ItunesDbParseOptions {
+    /// Where the iPod is mounted, to read its other files (SysInfo, Play Counts, artwork...) and check the track files
+    pub ipod_mount_point: Option<String>,
+    /// An iTunes Library XML file to compare the iPod's songs with
+    pub itunes_library_filename: Option<String>,
+    /// Hash every track file with this algorithm (e.g. "md5"), needs the mount point
+    pub checksum_algorithm: Option<String>,
+    /// The CSV columns to keep, comma-separated
+    pub music_columns: Option<String>,
+    pub podcast_columns: Option<String>,
+    /// Where to stream a single export as NDJSON ("-" for stdout), and which one
+    pub output_path: Option<String>,
+    pub export_name: Option<String>,
+}
 
-pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: String) {
+pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: String, parse_options: ItunesDbParseOptions) {
+    let ItunesDbParseOptions {
+        ipod_mount_point,
+        itunes_library_filename,
+        checksum_algorithm,
+        music_columns,
+        podcast_columns,
+        output_path,
+        export_name,
+    } = parse_options;
+
+    // With `--output -`, stdout is only for the chosen export, so everything printed before it goes to stderr instead
+    let stdout_redirect = if output_path.as_deref() == Some("-") {
+        Some(gag::Redirect::stdout(io::stderr()).expect("Can't move the progress messages from stdout to stderr"))
+    } else {
+        None
+    };
 
     let mut songs_found: Vec<itunesdb::Song> = Vec::new();
     let mut podcasts_found: Vec<itunesdb::Podcast> = Vec::new();
+    let mut videos_found: Vec<itunesdb::Video> = Vec::new();
+    let mut itunesu_items_found: Vec<itunesdb::ITunesUItem> = Vec::new();
+    let mut ringtones_found: Vec<itunesdb::Ringtone> = Vec::new();
+    let mut voice_memos_found: Vec<itunesdb::VoiceMemo> = Vec::new();
+    let mut audiobooks_found: Vec<itunesdb::Audiobook> = Vec::new();
+    let mut playlists_found: Vec<itunesdb::Playlist> = Vec::new();
+    let mut podcast_groups_found: Vec<itunesdb::PodcastGroup> = Vec::new();
+    let mut artwork_found: Vec<itunesdb::TrackArtwork> = Vec::new();
+    // Hidden tracks are parsed like any other, and only left out of the exports at the end
+    let mut hidden_track_ids: Vec<u32> = Vec::new();
+    // Every track item's file, whatever its media type. The file location is filled in from its data object
+    let mut track_files_found: Vec<track_files::TrackFile> = Vec::new();
 
     let mut curr_song = itunesdb::Song::default();
     let mut curr_podcast = itunesdb::Podcast::default();
+    let mut curr_video = itunesdb::Video::default();
+    let mut curr_itunesu_item = itunesdb::ITunesUItem::default();
+    let mut curr_ringtone = itunesdb::Ringtone::default();
+    let mut is_curr_song_voice_memo = false;
+    // Newer databases have a longer track item header than documented, reported once after the loop
+    let mut longest_track_item_header_len: u32 = 0;
+    // Data objects of types that aren't decoded, by type, also reported once after the loop
+    let mut unknown_data_object_type_counts: BTreeMap<u32, u32> = BTreeMap::new();
+    let mut curr_track_unique_id: u32 = 0;
+    // Other files (e.g. Play Counts) refer to tracks by their position in the Track List
+    let mut track_list_track_ids: Vec<u32> = Vec::new();
+    let mut curr_audiobook = itunesdb::Audiobook::default();
+
+    let mut curr_playlist: Option<itunesdb::Playlist> = None;
+    let mut is_in_podcast_group_playlist = false;
+    let mut is_curr_playlist_genius_flag_set = false;
+
+    // mhit/mhyp/mhip headers declare how many data objects follow them. Anything past
+    // that count doesn't belong to the current record, and must not be attached to it
+    let mut data_objects_remaining: u32 = 0;
+
+    let mut parse_report = parse_report::ParseReport::default();
+
+    let mut ipod_device_info = ipod_device_info::IpodDeviceInfo::default();
+    let mut is_database_signed_with_hash58 = false;
+
+    let mut library_stats = library_stats::LibraryStats::default();
 
     let mut curr_media_type = itunesdb::HandleableMediaType::UNKNOWN;
+    let mut curr_dataset_type: u32 = 0;
 
     let mut idx = 0;
 
@@ -33,19 +147,79 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 itunesdb_constants::DATABASE_OBJECT_LANGUAGE_LEN,
             );
 
-            let db_language = std::str::from_utf8(&db_language_raw)
-                .expect("Can't parse database language string");
+            if let Some((db_language_code, db_language_name)) =
+                ipod_device_info::decode_database_language(&db_language_raw)
+            {
+                ipod_device_info.language_code = db_language_code;
+                ipod_device_info.language_name = db_language_name;
+            } else {
+                println!(
+                    "Warning: unrecognized database language bytes {:?}, using '{}'",
+                    db_language_raw,
+                    ipod_device_info::UNKNOWN_LANGUAGE
+                );
+
+                parse_report.add_entry(
+                    "Unrecognized database language",
+                    None,
+                    format!("Language bytes {:?} aren't an ISO 639-1 code", db_language_raw),
+                );
+            }
+
+            ipod_device_info.database_version = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::DATABASE_OBJECT_VERSION_NUMBER_OFFSET,
+                itunesdb_constants::DATABASE_OBJECT_VERSION_NUMBER_LEN,
+            );
+
+            ipod_device_info.itunes_version =
+                itunesdb::parse_version_number(ipod_device_info.database_version);
 
             println!(
                 "File is using language: {}, and has iTunes version: {}",
-                db_language,
-                itunesdb::parse_version_number(helpers::get_slice_as_le_u32(
+                ipod_device_info.language_code, ipod_device_info.itunes_version
+            );
+
+            let (hashing_scheme_name, hashing_scheme) =
+                itunesdb::decode_hashing_scheme(helpers::get_slice_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
-                    itunesdb_constants::DATABASE_OBJECT_VERSION_NUMBER_OFFSET,
-                    itunesdb_constants::DATABASE_OBJECT_VERSION_NUMBER_LEN
-                ))
-            );
+                    itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_OFFSET,
+                    itunesdb_constants::DATABASE_OBJECT_HASHING_SCHEME_LEN,
+                ));
+
+            is_database_signed_with_hash58 = hashing_scheme == Some(itunesdb::HandleableHashingScheme::Hash58);
+
+            // Checking the hash needs the device's FireWire GUID, that's done once SysInfo is read. Here it's only
+            // checked that there is one at all
+            if let Some((hash_offset, hash_len)) = hashing_scheme
+                .as_ref()
+                .and_then(itunesdb::get_database_hash_location)
+            {
+                let stored_hash = helpers::get_slice_from_offset_with_len(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    hash_offset,
+                    hash_len,
+                );
+
+                if stored_hash.iter().all(|byte| *byte == 0) {
+                    parse_report.add_entry(
+                        "Database signature missing",
+                        None,
+                        format!(
+                            "Database is meant to be signed with {}, but the hash is empty. The iPod will likely refuse it",
+                            hashing_scheme_name
+                        ),
+                    );
+                }
+
+            }
+
+            println!("Database signature: {}", hashing_scheme_name);
+
+            ipod_device_info.hashing_scheme = hashing_scheme_name;
 
             idx += itunesdb_constants::DATABASE_OBJECT_LAST_OFFSET;
         }
@@ -60,6 +234,16 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
 
             let dataset_type_parsed = itunesdb::parse_dataset_type(dataset_type_raw[0] as u32);
 
+            curr_dataset_type = dataset_type_raw[0] as u32;
+
+            // The last playlist of the previous dataset is complete
+            if let Some(playlist) = curr_playlist.take() {
+                playlists_found.push(playlist);
+            }
+
+            is_in_podcast_group_playlist = false;
+            data_objects_remaining = 0;
+
             // println!(
             //     "Dataset Type: {}",
             //     dataset_type_parsed
@@ -78,25 +262,57 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
 
             println!("{} songs in tracklist", num_songs_in_db);
 
+            library_stats.declared_num_tracks = Some(num_songs_in_db);
+
             idx += itunesdb_constants::TRACKLIST_LAST_OFFSET;
+        } else if potential_section_heading == itunesdb_constants::PLAYLIST_LIST_KEY.as_bytes() {
+            let num_playlists_in_list = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_LIST_NUM_PLAYLISTS_OFFSET,
+                itunesdb_constants::PLAYLIST_LIST_NUM_PLAYLISTS_LEN,
+            );
+
+            // Only these two datasets end up in the parsed playlists
+            if (curr_dataset_type == itunesdb::HandleableDatasetType::Playlists as u32)
+                || (curr_dataset_type == itunesdb::HandleableDatasetType::SmartPlaylists as u32)
+            {
+                library_stats.declared_num_playlists = Some(
+                    library_stats.declared_num_playlists.unwrap_or(0) + num_playlists_in_list,
+                );
+            }
+
+            idx += itunesdb_constants::PLAYLIST_LIST_LAST_OFFSET;
         } else if potential_section_heading == itunesdb_constants::TRACK_ITEM_KEY.as_bytes() {
             let mut track_item_info: String = String::new();
 
+            let track_index = track_list_track_ids.len() as u32;
+
+            data_objects_remaining = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                itunesdb_constants::TRACK_ITEM_NUM_DATA_OBJECTS_LEN,
+            );
+
+            let track_number = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_NUMBER_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_NUMBER_LEN,
+            );
+
+            let num_tracks_in_album = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_NUM_TRACKS_IN_ALBUM_OFFSET,
+                itunesdb_constants::TRACK_ITEM_NUM_TRACKS_IN_ALBUM_LEN,
+            );
+
             write!(
                 track_item_info,
                 "========== Track #{} of {} ",
-                helpers::get_slice_as_le_u32(
-                    idx,
-                    &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_TRACK_NUMBER_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_TRACK_NUMBER_LEN
-                ),
-                helpers::get_slice_as_le_u32(
-                    idx,
-                    &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_NUM_TRACKS_IN_ALBUM_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_NUM_TRACKS_IN_ALBUM_LEN
-                )
+                track_number, num_tracks_in_album
             )
             .unwrap();
 
@@ -107,15 +323,15 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 itunesdb_constants::TRACK_ITEM_TRACK_TOTAL_NUM_DISCS_LEN,
             );
 
+            let tracks_current_disc_num = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_DISC_NUMBER_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_DISC_NUMBER_LEN,
+            );
+
             // Only print disc info if current song is part of multi-disc set
             if num_discs > 0 {
-                let tracks_current_disc_num = helpers::get_slice_as_le_u32(
-                    idx,
-                    &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_TRACK_DISC_NUMBER_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_TRACK_DISC_NUMBER_LEN,
-                );
-
                 write!(
                     track_item_info,
                     " | 💿 #{} of {}",
@@ -126,17 +342,28 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
 
             write!(track_item_info, "==========\n").unwrap();
 
+            let track_item_header_len = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_HEADER_LEN_OFFSET,
+                itunesdb_constants::TRACK_ITEM_HEADER_LEN_LEN,
+            );
+
+            longest_track_item_header_len = longest_track_item_header_len.max(track_item_header_len);
+
             let track_filetype_raw = &itunesdb_file_as_bytes[idx
                 + itunesdb_constants::TRACK_ITEM_TRACK_FILETYPE_OFFSET
                 ..idx
                     + itunesdb_constants::TRACK_ITEM_TRACK_FILETYPE_OFFSET
                     + itunesdb_constants::TRACK_ITEM_TRACK_FILETYPE_LEN];
 
+            let mut track_item_extension: String = String::new();
+
             // TODO: encapsulate this logic elsewhere
             if helpers::build_le_u32_from_bytes(track_filetype_raw) == 0 {
                 println!("Track Item file type missing. Is this is a 1st - 4th gen iPod?");
             } else {
-                let track_item_extension = itunesdb::decode_track_item_filetype(track_filetype_raw);
+                track_item_extension = itunesdb::decode_track_item_filetype(track_filetype_raw);
                 write!(
                     track_item_info,
                     "Track extension: '{}' | ",
@@ -144,62 +371,214 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 )
                 .unwrap();
 
-                curr_song.file_extension = track_item_extension;
+                curr_song.file_extension = track_item_extension.to_string();
             }
 
-            let track_media_type_raw = &itunesdb_file_as_bytes[idx
-                + itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_OFFSET
-                ..idx
-                    + itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_OFFSET
-                    + itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_LEN];
+            // Fields past the end of the header weren't written by this database's iTunes version
+            let track_media_type_raw = itunesdb_helpers::get_header_field_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                track_item_header_len,
+                itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_LEN,
+            )
+            .unwrap_or(itunesdb_constants::TRACK_ITEM_TRACK_MEDIA_TYPE_AUDIO)
+            .to_le_bytes();
 
-            let track_movie_file_flag = helpers::get_slice_as_le_u32(
+            let track_is_movie_file = itunesdb_helpers::get_header_byte_flag(
                 idx,
                 &itunesdb_file_as_bytes,
+                track_item_header_len,
                 itunesdb_constants::TRACK_ITEM_TRACK_MOVIE_FLAG_SETTING_OFFSET,
-                itunesdb_constants::TRACK_ITEM_TRACK_MOVIE_FLAG_SETTING_LEN,
             );
 
-            let (track_media_type_name, track_media_type_enum) =
-                itunesdb::decode_track_media_type(track_media_type_raw);
+            let (track_media_type_name, mut track_media_type_enum) =
+                itunesdb::decode_track_media_type(&track_media_type_raw);
+
+            let mut track_media_kind = track_media_type_name.to_string();
+
+            if itunesdb::is_audiobook_file_extension(&track_item_extension) {
+                track_media_type_enum = itunesdb::HandleableMediaType::Audiobook;
+                track_media_kind = "Audiobook".to_string();
+            } else if itunesdb::is_ringtone_file_extension(&track_item_extension) {
+                track_media_type_enum = itunesdb::HandleableMediaType::Ringtone;
+                track_media_kind = "Ringtone".to_string();
+            }
+
+            // Voice memos are collected like songs, and only split off once their file location shows up
+            is_curr_song_voice_memo =
+                track_media_type_enum == itunesdb::HandleableMediaType::VoiceMemo;
+
+            if is_curr_song_voice_memo {
+                track_media_type_enum = itunesdb::HandleableMediaType::SongLike;
+            }
+
+            // TV shows are collected like any other video, and told apart by their media kind
+            if track_media_type_enum == itunesdb::HandleableMediaType::Television {
+                track_media_type_enum = itunesdb::HandleableMediaType::Video;
+            }
+
+            let track_unique_id = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_UNIQUE_ID_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_UNIQUE_ID_LEN,
+            );
+
+            curr_track_unique_id = track_unique_id;
+            track_list_track_ids.push(track_unique_id);
+            library_stats.parsed_num_tracks += 1;
+
+            let track_visible_setting = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_VISIBLE_SETTING_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_VISIBLE_SETTING_LEN,
+            );
+
+            if track_visible_setting != 1 {
+                hidden_track_ids.push(track_unique_id);
+            }
+
+            let track_dbid = helpers::get_slice_as_le_u64(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_DBID_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_DBID_LEN,
+            );
 
             write!(
                 track_item_info,
                 "Movie file flag: {} | Media Type: {} \n",
-                (track_movie_file_flag == 1),
+                track_is_movie_file,
                 track_media_type_name
             )
             .unwrap();
 
-            if matches!(
-                track_media_type_enum,
-                itunesdb::HandleableMediaType::Television
-            ) {
-                let season_number = helpers::get_slice_as_le_u32(
-                    idx,
-                    &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_TRACK_SEASON_NUMBER_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_TRACK_SEASON_NUMBER_LEN,
-                );
 
-                let episode_number = helpers::get_slice_as_le_u32(
+            // Every media type can have cover art, not just songs
+            let mut track_artwork: Option<itunesdb::TrackArtwork> = None;
+
+            if itunesdb_helpers::is_field_in_header(
+                track_item_header_len,
+                itunesdb_constants::TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_OFFSET,
+                1,
+            ) && helpers::get_byte_flag(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_OFFSET,
+            ) {
+                let track_associated_artwork_size = itunesdb_helpers::get_header_field_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_TRACK_EPISODE_NUMBER_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_TRACK_EPISODE_NUMBER_LEN,
-                );
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_LEN,
+                )
+                .unwrap_or(0);
 
-                write!(
+                writeln!(
                     track_item_info,
-                    "Season #{} Episode #{}",
-                    season_number, episode_number
+                    "🎨 artwork size: {} bytes ",
+                    track_associated_artwork_size
                 )
                 .unwrap();
-            } else if matches!(
+
+                track_artwork = Some(itunesdb::TrackArtwork {
+                    track_id: track_unique_id,
+                    dbid: track_dbid,
+                    media_kind: track_media_kind.to_string(),
+                    artwork_count: itunesdb_helpers::get_header_field_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        track_item_header_len,
+                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_COUNT_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_COUNT_LEN,
+                    )
+                    .unwrap_or(0),
+                    artwork_size_bytes: track_associated_artwork_size,
+                    // Shorter headers (0x9C, 0xF4, 0x148) end before the ArtworkDB link, it stays 0 for them
+                    artwork_id: itunesdb_helpers::get_header_field_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        track_item_header_len,
+                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_ID_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_ID_LEN,
+                    )
+                    .unwrap_or(0),
+                    ..itunesdb::TrackArtwork::default()
+                });
+            }
+
+            let track_file_bitrate_kbps = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_BITRATE_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_BITRATE_LEN,
+            );
+
+            let track_file_duration_ms = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_OFFSET,
+                itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_LEN,
+            );
+
+            let (track_file_size_bytes, is_track_file_size_estimated) =
+                itunesdb::read_track_file_size(idx, &itunesdb_file_as_bytes);
+
+            if is_track_file_size_estimated {
+                parse_report.add_entry(
+                    "File size corrected for 4GB wraparound",
+                    Some(track_unique_id),
+                    format!(
+                        "Stored size of {} bytes is too small for {} kbps over {} ms, estimated {} bytes",
+                        track_file_size_bytes as u32,
+                        track_file_bitrate_kbps,
+                        track_file_duration_ms,
+                        track_file_size_bytes
+                    ),
+                );
+            }
+
+            track_files_found.push(track_files::TrackFile {
+                track_id: track_unique_id,
+                dbid: track_dbid,
+                media_kind: track_media_kind.to_string(),
+                database_size_bytes: track_file_size_bytes,
+                bitrate_kbps: track_file_bitrate_kbps,
+                duration_ms: track_file_duration_ms,
+                ..track_files::TrackFile::default()
+            });
+
+            if matches!(
                 track_media_type_enum,
                 itunesdb::HandleableMediaType::SongLike
             ) {
-                curr_media_type = track_media_type_enum;
+                curr_song.track_id = track_unique_id;
+                curr_song.track_index = track_index;
+                curr_song.dbid = track_dbid;
+                curr_song.media_kind = track_media_kind.to_string();
+                curr_song.media_type_raw = helpers::build_le_u32_from_bytes(&track_media_type_raw);
+
+                curr_song.resume_position_ms = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_LEN,
+                );
+
+                curr_song.track_number = track_number;
+                curr_song.num_tracks_in_album = num_tracks_in_album;
+                curr_song.disc_number = tracks_current_disc_num;
+                curr_song.num_discs = num_discs;
+
+                // The setting is on for unchecked songs
+                curr_song.is_checked = !helpers::get_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_CHECKED_SETTING_OFFSET,
+                );
 
                 let track_advanced_audio_type = helpers::get_slice_as_le_u32(
                     idx,
@@ -215,6 +594,8 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 )
                 .unwrap();
 
+                curr_song.audio_type_raw = track_advanced_audio_type;
+
                 let apple_user_id = helpers::get_slice_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
@@ -226,6 +607,8 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     write!(track_item_info, "Apple User ID: {} \n", apple_user_id).unwrap();
                 }
 
+                curr_song.apple_user_id = apple_user_id;
+
                 let track_bitrate_type_raw = &itunesdb_file_as_bytes[idx
                     + itunesdb_constants::TRACK_ITEM_TRACK_BITRATE_SETTING_OFFSET
                     ..idx
@@ -276,21 +659,17 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
 
                 curr_song.bitrate_kbps = track_bitrate;
                 curr_song.sample_rate_hz = track_sample_rate_hz;
+                curr_song.volume_adjustment = track_volume_setting as i32;
+                curr_song.bpm = track_bpm;
 
-                let track_size_bytes = helpers::get_slice_as_le_u32(
-                    idx,
-                    &itunesdb_file_as_bytes,
-                    itunesdb_constants::TRACK_ITEM_TRACK_FILE_SIZE_BYTES_OFFSET,
-                    itunesdb_constants::TRACK_ITEM_TRACK_FILE_SIZE_BYTES_LEN,
-                );
-
-                if track_size_bytes < 1 {
+                if track_file_size_bytes < 1 {
                     panic!("Error: Track must have non-zero file size");
                 }
 
-                write!(track_item_info, "Track size: {} bytes | ", track_size_bytes).unwrap();
+                write!(track_item_info, "Track size: {} bytes | ", track_file_size_bytes).unwrap();
 
-                curr_song.set_song_filesize(track_size_bytes);
+                curr_song.set_song_filesize(track_file_size_bytes);
+                curr_song.file_size_is_estimated = is_track_file_size_estimated;
 
                 let track_length_raw = helpers::get_slice_as_le_u32(
                     idx,
@@ -327,6 +706,9 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     itunesdb_constants::TRACK_ITEM_TRACK_STOP_TIME_LEN,
                 );
 
+                curr_song.start_time_ms = track_start_time_offset;
+                curr_song.stop_time_ms = track_stop_time_offset;
+
                 write!(
                     track_item_info,
                     "{} \n",
@@ -347,12 +729,14 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
 
                 curr_song.num_plays = track_play_count;
 
-                let track_skipped_count = helpers::get_slice_as_le_u32(
+                let track_skipped_count = itunesdb_helpers::get_header_field_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
+                    track_item_header_len,
                     itunesdb_constants::TRACK_ITEM_TRACK_SKIPPED_COUNT_OFFSET,
                     itunesdb_constants::TRACK_ITEM_TRACK_SKIPPED_COUNT_LEN,
-                );
+                )
+                .unwrap_or(0);
 
                 // TODO: WHy are the last played timestamps zero sometimes?
 
@@ -363,40 +747,94 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     itunesdb_constants::TRACK_ITEM_TRACK_LAST_PLAYED_TIMESTAMP_LEN,
                 );
 
-                let track_last_skipped_timestamp = helpers::get_slice_as_mac_timestamp(
+                let track_last_played_epoch = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_LAST_PLAYED_TIMESTAMP_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_LAST_PLAYED_TIMESTAMP_LEN,
+                );
+
+                if track_last_played_epoch > 0 {
+                    curr_song.last_played_at = Some(track_last_played_timestamp);
+                }
+
+                curr_song.num_skips = track_skipped_count;
+
+                let track_last_skipped_epoch = itunesdb_helpers::get_header_field_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
+                    track_item_header_len,
                     itunesdb_constants::TRACK_ITEM_TRACK_LAST_SKIPPED_TIMESTAMP_OFFSET,
                     itunesdb_constants::TRACK_ITEM_TRACK_LAST_SKIPPED_TIMESTAMP_LEN,
-                );
+                )
+                .unwrap_or(0);
 
-                let track_skip_when_shuffle_setting = &itunesdb_file_as_bytes[idx
-                    + itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET
-                    ..idx
-                        + itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET
-                        + itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_LEN];
+                let track_last_skipped_timestamp = helpers::get_timestamp_as_mac(track_last_skipped_epoch as u64);
 
-                write!(track_item_info, "Play/Skip statistics: # of plays: {} , Last played on: {} | # of skips: {}, Last skipped on: {} (Skip when shuffling? {}) ", track_play_count, track_last_played_timestamp, track_skipped_count, track_last_skipped_timestamp, track_skip_when_shuffle_setting[0] ).unwrap();
+                if track_last_skipped_epoch > 0 {
+                    curr_song.last_skipped_at =
+                        Some(helpers::get_timestamp_as_mac(track_last_skipped_epoch as u64));
+                }
 
-                let track_is_compilation_setting_raw = &itunesdb_file_as_bytes[idx
-                    + itunesdb_constants::TRACK_ITEM_IS_COMPILATION_SETTING_OFFSET
-                    ..idx
-                        + itunesdb_constants::TRACK_ITEM_IS_COMPILATION_SETTING_OFFSET
-                        + itunesdb_constants::TRACK_ITEM_IS_COMPILATION_SETTING_LEN];
+                curr_song.skip_when_shuffling = itunesdb_helpers::get_header_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET,
+                );
 
-                let track_has_lyrics_setting_raw = &itunesdb_file_as_bytes[idx
-                    + itunesdb_constants::TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_OFFSET
-                    ..idx
-                        + itunesdb_constants::TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_OFFSET
-                        + itunesdb_constants::TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_LEN];
+                curr_song.explicitness = itunesdb::decode_explicitness(
+                    helpers::get_slice_from_offset_with_len(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EXPLICIT_FLAG_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EXPLICIT_FLAG_LEN,
+                    )[0],
+                );
+                curr_song.remember_playback_position = itunesdb_helpers::is_field_in_header(
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET,
+                    1,
+                ) && helpers::get_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET,
+                );
+
+                write!(track_item_info, "Play/Skip statistics: # of plays: {} , Last played on: {} | # of skips: {}, Last skipped on: {} (Skip when shuffling? {}, Remember playback position? {}) ", track_play_count, track_last_played_timestamp, track_skipped_count, track_last_skipped_timestamp, curr_song.skip_when_shuffling, curr_song.remember_playback_position ).unwrap();
+
+                curr_song.is_compilation = helpers::get_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_IS_COMPILATION_SETTING_OFFSET,
+                );
+                curr_song.has_lyrics = itunesdb_helpers::get_header_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_LYRICS_AVAILABLE_SETTING_OFFSET,
+                );
 
                 write!(
                     track_item_info,
                     " \n Is part of compilation? {} , Has lyrics? {}",
-                    track_is_compilation_setting_raw[0], track_has_lyrics_setting_raw[0]
+                    curr_song.is_compilation, curr_song.has_lyrics
                 )
                 .unwrap();
 
+                curr_song.sound_check_raw = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_SOUND_CHECK_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_SOUND_CHECK_LEN,
+                );
+
+                curr_song.sound_check_gain_db =
+                    itunesdb::decode_sound_check_gain_db(curr_song.sound_check_raw);
+
+                curr_song.replaygain_track_gain =
+                    itunesdb::get_replaygain_track_gain(curr_song.sound_check_gain_db);
+
                 let track_rating = helpers::get_slice_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
@@ -404,16 +842,20 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     itunesdb_constants::TRACK_ITEM_TRACK_RATING_LEN,
                 );
 
+                let track_prev_rating = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_PREVIOUS_RATING_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_PREVIOUS_RATING_LEN,
+                );
+
+                curr_song.song_previous_rating_raw = track_prev_rating as u8;
+                curr_song.rating_source =
+                    itunesdb::decode_rating_source(track_rating as u8, track_prev_rating as u8);
+
                 if track_rating > 0 {
                     curr_song.song_rating_raw = track_rating as u8;
 
-                    let track_prev_rating = helpers::get_slice_as_le_u32(
-                        idx,
-                        &itunesdb_file_as_bytes,
-                        itunesdb_constants::TRACK_ITEM_TRACK_PREVIOUS_RATING_OFFSET,
-                        itunesdb_constants::TRACK_ITEM_TRACK_PREVIOUS_RATING_LEN,
-                    );
-
                     write!(
                         track_item_info,
                         "\n Rating info: Current rating: {} | Previous rating: {} \n",
@@ -423,12 +865,14 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     .unwrap();
                 }
 
-                let gapless_playback_setting_for_track = helpers::get_slice_as_le_u32(
+                let gapless_playback_setting_for_track = itunesdb_helpers::get_header_field_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
+                    track_item_header_len,
                     itunesdb_constants::TRACK_ITEM_TRACK_GAPLESS_PLAYBACK_SETTING_OFFSET,
                     itunesdb_constants::TRACK_ITEM_TRACK_GAPLESS_PLAYBACK_SETTING_LEN,
-                );
+                )
+                .unwrap_or(0);
 
                 if gapless_playback_setting_for_track == 1 {
                     let num_beginning_silence_samples = helpers::get_slice_as_le_u32(
@@ -454,15 +898,22 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                         itunesdb_constants::TRACK_ITEM_TRACK_NUM_SAMPLES_LEN,
                     );
 
+                    curr_song.gapless_enabled = true;
+                    curr_song.pregap_samples = num_beginning_silence_samples;
+                    curr_song.postgap_samples = num_ending_silence_samples;
+                    curr_song.num_samples = num_total_samples;
+
                     write!(track_item_info, "[Gapless playback info] # of silent samples ({} at start, {} at end) - Total {}\n", num_beginning_silence_samples, num_ending_silence_samples, num_total_samples).unwrap();
                 }
 
-                let track_crossfade_setting = helpers::get_slice_as_le_u32(
+                let track_crossfade_setting = itunesdb_helpers::get_header_field_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
+                    track_item_header_len,
                     itunesdb_constants::TRACK_ITEM_TRACK_CROSSFADING_SETTING_OFFSET,
                     itunesdb_constants::TRACK_ITEM_TRACK_CROSSFADING_SETTING_LEN,
-                );
+                )
+                .unwrap_or(0);
 
                 write!(
                     track_item_info,
@@ -475,27 +926,11 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 )
                 .unwrap();
 
-                let track_has_artwork_setting = &itunesdb_file_as_bytes[idx
-                    + itunesdb_constants::TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_OFFSET
-                    ..idx
-                        + itunesdb_constants::TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_OFFSET
-                        + itunesdb_constants::TRACK_ITEM_TRACK_HAS_ARTWORK_SETTING_LEN];
-
-                // TODO: Encapsulate this logic elsewhere
-                if itunesdb::track_has_artwork(track_has_artwork_setting) {
-                    let track_associated_artwork_size = helpers::get_slice_as_le_u32(
-                        idx,
-                        &itunesdb_file_as_bytes,
-                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_OFFSET,
-                        itunesdb_constants::TRACK_ITEM_TRACK_ARTWORK_SIZE_BYTES_LEN,
-                    );
-
-                    write!(
-                        track_item_info,
-                        "🎨 artwork size: {} bytes \n",
-                        track_associated_artwork_size
-                    )
-                    .unwrap();
+                if let Some(track_artwork) = track_artwork.as_ref() {
+                    curr_song.has_artwork = true;
+                    curr_song.artwork_count = track_artwork.artwork_count;
+                    curr_song.artwork_size_bytes = track_artwork.artwork_size_bytes;
+                    curr_song.artwork_id = track_artwork.artwork_id;
                 }
 
                 let track_year_released = helpers::get_slice_as_le_u32(
@@ -549,6 +984,9 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 if track_modified_epoch > 0 {
                     let track_modified_timestamp =
                         helpers::get_timestamp_as_mac(track_modified_epoch as u64);
+
+                    curr_song.set_song_modified_timestamp(track_modified_epoch as u64);
+
                     write!(
                         track_item_info,
                         "Track last modified: {} | ",
@@ -567,6 +1005,8 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     let track_published_to_store_timestamp: chrono::DateTime<chrono::Utc> =
                         helpers::get_timestamp_as_mac(track_published_to_store_epoch as u64);
 
+                    curr_song.set_song_released_timestamp(track_published_to_store_epoch as u64);
+
                     write!(
                         track_item_info,
                         "Date published on iTunes: {}",
@@ -581,59 +1021,340 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
             ) {
                 println!("TrackItem: Podcast found");
 
-                curr_media_type = track_media_type_enum;
-            }
-
-            idx += itunesdb_constants::TRACK_ITEM_LAST_OFFSET;
-        } else if potential_section_heading == itunesdb_constants::PLAYLIST_KEY.as_bytes() {
-            let mut playlist_info: String = "==== ".to_string();
+                curr_podcast.track_id = track_unique_id;
+                curr_podcast.dbid = track_dbid;
+                curr_podcast.media_kind = track_media_kind.to_string();
+                curr_podcast.media_type_raw = helpers::build_le_u32_from_bytes(&track_media_type_raw);
 
-            let is_master_playlist_setting = &itunesdb_file_as_bytes[idx
-                + itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_OFFSET
-                ..idx
-                    + itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_OFFSET
-                    + itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_LEN];
+                curr_podcast.resume_position_ms = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_BOOKMARK_TIME_MILLISECONDS_LEN,
+                );
 
-            if is_master_playlist_setting[0] == 1 {
-                write!(playlist_info, "Master ").unwrap();
-            }
+                curr_podcast.remember_playback_position = itunesdb_helpers::is_field_in_header(
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET,
+                    1,
+                ) && helpers::get_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_REMEMBER_PLAYBACK_POSITION_SETTING_OFFSET,
+                );
 
-            write!(playlist_info, "Playlist found!").unwrap();
+                curr_podcast.skip_when_shuffling = itunesdb_helpers::get_header_byte_flag(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_SKIP_WHEN_SHUFFLING_SETTING_OFFSET,
+                );
 
-            let playlist_created_timestamp = helpers::get_slice_as_mac_timestamp(
-                idx,
-                &itunesdb_file_as_bytes,
-                itunesdb_constants::PLAYLIST_CREATED_TIMESTAMP_OFFSET,
-                itunesdb_constants::PLAYLIST_CREATED_TIMESTAMP_LEN,
-            );
+                curr_podcast.explicitness = itunesdb::decode_explicitness(
+                    helpers::get_slice_from_offset_with_len(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EXPLICIT_FLAG_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EXPLICIT_FLAG_LEN,
+                    )[0],
+                );
 
-            write!(
-                playlist_info,
-                " | Playlist created at: {} ",
-                playlist_created_timestamp
-            )
-            .unwrap();
+                curr_podcast.duration_s = itunesdb::decode_raw_track_length_to_s(
+                    helpers::get_slice_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_LENGTH_MILLISECONDS_LEN,
+                    ),
+                );
 
-            let playlist_sort_order = helpers::get_slice_as_le_u32(
-                idx,
-                &itunesdb_file_as_bytes,
-                itunesdb_constants::PLAYLIST_PLAYLIST_SORT_ORDER_OFFSET,
-                itunesdb_constants::PLAYLIST_PLAYLIST_SORT_ORDER_LEN,
-            );
+                curr_podcast.file_size_bytes = track_file_size_bytes;
+                curr_podcast.file_size_friendly =
+                    helpers::convert_bytes_to_human_readable_size(track_file_size_bytes);
+                curr_podcast.file_size_is_estimated = is_track_file_size_estimated;
+
+                let podcast_release_epoch = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_RELEASED_TIMESTAMP_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_RELEASED_TIMESTAMP_LEN,
+                );
+
+                if podcast_release_epoch > 0 {
+                    curr_podcast.release_date =
+                        Some(helpers::get_timestamp_as_mac(podcast_release_epoch as u64));
+                }
+
+                // 0 (no mark) when the track item header ends before it, as in 0x9C headers
+                let podcast_unplayed_mark = if itunesdb_helpers::is_field_in_header(
+                    track_item_header_len,
+                    itunesdb_constants::TRACK_ITEM_TRACK_UNPLAYED_MARK_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_UNPLAYED_MARK_LEN,
+                ) {
+                    helpers::get_slice_from_offset_with_len(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        itunesdb_constants::TRACK_ITEM_TRACK_UNPLAYED_MARK_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_UNPLAYED_MARK_LEN,
+                    )[0]
+                } else {
+                    0
+                };
+
+                let podcast_play_count = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::TRACK_ITEM_TRACK_PLAY_COUNT_OFFSET,
+                    itunesdb_constants::TRACK_ITEM_TRACK_PLAY_COUNT_LEN,
+                );
+
+                // Episodes played on the iPod itself only get their play count bumped,
+                // the mark is updated on the next sync
+                curr_podcast.is_played = podcast_unplayed_mark == 0x01 || podcast_play_count > 0;
+
+                // Until then the mark still says new, but a played episode isn't
+                curr_podcast.is_new = podcast_unplayed_mark == 0x02 && !curr_podcast.is_played;
+            } else if matches!(
+                track_media_type_enum,
+                itunesdb::HandleableMediaType::Video
+            ) {
+                curr_video.track = itunesdb::read_track_core(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    &track_media_kind,
+                    &track_item_extension,
+                );
+                curr_video.video_kind = track_media_type_name;
+
+                if curr_video.is_tv_show() {
+                    curr_video.tv_season_number = itunesdb_helpers::get_header_field_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        track_item_header_len,
+                        itunesdb_constants::TRACK_ITEM_TRACK_SEASON_NUMBER_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_SEASON_NUMBER_LEN,
+                    )
+                    .unwrap_or(0);
+
+                    curr_video.tv_episode_number = itunesdb_helpers::get_header_field_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        track_item_header_len,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EPISODE_NUMBER_OFFSET,
+                        itunesdb_constants::TRACK_ITEM_TRACK_EPISODE_NUMBER_LEN,
+                    )
+                    .unwrap_or(0);
+                }
+            } else if matches!(
+                track_media_type_enum,
+                itunesdb::HandleableMediaType::Ringtone
+            ) {
+                curr_ringtone.track = itunesdb::read_track_core(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    &track_media_kind,
+                    &track_item_extension,
+                );
+            } else if matches!(
+                track_media_type_enum,
+                itunesdb::HandleableMediaType::ITunesU
+            ) {
+                curr_itunesu_item.track = itunesdb::read_track_core(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    &track_media_kind,
+                    &track_item_extension,
+                );
+            } else if matches!(
+                track_media_type_enum,
+                itunesdb::HandleableMediaType::Audiobook
+            ) {
+                curr_audiobook.track = itunesdb::read_track_core(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    track_item_header_len,
+                    &track_media_kind,
+                    &track_item_extension,
+                );
+            }
+
+            // Every track item resets the media type, otherwise the data objects of
+            // an unhandled track would get attached to whatever track came before it
+            curr_media_type = track_media_type_enum;
+
+            if let Some(track_artwork) = track_artwork {
+                artwork_found.push(track_artwork);
+            }
+
+            // Skip to the first data object, however long this iTunes version's header is
+            if track_item_header_len as usize > itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE {
+                idx += track_item_header_len as usize - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+            }
+        } else if potential_section_heading == itunesdb_constants::PLAYLIST_KEY.as_bytes() {
+            let mut playlist_info: String = "==== ".to_string();
+
+            data_objects_remaining = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_NUM_DATA_OBJECTS_OFFSET,
+                itunesdb_constants::PLAYLIST_NUM_DATA_OBJECTS_LEN,
+            );
+
+            if let Some(playlist) = curr_playlist.take() {
+                playlists_found.push(playlist);
+            }
+
+            // The Podcast List repeats the playlists of the Playlist List, so only collect them once
+            if (curr_dataset_type == itunesdb::HandleableDatasetType::Playlists as u32)
+                || (curr_dataset_type == itunesdb::HandleableDatasetType::SmartPlaylists as u32)
+            {
+                curr_playlist = Some(itunesdb::Playlist::default());
+            }
+
+            let is_master_playlist = helpers::get_byte_flag(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_IS_MASTER_PLAYLIST_SETTING_OFFSET,
+            );
+
+            if is_master_playlist {
+                write!(playlist_info, "Master ").unwrap();
+            }
+
+            if let Some(playlist) = curr_playlist.as_mut() {
+                playlist.is_master_playlist = is_master_playlist;
+            }
+
+            // Only counts once the playlist's Genius data object turns up, see `GeniusPlaylistData`
+            is_curr_playlist_genius_flag_set = helpers::get_byte_flag(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_IS_GENIUS_PLAYLIST_SETTING_OFFSET,
+            );
+
+            let is_podcast_playlist = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_IS_PODCAST_PLAYLIST_SETTING_OFFSET,
+                itunesdb_constants::PLAYLIST_IS_PODCAST_PLAYLIST_SETTING_LEN,
+            ) == 1;
+
+            // In the Podcast List, the podcast playlist groups its episodes by show
+            is_in_podcast_group_playlist = (curr_dataset_type
+                == itunesdb::HandleableDatasetType::Podcasts as u32)
+                && is_podcast_playlist;
+
+            write!(playlist_info, "Playlist found!").unwrap();
+
+            let playlist_created_timestamp = helpers::get_slice_as_mac_timestamp(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_CREATED_TIMESTAMP_OFFSET,
+                itunesdb_constants::PLAYLIST_CREATED_TIMESTAMP_LEN,
+            );
+
+            write!(
+                playlist_info,
+                " | Playlist created at: {} ",
+                playlist_created_timestamp
+            )
+            .unwrap();
+
+            let playlist_sort_order = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_PLAYLIST_SORT_ORDER_OFFSET,
+                itunesdb_constants::PLAYLIST_PLAYLIST_SORT_ORDER_LEN,
+            );
 
             write!(
                 playlist_info,
-                "| {} \n",
+                "| Playlist sort order: {} \n",
                 itunesdb::decode_playlist_sort_order(playlist_sort_order)
             )
             .unwrap();
 
+            if let Some(playlist) = curr_playlist.as_mut() {
+                playlist.is_podcast_playlist = is_podcast_playlist;
+                playlist.persistent_playlist_id = helpers::get_slice_as_le_u64(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::PLAYLIST_PERSISTENT_ID_OFFSET,
+                    itunesdb_constants::PLAYLIST_PERSISTENT_ID_LEN,
+                );
+                playlist.created_ts = playlist_created_timestamp;
+                playlist.sort_order_raw = playlist_sort_order;
+                playlist.sort_order = itunesdb::decode_playlist_sort_order(playlist_sort_order);
+            }
+
             //println!("{} ====", playlist_info);
 
             idx += itunesdb_constants::PLAYLIST_LAST_OFFSET;
         } else if potential_section_heading == itunesdb_constants::PLAYLIST_ITEM_KEY.as_bytes() {
             let mut playlist_item_info: String = "-----".to_string();
 
+            data_objects_remaining = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::PLAYLIST_ITEM_NUM_DATA_OBJECTS_OFFSET,
+                itunesdb_constants::PLAYLIST_ITEM_NUM_DATA_OBJECTS_LEN,
+            );
+
+            if let Some(playlist) = curr_playlist.as_mut() {
+                playlist.stored_track_ids.push(helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::PLAYLIST_ITEM_TRACK_ID_OFFSET,
+                    itunesdb_constants::PLAYLIST_ITEM_TRACK_ID_LEN,
+                ));
+            }
+
+            if is_in_podcast_group_playlist {
+                let podcast_grouping_flag = helpers::get_slice_as_le_u32(
+                    idx,
+                    &itunesdb_file_as_bytes,
+                    itunesdb_constants::PLAYLIST_ITEM_PODCAST_GROUPING_FLAG_OFFSET,
+                    itunesdb_constants::PLAYLIST_ITEM_PODCAST_GROUPING_FLAG_LEN,
+                );
+
+                // Podcast groups are followed by a title data object with the show's name,
+                // episodes point back at their group using the grouping reference
+                if podcast_grouping_flag == 0x100 {
+                    podcast_groups_found.push(itunesdb::PodcastGroup {
+                        group_name: "".to_string(),
+                        group_id: helpers::get_slice_as_le_u32(
+                            idx,
+                            &itunesdb_file_as_bytes,
+                            itunesdb_constants::PLAYLIST_ITEM_GROUP_ID_OFFSET,
+                            itunesdb_constants::PLAYLIST_ITEM_GROUP_ID_LEN,
+                        ),
+                        track_ids: Vec::new(),
+                    });
+                } else {
+                    let podcast_grouping_reference = helpers::get_slice_as_le_u32(
+                        idx,
+                        &itunesdb_file_as_bytes,
+                        itunesdb_constants::PLAYLIST_ITEM_PODCAST_GROUPING_REFERENCE_OFFSET,
+                        itunesdb_constants::PLAYLIST_ITEM_PODCAST_GROUPING_REFERENCE_LEN,
+                    );
+
+                    if let Some(podcast_group) = podcast_groups_found
+                        .iter_mut()
+                        .find(|group| group.group_id == podcast_grouping_reference)
+                    {
+                        podcast_group.track_ids.push(helpers::get_slice_as_le_u32(
+                            idx,
+                            &itunesdb_file_as_bytes,
+                            itunesdb_constants::PLAYLIST_ITEM_TRACK_ID_OFFSET,
+                            itunesdb_constants::PLAYLIST_ITEM_TRACK_ID_LEN,
+                        ));
+                    }
+                }
+            }
+
             let playlist_item_added_timestamp = helpers::get_slice_as_mac_timestamp(
                 idx,
                 &itunesdb_file_as_bytes,
@@ -654,35 +1375,52 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
         } else if potential_section_heading == itunesdb_constants::ALBUM_LIST_KEY.as_bytes() {
             let mut album_list_info: String = "~~~~~~~".to_string();
 
-            let album_item_total_num_songs = helpers::get_slice_as_le_u32(
+            let num_albums_in_list = helpers::get_slice_as_le_u32(
                 idx,
                 &itunesdb_file_as_bytes,
-                itunesdb_constants::ALBUM_LIST_TOTAL_NUM_SONGS_OFFSET,
-                itunesdb_constants::ALBUM_LIST_TOTAL_NUM_SONGS_LEN,
+                itunesdb_constants::ALBUM_LIST_NUM_ALBUMS_OFFSET,
+                itunesdb_constants::ALBUM_LIST_NUM_ALBUMS_LEN,
             );
 
             write!(
                 album_list_info,
-                " {} songs in Album List",
-                album_item_total_num_songs
+                " {} albums in Album List",
+                num_albums_in_list
             )
             .unwrap();
 
             //println!("{}  ~~~~~~~\n", album_list_info);
 
-            idx += itunesdb_constants::ALBUM_LIST_LAST_OFFSET;
-        }
-        // else if potential_section_heading == iTunesDB::ALBUM_ITEM_KEY.as_bytes() {
-
-        //     let album_item_info : String = "######## Album item found! | ".to_string();
-
-        //     // write!(album_item_info, " {} ########\n", itunesdb_helpers::get_timestamp_as_mac(helpers::build_le_u32_from_bytes(album_item_unknown_timestamp_raw) as u64)).unwrap();
+            library_stats.declared_num_albums = Some(num_albums_in_list);
 
-        //     println!("{} ########\n", album_item_info);
-
-        //     idx += iTunesDB::ALBUM_ITEM_LAST_OFFSET;
+            idx += itunesdb_constants::ALBUM_LIST_LAST_OFFSET;
+        } else if potential_section_heading == itunesdb_constants::ALBUM_ITEM_KEY.as_bytes()
+            && idx + itunesdb_constants::CHUNK_MIN_HEADER_LEN <= itunesdb_file_as_bytes.len()
+        {
+            let album_item_header_len = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::CHUNK_HEADER_LEN_OFFSET,
+                itunesdb_constants::CHUNK_HEADER_LEN_LEN,
+            ) as usize;
 
-        // }
+            let album_item_total_len = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::CHUNK_TOTAL_LEN_OFFSET,
+                itunesdb_constants::CHUNK_TOTAL_LEN_LEN,
+            ) as usize;
+
+            // "mhia" can turn up inside other data too, a real album item's header fits in it and in the file
+            if album_item_header_len >= itunesdb_constants::CHUNK_MIN_HEADER_LEN
+                && album_item_total_len >= album_item_header_len
+                && idx + album_item_total_len <= itunesdb_file_as_bytes.len()
+            {
+                library_stats.parsed_num_albums += 1;
+
+                idx += album_item_header_len - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+            }
+        }
         else if potential_section_heading == itunesdb_constants::DATA_OBJECT_KEY.as_bytes() {
             let mut data_object_info: String = "%%%%%%% Data Object found!\n".to_string();
 
@@ -693,15 +1431,39 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 itunesdb_constants::DATA_OBJECT_TYPE_LEN,
             );
 
+            let data_object_type = itunesdb::decode_data_object_type(data_object_type_raw);
+
+            if let itunesdb::DataObjectType::Unknown(unknown_data_object_type) = data_object_type {
+                *unknown_data_object_type_counts.entry(unknown_data_object_type).or_insert(0) += 1;
+            }
+
             write!(
                 data_object_info,
                 "Type (raw) = {}, Decoded= '{}' | ",
                 data_object_type_raw,
-                itunesdb::decode_data_object_type(data_object_type_raw)
+                data_object_type
             )
             .unwrap();
 
-            if itunesdb::is_data_object_type_string(data_object_type_raw) {
+            let is_data_object_attached = data_objects_remaining > 0;
+
+            if is_data_object_attached {
+                data_objects_remaining -= 1;
+            } else if curr_dataset_type != itunesdb::HandleableDatasetType::Albums as u32 {
+                // Album items aren't parsed, so their data objects are expected to be unattached
+                parse_report.add_entry(
+                    "Data object beyond its parent's child count",
+                    None,
+                    format!(
+                        "Skipped data object of type {} at offset {}",
+                        data_object_type_raw, idx
+                    ),
+                );
+            }
+
+            if !is_data_object_attached {
+                write!(data_object_info, "Not attached to any record, skipping").unwrap();
+            } else if itunesdb::is_data_object_type_string(data_object_type_raw) {
                 let data_object_string_len = helpers::get_slice_as_le_u32(
                     idx,
                     &itunesdb_file_as_bytes,
@@ -717,9 +1479,28 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 );
 
                 // let data_object_str = std::str::from_utf8(&data_object_str_bytes).expect("Can't parse string data object!");
-                let data_object_str =
-                    String::from_utf16(&helpers::return_utf16_from_utf8(&data_object_str_bytes))
-                        .expect("Can't decode string to UTF-16");
+                let (data_object_str, is_data_object_str_valid) =
+                    helpers::decode_utf16_le_lossy(&data_object_str_bytes);
+
+                if !is_data_object_str_valid {
+                    println!(
+                        "Warning: data object of type {} isn't valid UTF-16, decoded it as '{}'",
+                        data_object_type_raw, data_object_str
+                    );
+
+                    parse_report.add_entry(
+                        "Invalid UTF-16 in string data object",
+                        if curr_dataset_type == itunesdb::HandleableDatasetType::Tracks as u32 {
+                            Some(curr_track_unique_id)
+                        } else {
+                            None
+                        },
+                        format!(
+                            "Data object of type {} at offset {} decoded with replacement characters as '{}'",
+                            data_object_type_raw, idx, data_object_str
+                        ),
+                    );
+                }
 
                 write!(
                     data_object_info,
@@ -728,21 +1509,65 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 )
                 .unwrap();
 
+                // Outside of the Track List, the only string we care about is the playlist name.
+                // That's the first title; later ones (e.g. podcast group names) come after the playlist items
+                if curr_dataset_type != itunesdb::HandleableDatasetType::Tracks as u32 {
+                    if let Some(playlist) = curr_playlist.as_mut() {
+                        if (data_object_type_raw == itunesdb::HandleableDataObjectType::Title as u32)
+                            && playlist.playlist_name.is_empty()
+                            && playlist.stored_track_ids.is_empty()
+                        {
+                            playlist.playlist_name = data_object_str;
+                        }
+                    } else if is_in_podcast_group_playlist {
+                        if let Some(podcast_group) = podcast_groups_found.last_mut() {
+                            if (data_object_type_raw
+                                == itunesdb::HandleableDataObjectType::Title as u32)
+                                && podcast_group.group_name.is_empty()
+                                && podcast_group.track_ids.is_empty()
+                            {
+                                podcast_group.group_name = data_object_str;
+                            }
+                        }
+                    }
+                }
                 // We've found a title, now, use the TrackItem info to determine if the title is for a song or for a podcast
-                if data_object_type_raw == itunesdb::HandleableDataObjectType::Title as u32 {
+                else if data_object_type_raw == itunesdb::HandleableDataObjectType::Title as u32 {
                     if curr_media_type == itunesdb::HandleableMediaType::SongLike {
                         curr_song.song_title = data_object_str;
                     } else if curr_media_type == itunesdb::HandleableMediaType::Podcast {
                         curr_podcast.podcast_title = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Video {
+                        curr_video.track.title = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        curr_audiobook.track.title = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.title = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Ringtone {
+                        curr_ringtone.track.title = data_object_str;
                     }
                 } else if data_object_type_raw == itunesdb::HandleableDataObjectType::Album as u32 {
-                    curr_song.song_album = data_object_str;
+                    if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        curr_audiobook.track.album = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.album = data_object_str;
+                    } else {
+                        curr_song.song_album = data_object_str;
+                    }
                 } else if data_object_type_raw == itunesdb::HandleableDataObjectType::Artist as u32
                 {
                     if curr_media_type == itunesdb::HandleableMediaType::SongLike {
                         curr_song.song_artist = data_object_str;
                     } else if curr_media_type == itunesdb::HandleableMediaType::Podcast {
                         curr_podcast.podcast_publisher = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Video {
+                        curr_video.track.artist = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        curr_audiobook.track.artist = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.artist = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Ringtone {
+                        curr_ringtone.track.artist = data_object_str;
                     }
                 } else if data_object_type_raw == itunesdb::HandleableDataObjectType::Genre as u32 {
                     if curr_media_type == itunesdb::HandleableMediaType::SongLike {
@@ -751,6 +1576,8 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                         if curr_podcast.podcast_genre.is_empty() {
                             curr_podcast.podcast_genre = data_object_str;
                         }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        curr_audiobook.track.genre = data_object_str;
                     }
                 } else if data_object_type_raw == itunesdb::HandleableDataObjectType::Comment as u32
                 {
@@ -759,18 +1586,238 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                     } else if curr_media_type == itunesdb::HandleableMediaType::Podcast {
                         curr_podcast.podcast_subtitle = data_object_str;
                     }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::EqualizerSetting as u32
+                {
+                    if curr_media_type == itunesdb::HandleableMediaType::SongLike {
+                        let song = match songs_found.last_mut() {
+                            Some(last_song) if last_song.track_id == curr_track_unique_id => {
+                                last_song
+                            }
+                            _ => &mut curr_song,
+                        };
+
+                        song.equalizer_preset_id =
+                            itunesdb::decode_equalizer_setting(&data_object_str);
+                        song.equalizer_preset = data_object_str;
+                    }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::Category as u32
+                {
+                    if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.category = data_object_str;
+                    }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::Grouping as u32
+                {
+                    if curr_media_type == itunesdb::HandleableMediaType::SongLike {
+                        match songs_found.last_mut() {
+                            Some(last_song) if last_song.track_id == curr_track_unique_id => {
+                                last_song.song_grouping = data_object_str;
+                            }
+                            _ => {
+                                curr_song.song_grouping = data_object_str;
+                            }
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Podcast {
+                        curr_podcast.podcast_grouping = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.grouping = data_object_str;
+                    }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::Keywords as u32
+                {
+                    // Keywords come after the data objects that complete the track
+                    if curr_media_type == itunesdb::HandleableMediaType::SongLike {
+                        match songs_found.last_mut() {
+                            Some(last_song) if last_song.track_id == curr_track_unique_id => {
+                                last_song.song_keywords = data_object_str;
+                            }
+                            _ => {
+                                curr_song.song_keywords = data_object_str;
+                            }
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Podcast {
+                        match podcasts_found.last_mut() {
+                            Some(podcast) if podcast.track_id == curr_track_unique_id => {
+                                podcast.podcast_keywords = data_object_str;
+                            }
+                            _ => {
+                                curr_podcast.podcast_keywords = data_object_str;
+                            }
+                        }
+                    }
                 } else if data_object_type_raw
                     == itunesdb::HandleableDataObjectType::Composer as u32
                 {
                     curr_song.song_composer = data_object_str;
+                } else if (data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::AlbumArtist as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SortTitle as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SortArtist as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SortAlbum as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SortComposer as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SortAlbumArtist as u32)
+                {
+                    if curr_media_type == itunesdb::HandleableMediaType::SongLike {
+                        // The sort fields usually come after the file location,
+                        // by which point the song has already been collected
+                        let song = match songs_found.last_mut() {
+                            Some(last_song) if last_song.track_id == curr_track_unique_id => {
+                                last_song
+                            }
+                            _ => &mut curr_song,
+                        };
+
+                        if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::AlbumArtist as u32
+                        {
+                            song.song_album_artist = data_object_str;
+                        } else if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::SortTitle as u32
+                        {
+                            song.song_sort_title = data_object_str;
+                        } else if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::SortArtist as u32
+                        {
+                            song.song_sort_artist = data_object_str;
+                        } else if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::SortAlbum as u32
+                        {
+                            song.song_sort_album = data_object_str;
+                        } else if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::SortAlbumArtist as u32
+                        {
+                            song.song_sort_album_artist = data_object_str;
+                        } else {
+                            song.song_sort_composer = data_object_str;
+                        }
+                    }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::Subtitle as u32
+                {
+                    // Podcasts usually repeat the subtitle in the comment, but this is the real one
+                    if curr_media_type == itunesdb::HandleableMediaType::Podcast {
+                        match podcasts_found.last_mut() {
+                            Some(podcast) if podcast.track_id == curr_track_unique_id => {
+                                podcast.podcast_subtitle = data_object_str;
+                            }
+                            _ => {
+                                curr_podcast.podcast_subtitle = data_object_str;
+                            }
+                        }
+                    }
+                } else if (data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::TVShow as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::TVEpisodeID as u32)
+                    || (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::TVNetwork as u32)
+                {
+                    if curr_media_type == itunesdb::HandleableMediaType::Video {
+                        let video = match videos_found.last_mut() {
+                            Some(last_video) if last_video.track.track_id == curr_track_unique_id => {
+                                last_video
+                            }
+                            _ => &mut curr_video,
+                        };
+
+                        if data_object_type_raw == itunesdb::HandleableDataObjectType::TVShow as u32 {
+                            video.tv_show = data_object_str;
+                        } else if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::TVEpisodeID as u32
+                        {
+                            video.tv_episode_id = data_object_str;
+                        } else {
+                            video.tv_network = data_object_str;
+                        }
+                    }
                 } else if data_object_type_raw
                     == itunesdb::HandleableDataObjectType::FileLocation as u32
                 {
-                    curr_song.set_song_filename(data_object_str);
+                    match track_files_found.last_mut() {
+                        Some(track_file) if track_file.track_id == curr_track_unique_id && !data_object_str.is_empty() => {
+                            track_file.filename = itunesdb_helpers::get_canonical_path(data_object_str.to_string());
+                        }
+                        _ => {}
+                    }
+
+                    if curr_media_type == itunesdb::HandleableMediaType::Video {
+                        curr_video.track.set_filename(data_object_str);
+
+                        if !curr_video.track.title.is_empty() {
+                            videos_found.push(curr_video);
+                            curr_video = itunesdb::Video::default();
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        curr_audiobook.track.set_filename(data_object_str);
+
+                        curr_audiobook.is_protected =
+                            itunesdb::is_protected_file_extension(&curr_audiobook.track.file_extension);
+
+                        if !curr_audiobook.track.title.is_empty() {
+                            audiobooks_found.push(curr_audiobook);
+                            curr_audiobook = itunesdb::Audiobook::default();
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.set_filename(data_object_str);
 
-                    if curr_song.are_enough_fields_valid() {
-                        songs_found.push(curr_song);
-                        curr_song = itunesdb::Song::default();
+                        if !curr_itunesu_item.track.title.is_empty() {
+                            itunesu_items_found.push(curr_itunesu_item);
+                            curr_itunesu_item = itunesdb::ITunesUItem::default();
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Ringtone {
+                        curr_ringtone.track.set_filename(data_object_str);
+
+                        if !curr_ringtone.track.title.is_empty() {
+                            ringtones_found.push(curr_ringtone);
+                            curr_ringtone = itunesdb::Ringtone::default();
+                        }
+                    } else {
+                        curr_song.set_song_filename(data_object_str);
+
+                        if curr_song.file_extension.is_empty() {
+                            if let Some(file_extension) =
+                                itunesdb_helpers::get_file_extension_from_path(
+                                    &curr_song.song_filename,
+                                )
+                            {
+                                parse_report.add_entry(
+                                    "File extension inferred from file location",
+                                    Some(curr_song.track_id),
+                                    format!(
+                                        "Filetype is empty, using '{}' from '{}'",
+                                        file_extension, curr_song.song_filename
+                                    ),
+                                );
+
+                                curr_song.file_extension = file_extension;
+                            }
+                        }
+
+                        curr_song.is_protected =
+                            itunesdb::is_protected_file_extension(&curr_song.file_extension);
+                        curr_song.codec = itunesdb::decode_track_codec(
+                            &curr_song.file_extension,
+                            curr_song.audio_type_raw,
+                        );
+                        curr_song.is_lossless =
+                            itunesdb::is_lossless_file_extension(&curr_song.file_extension);
+
+                        if is_curr_song_voice_memo || curr_song.is_voice_memo() {
+                            if curr_song.file_size_bytes > 0 {
+                                voice_memos_found.push(itunesdb::VoiceMemo::from_song(curr_song));
+                                curr_song = itunesdb::Song::default();
+                            }
+                        } else if curr_song.are_enough_fields_valid() {
+                            songs_found.push(curr_song);
+                            curr_song = itunesdb::Song::default();
+                        }
                     }
                 } else if data_object_type_raw
                     == itunesdb::HandleableDataObjectType::FileType as u32
@@ -783,6 +1830,17 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 {
                     if curr_media_type == itunesdb::HandleableMediaType::Podcast {
                         curr_podcast.podcast_description = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::ITunesU {
+                        curr_itunesu_item.track.description = data_object_str;
+                    } else if curr_media_type == itunesdb::HandleableMediaType::SongLike {
+                        match songs_found.last_mut() {
+                            Some(last_song) if last_song.track_id == curr_track_unique_id => {
+                                last_song.song_description = data_object_str;
+                            }
+                            _ => {
+                                curr_song.song_description = data_object_str;
+                            }
+                        }
                     }
 
                     if !curr_podcast.podcast_title.is_empty() {
@@ -806,93 +1864,950 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                         podcast_url
                     )
                     .unwrap();
+
+                    if curr_media_type == itunesdb::HandleableMediaType::Podcast {
+                        let podcast = match podcasts_found.last_mut() {
+                            Some(last_podcast) if last_podcast.track_id == curr_track_unique_id => {
+                                last_podcast
+                            }
+                            _ => &mut curr_podcast,
+                        };
+
+                        if data_object_type_raw
+                            == itunesdb::HandleableDataObjectType::PodcastEnclosureURL as u32
+                        {
+                            podcast.enclosure_url = podcast_url;
+                        } else {
+                            podcast.rss_url = podcast_url;
+                        }
+                    }
+                } else if data_object_type_raw
+                    == itunesdb::HandleableDataObjectType::ChapterData as u32
+                {
+                    let chapters = itunesdb::decode_chapter_data(idx, &itunesdb_file_as_bytes);
+
+                    write!(data_object_info, "{} chapters", chapters.len()).unwrap();
+
+                    // Chapter data comes after the data objects that complete the track,
+                    // so the track has usually been collected already
+                    if curr_media_type == itunesdb::HandleableMediaType::Podcast {
+                        match podcasts_found.last_mut() {
+                            Some(podcast) if podcast.track_id == curr_track_unique_id => {
+                                podcast.chapters = chapters;
+                            }
+                            _ => {
+                                curr_podcast.chapters = chapters;
+                            }
+                        }
+                    } else if curr_media_type == itunesdb::HandleableMediaType::Audiobook {
+                        match audiobooks_found.last_mut() {
+                            Some(audiobook) if audiobook.track.track_id == curr_track_unique_id => {
+                                audiobook.chapters = chapters;
+                            }
+                            _ => {
+                                curr_audiobook.chapters = chapters;
+                            }
+                        }
+                    }
+                } else if let Some(playlist) = curr_playlist.as_mut() {
+                    if data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SmartPlaylistData as u32
+                    {
+                        playlist.is_smart_playlist = true;
+                        playlist.smart_playlist_preferences = Some(
+                            smart_playlist::parse_smart_playlist_preferences(
+                                idx,
+                                &itunesdb_file_as_bytes,
+                            ),
+                        );
+                    } else if data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::SmartPlaylistRules as u32
+                    {
+                        playlist.smart_playlist_rules = Some(
+                            smart_playlist::parse_smart_playlist_rules(idx, &itunesdb_file_as_bytes),
+                        );
+                    } else if (data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::PlaylistColumns as u32)
+                        && (helpers::get_slice_as_le_u32(
+                            idx,
+                            &itunesdb_file_as_bytes,
+                            itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_OFFSET,
+                            itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_LEN,
+                        ) as usize
+                            == itunesdb_constants::PLAYLIST_COLUMNS_TOTAL_LEN)
+                    {
+                        playlist.column_settings = Some(itunesdb::decode_playlist_column_settings(
+                            idx,
+                            &itunesdb_file_as_bytes,
+                        ));
+                    } else if data_object_type_raw
+                        == itunesdb::HandleableDataObjectType::GeniusPlaylistData as u32
+                    {
+                        playlist.is_genius_playlist = is_curr_playlist_genius_flag_set;
+                    }
                 }
             }
 
             //println!("{} %%%%%%% \r\n", data_object_info);
 
-            idx += itunesdb_constants::DATA_OBJECT_LAST_OFFSET;
+            // String lengths aren't always a multiple of 4, so skip over the whole data object.
+            // Otherwise the next heading can end up unaligned and get missed entirely
+            let data_object_total_len = helpers::get_slice_as_le_u32(
+                idx,
+                &itunesdb_file_as_bytes,
+                itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_OFFSET,
+                itunesdb_constants::DATA_OBJECT_TOTAL_LENGTH_LEN,
+            ) as usize;
+
+            if data_object_total_len > itunesdb_constants::DATA_OBJECT_LAST_OFFSET {
+                idx += data_object_total_len - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+            } else {
+                idx += itunesdb_constants::DATA_OBJECT_LAST_OFFSET;
+            }
         }
 
         idx += itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
     }
 
-    println!("{} podcasts found", podcasts_found.len());
-    println!("{} songs found", songs_found.len());
+    if let Some(playlist) = curr_playlist.take() {
+        playlists_found.push(playlist);
+    }
 
-    // Add JSON output @joshkenney
-    if output_format == "json" {
-        // Only create JSON output
-        if !songs_found.is_empty() {
-            let songs_json = serde_json::to_string_pretty(&songs_found)
-                .expect("Error serializing songs to JSON");
-            let mut songs_json_file = File::create("music.json")
-                .expect("Error creating songs JSON file");
-            io::Write::write_all(&mut songs_json_file, songs_json.as_bytes())
-                .expect("Error writing songs JSON file");
-            println!("Created music.json with {} songs", songs_found.len());
+    if longest_track_item_header_len > itunesdb_constants::TRACK_ITEM_DOCUMENTED_HEADER_LEN {
+        parse_report.add_entry(
+            "Track item header longer than documented",
+            None,
+            format!(
+                "Track items have up to a {} ({:#X}) byte header, nothing past byte {:#X} is decoded",
+                longest_track_item_header_len,
+                longest_track_item_header_len,
+                itunesdb_constants::TRACK_ITEM_DOCUMENTED_HEADER_LEN
+            ),
+        );
+    }
+
+    for (unknown_data_object_type, num_data_objects) in unknown_data_object_type_counts.iter() {
+        parse_report.add_entry(
+            "Unknown data object type",
+            None,
+            format!(
+                "{} data object(s) of type #{} weren't decoded",
+                num_data_objects, unknown_data_object_type
+            ),
+        );
+    }
+
+    // The name the user gave the iPod is kept in DeviceInfo, not in the database
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let deviceinfo_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join(deviceinfo_constants::DEVICEINFO_FILENAME);
+
+        if let Ok(deviceinfo_file_as_bytes) = std::fs::read(&deviceinfo_file_path) {
+            if deviceinfo_parser::read_device_info(&deviceinfo_file_as_bytes, &mut ipod_device_info) {
+                println!("iPod name: {:?}", ipod_device_info.ipod_name);
+            }
         }
 
-        if !podcasts_found.is_empty() {
-            let podcasts_json = serde_json::to_string_pretty(&podcasts_found)
-                .expect("Error serializing podcasts to JSON");
-            let mut podcasts_json_file = File::create("podcasts.json")
-                .expect("Error creating podcasts JSON file");
-            io::Write::write_all(&mut podcasts_json_file, podcasts_json.as_bytes())
-                .expect("Error writing podcasts JSON file");
-            println!("Created podcasts.json with {} podcasts", podcasts_found.len());
+        // How iTunes syncs it, and how it was set up on the iPod itself
+        let itunesprefs_file_path = deviceinfo_file_path.with_file_name(itunesprefs_constants::ITUNESPREFS_FILENAME);
+
+        if let Ok(itunesprefs_file_as_bytes) = std::fs::read(&itunesprefs_file_path) {
+            ipod_device_info.itunes_prefs = preferences_parser::read_itunes_prefs(&itunesprefs_file_as_bytes);
         }
-     // default to CSV output
-    } else {
-        let mut music_csv_writer = helpers::init_csv_writer("music.csv");
-        let mut podcast_csv_writer = helpers::init_csv_writer("podcasts.csv");
 
-        if !podcasts_found.is_empty() {
-            podcast_csv_writer.write_record(&[
-                "Episode Title",
-                "Publisher",
-                "Genre",
+        let preferences_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("Device")
+            .join(preferences_constants::PREFERENCES_FILENAME);
+
+        if let Ok(preferences_file_as_bytes) = std::fs::read(&preferences_file_path) {
+            ipod_device_info.preferences = preferences_parser::read_preferences(&preferences_file_as_bytes);
+        }
+    }
+
+    // The database only says which iTunes wrote it, the iPod describes itself in SysInfo
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        if let Some(sysinfo) = sysinfo_parser::read_device_sysinfo(ipod_mount_point) {
+            if let Some(model_mismatch_warning) = ipod_device_info.set_sysinfo(sysinfo) {
+                parse_report.add_entry("Serial number doesn't match model", None, model_mismatch_warning);
+            }
+
+            println!(
+                "iPod model: {} (from its {})",
+                ipod_device_info
+                    .model
+                    .as_ref()
+                    .map(|ipod_model| ipod_model.get_description())
+                    .unwrap_or("unknown".to_string()),
+                ipod_device_info.model_source.to_lowercase()
+            );
+        }
+    }
+
+    // The hash58 is keyed on the device, so it can only be checked against the FireWire GUID the iPod has in its SysInfo
+    if is_database_signed_with_hash58 {
+        let firewire_guid = ipod_device_info
+            .sysinfo
+            .as_ref()
+            .map(|sysinfo| sysinfo.firewire_guid.to_string())
+            .unwrap_or_default();
+
+        if firewire_guid.is_empty() {
+            println!("Database signature not verified, that needs the iPod's FireWire GUID from its SysInfo (give the iPod mount point)");
+        } else {
+            match database_hash::verify_database_hash(&itunesdb_file_as_bytes, &firewire_guid) {
+                Ok(true) => {
+                    println!("Database signature: hash58 matches the iPod (FireWire GUID {})", firewire_guid);
+                }
+                Ok(false) => {
+                    parse_report.add_entry(
+                        "Database signature mismatch",
+                        None,
+                        format!(
+                            "The hash58 doesn't match the iPod with FireWire GUID {}, it will likely refuse the database. It was either changed after iTunes signed it or copied from another iPod",
+                            firewire_guid
+                        ),
+                    );
+
+                    println!("Database signature: hash58 doesn't match the iPod (FireWire GUID {})", firewire_guid);
+                }
+                Err(verification_error) => {
+                    println!("Database signature not verified: {}", verification_error);
+                }
+            }
+        }
+    }
+
+    // Songs only have the number of their EQ preset, the names are in the EQ Presets file
+    let mut equalizer_presets: Vec<equalizer::EqualizerPreset> = Vec::new();
+
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let equalizer_presets_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join(equalizer_constants::EQUALIZER_PRESETS_FILENAME);
+
+        if let Ok(equalizer_presets_file_as_bytes) = std::fs::read(&equalizer_presets_file_path) {
+            equalizer_presets = equalizer_parser::read_equalizer_presets(&equalizer_presets_file_as_bytes);
+
+            for song in songs_found.iter_mut() {
+                if let Some(equalizer_preset) = song.equalizer_preset_id.and_then(|equalizer_preset_id| {
+                    equalizer_presets
+                        .iter()
+                        .find(|equalizer_preset| equalizer_preset.equalizer_preset_id == equalizer_preset_id)
+                }) {
+                    song.equalizer_preset_name = equalizer_preset.equalizer_preset_name.to_string();
+                }
+            }
+
+            println!("Read {} EQ presets", equalizer_presets.len());
+        }
+    }
+
+    // Anything played on the device since the last sync is only in the Play Counts file
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let playcounts_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join("Play Counts");
+
+        if let Ok(playcounts_file_as_bytes) = std::fs::read(&playcounts_file_path) {
+            let playcounts_entries =
+                playcounts_parser::read_playcounts_entries(&playcounts_file_as_bytes);
+
+            // The entries are matched by position, so they're useless if the counts differ
+            if playcounts_entries.len() == track_list_track_ids.len() {
+                let num_songs_merged =
+                    playcounts::merge_playcounts_into_songs(&playcounts_entries, &mut songs_found);
+
+                println!("Merged Play Counts into {} songs", num_songs_merged);
+            } else {
+                parse_report.add_entry(
+                    "Play Counts not merged",
+                    None,
+                    format!(
+                        "Play Counts has {} entries, but the database has {} tracks",
+                        playcounts_entries.len(),
+                        track_list_track_ids.len()
+                    ),
+                );
+            }
+        }
+    }
+
+    // Episodes are normally recognized by their media type, but the Podcast List dataset
+    // is the authority on what's a podcast. Anything it groups under a show is one
+    let podcast_episode_ids: Vec<u32> = podcast_groups_found
+        .iter()
+        .flat_map(|podcast_group| podcast_group.track_ids.iter().copied())
+        .collect();
+
+    let (podcast_songs, other_songs): (Vec<itunesdb::Song>, Vec<itunesdb::Song>) = songs_found
+        .into_iter()
+        .partition(|song| podcast_episode_ids.contains(&song.track_id));
+
+    songs_found = other_songs;
+
+    for podcast_song in podcast_songs.into_iter() {
+        parse_report.add_entry(
+            "Track moved to podcasts",
+            Some(podcast_song.track_id),
+            format!(
+                "'{}' has media type '{}', but is listed as an episode in the Podcast List",
+                podcast_song.song_title, podcast_song.media_kind
+            ),
+        );
+
+        podcasts_found.push(itunesdb::Podcast::from_song(podcast_song));
+    }
+
+    for podcast in podcasts_found.iter_mut() {
+        if let Some(podcast_group) = podcast_groups_found
+            .iter()
+            .find(|podcast_group| podcast_group.track_ids.contains(&podcast.track_id))
+        {
+            podcast.podcast_show = podcast_group.group_name.to_string();
+
+            // The show's playlist items are stored in the order the episodes appear on the device
+            podcast.episode_order = podcast_group
+                .track_ids
+                .iter()
+                .position(|track_id| *track_id == podcast.track_id)
+                .map(|episode_idx| episode_idx as u32 + 1);
+        }
+    }
+
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        for song in songs_found.iter_mut() {
+            song.set_song_absolute_path(ipod_mount_point);
+
+            let song_file_path = song.song_absolute_path.clone().unwrap_or_default();
+
+            // MP3s are never lossless, so there's nothing to learn from opening them
+            if song.is_lossless || song.codec == "AAC or Apple Lossless" {
+                match audio_properties::read_audio_properties_from_audio_file(
+                    std::path::Path::new(&song_file_path),
+                ) {
+                    Some(song_audio_properties) => {
+                        song.codec = song_audio_properties.codec;
+                        song.is_lossless = song_audio_properties.is_lossless;
+                        song.bit_depth = song_audio_properties.bit_depth;
+                    }
+                    None => {
+                        parse_report.add_entry(
+                            "Audio properties not read",
+                            Some(song.track_id),
+                            format!(
+                                "Couldn't read the codec or bit depth from '{}'",
+                                song_file_path
+                            ),
+                        );
+                    }
+                }
+            }
+
+            if !song.has_lyrics {
+                continue;
+            }
+
+            song.lyrics = lyrics::read_lyrics_from_audio_file(std::path::Path::new(&song_file_path));
+
+            if song.lyrics.is_none() {
+                parse_report.add_entry(
+                    "Lyrics not found in audio file",
+                    Some(song.track_id),
+                    format!(
+                        "Track is flagged as having lyrics, but none could be read from '{}'",
+                        song_file_path
+                    ),
+                );
+            }
+        }
+
+        for video in videos_found.iter_mut() {
+            let video_file_path = std::path::Path::new(ipod_mount_point)
+                .join(&video.track.filename)
+                .display()
+                .to_string();
+
+            match video_properties::read_video_properties_from_video_file(std::path::Path::new(
+                &video_file_path,
+            )) {
+                Some(curr_video_properties) => {
+                    video.video_codec = curr_video_properties.codec;
+                    video.video_width = Some(curr_video_properties.width);
+                    video.video_height = Some(curr_video_properties.height);
+                }
+                None => {
+                    parse_report.add_entry(
+                        "Video properties not read",
+                        Some(video.track.track_id),
+                        format!(
+                            "Couldn't read the frame size or codec from '{}'",
+                            video_file_path
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    library_stats.parsed_num_playlists = playlists_found.len() as u32;
+    library_stats.cross_check(&mut parse_report);
+
+    for hidden_track_id in hidden_track_ids.iter() {
+        parse_report.add_entry(
+            "Hidden track excluded",
+            Some(*hidden_track_id),
+            "Track item isn't marked as visible, so it was left out of the exports".to_string(),
+        );
+    }
+
+    songs_found.retain(|song| !hidden_track_ids.contains(&song.track_id));
+    podcasts_found.retain(|podcast| !hidden_track_ids.contains(&podcast.track_id));
+    videos_found.retain(|video| !hidden_track_ids.contains(&video.track.track_id));
+    audiobooks_found.retain(|audiobook| !hidden_track_ids.contains(&audiobook.track.track_id));
+    itunesu_items_found.retain(|itunesu_item| !hidden_track_ids.contains(&itunesu_item.track.track_id));
+    ringtones_found.retain(|ringtone| !hidden_track_ids.contains(&ringtone.track.track_id));
+    voice_memos_found.retain(|voice_memo| !hidden_track_ids.contains(&voice_memo.track.track_id));
+    artwork_found.retain(|track_artwork| !hidden_track_ids.contains(&track_artwork.track_id));
+
+    // On-The-Go playlists made since the last sync only exist in their own files
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let itunes_folder_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes");
+
+        let mut otg_playlist_num = 0;
+
+        loop {
+            let (otg_playlist_filename, otg_playlist_name) = if otg_playlist_num == 0 {
+                (
+                    otgplaylist_constants::OTG_PLAYLIST_FILENAME.to_string(),
+                    "On-The-Go".to_string(),
+                )
+            } else {
+                (
+                    format!("{}_{}", otgplaylist_constants::OTG_PLAYLIST_FILENAME, otg_playlist_num),
+                    format!("On-The-Go {}", otg_playlist_num),
+                )
+            };
+
+            let otg_playlist_file_path = itunes_folder_path.join(&otg_playlist_filename);
+
+            let otg_playlist_file_as_bytes = match std::fs::read(&otg_playlist_file_path) {
+                Ok(otg_playlist_file_as_bytes) => otg_playlist_file_as_bytes,
+                Err(_) => break,
+            };
+
+            let mut otg_playlist = itunesdb::Playlist {
+                playlist_name: otg_playlist_name,
+                membership_source: format!("{} file (not synced yet)", otg_playlist_filename),
+                ..itunesdb::Playlist::default()
+            };
+
+            if let Ok(otg_playlist_modified_at) = std::fs::metadata(&otg_playlist_file_path)
+                .and_then(|otg_playlist_metadata| otg_playlist_metadata.modified())
+            {
+                // Whole seconds, like every timestamp in the database itself
+                otg_playlist.created_ts = chrono::SubsecRound::trunc_subsecs(
+                    chrono::DateTime::<chrono::Utc>::from(otg_playlist_modified_at),
+                    0,
+                );
+            }
+
+            for track_index in
+                otgplaylist_parser::read_otg_playlist_track_indices(&otg_playlist_file_as_bytes)
+            {
+                match track_list_track_ids.get(track_index as usize) {
+                    Some(track_id) => otg_playlist.stored_track_ids.push(*track_id),
+                    None => {
+                        parse_report.add_entry(
+                            "On-The-Go playlist track not found",
+                            None,
+                            format!(
+                                "'{}' refers to track #{}, but the database only has {} tracks",
+                                otg_playlist_filename,
+                                track_index,
+                                track_list_track_ids.len()
+                            ),
+                        );
+                    }
+                }
+            }
+
+            println!(
+                "On-The-Go playlist '{}' has {} tracks",
+                otg_playlist.playlist_name,
+                otg_playlist.stored_track_ids.len()
+            );
+
+            playlists_found.push(otg_playlist);
+            otg_playlist_num += 1;
+        }
+    }
+
+    // Hidden tracks' files still belong to them, so they aren't orphaned (see below)
+    let referenced_file_locations: Vec<String> = track_files_found
+        .iter()
+        .map(|track_file| track_file.filename.to_string())
+        .collect();
+
+    // Track items whose file is gone, can't hold the whole track, or isn't the size the database says
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        // Hidden tracks are waiting to be deleted, their files may well be gone already
+        track_files_found.retain(|track_file| {
+            !hidden_track_ids.contains(&track_file.track_id) && !track_file.filename.is_empty()
+        });
+
+        for track_file in track_files_found.iter_mut() {
+            track_files::check_track_file(track_file, ipod_mount_point, checksum_algorithm.as_deref());
+
+            if track_file.file_status != "OK" {
+                parse_report.add_entry(
+                    &format!("{} track file", track_file.file_status),
+                    Some(track_file.track_id),
+                    format!(
+                        "'{}': {} on the iPod, {} bytes in the database",
+                        track_file.filename,
+                        track_file
+                            .on_device_size_bytes
+                            .map_or("not found".to_string(), |on_device_size_bytes| format!("{} bytes", on_device_size_bytes)),
+                        track_file.database_size_bytes
+                    ),
+                );
+            }
+        }
+
+        let num_track_files_not_ok = track_files_found
+            .iter()
+            .filter(|track_file| track_file.file_status != "OK")
+            .count();
+
+        let num_track_files_size_mismatch = track_files_found
+            .iter()
+            .filter(|track_file| track_file.file_status == "Size mismatch")
+            .count();
+
+        println!(
+            "Checked the files of {} tracks, {} missing or likely corrupt, {} not the size in the database",
+            track_files_found.len(),
+            num_track_files_not_ok - num_track_files_size_mismatch,
+            num_track_files_size_mismatch
+        );
+
+        // Songs carry their checksum in music.csv too
+        if checksum_algorithm.is_some() {
+            for song in songs_found.iter_mut() {
+                song.file_checksum = track_files_found
+                    .iter()
+                    .find(|track_file| track_file.track_id == song.track_id)
+                    .and_then(|track_file| track_file.file_checksum.clone());
+            }
+        }
+    }
+
+    // Smart playlists get evaluated against everything that was parsed, as of right now
+    let mut smart_playlist_library: Vec<&dyn smart_playlist::SmartPlaylistTrack> = Vec::new();
+
+    for song in songs_found.iter() {
+        smart_playlist_library.push(song);
+    }
+    for podcast in podcasts_found.iter() {
+        smart_playlist_library.push(podcast);
+    }
+    for video in videos_found.iter() {
+        smart_playlist_library.push(&video.track);
+    }
+    for audiobook in audiobooks_found.iter() {
+        smart_playlist_library.push(&audiobook.track);
+    }
+    for itunesu_item in itunesu_items_found.iter() {
+        smart_playlist_library.push(&itunesu_item.track);
+    }
+    for ringtone in ringtones_found.iter() {
+        smart_playlist_library.push(&ringtone.track);
+    }
+    for voice_memo in voice_memos_found.iter() {
+        smart_playlist_library.push(&voice_memo.track);
+    }
+
+    // The titles only show up in the data objects after each track item
+    for track_artwork in artwork_found.iter_mut() {
+        if let Some(track) = smart_playlist_library
+            .iter()
+            .find(|track| track.get_track_id() == track_artwork.track_id)
+        {
+            track_artwork.title = track
+                .get_string_field(smart_playlist::SmartPlaylistRuleField::SongName as u32)
+                .unwrap_or_default();
+            track_artwork.artist = track
+                .get_string_field(smart_playlist::SmartPlaylistRuleField::Artist as u32)
+                .unwrap_or_default();
+            track_artwork.album = track
+                .get_string_field(smart_playlist::SmartPlaylistRuleField::Album as u32)
+                .unwrap_or_default();
+        }
+    }
+
+    for track_file in track_files_found.iter_mut() {
+        if let Some(track) = smart_playlist_library
+            .iter()
+            .find(|track| track.get_track_id() == track_file.track_id)
+        {
+            track_file.title = track
+                .get_string_field(smart_playlist::SmartPlaylistRuleField::SongName as u32)
+                .unwrap_or_default();
+            track_file.artist = track
+                .get_string_field(smart_playlist::SmartPlaylistRuleField::Artist as u32)
+                .unwrap_or_default();
+        }
+    }
+
+    // The ArtworkDB refers to tracks by dbid, and says which ithmb file holds each size of their cover art
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let artworkdb_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("Artwork")
+            .join("ArtworkDB");
+
+        if let Ok(artworkdb_file_as_bytes) = std::fs::read(&artworkdb_file_path) {
+            let (_, artwork_thumbnails, _) = photo_type_parser::read_photo_database(&artworkdb_file_as_bytes);
+
+            let mut num_tracks_with_artwork_on_device = 0;
+
+            for track_artwork in artwork_found.iter_mut() {
+                track_artwork.thumbnails = artwork_thumbnails
+                    .iter()
+                    .filter(|thumbnail| thumbnail.song_dbid == track_artwork.dbid)
+                    .map(|thumbnail| itunesdb::ArtworkThumbnail {
+                        correlation_id: thumbnail.correlation_id,
+                        width: thumbnail.width,
+                        height: thumbnail.height,
+                        ithmb_filename: thumbnail.ithmb_filename.to_string(),
+                        ithmb_offset: thumbnail.ithmb_offset,
+                        image_size_bytes: thumbnail.image_size_bytes,
+                    })
+                    .collect();
+
+                track_artwork.is_on_device = Some(!track_artwork.thumbnails.is_empty());
+
+                if !track_artwork.thumbnails.is_empty() {
+                    num_tracks_with_artwork_on_device += 1;
+                }
+            }
+
+            println!(
+                "Cover art of {} of {} tracks found in the ArtworkDB",
+                num_tracks_with_artwork_on_device,
+                artwork_found.len()
+            );
+        }
+    }
+
+    let reference_timestamp_mac = helpers::get_mac_timestamp_from_datetime(chrono::Utc::now());
+
+    for playlist in playlists_found.iter_mut() {
+        playlist.playlist_kind = itunesdb::decode_playlist_kind(playlist);
+        playlist.track_ids = playlist.stored_track_ids.clone();
+
+        if playlist.is_smart_playlist {
+            smart_playlist::materialize_smart_playlist(
+                playlist,
+                &smart_playlist_library,
+                reference_timestamp_mac,
+            );
+        }
+    }
+
+    println!("{} podcasts found", podcasts_found.len());
+    println!("{} songs found", songs_found.len());
+    println!("{} videos found", videos_found.len());
+    println!("{} audiobooks found", audiobooks_found.len());
+    println!("{} iTunes U items found", itunesu_items_found.len());
+    println!("{} ringtones found", ringtones_found.len());
+    println!("{} voice memos found", voice_memos_found.len());
+    println!("{} playlists found", playlists_found.len());
+
+    // A single export as NDJSON, to stdout or a file, instead of all the usual files
+    if let Some(output_path) = output_path.as_ref() {
+        io::Write::flush(&mut io::stdout()).expect("Can't flush stdout");
+        drop(stdout_redirect);
+
+        let ndjson_writer: Box<dyn io::Write> = if output_path == "-" {
+            Box::new(io::stdout().lock())
+        } else {
+            helpers::create_output_file(output_path)
+        };
+
+        let export_name = export_name.as_deref().unwrap_or("songs");
+
+        if export_name == "songs" || export_name == "music" {
+            helpers::write_ndjson(ndjson_writer, &json_schema::get_versioned_records(&songs_found));
+        } else if export_name == "podcasts" {
+            helpers::write_ndjson(ndjson_writer, &json_schema::get_versioned_records(&podcasts_found));
+        } else if export_name == "videos" {
+            helpers::write_ndjson(ndjson_writer, &videos_found);
+        } else if export_name == "audiobooks" {
+            helpers::write_ndjson(ndjson_writer, &audiobooks_found);
+        } else if export_name == "itunesu" {
+            helpers::write_ndjson(ndjson_writer, &itunesu_items_found);
+        } else if export_name == "ringtones" {
+            helpers::write_ndjson(ndjson_writer, &ringtones_found);
+        } else if export_name == "voice_memos" {
+            helpers::write_ndjson(ndjson_writer, &voice_memos_found);
+        } else if export_name == "playlists" {
+            helpers::write_ndjson(ndjson_writer, &json_schema::get_versioned_records(&playlists_found));
+        } else {
+            eprintln!(
+                "Unknown export '{}', use songs, podcasts, videos, audiobooks, itunesu, ringtones, voice_memos or playlists",
+                export_name
+            );
+        }
+
+        return;
+    }
+
+    // Add JSON output @joshkenney
+    // YAML is a superset of JSON, so with YAML output only the songs, podcasts and playlists are written as YAML
+    if output_format == "json" || output_format == "yaml" {
+        // Only create JSON output
+        if !songs_found.is_empty() {
+            if output_format == "yaml" {
+                helpers::write_yaml_file("music.yaml", &json_schema::get_versioned_records(&songs_found));
+                println!("Created music.yaml with {} songs", songs_found.len());
+            } else {
+                let songs_json = serde_json::to_string_pretty(&json_schema::get_versioned_records(&songs_found))
+                    .expect("Error serializing songs to JSON");
+                let mut songs_json_file = helpers::create_output_file("music.json");
+                io::Write::write_all(&mut songs_json_file, songs_json.as_bytes())
+                    .expect("Error writing songs JSON file");
+                println!("Created music.json with {} songs", songs_found.len());
+            }
+        }
+
+        if !podcasts_found.is_empty() {
+            if output_format == "yaml" {
+                helpers::write_yaml_file("podcasts.yaml", &json_schema::get_versioned_records(&podcasts_found));
+                println!("Created podcasts.yaml with {} podcasts", podcasts_found.len());
+            } else {
+                let podcasts_json = serde_json::to_string_pretty(&json_schema::get_versioned_records(&podcasts_found))
+                    .expect("Error serializing podcasts to JSON");
+                let mut podcasts_json_file = helpers::create_output_file("podcasts.json");
+                io::Write::write_all(&mut podcasts_json_file, podcasts_json.as_bytes())
+                    .expect("Error writing podcasts JSON file");
+                println!("Created podcasts.json with {} podcasts", podcasts_found.len());
+            }
+        }
+
+        if !videos_found.is_empty() {
+            helpers::write_json_file("videos.json", &videos_found);
+            println!("Created videos.json with {} videos", videos_found.len());
+        }
+
+        if !audiobooks_found.is_empty() {
+            helpers::write_json_file("audiobooks.json", &audiobooks_found);
+            println!("Created audiobooks.json with {} audiobooks", audiobooks_found.len());
+        }
+
+        if !itunesu_items_found.is_empty() {
+            helpers::write_json_file("itunesu.json", &itunesu_items_found);
+            println!("Created itunesu.json with {} iTunes U items", itunesu_items_found.len());
+        }
+
+        if !ringtones_found.is_empty() {
+            helpers::write_json_file("ringtones.json", &ringtones_found);
+            println!("Created ringtones.json with {} ringtones", ringtones_found.len());
+        }
+
+        if !voice_memos_found.is_empty() {
+            helpers::write_json_file("voice_memos.json", &voice_memos_found);
+            println!("Created voice_memos.json with {} voice memos", voice_memos_found.len());
+        }
+
+        if !playlists_found.is_empty() {
+            if output_format == "yaml" {
+                helpers::write_yaml_file("playlists.yaml", &json_schema::get_versioned_records(&playlists_found));
+                println!("Created playlists.yaml with {} playlists", playlists_found.len());
+            } else {
+                helpers::write_json_file("playlists.json", &json_schema::get_versioned_records(&playlists_found));
+                println!("Created playlists.json with {} playlists", playlists_found.len());
+            }
+        }
+
+        if !artwork_found.is_empty() {
+            helpers::write_json_file("artwork.json", &artwork_found);
+            println!("Created artwork.json with {} tracks", artwork_found.len());
+        }
+
+        helpers::write_json_file("device_info.json", &ipod_device_info);
+        println!("Created device_info.json");
+
+        if !equalizer_presets.is_empty() {
+            helpers::write_json_file("equalizer_presets.json", &equalizer_presets);
+            println!("Created equalizer_presets.json with {} presets", equalizer_presets.len());
+        }
+
+        helpers::write_json_file("library_stats.json", &library_stats);
+        println!("Created library_stats.json");
+     // default to CSV output
+    } else {
+        let mut music_csv_writer = helpers::init_csv_writer("music.csv");
+        let mut podcast_csv_writer = helpers::init_csv_writer("podcasts.csv");
+
+        if !podcasts_found.is_empty() {
+            let podcast_csv_headers = [
+                "DBID",
+                "Media kind",
+                "Episode Title",
+                "Show",
+                "Episode order",
+                "Publisher",
+                "Genre",
                 "Subtitle",
                 "Description",
-                "File Type"
-            ]).expect("Error can't create CSV file headers for podcast file");
+                "File Type",
+                "Grouping",
+                "Keywords",
+                "Resume position (ms)",
+                "Release date",
+                "Duration (seconds)",
+                "Played",
+                "New",
+                "Remember playback position",
+                "Skip when shuffling",
+                "Explicitness",
+                "Enclosure URL",
+                "RSS URL"
+            ];
+
+            let podcast_column_idxs = helpers::get_selected_column_idxs(&podcast_csv_headers, podcast_columns.as_deref(), "podcasts.csv");
+
+            podcast_csv_writer
+                .write_record(helpers::get_selected_columns(&podcast_csv_headers, &podcast_column_idxs))
+                .expect("Error can't create CSV file headers for podcast file");
 
             for episode in podcasts_found.iter() {
-                podcast_csv_writer.write_record(&[
+                let release_date = match episode.release_date {
+                    Some(release_date) => release_date.to_string(),
+                    None => "".to_string(),
+                };
+
+                podcast_csv_writer.write_record(helpers::get_selected_columns(&[
+                    episode.dbid.to_string(),
+                    episode.media_kind.to_string(),
                     episode.podcast_title.to_string(),
+                    episode.podcast_show.to_string(),
+                    episode
+                        .episode_order
+                        .map_or("".to_string(), |episode_order| episode_order.to_string()),
                     episode.podcast_publisher.to_string(),
                     episode.podcast_genre.to_string(),
                     episode.podcast_subtitle.to_string(),
                     episode.podcast_description.to_string().replace("\n", ""),
-                    episode.podcast_file_type.to_string()
-                ]).expect("Can't write row to podcast CSV file");
+                    episode.podcast_file_type.to_string(),
+                    episode.podcast_grouping.to_string(),
+                    episode.podcast_keywords.to_string(),
+                    episode.resume_position_ms.to_string(),
+                    release_date,
+                    episode.duration_s.to_string(),
+                    episode.is_played.to_string(),
+                    episode.is_new.to_string(),
+                    episode.remember_playback_position.to_string(),
+                    episode.skip_when_shuffling.to_string(),
+                    episode.explicitness.to_string(),
+                    episode.enclosure_url.to_string(),
+                    episode.rss_url.to_string()
+                ], &podcast_column_idxs)).expect("Can't write row to podcast CSV file");
             }
             println!("Created podcasts.csv with {} podcasts", podcasts_found.len());
         }
 
         if !songs_found.is_empty() {
+            let music_csv_headers = [
+                "DBID",
+                "Media kind",
+                "Song Title",
+                "Artist",
+                "Album",
+                "Year released",
+                "File size",
+                "Song Duration",
+                "Filename",
+                "Genre",
+                "File extension",
+                "Bitrate (kbps)",
+                "Sample Rate (Hz)",
+                "File size (bytes)",
+                "Song duration (seconds)",
+                "Play count",
+                "Rating",
+                "Added to library on (timestamp)",
+                "Added to library on (epoch)",
+                "Composer",
+                "Comment",
+                "Album Artist",
+                "Sort Title",
+                "Sort Artist",
+                "Sort Album",
+                "Sort Composer",
+                "Sort Album Artist",
+                "Grouping",
+                "Description",
+                "Keywords",
+                "Has artwork",
+                "Artwork count",
+                "Artwork size (bytes)",
+                "Artwork ID",
+                "Start time (ms)",
+                "Stop time (ms)",
+                "Volume adjustment",
+                "Last played",
+                "Skip count",
+                "Last skipped",
+                "Compilation",
+                "Gapless",
+                "Pregap samples",
+                "Postgap samples",
+                "Total samples",
+                "BPM",
+                "EQ preset",
+                "EQ preset ID",
+                "EQ preset name",
+                "Track number",
+                "Tracks in album",
+                "Disc number",
+                "Discs in set",
+                "Previous rating",
+                "Rating source",
+                "Last modified (timestamp)",
+                "Last modified (epoch)",
+                "Released on iTunes (timestamp)",
+                "Released on iTunes (epoch)",
+                "Apple user ID",
+                "Protected",
+                "Checked",
+                "Remember playback position",
+                "Skip when shuffling",
+                "Explicitness",
+                "Has lyrics",
+                "Sound Check (raw)",
+                "ReplayGain track gain",
+                "Filename (as stored)",
+                "Absolute path",
+                "Codec",
+                "Lossless",
+                "Bit depth",
+                "Resume position (ms)",
+                "File checksum",
+            ];
+
+            let music_column_idxs = helpers::get_selected_column_idxs(&music_csv_headers, music_columns.as_deref(), "music.csv");
+
             music_csv_writer
-                .write_record(&[
-                    "Song Title",
-                    "Artist",
-                    "Album",
-                    "Year released",
-                    "File size",
-                    "Song Duration",
-                    "Filename",
-                    "Genre",
-                    "File extension",
-                    "Bitrate (kbps)",
-                    "Sample Rate (Hz)",
-                    "File size (bytes)",
-                    "Song duration (seconds)",
-                    "Play count",
-                    "Rating",
-                    "Added to library on (timestamp)",
-                    "Added to library on (epoch)",
-                    "Composer",
-                    "Comment",
-                ])
+                .write_record(helpers::get_selected_columns(&music_csv_headers, &music_column_idxs))
                 .expect("Can't create CSV file headers for music file");
 
             for song in songs_found.iter() {
@@ -900,8 +2815,35 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                 // cannot move out of `song.song_title` which is behind a shared reference
                 // move occurs because `song.song_title` has type `String`, which does not implement the `Copy` trait
 
+                let last_played = match song.last_played_at {
+                    Some(last_played_at) => last_played_at.to_string(),
+                    None => "".to_string(),
+                };
+
+                let last_skipped = match song.last_skipped_at {
+                    Some(last_skipped_at) => last_skipped_at.to_string(),
+                    None => "".to_string(),
+                };
+
+                let added_to_library = match song.song_added_to_library_ts {
+                    Some(added_to_library_ts) => added_to_library_ts.to_string(),
+                    None => "".to_string(),
+                };
+
+                let last_modified = match song.song_modified_ts {
+                    Some(modified_ts) => modified_ts.to_string(),
+                    None => "".to_string(),
+                };
+
+                let released = match song.song_released_ts {
+                    Some(released_ts) => released_ts.to_string(),
+                    None => "".to_string(),
+                };
+
                 music_csv_writer
-                    .write_record(&[
+                    .write_record(helpers::get_selected_columns(&[
+                        song.dbid.to_string(),
+                        song.media_kind.to_string(),
                         song.song_title.to_string(),
                         song.song_artist.to_string(),
                         song.song_album.to_string(),
@@ -917,14 +2859,1053 @@ pub fn parse_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: Strin
                         song.song_duration_s.to_string(),
                         song.num_plays.to_string(),
                         itunesdb_helpers::decode_itunes_stars(song.song_rating_raw),
-                        song.song_added_to_library_ts.to_string(),
+                        added_to_library,
                         song.song_added_to_library_epoch.to_string(),
                         song.song_composer.to_string(),
                         song.song_comment.to_string(),
-                    ])
+                        song.song_album_artist.to_string(),
+                        song.song_sort_title.to_string(),
+                        song.song_sort_artist.to_string(),
+                        song.song_sort_album.to_string(),
+                        song.song_sort_composer.to_string(),
+                        song.song_sort_album_artist.to_string(),
+                        song.song_grouping.to_string(),
+                        song.song_description.to_string().replace("\n", ""),
+                        song.song_keywords.to_string(),
+                        song.has_artwork.to_string(),
+                        song.artwork_count.to_string(),
+                        song.artwork_size_bytes.to_string(),
+                        song.artwork_id.to_string(),
+                        song.start_time_ms.to_string(),
+                        song.stop_time_ms.to_string(),
+                        song.volume_adjustment.to_string(),
+                        last_played,
+                        song.num_skips.to_string(),
+                        last_skipped,
+                        song.is_compilation.to_string(),
+                        song.gapless_enabled.to_string(),
+                        song.pregap_samples.to_string(),
+                        song.postgap_samples.to_string(),
+                        song.num_samples.to_string(),
+                        song.bpm.to_string(),
+                        song.equalizer_preset.to_string(),
+                        song.equalizer_preset_id
+                            .map_or("".to_string(), |equalizer_preset_id| equalizer_preset_id.to_string()),
+                        song.equalizer_preset_name.to_string(),
+                        song.track_number.to_string(),
+                        song.num_tracks_in_album.to_string(),
+                        song.disc_number.to_string(),
+                        song.num_discs.to_string(),
+                        itunesdb_helpers::decode_itunes_stars(song.song_previous_rating_raw),
+                        song.rating_source.to_string(),
+                        last_modified,
+                        song.song_modified_epoch.to_string(),
+                        released,
+                        song.song_released_epoch.to_string(),
+                        song.apple_user_id.to_string(),
+                        song.is_protected.to_string(),
+                        song.is_checked.to_string(),
+                        song.remember_playback_position.to_string(),
+                        song.skip_when_shuffling.to_string(),
+                        song.explicitness.to_string(),
+                        song.has_lyrics.to_string(),
+                        song.sound_check_raw.to_string(),
+                        song.replaygain_track_gain.to_string(),
+                        song.song_filename_raw.to_string(),
+                        song.song_absolute_path.clone().unwrap_or_default(),
+                        song.codec.to_string(),
+                        song.is_lossless.to_string(),
+                        song.bit_depth.map_or("".to_string(), |bit_depth| bit_depth.to_string()),
+                        song.resume_position_ms.to_string(),
+                        song.file_checksum.clone().unwrap_or_default(),
+                    ], &music_column_idxs))
                     .expect("Can't write row to CSV");
             }
             println!("Created music.csv with {} songs", songs_found.len());
         }
+
+        // Movies and music videos, TV shows get their own file below
+        let (tv_shows_found, movies_found): (Vec<&itunesdb::Video>, Vec<&itunesdb::Video>) =
+            videos_found.iter().partition(|video| video.is_tv_show());
+
+        if !movies_found.is_empty() {
+            let mut video_csv_writer = helpers::init_csv_writer("videos.csv");
+
+            video_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Artist",
+                    "Kind",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Filename",
+                    "TV show",
+                    "TV episode ID",
+                    "TV network",
+                    "Codec",
+                    "Width",
+                    "Height",
+                ])
+                .expect("Can't create CSV file headers for video file");
+
+            for video in movies_found.iter() {
+                video_csv_writer
+                    .write_record(&[
+                        video.track.dbid.to_string(),
+                        video.track.media_kind.to_string(),
+                        video.track.title.to_string(),
+                        video.track.artist.to_string(),
+                        video.video_kind.to_string(),
+                        video.track.file_extension.to_string(),
+                        video.track.file_size_friendly.to_string(),
+                        video.track.file_size_bytes.to_string(),
+                        video.track.duration_friendly.to_string(),
+                        video.track.duration_s.to_string(),
+                        video.track.filename.to_string(),
+                        video.tv_show.to_string(),
+                        video.tv_episode_id.to_string(),
+                        video.tv_network.to_string(),
+                        video.video_codec.to_string(),
+                        video.video_width.map_or("".to_string(), |width| width.to_string()),
+                        video.video_height.map_or("".to_string(), |height| height.to_string()),
+                    ])
+                    .expect("Can't write row to video CSV file");
+            }
+            println!("Created videos.csv with {} videos", movies_found.len());
+        }
+
+        if !tv_shows_found.is_empty() {
+            let mut tv_show_csv_writer = helpers::init_csv_writer("tv_shows.csv");
+
+            tv_show_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Show",
+                    "Season",
+                    "Episode",
+                    "Episode ID",
+                    "Title",
+                    "Network",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Filename",
+                    "Codec",
+                    "Width",
+                    "Height",
+                ])
+                .expect("Can't create CSV file headers for TV show file");
+
+            for tv_show in tv_shows_found.iter() {
+                tv_show_csv_writer
+                    .write_record(&[
+                        tv_show.track.dbid.to_string(),
+                        tv_show.track.media_kind.to_string(),
+                        tv_show.tv_show.to_string(),
+                        tv_show.tv_season_number.to_string(),
+                        tv_show.tv_episode_number.to_string(),
+                        tv_show.tv_episode_id.to_string(),
+                        tv_show.track.title.to_string(),
+                        tv_show.tv_network.to_string(),
+                        tv_show.track.file_extension.to_string(),
+                        tv_show.track.file_size_friendly.to_string(),
+                        tv_show.track.file_size_bytes.to_string(),
+                        tv_show.track.duration_friendly.to_string(),
+                        tv_show.track.duration_s.to_string(),
+                        tv_show.track.filename.to_string(),
+                        tv_show.video_codec.to_string(),
+                        tv_show.video_width.map_or("".to_string(), |width| width.to_string()),
+                        tv_show.video_height.map_or("".to_string(), |height| height.to_string()),
+                    ])
+                    .expect("Can't write row to TV show CSV file");
+            }
+            println!("Created tv_shows.csv with {} episodes", tv_shows_found.len());
+        }
+
+        if !audiobooks_found.is_empty() {
+            let mut audiobook_csv_writer = helpers::init_csv_writer("audiobooks.csv");
+
+            audiobook_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Author",
+                    "Album",
+                    "Genre",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Resume position (ms)",
+                    "Remember playback position",
+                    "Skip when shuffling",
+                    "Play count",
+                    "Filename",
+                    "Protected",
+                ])
+                .expect("Can't create CSV file headers for audiobook file");
+
+            for audiobook in audiobooks_found.iter() {
+                audiobook_csv_writer
+                    .write_record(&[
+                        audiobook.track.dbid.to_string(),
+                        audiobook.track.media_kind.to_string(),
+                        audiobook.track.title.to_string(),
+                        audiobook.track.artist.to_string(),
+                        audiobook.track.album.to_string(),
+                        audiobook.track.genre.to_string(),
+                        audiobook.track.file_extension.to_string(),
+                        audiobook.track.file_size_friendly.to_string(),
+                        audiobook.track.file_size_bytes.to_string(),
+                        audiobook.track.duration_friendly.to_string(),
+                        audiobook.track.duration_s.to_string(),
+                        audiobook.track.resume_position_ms.to_string(),
+                        audiobook.track.remember_playback_position.to_string(),
+                        audiobook.track.skip_when_shuffling.to_string(),
+                        audiobook.track.num_plays.to_string(),
+                        audiobook.track.filename.to_string(),
+                        audiobook.is_protected.to_string(),
+                    ])
+                    .expect("Can't write row to audiobook CSV file");
+            }
+            println!("Created audiobooks.csv with {} audiobooks", audiobooks_found.len());
+        }
+
+        if !itunesu_items_found.is_empty() {
+            let mut itunesu_csv_writer = helpers::init_csv_writer("itunesu.csv");
+
+            itunesu_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Course",
+                    "Provider",
+                    "Grouping",
+                    "Category",
+                    "Description",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Filename",
+                ])
+                .expect("Can't create CSV file headers for iTunes U file");
+
+            for itunesu_item in itunesu_items_found.iter() {
+                itunesu_csv_writer
+                    .write_record(&[
+                        itunesu_item.track.dbid.to_string(),
+                        itunesu_item.track.media_kind.to_string(),
+                        itunesu_item.track.title.to_string(),
+                        itunesu_item.track.album.to_string(),
+                        itunesu_item.track.artist.to_string(),
+                        itunesu_item.track.grouping.to_string(),
+                        itunesu_item.track.category.to_string(),
+                        itunesu_item.track.description.to_string(),
+                        itunesu_item.track.file_extension.to_string(),
+                        itunesu_item.track.file_size_friendly.to_string(),
+                        itunesu_item.track.file_size_bytes.to_string(),
+                        itunesu_item.track.duration_friendly.to_string(),
+                        itunesu_item.track.duration_s.to_string(),
+                        itunesu_item.track.filename.to_string(),
+                    ])
+                    .expect("Can't write row to iTunes U CSV file");
+            }
+            println!("Created itunesu.csv with {} iTunes U items", itunesu_items_found.len());
+        }
+
+        if !ringtones_found.is_empty() {
+            let mut ringtone_csv_writer = helpers::init_csv_writer("ringtones.csv");
+
+            ringtone_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Artist",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Filename",
+                ])
+                .expect("Can't create CSV file headers for ringtone file");
+
+            for ringtone in ringtones_found.iter() {
+                ringtone_csv_writer
+                    .write_record(&[
+                        ringtone.track.dbid.to_string(),
+                        ringtone.track.media_kind.to_string(),
+                        ringtone.track.title.to_string(),
+                        ringtone.track.artist.to_string(),
+                        ringtone.track.file_extension.to_string(),
+                        ringtone.track.file_size_friendly.to_string(),
+                        ringtone.track.file_size_bytes.to_string(),
+                        ringtone.track.duration_friendly.to_string(),
+                        ringtone.track.duration_s.to_string(),
+                        ringtone.track.filename.to_string(),
+                    ])
+                    .expect("Can't write row to ringtone CSV file");
+            }
+            println!("Created ringtones.csv with {} ringtones", ringtones_found.len());
+        }
+
+        if !voice_memos_found.is_empty() {
+            let mut voice_memo_csv_writer = helpers::init_csv_writer("voice_memos.csv");
+
+            voice_memo_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Genre",
+                    "File extension",
+                    "File size",
+                    "File size (bytes)",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Recorded",
+                    "Added to library",
+                    "Filename",
+                ])
+                .expect("Can't create CSV file headers for voice memo file");
+
+            for voice_memo in voice_memos_found.iter() {
+                let recorded = match voice_memo.recorded_ts {
+                    Some(recorded_ts) => recorded_ts.to_string(),
+                    None => "".to_string(),
+                };
+
+                voice_memo_csv_writer
+                    .write_record(&[
+                        voice_memo.track.dbid.to_string(),
+                        voice_memo.track.media_kind.to_string(),
+                        voice_memo.track.title.to_string(),
+                        voice_memo.track.genre.to_string(),
+                        voice_memo.track.file_extension.to_string(),
+                        voice_memo.track.file_size_friendly.to_string(),
+                        voice_memo.track.file_size_bytes.to_string(),
+                        voice_memo.track.duration_friendly.to_string(),
+                        voice_memo.track.duration_s.to_string(),
+                        recorded,
+                        voice_memo
+                            .track
+                            .added_to_library_ts
+                            .map_or("".to_string(), |added_to_library_ts| added_to_library_ts.to_string()),
+                        voice_memo.track.filename.to_string(),
+                    ])
+                    .expect("Can't write row to voice memo CSV file");
+            }
+            println!("Created voice_memos.csv with {} voice memos", voice_memos_found.len());
+        }
+
+        if !playlists_found.is_empty() {
+            let mut playlist_csv_writer = helpers::init_csv_writer("playlists.csv");
+
+            playlist_csv_writer
+                .write_record([
+                    "Persistent ID",
+                    "Playlist Name",
+                    "Master playlist",
+                    "Podcast playlist",
+                    "Smart playlist",
+                    "Playlist kind",
+                    "Track count",
+                    "Membership source",
+                    "Smart playlist rules",
+                    "Track IDs",
+                    "Columns",
+                    "Sort order",
+                    "Created",
+                ])
+                .expect("Can't create CSV file headers for playlist file");
+
+            for playlist in playlists_found.iter() {
+                let rules_description = match &playlist.smart_playlist_rules {
+                    Some(rules) => smart_playlist::describe_smart_playlist_rules(rules),
+                    None => "".to_string(),
+                };
+
+                let track_ids: Vec<String> =
+                    playlist.track_ids.iter().map(|id| id.to_string()).collect();
+
+                let columns_description = match &playlist.column_settings {
+                    Some(column_settings) => column_settings
+                        .columns
+                        .iter()
+                        .map(|column| format!("{} ({}px)", column.column_name, column.width_px))
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    None => "".to_string(),
+                };
+
+                playlist_csv_writer
+                    .write_record(&[
+                        playlist.persistent_playlist_id.to_string(),
+                        playlist.playlist_name.to_string(),
+                        playlist.is_master_playlist.to_string(),
+                        playlist.is_podcast_playlist.to_string(),
+                        playlist.is_smart_playlist.to_string(),
+                        playlist.playlist_kind.to_string(),
+                        playlist.track_ids.len().to_string(),
+                        playlist.membership_source.to_string(),
+                        rules_description,
+                        track_ids.join(" "),
+                        columns_description,
+                        playlist.sort_order.to_string(),
+                        playlist.created_ts.to_string(),
+                    ])
+                    .expect("Can't write row to playlist CSV file");
+            }
+            println!("Created playlists.csv with {} playlists", playlists_found.len());
+        }
+
+        if !artwork_found.is_empty() {
+            let mut artwork_csv_writer = helpers::init_csv_writer("artwork.csv");
+
+            artwork_csv_writer
+                .write_record([
+                    "Track ID",
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Artist",
+                    "Album",
+                    "Artwork count",
+                    "Artwork size (bytes)",
+                    "Artwork ID",
+                    "On device",
+                    "Thumbnail sizes",
+                    "ithmb locations",
+                ])
+                .expect("Can't create CSV file headers for artwork file");
+
+            for track_artwork in artwork_found.iter() {
+                artwork_csv_writer
+                    .write_record(&[
+                        track_artwork.track_id.to_string(),
+                        track_artwork.dbid.to_string(),
+                        track_artwork.media_kind.to_string(),
+                        track_artwork.title.to_string(),
+                        track_artwork.artist.to_string(),
+                        track_artwork.album.to_string(),
+                        track_artwork.artwork_count.to_string(),
+                        track_artwork.artwork_size_bytes.to_string(),
+                        track_artwork.artwork_id.to_string(),
+                        track_artwork
+                            .is_on_device
+                            .map_or("".to_string(), |is_on_device| is_on_device.to_string()),
+                        track_artwork
+                            .thumbnails
+                            .iter()
+                            .map(|thumbnail| format!("{}x{}", thumbnail.width, thumbnail.height))
+                            .collect::<Vec<String>>()
+                            .join("; "),
+                        track_artwork
+                            .thumbnails
+                            .iter()
+                            .map(|thumbnail| format!("{} @ {}", thumbnail.ithmb_filename, thumbnail.ithmb_offset))
+                            .collect::<Vec<String>>()
+                            .join("; "),
+                    ])
+                    .expect("Can't write row to artwork CSV file");
+            }
+            println!("Created artwork.csv with {} tracks", artwork_found.len());
+        }
+
+        let mut device_info_csv_writer = helpers::init_csv_writer("device_info.csv");
+
+        device_info_csv_writer
+            .write_record([
+                "iPod name",
+                "User name",
+                "Host computer",
+                "Language code",
+                "Language",
+                "Database version",
+                "iTunes version",
+                "Hashing scheme",
+                "Model number",
+                "Model",
+                "Serial number",
+                "FireWire GUID",
+                "Firmware version",
+                "Board",
+                "Artwork formats",
+                "Manually manage music",
+                "Sync selection",
+                "Disk use enabled",
+                "iPod language",
+                "Volume limit",
+            ])
+            .expect("Can't create CSV file headers for device info file");
+
+        // Left empty if SysInfo, iTunesPrefs or Preferences weren't read
+        let sysinfo = ipod_device_info.sysinfo.as_ref();
+        let itunes_prefs = ipod_device_info.itunes_prefs.as_ref();
+        let device_preferences = ipod_device_info.preferences.as_ref();
+
+        device_info_csv_writer
+            .write_record(&[
+                ipod_device_info.ipod_name.to_string(),
+                ipod_device_info.user_name.to_string(),
+                ipod_device_info.host_computer_name.to_string(),
+                ipod_device_info.language_code.to_string(),
+                ipod_device_info.language_name.to_string(),
+                ipod_device_info.database_version.to_string(),
+                ipod_device_info.itunes_version.to_string(),
+                ipod_device_info.hashing_scheme.to_string(),
+                sysinfo.map(|sysinfo| sysinfo.model_number.to_string()).unwrap_or_default(),
+                ipod_device_info
+                    .model
+                    .as_ref()
+                    .map(|ipod_model| ipod_model.get_description())
+                    .unwrap_or_default(),
+                sysinfo.map(|sysinfo| sysinfo.serial_number.to_string()).unwrap_or_default(),
+                sysinfo.map(|sysinfo| sysinfo.firewire_guid.to_string()).unwrap_or_default(),
+                sysinfo.map(|sysinfo| sysinfo.firmware_version.to_string()).unwrap_or_default(),
+                sysinfo.map(|sysinfo| sysinfo.board_revision.to_string()).unwrap_or_default(),
+                sysinfo
+                    .map(|sysinfo| {
+                        sysinfo
+                            .artwork_formats
+                            .iter()
+                            .map(|artwork_format| {
+                                format!(
+                                    "{} {} ({}x{} {})",
+                                    artwork_format.usage,
+                                    artwork_format.format_id,
+                                    artwork_format.width,
+                                    artwork_format.height,
+                                    artwork_format.pixel_format
+                                )
+                            })
+                            .collect::<Vec<String>>()
+                            .join("; ")
+                    })
+                    .unwrap_or_default(),
+                itunes_prefs.map(|itunes_prefs| itunes_prefs.is_music_manually_managed().to_string()).unwrap_or_default(),
+                itunes_prefs.map(|itunes_prefs| itunes_prefs.sync_selection.to_string()).unwrap_or_default(),
+                itunes_prefs.map(|itunes_prefs| itunes_prefs.disk_use_enabled.to_string()).unwrap_or_default(),
+                device_preferences.map(|device_preferences| device_preferences.language.to_string()).unwrap_or_default(),
+                device_preferences
+                    .and_then(|device_preferences| device_preferences.volume_limit)
+                    .map(|volume_limit| volume_limit.to_string())
+                    .unwrap_or_default(),
+            ])
+            .expect("Can't write row to device info CSV file");
+        println!("Created device_info.csv");
+
+        if !equalizer_presets.is_empty() {
+            equalizer_parser::write_equalizer_presets_csv(
+                &equalizer_presets,
+                helpers::init_csv_writer("equalizer_presets.csv"),
+            );
+            println!("Created equalizer_presets.csv with {} presets", equalizer_presets.len());
+        }
+
+        let mut library_stats_csv_writer = helpers::init_csv_writer("library_stats.csv");
+
+        library_stats_csv_writer
+            .write_record([
+                "Declared tracks",
+                "Parsed tracks",
+                "Declared playlists",
+                "Parsed playlists",
+                "Declared albums",
+                "Parsed albums",
+                "Counts mismatch",
+            ])
+            .expect("Can't create CSV file headers for library stats file");
+
+        library_stats_csv_writer
+            .write_record(&[
+                library_stats
+                    .declared_num_tracks
+                    .map_or("".to_string(), |num_tracks| num_tracks.to_string()),
+                library_stats.parsed_num_tracks.to_string(),
+                library_stats
+                    .declared_num_playlists
+                    .map_or("".to_string(), |num_playlists| num_playlists.to_string()),
+                library_stats.parsed_num_playlists.to_string(),
+                library_stats
+                    .declared_num_albums
+                    .map_or("".to_string(), |num_albums| num_albums.to_string()),
+                library_stats.parsed_num_albums.to_string(),
+                library_stats.has_mismatches.to_string(),
+            ])
+            .expect("Can't write row to library stats CSV file");
+        println!("Created library_stats.csv");
+    }
+
+    // Tracks the last sync left out, and stats that haven't made it back to the computer yet
+    if let Some(itunes_library_filename) = itunes_library_filename.as_ref() {
+        let library_tracks = std::fs::read(itunes_library_filename)
+            .ok()
+            .and_then(|itunes_library_file_as_bytes| itunes_library_parser::read_itunes_library(&itunes_library_file_as_bytes));
+
+        if let Some(library_tracks) = library_tracks {
+            let library_differences = itunes_library::compare_songs_with_library(&songs_found, &library_tracks);
+
+            println!(
+                "Compared {} songs with {} tracks in the iTunes Library, {} differences found",
+                songs_found.len(),
+                library_tracks.len(),
+                library_differences.len()
+            );
+
+            if output_format == "json" || output_format == "yaml" {
+                helpers::write_json_file("library_comparison.json", &library_differences);
+                println!("Created library_comparison.json");
+            } else {
+                let mut library_comparison_csv_writer = helpers::init_csv_writer("library_comparison.csv");
+
+                library_comparison_csv_writer
+                    .write_record([
+                        "DBID",
+                        "Title",
+                        "Artist",
+                        "Album",
+                        "Difference",
+                        "On device",
+                        "In library",
+                    ])
+                    .expect("Can't create CSV file headers for library comparison file");
+
+                for library_difference in library_differences.iter() {
+                    library_comparison_csv_writer
+                        .write_record(&[
+                            library_difference.dbid.to_string(),
+                            library_difference.title.to_string(),
+                            library_difference.artist.to_string(),
+                            library_difference.album.to_string(),
+                            library_difference.difference.to_string(),
+                            library_difference.device_value.to_string(),
+                            library_difference.library_value.to_string(),
+                        ])
+                        .expect("Can't write row to library comparison CSV file");
+                }
+                println!("Created library_comparison.csv");
+            }
+        } else {
+            eprintln!("Can't read the iTunes Library '{}', it wasn't compared", itunes_library_filename);
+        }
+    }
+
+    // The tracks of every playlist, by DBID, to rebuild them elsewhere
+    let library_tracks = itunesdb::LibraryTracks {
+        songs: &songs_found,
+        podcasts: &podcasts_found,
+        videos: &videos_found,
+        audiobooks: &audiobooks_found,
+        itunesu_items: &itunesu_items_found,
+        ringtones: &ringtones_found,
+        voice_memos: &voice_memos_found,
+    };
+
+    let playlist_items_found = playlist_items::build_playlist_items(
+        &playlists_found,
+        &library_tracks,
+    );
+
+    if !playlist_items_found.is_empty() {
+        if output_format == "json" || output_format == "yaml" {
+            helpers::write_json_file("playlist_items.json", &playlist_items_found);
+            println!("Created playlist_items.json with {} items", playlist_items_found.len());
+        } else {
+            let mut playlist_items_csv_writer = helpers::init_csv_writer("playlist_items.csv");
+
+            playlist_items_csv_writer
+                .write_record([
+                    "Playlist #",
+                    "Persistent ID",
+                    "Playlist Name",
+                    "Position",
+                    "Track ID",
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Artist",
+                ])
+                .expect("Can't create CSV file headers for playlist items file");
+
+            for playlist_item in playlist_items_found.iter() {
+                playlist_items_csv_writer
+                    .write_record(&[
+                        playlist_item.playlist_number.to_string(),
+                        playlist_item.persistent_playlist_id.to_string(),
+                        playlist_item.playlist_name.to_string(),
+                        playlist_item.position.to_string(),
+                        playlist_item.track_id.to_string(),
+                        playlist_item.dbid.to_string(),
+                        playlist_item.media_kind.to_string(),
+                        playlist_item.title.to_string(),
+                        playlist_item.artist.to_string(),
+                    ])
+                    .expect("Can't write row to playlist items CSV file");
+            }
+            println!("Created playlist_items.csv with {} items", playlist_items_found.len());
+        }
+    }
+
+    // The songs rolled up into albums, to see at a glance what the library holds and which albums are incomplete
+    let albums_found = albums::build_albums(&songs_found);
+
+    if !albums_found.is_empty() {
+        if output_format == "json" || output_format == "yaml" {
+            helpers::write_json_file("albums.json", &albums_found);
+            println!("Created albums.json with {} albums", albums_found.len());
+        } else {
+            let mut albums_csv_writer = helpers::init_csv_writer("albums.csv");
+
+            albums_csv_writer
+                .write_record([
+                    "Album Artist",
+                    "Album",
+                    "Year",
+                    "Genre",
+                    "Compilation",
+                    "Tracks",
+                    "Tracks in album",
+                    "Discs",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Size",
+                    "Size (bytes)",
+                    "Play count",
+                    "Has artwork",
+                ])
+                .expect("Can't create CSV file headers for albums file");
+
+            for album in albums_found.iter() {
+                albums_csv_writer
+                    .write_record(&[
+                        album.artist.to_string(),
+                        album.album.to_string(),
+                        album.year.to_string(),
+                        album.genre.to_string(),
+                        album.is_compilation.to_string(),
+                        album.num_tracks.to_string(),
+                        album.num_tracks_in_album.to_string(),
+                        album.num_discs.to_string(),
+                        album.total_duration_friendly.to_string(),
+                        album.total_duration_s.to_string(),
+                        album.total_size_friendly.to_string(),
+                        album.total_size_bytes.to_string(),
+                        album.num_plays.to_string(),
+                        album.has_artwork.to_string(),
+                    ])
+                    .expect("Can't write row to albums CSV file");
+            }
+            println!("Created albums.csv with {} albums", albums_found.len());
+        }
+    }
+
+    // The songs rolled up into artists, with how much they were played and when they were added
+    let artists_found = artists::build_artists(&songs_found);
+
+    if !artists_found.is_empty() {
+        if output_format == "json" || output_format == "yaml" {
+            helpers::write_json_file("artists.json", &artists_found);
+            println!("Created artists.json with {} artists", artists_found.len());
+        } else {
+            let mut artists_csv_writer = helpers::init_csv_writer("artists.csv");
+
+            artists_csv_writer
+                .write_record([
+                    "Artist",
+                    "Tracks",
+                    "Albums",
+                    "Play count",
+                    "Duration",
+                    "Duration (seconds)",
+                    "Size",
+                    "Size (bytes)",
+                    "First added",
+                    "Last added",
+                ])
+                .expect("Can't create CSV file headers for artists file");
+
+            for artist in artists_found.iter() {
+                let first_added = match artist.first_added_at {
+                    Some(first_added_at) => first_added_at.to_string(),
+                    None => "".to_string(),
+                };
+
+                let last_added = match artist.last_added_at {
+                    Some(last_added_at) => last_added_at.to_string(),
+                    None => "".to_string(),
+                };
+
+                artists_csv_writer
+                    .write_record(&[
+                        artist.artist.to_string(),
+                        artist.num_tracks.to_string(),
+                        artist.num_albums.to_string(),
+                        artist.num_plays.to_string(),
+                        artist.total_duration_friendly.to_string(),
+                        artist.total_duration_s.to_string(),
+                        artist.total_size_friendly.to_string(),
+                        artist.total_size_bytes.to_string(),
+                        first_added,
+                        last_added,
+                    ])
+                    .expect("Can't write row to artists CSV file");
+            }
+            println!("Created artists.csv with {} artists", artists_found.len());
+        }
+    }
+
+    // Plays for Last.fm, in the CSV format the bulk scrobbling tools read (whatever the output format)
+    let scrobbles_found = scrobbles::build_scrobbles(&songs_found);
+
+    if !scrobbles_found.is_empty() {
+        // The scrobbling tools only read comma-delimited files, whatever `--delimiter=` is
+        let mut scrobbles_csv_writer = helpers::init_csv_writer_with_format("scrobbles.csv", &helpers::CsvFormat::default());
+
+        // No header row, the tools take the columns by position
+        for scrobble in scrobbles_found.iter() {
+            scrobbles_csv_writer
+                .write_record(&[
+                    scrobble.artist.to_string(),
+                    scrobble.track.to_string(),
+                    scrobble.album.to_string(),
+                    scrobble.played_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    scrobble.album_artist.to_string(),
+                    scrobble.duration_s.to_string(),
+                ])
+                .expect("Can't write row to scrobbles CSV file");
+        }
+        println!("Created scrobbles.csv with {} plays", scrobbles_found.len());
+    }
+
+    let num_unscrobblable_plays = scrobbles::get_num_unscrobblable_plays(&songs_found);
+
+    if num_unscrobblable_plays > 0 {
+        eprintln!(
+            "{} plays of songs with no last played time or artist were left out of scrobbles.csv",
+            num_unscrobblable_plays
+        );
+    }
+
+    // Files in iPod_Control/Music that no track item points to anymore
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let orphaned_files_found =
+            orphaned_files::find_orphaned_files(ipod_mount_point, &referenced_file_locations);
+
+        println!("{} orphaned audio files found", orphaned_files_found.len());
+
+        if !orphaned_files_found.is_empty() {
+            if output_format == "json" || output_format == "yaml" {
+                helpers::write_json_file("orphaned_files.json", &orphaned_files_found);
+                println!("Created orphaned_files.json with {} files", orphaned_files_found.len());
+            } else {
+                let mut orphaned_files_csv_writer = helpers::init_csv_writer("orphaned_files.csv");
+
+                orphaned_files_csv_writer
+                    .write_record([
+                        "Filename",
+                        "Absolute path",
+                        "File extension",
+                        "File size",
+                        "File size (bytes)",
+                    ])
+                    .expect("Can't create CSV file headers for orphaned files file");
+
+                for orphaned_file in orphaned_files_found.iter() {
+                    orphaned_files_csv_writer
+                        .write_record(&[
+                            orphaned_file.filename.to_string(),
+                            orphaned_file.absolute_path.to_string(),
+                            orphaned_file.file_extension.to_string(),
+                            orphaned_file.file_size_friendly.to_string(),
+                            orphaned_file.file_size_bytes.to_string(),
+                        ])
+                        .expect("Can't write row to orphaned files CSV file");
+                }
+                println!("Created orphaned_files.csv with {} files", orphaned_files_found.len());
+            }
+        }
+    }
+
+    // And the other way around, the track files checked earlier
+    if ipod_mount_point.is_some() {
+        if output_format == "json" || output_format == "yaml" {
+            helpers::write_json_file("track_files.json", &track_files_found);
+            println!("Created track_files.json with {} tracks", track_files_found.len());
+        } else {
+            let mut track_files_csv_writer = helpers::init_csv_writer("track_files.csv");
+
+            track_files_csv_writer
+                .write_record([
+                    "DBID",
+                    "Media kind",
+                    "Title",
+                    "Artist",
+                    "Filename",
+                    "Status",
+                    "Size in database (bytes)",
+                    "Size on iPod (bytes)",
+                    "Bitrate (kbps)",
+                    "Duration (ms)",
+                    "File checksum",
+                ])
+                .expect("Can't create CSV file headers for track files file");
+
+            for track_file in track_files_found.iter() {
+                track_files_csv_writer
+                    .write_record(&[
+                        track_file.dbid.to_string(),
+                        track_file.media_kind.to_string(),
+                        track_file.title.to_string(),
+                        track_file.artist.to_string(),
+                        track_file.filename.to_string(),
+                        track_file.file_status.to_string(),
+                        track_file.database_size_bytes.to_string(),
+                        track_file
+                            .on_device_size_bytes
+                            .map_or("".to_string(), |on_device_size_bytes| on_device_size_bytes.to_string()),
+                        track_file.bitrate_kbps.to_string(),
+                        track_file.duration_ms.to_string(),
+                        track_file.file_checksum.clone().unwrap_or_default(),
+                    ])
+                    .expect("Can't write row to track files CSV file");
+            }
+            println!("Created track_files.csv with {} tracks", track_files_found.len());
+        }
+    }
+
+    let track_filenames: HashMap<u32, String> = track_files_found
+        .iter()
+        .filter(|track_file| !track_file.filename.is_empty())
+        .map(|track_file| (track_file.track_id, track_file.filename.to_string()))
+        .collect();
+
+    // The same library, in the format iTunes exports its own, so it can be imported back
+    let itunes_library_plist = itunes_library::build_itunes_library_plist(
+        &library_tracks,
+        &playlists_found,
+        &track_filenames,
+        ipod_mount_point.as_deref(),
+    );
+    helpers::write_output_file("itunes_library.xml", plist_helpers::write_xml_plist(&itunes_library_plist).as_bytes());
+    println!("Created itunes_library.xml with {} playlists", playlists_found.len());
+
+    if output_format == "msgpack" {
+        let parsed_database = itunesdb::ParsedDatabase {
+            schema_version: export_schema_constants::EXPORT_SCHEMA_VERSION,
+            device_info: &ipod_device_info,
+            library_stats: &library_stats,
+            songs: &songs_found,
+            podcasts: &podcasts_found,
+            videos: &videos_found,
+            audiobooks: &audiobooks_found,
+            itunesu_items: &itunesu_items_found,
+            ringtones: &ringtones_found,
+            voice_memos: &voice_memos_found,
+            playlists: &playlists_found,
+            podcast_groups: &podcast_groups_found,
+            artwork: &artwork_found,
+        };
+
+        helpers::write_messagepack_file("itunesdb.msgpack", &parsed_database);
+        println!("Created itunesdb.msgpack with {} songs and {} playlists", songs_found.len(), playlists_found.len());
+    }
+
+    #[cfg(feature = "sqlite")]
+    if output_format == "sqlite" {
+        sqlite_export::write_itunesdb_sqlite(
+            "itunesdb.sqlite",
+            &songs_found,
+            &podcasts_found,
+            &videos_found,
+            &audiobooks_found,
+            &itunesu_items_found,
+            &ringtones_found,
+            &voice_memos_found,
+            &playlists_found,
+            &track_filenames,
+        );
+
+        // SQLite needs a plain file to write to, so it's compressed once it's done
+        helpers::compress_output_file("itunesdb.sqlite");
+    }
+
+    #[cfg(feature = "arrow")]
+    if output_format == "feather" {
+        let tracks_record_batch = arrow_export::build_tracks_record_batch(
+            &songs_found,
+            &podcasts_found,
+            &videos_found,
+            &audiobooks_found,
+            &itunesu_items_found,
+            &ringtones_found,
+            &voice_memos_found,
+            &track_filenames,
+        );
+
+        arrow_export::write_feather_file("tracks.feather", &tracks_record_batch);
+        println!("Created tracks.feather with {} tracks", tracks_record_batch.num_rows());
+    }
+
+    #[cfg(feature = "xlsx")]
+    if output_format == "xlsx" {
+        xlsx_export::write_itunesdb_workbook("itunesdb.xlsx", &songs_found, &podcasts_found, &playlists_found, &ipod_device_info);
+        println!("Created itunesdb.xlsx with {} songs and {} podcasts", songs_found.len(), podcasts_found.len());
+    }
+
+    let library_summary = library_report::build_library_summary(
+        &library_tracks,
+        &playlists_found,
+        &ipod_device_info,
+    );
+
+    // Something to browse the library with, without any other tools
+    helpers::write_output_file(
+        "itunesdb_report.html",
+        library_report::build_html_report(&library_summary, &songs_found, &podcasts_found, &playlists_found).as_bytes(),
+    );
+    println!("Created itunesdb_report.html");
+
+    // The menu tree is nested, so it only makes sense as JSON
+    let device_menu_snapshot = device_menu::build_device_menu_snapshot(
+        &songs_found,
+        &podcasts_found,
+        &playlists_found,
+        &podcast_groups_found,
+        &smart_playlist_library,
+    );
+    helpers::write_json_file("device_menu.json", &device_menu_snapshot);
+    println!(
+        "Created device_menu.json with {} playlists and {} podcasts",
+        device_menu_snapshot.playlists.len(),
+        device_menu_snapshot.podcasts.len()
+    );
+
+    // Written last, so it has everything that went into the parse report
+    helpers::write_output_file(
+        "itunesdb_summary.md",
+        library_report::build_markdown_summary(&library_summary, &ipod_device_info, &parse_report).as_bytes(),
+    );
+    println!("Created itunesdb_summary.md");
+
+    if !parse_report.is_empty() {
+        parse_report.print_summary();
+
+        helpers::write_json_file("parse_report.json", &parse_report);
+        println!("Created parse_report.json with {} entries", parse_report.entries.len());
     }
 }
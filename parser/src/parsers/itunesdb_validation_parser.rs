@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use crate::helpers::helpers;
+use crate::itunesdb_validation;
+
+/// Checks an iTunesDB's structure the way libgpod reads it, rather than parsing its contents.
+/// See `itunesdb_validation::validate_itunesdb` for what's checked
+pub fn validate_itunesdb_file(itunesdb_file_as_bytes: Vec<u8>, output_format: String) {
+    let validation_issues = itunesdb_validation::validate_itunesdb(&itunesdb_file_as_bytes);
+
+    if validation_issues.is_empty() {
+        println!("No structural problems found, libgpod should read this database as it is");
+        return;
+    }
+
+    let mut num_issues_per_category: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for validation_issue in validation_issues.iter() {
+        *num_issues_per_category
+            .entry(validation_issue.category.as_str())
+            .or_insert(0) += 1;
+    }
+
+    for (category, num_issues) in num_issues_per_category.iter() {
+        println!("Validation: {} x '{}'", num_issues, category);
+    }
+
+    if output_format == "json" {
+        helpers::write_json_file("itunesdb_validation.json", &validation_issues);
+        println!("Created itunesdb_validation.json with {} issues", validation_issues.len());
+    } else {
+        let mut validation_csv_writer = helpers::init_csv_writer("itunesdb_validation.csv");
+
+        validation_csv_writer
+            .write_record(["Offset", "Chunk", "Category", "Message"])
+            .expect("Can't create CSV file headers for validation file");
+
+        for validation_issue in validation_issues.iter() {
+            validation_csv_writer
+                .write_record(&[
+                    format!("{:#X}", validation_issue.offset),
+                    validation_issue.chunk.to_string(),
+                    validation_issue.category.to_string(),
+                    validation_issue.message.to_string(),
+                ])
+                .expect("Can't write row to validation CSV file");
+        }
+
+        println!("Created itunesdb_validation.csv with {} issues", validation_issues.len());
+    }
+}
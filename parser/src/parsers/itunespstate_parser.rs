@@ -0,0 +1,38 @@
+use crate::constants::itunespstate_constants;
+use crate::constants::itunessd_constants;
+
+use crate::helpers::helpers;
+use crate::ipod_device_info;
+
+/// Returns `None` if the file is too short. The song's filename is left for the caller to fill in,
+/// since it has to come from the iTunesSD
+pub fn read_playback_state(itunespstate_file_as_bytes: &[u8]) -> Option<ipod_device_info::PlaybackState> {
+
+    if itunespstate_file_as_bytes.len() < itunespstate_constants::ITUNESPSTATE_FILE_LENGTH {
+        eprintln!("iTunesPState file is too short ({} bytes, expected {})", itunespstate_file_as_bytes.len(), itunespstate_constants::ITUNESPSTATE_FILE_LENGTH);
+        return None;
+    }
+
+    return Some(ipod_device_info::PlaybackState {
+        volume_raw: helpers::get_slice_as_le_u32(0, itunespstate_file_as_bytes, itunespstate_constants::ITUNESPSTATE_VOLUME_OFFSET, itunespstate_constants::ITUNESPSTATE_VOLUME_LEN),
+        current_track_index: helpers::get_slice_as_le_u32(0, itunespstate_file_as_bytes, itunespstate_constants::ITUNESPSTATE_TRACK_INDEX_OFFSET, itunespstate_constants::ITUNESPSTATE_TRACK_INDEX_LEN),
+        current_song_filename: None,
+        playback_position_ms: helpers::get_slice_as_le_u32(0, itunespstate_file_as_bytes, itunespstate_constants::ITUNESPSTATE_TRACK_POSITION_OFFSET, itunespstate_constants::ITUNESPSTATE_TRACK_POSITION_LEN) * itunessd_constants::ITUNESSD_TIME_UNIT_MS,
+        is_shuffle_enabled: helpers::get_slice_as_le_u32(0, itunespstate_file_as_bytes, itunespstate_constants::ITUNESPSTATE_SHUFFLE_FLAG_OFFSET, itunespstate_constants::ITUNESPSTATE_SHUFFLE_FLAG_LEN) != 0,
+        shuffle_position: helpers::get_slice_as_le_u32(0, itunespstate_file_as_bytes, itunespstate_constants::ITUNESPSTATE_SHUFFLE_POSITION_OFFSET, itunespstate_constants::ITUNESPSTATE_SHUFFLE_POSITION_LEN),
+    });
+}
+
+pub fn parse_itunespstate_file(itunespstate_file_as_bytes: Vec<u8>) {
+
+    let playback_state = match read_playback_state(&itunespstate_file_as_bytes) {
+        Some(playback_state) => playback_state,
+        None => return,
+    };
+
+    println!("===========");
+    println!("Volume: {}", playback_state.volume_raw);
+    println!("Current song # (in the iTunesSD): {}", playback_state.current_track_index);
+    println!("Playback position: {} ms", playback_state.playback_position_ms);
+    println!("Shuffle enabled: {} (position {})", playback_state.is_shuffle_enabled, playback_state.shuffle_position);
+}
@@ -1,8 +1,29 @@
+use crate::constants::deviceinfo_constants;
+use crate::constants::itunespstate_constants;
 use crate::constants::itunessd_constants;
+use crate::constants::itunesstats_constants;
 use crate::helpers::helpers;
+use crate::ipod_device_info;
+use crate::itunesdb;
 use crate::itunessd;
+use crate::parsers::deviceinfo_parser;
+use crate::parsers::itunespstate_parser;
+use crate::parsers::itunesstats_parser;
+use crate::playcounts;
+
+/// The iPod shuffle doesn't read the iTunesDB, only the much simpler iTunesSD.
+/// It only has the file location and playback settings of each song, no titles or artists.
+/// The 3rd and 4th gen shuffles use a newer layout (version 3), see `parse_itunessd_v3_file`
+pub fn parse_itunessd_file(
+    itunessd_file_as_bytes: Vec<u8>,
+    output_format: String,
+    ipod_mount_point: Option<String>,
+) {
+    if itunessd_file_as_bytes.starts_with(itunessd_constants::ITUNESSD_V3_HEADER_KEY.as_bytes()) {
+        parse_itunessd_v3_file(itunessd_file_as_bytes, output_format, ipod_mount_point);
+        return;
+    }
 
-pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
     let num_songs = helpers::build_be_u32_from_bytes(&helpers::get_slice_from_offset_with_len(
         0,
         &itunessd_file_as_bytes,
@@ -29,9 +50,11 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
 
     println!("==========");
 
+    let mut songs_found: Vec<itunesdb::Song> = Vec::new();
+
     let mut file_idx: usize = itunessd_header_size as usize;
 
-    while file_idx < itunessd_file_as_bytes.len() - itunessd_constants::ITUNESSD_ENTRY_SIZE {
+    while file_idx + itunessd_constants::ITUNESSD_ENTRY_SIZE <= itunessd_file_as_bytes.len() {
         // Now parse the individual song entries... start by checking that the size of the entry object matches the known value
 
         let entry_size =
@@ -46,6 +69,12 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
             panic!("Invalid iTunesSD entry size value of '{}'", entry_size);
         }
 
+        let mut curr_song = itunesdb::Song {
+            // There's no Track List here, so this is the position of the entry in the iTunesSD
+            track_index: songs_found.len() as u32,
+            ..Default::default()
+        };
+
         let start_time =
             helpers::build_be_u32_from_bytes(&helpers::get_slice_from_offset_with_len(
                 file_idx,
@@ -54,9 +83,7 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
                 itunessd_constants::ITUNESSD_START_TIME_LEN,
             ));
 
-        if start_time != 0 {
-            println!("Start Time: {}", start_time);
-        }
+        curr_song.start_time_ms = start_time * itunessd_constants::ITUNESSD_TIME_UNIT_MS;
 
         let stop_time = helpers::build_be_u32_from_bytes(&helpers::get_slice_from_offset_with_len(
             file_idx,
@@ -65,9 +92,7 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
             itunessd_constants::ITUNESSD_STOP_TIME_LEN,
         ));
 
-        if stop_time != 0 {
-            println!("Stop Time: {}", stop_time);
-        }
+        curr_song.stop_time_ms = stop_time * itunessd_constants::ITUNESSD_TIME_UNIT_MS;
 
         let volume_raw =
             helpers::build_be_u32_from_bytes(&helpers::get_slice_from_offset_with_len(
@@ -77,6 +102,8 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
                 itunessd_constants::ITUNESSD_VOLUME_LEN,
             ));
 
+        curr_song.volume_adjustment = itunessd::decode_itunessd_volume_adjustment(volume_raw);
+
         let file_type_raw =
             helpers::build_be_u32_from_bytes(&helpers::get_slice_from_offset_with_len(
                 file_idx,
@@ -85,10 +112,10 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
                 itunessd_constants::ITUNESSD_FILE_TYPE_LEN,
             ));
 
-        println!(
-            "File Type: {}",
-            itunessd::decode_itunessd_file_type(file_type_raw)
-        );
+        let file_type = itunessd::decode_itunessd_file_type(file_type_raw);
+
+        curr_song.codec = itunessd::decode_itunessd_codec(&file_type);
+        curr_song.is_lossless = matches!(file_type, itunessd::iTunesSDFileType::WAV);
 
         let song_filename = String::from_utf16(&helpers::return_utf16_from_utf8(
             &helpers::get_slice_from_offset_with_len(
@@ -104,13 +131,781 @@ pub fn parse_itunessd_file(itunessd_file_as_bytes: Vec<u8>) {
         // which look like: "/iPod_Control/Music/F00/XZYL.m4a\0\0\0\0\0\"
         // so we need to trim that before printing it
         // https://stackoverflow.com/questions/49406517/how-to-remove-trailing-null-characters-from-string
-        println!(
-            "Song Filename: {:?}",
-            song_filename.trim_matches(char::from(0))
+        set_itunessd_song_filename(
+            &mut curr_song,
+            song_filename.trim_matches(char::from(0)),
+            &ipod_mount_point,
         );
 
-        println!("----------");
+        let shuffle_flag = helpers::get_slice_from_offset_with_len(
+            file_idx,
+            &itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_SHUFFLE_FLAG_OFFSET,
+            itunessd_constants::ITUNESSD_SHUFFLE_FLAG_LEN,
+        )[0];
+
+        curr_song.skip_when_shuffling = shuffle_flag == 0;
+
+        let bookmark_flag = helpers::get_slice_from_offset_with_len(
+            file_idx,
+            &itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_BOOKMARK_FLAG_OFFSET,
+            itunessd_constants::ITUNESSD_BOOKMARK_FLAG_LEN,
+        )[0];
+
+        curr_song.remember_playback_position = bookmark_flag != 0;
+
+        println!("Song Filename: {:?} ({})", curr_song.song_filename, file_type);
+
+        songs_found.push(curr_song);
 
         file_idx += itunessd_constants::ITUNESSD_ENTRY_SIZE;
     }
+
+    if songs_found.len() != num_songs as usize {
+        eprintln!(
+            "iTunesSD header says there are {} songs, but {} were found",
+            num_songs,
+            songs_found.len()
+        );
+    }
+
+    // What was played on the shuffle since the last sync is only in the iTunesStats file
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let itunesstats_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join(itunesstats_constants::ITUNESSTATS_FILENAME);
+
+        if let Ok(itunesstats_file_as_bytes) = std::fs::read(&itunesstats_file_path) {
+            let itunesstats_entries =
+                itunesstats_parser::read_itunesstats_entries(&itunesstats_file_as_bytes);
+
+            // The entries are matched by position, so they're useless if the counts differ
+            if itunesstats_entries.len() == songs_found.len() {
+                let num_songs_merged =
+                    playcounts::merge_playcounts_into_songs(&itunesstats_entries, &mut songs_found);
+
+                println!("Merged iTunesStats into {} songs", num_songs_merged);
+            } else {
+                eprintln!(
+                    "iTunesStats not merged: it has {} entries, but the iTunesSD has {} songs",
+                    itunesstats_entries.len(),
+                    songs_found.len()
+                );
+            }
+        }
+    }
+
+    // The shuffle saves what it was playing in the iTunesPState file
+    let mut ipod_device_info = ipod_device_info::IpodDeviceInfo::default();
+
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let itunespstate_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join(itunespstate_constants::ITUNESPSTATE_FILENAME);
+
+        if let Ok(itunespstate_file_as_bytes) = std::fs::read(&itunespstate_file_path) {
+            ipod_device_info.playback_state =
+                itunespstate_parser::read_playback_state(&itunespstate_file_as_bytes);
+        }
+
+        let deviceinfo_file_path = itunespstate_file_path.with_file_name(deviceinfo_constants::DEVICEINFO_FILENAME);
+
+        if let Ok(deviceinfo_file_as_bytes) = std::fs::read(&deviceinfo_file_path) {
+            deviceinfo_parser::read_device_info(&deviceinfo_file_as_bytes, &mut ipod_device_info);
+        }
+    }
+
+    if let Some(playback_state) = ipod_device_info.playback_state.as_mut() {
+        playback_state.current_song_filename = songs_found
+            .get(playback_state.current_track_index as usize)
+            .map(|song| song.song_filename.to_string());
+
+        // The shuffle switch only shuffles by song
+        ipod_device_info.playback_settings = Some(ipod_device_info::PlaybackSettings {
+            shuffle_mode: Some(if playback_state.is_shuffle_enabled { "Songs" } else { "Off" }.to_string()),
+            ..Default::default()
+        });
+    }
+
+    println!("==========");
+    println!("{} songs found", songs_found.len());
+
+    write_itunessd_songs(&songs_found, &output_format);
+
+    write_itunessd_device_info(&ipod_device_info, &output_format);
+}
+
+/// Every chunk is found through an offset stored in the one before it:
+/// the header points to the track and playlist headers, which point to each track/playlist.
+/// None if the file is too short for the header
+fn read_itunessd_v3_file(
+    itunessd_file_as_bytes: &[u8],
+    ipod_mount_point: &Option<String>,
+) -> Option<(ipod_device_info::IpodDeviceInfo, Vec<itunesdb::Song>, Vec<itunesdb::Playlist>)> {
+    if itunessd_file_as_bytes.len() < itunessd_constants::ITUNESSD_V3_HEADER_MIN_LEN {
+        eprintln!(
+            "iTunesSD (version 3) file is truncated, its header needs {} bytes but the file has {}",
+            itunessd_constants::ITUNESSD_V3_HEADER_MIN_LEN,
+            itunessd_file_as_bytes.len()
+        );
+        return None;
+    }
+
+    let num_tracks = helpers::get_slice_as_le_u32(
+        0,
+        itunessd_file_as_bytes,
+        itunessd_constants::ITUNESSD_V3_NUM_TRACKS_OFFSET,
+        itunessd_constants::ITUNESSD_V3_NUM_TRACKS_LEN,
+    );
+
+    let num_playlists = helpers::get_slice_as_le_u32(
+        0,
+        itunessd_file_as_bytes,
+        itunessd_constants::ITUNESSD_V3_NUM_PLAYLISTS_OFFSET,
+        itunessd_constants::ITUNESSD_V3_NUM_PLAYLISTS_LEN,
+    );
+
+    println!(
+        "iTunesSD (version 3) file has {} songs and {} playlists",
+        num_tracks, num_playlists
+    );
+
+    let mut ipod_device_info = ipod_device_info::IpodDeviceInfo::default();
+
+    ipod_device_info.playback_settings = Some(ipod_device_info::PlaybackSettings {
+        max_volume_raw: Some(itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_MAX_VOLUME_OFFSET]),
+        is_voiceover_enabled: Some(itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_VOICEOVER_ENABLED_OFFSET] != 0),
+        ..Default::default()
+    });
+
+    println!(
+        "Max volume: {}, VoiceOver enabled: {}",
+        itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_MAX_VOLUME_OFFSET],
+        itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_VOICEOVER_ENABLED_OFFSET] != 0
+    );
+
+    // Its iTunesPState isn't laid out like the older shuffles', so only DeviceInfo is read
+    if let Some(ipod_mount_point) = ipod_mount_point.as_ref() {
+        let deviceinfo_file_path = std::path::Path::new(ipod_mount_point)
+            .join("iPod_Control")
+            .join("iTunes")
+            .join(deviceinfo_constants::DEVICEINFO_FILENAME);
+
+        if let Ok(deviceinfo_file_as_bytes) = std::fs::read(&deviceinfo_file_path) {
+            deviceinfo_parser::read_device_info(&deviceinfo_file_as_bytes, &mut ipod_device_info);
+        }
+    }
+
+    println!("==========");
+
+    let track_header_idx = helpers::get_slice_as_le_u32(
+        0,
+        itunessd_file_as_bytes,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_OFFSET_OFFSET,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_OFFSET_LEN,
+    ) as usize;
+
+    let track_offsets = read_itunessd_v3_chunk_offsets(
+        itunessd_file_as_bytes,
+        track_header_idx,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_KEY,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_NUM_TRACKS_OFFSET,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_NUM_TRACKS_LEN,
+        itunessd_constants::ITUNESSD_V3_TRACK_HEADER_OFFSETS_OFFSET,
+    );
+
+    let mut songs_found: Vec<itunesdb::Song> = Vec::new();
+
+    for track_idx in track_offsets {
+        if !is_itunessd_v3_chunk_at(
+            itunessd_file_as_bytes,
+            track_idx,
+            itunessd_constants::ITUNESSD_V3_TRACK_KEY,
+            itunessd_constants::ITUNESSD_V3_TRACK_LEN,
+        ) {
+            eprintln!("No iTunesSD track found at offset {}, skipping it", track_idx);
+            continue;
+        }
+
+        let mut curr_song = itunesdb::Song {
+            // Playlists refer to tracks by their position in the track list
+            track_index: songs_found.len() as u32,
+            ..Default::default()
+        };
+
+        curr_song.start_time_ms = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_START_TIME_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_START_TIME_LEN,
+        );
+
+        curr_song.stop_time_ms = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_STOP_TIME_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_STOP_TIME_LEN,
+        );
+
+        // Signed this time, same as in the iTunesDB
+        curr_song.volume_adjustment = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_VOLUME_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_VOLUME_LEN,
+        ) as i32;
+
+        let file_type = itunessd::decode_itunessd_file_type(helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_FILE_TYPE_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_FILE_TYPE_LEN,
+        ));
+
+        curr_song.codec = itunessd::decode_itunessd_codec(&file_type);
+        curr_song.is_lossless = matches!(file_type, itunessd::iTunesSDFileType::WAV);
+
+        let song_filename = String::from_utf8_lossy(&helpers::get_slice_from_offset_with_len(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_FILENAME_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_FILENAME_LEN,
+        ))
+        .to_string();
+
+        set_itunessd_song_filename(
+            &mut curr_song,
+            song_filename.trim_matches(char::from(0)),
+            ipod_mount_point,
+        );
+
+        curr_song.resume_position_ms = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_BOOKMARK_TIME_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_BOOKMARK_TIME_LEN,
+        );
+
+        curr_song.skip_when_shuffling = itunessd_file_as_bytes
+            [track_idx + itunessd_constants::ITUNESSD_V3_TRACK_DONT_SKIP_OFFSET]
+            == 0;
+
+        curr_song.remember_playback_position = itunessd_file_as_bytes
+            [track_idx + itunessd_constants::ITUNESSD_V3_TRACK_REMEMBER_POSITION_OFFSET]
+            != 0;
+
+        curr_song.pregap_samples = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_PREGAP_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_PREGAP_LEN,
+        );
+
+        curr_song.postgap_samples = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_POSTGAP_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_POSTGAP_LEN,
+        );
+
+        curr_song.num_samples = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_NUM_SAMPLES_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_NUM_SAMPLES_LEN,
+        ) as u64;
+
+        curr_song.track_number = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_TRACK_NUMBER_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_TRACK_NUMBER_LEN,
+        );
+
+        curr_song.disc_number = helpers::get_slice_as_le_u32(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_DISC_NUMBER_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_DISC_NUMBER_LEN,
+        );
+
+        curr_song.dbid = helpers::get_slice_as_le_u64(
+            track_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_TRACK_DBID_OFFSET,
+            itunessd_constants::ITUNESSD_V3_TRACK_DBID_LEN,
+        );
+
+        println!("Song Filename: {:?} ({})", curr_song.song_filename, file_type);
+
+        songs_found.push(curr_song);
+    }
+
+    if songs_found.len() != num_tracks as usize {
+        eprintln!(
+            "iTunesSD header says there are {} songs, but {} were found",
+            num_tracks,
+            songs_found.len()
+        );
+    }
+
+    let playlist_header_idx = helpers::get_slice_as_le_u32(
+        0,
+        itunessd_file_as_bytes,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_OFFSET_OFFSET,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_OFFSET_LEN,
+    ) as usize;
+
+    let playlist_offsets = read_itunessd_v3_chunk_offsets(
+        itunessd_file_as_bytes,
+        playlist_header_idx,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_KEY,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_NUM_PLAYLISTS_OFFSET,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_NUM_PLAYLISTS_LEN,
+        itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_OFFSETS_OFFSET,
+    );
+
+    let mut playlists_found: Vec<itunesdb::Playlist> = Vec::new();
+
+    for playlist_idx in playlist_offsets {
+        if !is_itunessd_v3_chunk_at(
+            itunessd_file_as_bytes,
+            playlist_idx,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_KEY,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDICES_OFFSET,
+        ) {
+            eprintln!("No iTunesSD playlist found at offset {}, skipping it", playlist_idx);
+            continue;
+        }
+
+        let mut curr_playlist = itunesdb::Playlist {
+            persistent_playlist_id: helpers::get_slice_as_le_u64(
+                playlist_idx,
+                itunessd_file_as_bytes,
+                itunessd_constants::ITUNESSD_V3_PLAYLIST_ID_OFFSET,
+                itunessd_constants::ITUNESSD_V3_PLAYLIST_ID_LEN,
+            ),
+            ..Default::default()
+        };
+
+        let playlist_type_raw = helpers::get_slice_as_le_u32(
+            playlist_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_TYPE_OFFSET,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_TYPE_LEN,
+        );
+
+        // The names aren't stored, the shuffle reads them out with VoiceOver
+        // from a separate sound file per playlist ID
+        curr_playlist.playlist_kind = itunessd::decode_itunessd_playlist_type(playlist_type_raw);
+        curr_playlist.is_master_playlist = curr_playlist.playlist_kind == itunesdb::PlaylistKind::Master;
+        curr_playlist.is_podcast_playlist = curr_playlist.playlist_kind == itunesdb::PlaylistKind::Podcasts;
+
+        let num_playlist_tracks = helpers::get_slice_as_le_u32(
+            playlist_idx,
+            itunessd_file_as_bytes,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_NUM_TRACKS_OFFSET,
+            itunessd_constants::ITUNESSD_V3_PLAYLIST_NUM_TRACKS_LEN,
+        ) as usize;
+
+        for playlist_track_num in 0..num_playlist_tracks {
+            let track_index_offset = itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDICES_OFFSET
+                + (playlist_track_num * itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDEX_LEN);
+
+            if playlist_idx + track_index_offset + itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDEX_LEN
+                > itunessd_file_as_bytes.len()
+            {
+                eprintln!("iTunesSD playlist at offset {} is truncated", playlist_idx);
+                break;
+            }
+
+            curr_playlist.stored_track_ids.push(helpers::get_slice_as_le_u32(
+                playlist_idx,
+                itunessd_file_as_bytes,
+                track_index_offset,
+                itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDEX_LEN,
+            ));
+        }
+
+        // There are no track IDs in the iTunesSD, so these are indices into the track list
+        // (i.e. the songs' `track_index`)
+        curr_playlist.track_ids = curr_playlist.stored_track_ids.clone();
+        curr_playlist.membership_source = "iTunesSD track indices".to_string();
+
+        playlists_found.push(curr_playlist);
+    }
+
+    println!("==========");
+    println!("{} songs found", songs_found.len());
+    println!("{} playlists found", playlists_found.len());
+
+    return Some((ipod_device_info, songs_found, playlists_found));
+}
+
+fn parse_itunessd_v3_file(
+    itunessd_file_as_bytes: Vec<u8>,
+    output_format: String,
+    ipod_mount_point: Option<String>,
+) {
+    let (ipod_device_info, songs_found, playlists_found) =
+        match read_itunessd_v3_file(&itunessd_file_as_bytes, &ipod_mount_point) {
+            Some(itunessd_contents) => itunessd_contents,
+            None => return,
+        };
+
+    write_itunessd_songs(&songs_found, &output_format);
+
+    write_itunessd_device_info(&ipod_device_info, &output_format);
+
+    if playlists_found.is_empty() {
+        return;
+    }
+
+    if output_format == "json" {
+        helpers::write_json_file("playlists.json", &playlists_found);
+    } else {
+        let mut playlist_csv_writer = helpers::init_csv_writer("playlists.csv");
+
+        playlist_csv_writer
+            .write_record([
+                "Persistent ID",
+                "Master playlist",
+                "Podcast playlist",
+                "Playlist kind",
+                "Track count",
+                "Membership source",
+                "Track indices",
+            ])
+            .expect("Can't create CSV file headers for playlist file");
+
+        for playlist in playlists_found.iter() {
+            let track_indices: Vec<String> =
+                playlist.track_ids.iter().map(|id| id.to_string()).collect();
+
+            playlist_csv_writer
+                .write_record(&[
+                    playlist.persistent_playlist_id.to_string(),
+                    playlist.is_master_playlist.to_string(),
+                    playlist.is_podcast_playlist.to_string(),
+                    playlist.playlist_kind.to_string(),
+                    playlist.track_ids.len().to_string(),
+                    playlist.membership_source.to_string(),
+                    track_indices.join(" "),
+                ])
+                .expect("Can't write row to playlist CSV file");
+        }
+    }
+
+    println!(
+        "Created playlists.{} with {} playlists",
+        output_format,
+        playlists_found.len()
+    );
+}
+
+/// Checks that the chunk at `chunk_idx` starts with `chunk_key` and fits in the file
+fn is_itunessd_v3_chunk_at(
+    itunessd_file_as_bytes: &[u8],
+    chunk_idx: usize,
+    chunk_key: &str,
+    chunk_len: usize,
+) -> bool {
+    return (chunk_idx + chunk_len <= itunessd_file_as_bytes.len())
+        && itunessd_file_as_bytes[chunk_idx..].starts_with(chunk_key.as_bytes());
+}
+
+/// The track and playlist headers both have the number of their chunks, followed by the offset of each
+fn read_itunessd_v3_chunk_offsets(
+    itunessd_file_as_bytes: &[u8],
+    chunk_header_idx: usize,
+    chunk_header_key: &str,
+    num_chunks_offset: usize,
+    num_chunks_len: usize,
+    chunk_offsets_offset: usize,
+) -> Vec<usize> {
+    let mut chunk_offsets: Vec<usize> = Vec::new();
+
+    if !is_itunessd_v3_chunk_at(
+        itunessd_file_as_bytes,
+        chunk_header_idx,
+        chunk_header_key,
+        chunk_offsets_offset,
+    ) {
+        eprintln!(
+            "No '{}' header found at offset {} of the iTunesSD",
+            chunk_header_key, chunk_header_idx
+        );
+        return chunk_offsets;
+    }
+
+    let num_chunks = helpers::get_slice_as_le_u32(
+        chunk_header_idx,
+        itunessd_file_as_bytes,
+        num_chunks_offset,
+        num_chunks_len,
+    ) as usize;
+
+    for chunk_num in 0..num_chunks {
+        let chunk_offset_idx = chunk_header_idx
+            + chunk_offsets_offset
+            + (chunk_num * itunessd_constants::ITUNESSD_V3_CHUNK_OFFSET_LEN);
+
+        if chunk_offset_idx + itunessd_constants::ITUNESSD_V3_CHUNK_OFFSET_LEN
+            > itunessd_file_as_bytes.len()
+        {
+            eprintln!("iTunesSD '{}' header is truncated", chunk_header_key);
+            break;
+        }
+
+        chunk_offsets.push(helpers::get_slice_as_le_u32(
+            chunk_offset_idx,
+            itunessd_file_as_bytes,
+            0,
+            itunessd_constants::ITUNESSD_V3_CHUNK_OFFSET_LEN,
+        ) as usize);
+    }
+
+    return chunk_offsets;
+}
+
+/// The iTunesSD stores paths Unix-style already, e.g. "/iPod_Control/Music/F00/XZYL.m4a"
+fn set_itunessd_song_filename(
+    song: &mut itunesdb::Song,
+    song_filename_raw: &str,
+    ipod_mount_point: &Option<String>,
+) {
+    song.song_filename_raw = song_filename_raw.to_string();
+    song.song_filename = song_filename_raw.trim_start_matches('/').to_string();
+
+    song.file_extension = std::path::Path::new(&song.song_filename)
+        .extension()
+        .map(|file_extension| file_extension.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+
+    if let Some(ipod_mount_point) = ipod_mount_point {
+        song.set_song_absolute_path(ipod_mount_point);
+    }
+}
+
+/// Written if there's anything to write: the shuffle doesn't always have a DeviceInfo or iTunesPState
+fn write_itunessd_device_info(ipod_device_info: &ipod_device_info::IpodDeviceInfo, output_format: &str) {
+    if ipod_device_info.playback_state.is_some()
+        || ipod_device_info.playback_settings.is_some()
+        || !ipod_device_info.ipod_name.is_empty()
+    {
+        if output_format == "json" {
+            helpers::write_json_file("device_info.json", ipod_device_info);
+            println!("Created device_info.json");
+        } else {
+            let mut device_info_csv_writer = helpers::init_csv_writer("device_info.csv");
+
+            device_info_csv_writer
+                .write_record([
+                    "iPod name",
+                    "User name",
+                    "Host computer",
+                    "Shuffle mode",
+                    "Max volume",
+                    "VoiceOver enabled",
+                    "Volume",
+                    "Current song #",
+                    "Current song",
+                    "Playback position (ms)",
+                    "Shuffle enabled",
+                    "Shuffle position",
+                ])
+                .expect("Can't create CSV file headers for device info file");
+
+            // Left empty if there's no iTunesPState, or they're not in this iTunesSD version
+            let playback_state = ipod_device_info.playback_state.as_ref();
+            let playback_settings = ipod_device_info.playback_settings.as_ref();
+
+            device_info_csv_writer
+                .write_record(&[
+                    ipod_device_info.ipod_name.to_string(),
+                    ipod_device_info.user_name.to_string(),
+                    ipod_device_info.host_computer_name.to_string(),
+                    playback_settings.and_then(|playback_settings| playback_settings.shuffle_mode.clone()).unwrap_or_default(),
+                    playback_settings
+                        .and_then(|playback_settings| playback_settings.max_volume_raw)
+                        .map(|max_volume_raw| max_volume_raw.to_string())
+                        .unwrap_or_default(),
+                    playback_settings
+                        .and_then(|playback_settings| playback_settings.is_voiceover_enabled)
+                        .map(|is_voiceover_enabled| is_voiceover_enabled.to_string())
+                        .unwrap_or_default(),
+                    playback_state.map(|playback_state| playback_state.volume_raw.to_string()).unwrap_or_default(),
+                    playback_state.map(|playback_state| playback_state.current_track_index.to_string()).unwrap_or_default(),
+                    playback_state.and_then(|playback_state| playback_state.current_song_filename.clone()).unwrap_or_default(),
+                    playback_state.map(|playback_state| playback_state.playback_position_ms.to_string()).unwrap_or_default(),
+                    playback_state.map(|playback_state| playback_state.is_shuffle_enabled.to_string()).unwrap_or_default(),
+                    playback_state.map(|playback_state| playback_state.shuffle_position.to_string()).unwrap_or_default(),
+                ])
+                .expect("Can't write row to device info CSV file");
+
+            println!("Created device_info.csv");
+        }
+    }
+}
+
+/// Same layout for every iTunesSD version; older versions just leave more of it empty
+fn write_itunessd_songs(songs_found: &[itunesdb::Song], output_format: &str) {
+    if output_format == "json" {
+        helpers::write_json_file("music.json", &songs_found);
+
+        println!("Created music.json with {} songs", songs_found.len());
+        return;
+    }
+
+    let mut music_csv_writer = helpers::init_csv_writer("music.csv");
+
+    music_csv_writer
+        .write_record([
+            "DBID",
+            "File path",
+            "File extension",
+            "Codec",
+            "Lossless",
+            "Track number",
+            "Disc number",
+            "Start time (ms)",
+            "Stop time (ms)",
+            "Volume adjustment",
+            "Skip when shuffling",
+            "Remember playback position",
+            "Resume position (ms)",
+            "Play count",
+            "Skip count",
+            "Absolute path",
+        ])
+        .expect("Can't create CSV file headers for music file");
+
+    for song in songs_found.iter() {
+        music_csv_writer
+            .write_record(&[
+                song.dbid.to_string(),
+                song.song_filename.to_string(),
+                song.file_extension.to_string(),
+                song.codec.to_string(),
+                song.is_lossless.to_string(),
+                song.track_number.to_string(),
+                song.disc_number.to_string(),
+                song.start_time_ms.to_string(),
+                song.stop_time_ms.to_string(),
+                song.volume_adjustment.to_string(),
+                song.skip_when_shuffling.to_string(),
+                song.remember_playback_position.to_string(),
+                song.resume_position_ms.to_string(),
+                song.num_plays.to_string(),
+                song.num_skips.to_string(),
+                song.song_absolute_path.clone().unwrap_or_default(),
+            ])
+            .expect("Can't write row to CSV");
+    }
+
+    println!("Created music.csv with {} songs", songs_found.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACK_HEADER_IDX: usize = 64;
+    const TRACK_IDX: usize = TRACK_HEADER_IDX + 24;
+    const PLAYLIST_HEADER_IDX: usize = TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_LEN;
+    const PLAYLIST_IDX: usize = PLAYLIST_HEADER_IDX + 24;
+
+    fn write_le_u32(itunessd_file_as_bytes: &mut [u8], field_idx: usize, field_value: u32) {
+        itunessd_file_as_bytes[field_idx..field_idx + 4].copy_from_slice(&field_value.to_le_bytes());
+    }
+
+    /// One MP3 in one master playlist: bdhs, then hths with its rths, then hphs with its lphs
+    fn build_test_itunessd() -> Vec<u8> {
+        let mut itunessd_file_as_bytes: Vec<u8> = vec![0; PLAYLIST_IDX + itunessd_constants::ITUNESSD_V3_PLAYLIST_TRACK_INDICES_OFFSET + 4];
+
+        itunessd_file_as_bytes[0..4].copy_from_slice(itunessd_constants::ITUNESSD_V3_HEADER_KEY.as_bytes());
+        write_le_u32(&mut itunessd_file_as_bytes, itunessd_constants::ITUNESSD_V3_NUM_TRACKS_OFFSET, 1);
+        write_le_u32(&mut itunessd_file_as_bytes, itunessd_constants::ITUNESSD_V3_NUM_PLAYLISTS_OFFSET, 1);
+        itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_MAX_VOLUME_OFFSET] = 12;
+        itunessd_file_as_bytes[itunessd_constants::ITUNESSD_V3_VOICEOVER_ENABLED_OFFSET] = 1;
+        write_le_u32(&mut itunessd_file_as_bytes, itunessd_constants::ITUNESSD_V3_TRACK_HEADER_OFFSET_OFFSET, TRACK_HEADER_IDX as u32);
+        write_le_u32(&mut itunessd_file_as_bytes, itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_OFFSET_OFFSET, PLAYLIST_HEADER_IDX as u32);
+
+        itunessd_file_as_bytes[TRACK_HEADER_IDX..TRACK_HEADER_IDX + 4].copy_from_slice(itunessd_constants::ITUNESSD_V3_TRACK_HEADER_KEY.as_bytes());
+        write_le_u32(&mut itunessd_file_as_bytes, TRACK_HEADER_IDX + itunessd_constants::ITUNESSD_V3_TRACK_HEADER_NUM_TRACKS_OFFSET, 1);
+        write_le_u32(&mut itunessd_file_as_bytes, TRACK_HEADER_IDX + itunessd_constants::ITUNESSD_V3_TRACK_HEADER_OFFSETS_OFFSET, TRACK_IDX as u32);
+
+        let song_filename = b"/iPod_Control/Music/F00/ABCD.mp3";
+
+        itunessd_file_as_bytes[TRACK_IDX..TRACK_IDX + 4].copy_from_slice(itunessd_constants::ITUNESSD_V3_TRACK_KEY.as_bytes());
+        write_le_u32(&mut itunessd_file_as_bytes, TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_FILE_TYPE_OFFSET, 0x01);
+        itunessd_file_as_bytes[TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_FILENAME_OFFSET
+            ..TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_FILENAME_OFFSET + song_filename.len()]
+            .copy_from_slice(song_filename);
+        write_le_u32(&mut itunessd_file_as_bytes, TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_BOOKMARK_TIME_OFFSET, 90_000);
+        itunessd_file_as_bytes[TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_DONT_SKIP_OFFSET] = 1;
+        itunessd_file_as_bytes[TRACK_IDX + itunessd_constants::ITUNESSD_V3_TRACK_TRACK_NUMBER_OFFSET] = 3;
+
+        itunessd_file_as_bytes[PLAYLIST_HEADER_IDX..PLAYLIST_HEADER_IDX + 4].copy_from_slice(itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_KEY.as_bytes());
+        itunessd_file_as_bytes[PLAYLIST_HEADER_IDX + itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_NUM_PLAYLISTS_OFFSET] = 1;
+        write_le_u32(&mut itunessd_file_as_bytes, PLAYLIST_HEADER_IDX + itunessd_constants::ITUNESSD_V3_PLAYLIST_HEADER_OFFSETS_OFFSET, PLAYLIST_IDX as u32);
+
+        itunessd_file_as_bytes[PLAYLIST_IDX..PLAYLIST_IDX + 4].copy_from_slice(itunessd_constants::ITUNESSD_V3_PLAYLIST_KEY.as_bytes());
+        write_le_u32(&mut itunessd_file_as_bytes, PLAYLIST_IDX + itunessd_constants::ITUNESSD_V3_PLAYLIST_NUM_TRACKS_OFFSET, 1);
+        write_le_u32(&mut itunessd_file_as_bytes, PLAYLIST_IDX + itunessd_constants::ITUNESSD_V3_PLAYLIST_TYPE_OFFSET, 1);
+
+        return itunessd_file_as_bytes;
+    }
+
+    #[test]
+    fn reads_tracks_and_playlists() {
+        let (ipod_device_info, songs_found, playlists_found) = read_itunessd_v3_file(&build_test_itunessd(), &None).unwrap();
+
+        let playback_settings = ipod_device_info.playback_settings.unwrap();
+        assert_eq!(playback_settings.max_volume_raw, Some(12));
+        assert_eq!(playback_settings.is_voiceover_enabled, Some(true));
+
+        assert_eq!(songs_found.len(), 1);
+        assert_eq!(songs_found[0].song_filename, "iPod_Control/Music/F00/ABCD.mp3");
+        assert_eq!(songs_found[0].file_extension, "MP3");
+        assert_eq!(songs_found[0].resume_position_ms, 90_000);
+        assert_eq!(songs_found[0].track_number, 3);
+        assert!(!songs_found[0].skip_when_shuffling);
+        assert!(!songs_found[0].remember_playback_position);
+
+        assert_eq!(playlists_found.len(), 1);
+        assert_eq!(playlists_found[0].playlist_kind, itunesdb::PlaylistKind::Master);
+        assert!(playlists_found[0].is_master_playlist);
+        assert_eq!(playlists_found[0].track_ids, vec![0]);
+    }
+
+    #[test]
+    fn truncated_files_dont_panic() {
+        let itunessd_file_as_bytes = build_test_itunessd();
+
+        // Not even the whole header
+        assert!(read_itunessd_v3_file(&itunessd_file_as_bytes[..20], &None).is_none());
+
+        // Just the header, pointing past the end of the file
+        let (_, songs_found, playlists_found) =
+            read_itunessd_v3_file(&itunessd_file_as_bytes[..TRACK_HEADER_IDX], &None).unwrap();
+        assert!(songs_found.is_empty());
+        assert!(playlists_found.is_empty());
+
+        // Cut off in the middle of the track
+        let (_, songs_found, playlists_found) =
+            read_itunessd_v3_file(&itunessd_file_as_bytes[..TRACK_IDX + 100], &None).unwrap();
+        assert!(songs_found.is_empty());
+        assert!(playlists_found.is_empty());
+
+        // Cut off in the middle of the playlist's track indices, which keeps the playlist without them
+        let (_, songs_found, playlists_found) =
+            read_itunessd_v3_file(&itunessd_file_as_bytes[..itunessd_file_as_bytes.len() - 2], &None).unwrap();
+        assert_eq!(songs_found.len(), 1);
+        assert_eq!(playlists_found.len(), 1);
+        assert!(playlists_found[0].track_ids.is_empty());
+    }
 }
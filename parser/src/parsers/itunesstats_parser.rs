@@ -0,0 +1,78 @@
+use crate::constants::itunessd_constants;
+use crate::constants::itunesstats_constants;
+
+use crate::helpers::helpers;
+use crate::playcounts;
+
+/// Entries are numbered in file order, which is the order of the songs in the iTunesSD.
+/// The shuffle has no clock, so there are no timestamps, and it can't rate songs either
+pub fn read_itunesstats_entries(itunesstats_file_as_bytes: &[u8]) -> Vec<playcounts::PlayCountsEntry> {
+
+    let mut itunesstats_entries : Vec<playcounts::PlayCountsEntry> = Vec::new();
+
+    if itunesstats_file_as_bytes.len() < itunesstats_constants::ITUNESSTATS_FILE_HEADER_LENGTH {
+        eprintln!("iTunesStats file is too short to have a header");
+        return itunesstats_entries;
+    }
+
+    let num_entries = helpers::get_slice_as_le_u32(0, itunesstats_file_as_bytes, itunesstats_constants::ITUNESSTATS_NUM_ENTRIES_OFFSET, itunesstats_constants::ITUNESSTATS_NUM_ENTRIES_LEN);
+
+    println!("iTunesStats file has {} songs", num_entries);
+
+    let mut stats_entry_idx = itunesstats_constants::ITUNESSTATS_FILE_HEADER_LENGTH;
+
+    for track_idx in 0 .. (num_entries as usize) {
+
+        if stats_entry_idx + itunesstats_constants::ITUNESSTATS_ENTRY_LENGTH_LEN > itunesstats_file_as_bytes.len() {
+            eprintln!("iTunesStats file is truncated, only {} of {} entries could be read", track_idx, num_entries);
+            break;
+        }
+
+        // Every entry says how long it is, so newer firmware can add fields at the end
+        let stats_entry_len = helpers::get_slice_as_le_u32(stats_entry_idx, itunesstats_file_as_bytes, itunesstats_constants::ITUNESSTATS_ENTRY_LENGTH_OFFSET, itunesstats_constants::ITUNESSTATS_ENTRY_LENGTH_LEN) as usize;
+
+        if (stats_entry_len < itunesstats_constants::ITUNESSTATS_ENTRY_NUM_SKIPS_OFFSET + itunesstats_constants::ITUNESSTATS_ENTRY_NUM_SKIPS_LEN)
+            || (stats_entry_idx + stats_entry_len > itunesstats_file_as_bytes.len()) {
+            eprintln!("iTunesStats entry {} has an invalid length of {}, the rest of the file was skipped", track_idx, stats_entry_len);
+            break;
+        }
+
+        itunesstats_entries.push(playcounts::PlayCountsEntry {
+            track_index: track_idx as u32,
+            num_plays: helpers::get_slice_as_le_u32(stats_entry_idx, itunesstats_file_as_bytes, itunesstats_constants::ITUNESSTATS_ENTRY_NUM_PLAYS_OFFSET, itunesstats_constants::ITUNESSTATS_ENTRY_NUM_PLAYS_LEN),
+            num_skips: helpers::get_slice_as_le_u32(stats_entry_idx, itunesstats_file_as_bytes, itunesstats_constants::ITUNESSTATS_ENTRY_NUM_SKIPS_OFFSET, itunesstats_constants::ITUNESSTATS_ENTRY_NUM_SKIPS_LEN),
+            audio_bookmark_ms: helpers::get_slice_as_le_u32(stats_entry_idx, itunesstats_file_as_bytes, itunesstats_constants::ITUNESSTATS_ENTRY_BOOKMARK_TIME_OFFSET, itunesstats_constants::ITUNESSTATS_ENTRY_BOOKMARK_TIME_LEN) * itunessd_constants::ITUNESSD_TIME_UNIT_MS,
+            ..playcounts::PlayCountsEntry::default()
+        });
+
+        stats_entry_idx += stats_entry_len;
+    }
+
+    return itunesstats_entries;
+}
+
+pub fn parse_itunesstats(itunesstats_file_as_bytes: Vec<u8>, mut csv_writer_obj : helpers::CsvWriter) {
+
+    let itunesstats_entries = read_itunesstats_entries(&itunesstats_file_as_bytes);
+
+    println!("===========");
+
+    if !itunesstats_entries.is_empty() {
+
+        csv_writer_obj.write_record(
+            ["Song # (in the iTunesSD)",
+            "# of times played (since last sync)",
+            "# of times skipped (since last sync)",
+            "Audio playback bookmark (ms)"]).expect("Error creating header column in CSV");
+    }
+
+    for itunesstats_entry in itunesstats_entries.iter() {
+
+        // Vectors have to be all the same type, hence the `to_string()`
+        csv_writer_obj.write_record(
+            &[itunesstats_entry.track_index.to_string(),
+            itunesstats_entry.num_plays.to_string(),
+            itunesstats_entry.num_skips.to_string(),
+            itunesstats_entry.audio_bookmark_ms.to_string()]).expect("Unable to write row");
+    }
+}
@@ -0,0 +1,433 @@
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+use std::collections::HashMap;
+
+/// iOS keeps its timestamps as seconds since Jan 1 2001 (the "Cocoa" epoch),
+/// which is this many seconds after the Mac epoch (Jan 1 1904) used everywhere else
+const COCOA_TO_MAC_EPOCH_CONVERSION: i64 = 3061152000;
+
+/// Same bits as `MPMediaType` in the iOS SDK
+const MEDIA_TYPE_MUSIC: i64 = 1 << 0;
+const MEDIA_TYPE_PODCAST: i64 = 1 << 1;
+const MEDIA_TYPE_AUDIOBOOK: i64 = 1 << 2;
+const MEDIA_TYPE_ITUNES_U_AUDIO: i64 = 1 << 3;
+const MEDIA_TYPE_MOVIE: i64 = 1 << 8;
+const MEDIA_TYPE_TV_SHOW: i64 = 1 << 9;
+const MEDIA_TYPE_VIDEO_PODCAST: i64 = 1 << 10;
+const MEDIA_TYPE_MUSIC_VIDEO: i64 = 1 << 11;
+const MEDIA_TYPE_ITUNES_U_VIDEO: i64 = 1 << 12;
+const MEDIA_TYPE_HOME_VIDEO: i64 = 1 << 13;
+
+/// Uses the same names as `itunesdb::decode_track_media_type` where there is one, so both exports can be compared
+fn decode_media_library_media_type(media_type_raw: i64) -> String {
+    if media_type_raw & MEDIA_TYPE_MUSIC_VIDEO != 0 {
+        return "Music Video".to_string();
+    } else if media_type_raw & MEDIA_TYPE_VIDEO_PODCAST != 0 {
+        return "Video Podcast".to_string();
+    } else if media_type_raw & MEDIA_TYPE_TV_SHOW != 0 {
+        return "TV Show".to_string();
+    } else if media_type_raw & (MEDIA_TYPE_MOVIE | MEDIA_TYPE_HOME_VIDEO) != 0 {
+        return "Video".to_string();
+    } else if media_type_raw & (MEDIA_TYPE_ITUNES_U_AUDIO | MEDIA_TYPE_ITUNES_U_VIDEO) != 0 {
+        return "iTunes U".to_string();
+    } else if media_type_raw & MEDIA_TYPE_AUDIOBOOK != 0 {
+        return "Audiobook".to_string();
+    } else if media_type_raw & MEDIA_TYPE_PODCAST != 0 {
+        return "Podcast".to_string();
+    } else if media_type_raw & MEDIA_TYPE_MUSIC != 0 {
+        return "Audio".to_string();
+    }
+
+    return format!("Unknown ({})", media_type_raw);
+}
+
+/// 0 (or NULL) means the date was never set
+fn get_cocoa_timestamp_as_mac(cocoa_timestamp: Option<i64>) -> u64 {
+    return match cocoa_timestamp {
+        Some(cocoa_timestamp) if cocoa_timestamp > 0 => (cocoa_timestamp + COCOA_TO_MAC_EPOCH_CONVERSION) as u64,
+        _ => 0,
+    };
+}
+
+/// The schema changed a bit between iOS releases, so any column that's missing
+/// is selected as NULL rather than failing the whole query
+fn select_column_or_null(connection: &rusqlite::Connection, table_name: &str, column_name: &str) -> String {
+    let mut table_info = connection
+        .prepare(&format!("PRAGMA table_info({})", table_name))
+        .expect("Can't read MediaLibrary table info");
+
+    let column_names: Vec<String> = table_info
+        .query_map([], |row| row.get::<_, String>(1))
+        .expect("Can't read MediaLibrary table info")
+        .filter_map(|column_name| column_name.ok())
+        .collect();
+
+    if column_names.iter().any(|existing_column| existing_column == column_name) {
+        return format!("{}.{}", table_name, column_name);
+    }
+
+    return "NULL".to_string();
+}
+
+/// Reads every item in the library into the same `Song` model the iTunesDB parser uses.
+/// The item's `item_pid` becomes its dbid, and track IDs are assigned in order (starting at 1)
+/// since MediaLibrary.sqlitedb doesn't have the iTunesDB's 32-bit unique IDs
+pub fn read_media_library_songs(connection: &rusqlite::Connection) -> Vec<itunesdb::Song> {
+    let mut songs: Vec<itunesdb::Song> = Vec::new();
+
+    let columns: Vec<String> = vec![
+        select_column_or_null(connection, "item", "item_pid"),
+        select_column_or_null(connection, "item_extra", "media_kind"),
+        select_column_or_null(connection, "item_extra", "title"),
+        select_column_or_null(connection, "item_extra", "sort_title"),
+        select_column_or_null(connection, "item_artist", "item_artist"),
+        select_column_or_null(connection, "item_artist", "sort_item_artist"),
+        select_column_or_null(connection, "album", "album"),
+        select_column_or_null(connection, "album", "sort_album"),
+        select_column_or_null(connection, "album_artist", "album_artist"),
+        select_column_or_null(connection, "composer", "composer"),
+        select_column_or_null(connection, "genre", "genre"),
+        select_column_or_null(connection, "item_extra", "year"),
+        select_column_or_null(connection, "item", "track_number"),
+        select_column_or_null(connection, "item_extra", "track_count"),
+        select_column_or_null(connection, "item", "disc_number"),
+        select_column_or_null(connection, "item_extra", "disc_count"),
+        select_column_or_null(connection, "item_extra", "total_time_ms"),
+        select_column_or_null(connection, "item_extra", "file_size"),
+        select_column_or_null(connection, "base_location", "path"),
+        select_column_or_null(connection, "item_extra", "location"),
+        select_column_or_null(connection, "item_stats", "play_count_user"),
+        select_column_or_null(connection, "item_stats", "skip_count_user"),
+        select_column_or_null(connection, "item_stats", "user_rating"),
+        select_column_or_null(connection, "item_stats", "date_played"),
+        select_column_or_null(connection, "item_stats", "date_skipped"),
+        select_column_or_null(connection, "item_stats", "bookmark_time_ms"),
+        select_column_or_null(connection, "item_extra", "date_modified"),
+    ];
+
+    let query = format!(
+        "SELECT {} FROM item \
+         LEFT JOIN item_extra ON item_extra.item_pid = item.item_pid \
+         LEFT JOIN item_artist ON item_artist.item_artist_pid = item.item_artist_pid \
+         LEFT JOIN album ON album.album_pid = item.album_pid \
+         LEFT JOIN album_artist ON album_artist.album_artist_pid = item.album_artist_pid \
+         LEFT JOIN composer ON composer.composer_pid = item.composer_pid \
+         LEFT JOIN genre ON genre.genre_id = item.genre_id \
+         LEFT JOIN base_location ON base_location.base_location_id = item.base_location_id \
+         LEFT JOIN item_stats ON item_stats.item_pid = item.item_pid \
+         ORDER BY item.item_pid",
+        columns.join(", ")
+    );
+
+    let mut statement = match connection.prepare(&query) {
+        Ok(statement) => statement,
+        Err(error) => {
+            eprintln!("Can't read the songs from the MediaLibrary database: {}", error);
+            return songs;
+        }
+    };
+
+    let mut rows = statement.query([]).expect("Can't query the MediaLibrary songs");
+
+    while let Some(row) = rows.next().expect("Can't read MediaLibrary song row") {
+        let mut song = itunesdb::Song::default();
+
+        song.dbid = row.get::<_, Option<i64>>(0).unwrap().unwrap_or(0) as u64;
+        song.track_id = (songs.len() + 1) as u32;
+        song.track_index = songs.len() as u32;
+
+        let media_type_raw = row.get::<_, Option<i64>>(1).unwrap().unwrap_or(0);
+        song.media_type_raw = media_type_raw as u32;
+        song.media_kind = decode_media_library_media_type(media_type_raw);
+
+        song.song_title = row.get::<_, Option<String>>(2).unwrap().unwrap_or_default();
+        song.song_sort_title = row.get::<_, Option<String>>(3).unwrap().unwrap_or_default();
+        song.song_artist = row.get::<_, Option<String>>(4).unwrap().unwrap_or_default();
+        song.song_sort_artist = row.get::<_, Option<String>>(5).unwrap().unwrap_or_default();
+        song.song_album = row.get::<_, Option<String>>(6).unwrap().unwrap_or_default();
+        song.song_sort_album = row.get::<_, Option<String>>(7).unwrap().unwrap_or_default();
+        song.song_album_artist = row.get::<_, Option<String>>(8).unwrap().unwrap_or_default();
+        song.song_composer = row.get::<_, Option<String>>(9).unwrap().unwrap_or_default();
+        song.song_genre = row.get::<_, Option<String>>(10).unwrap().unwrap_or_default();
+
+        song.song_year = row.get::<_, Option<i64>>(11).unwrap().unwrap_or(0) as u16;
+        song.track_number = row.get::<_, Option<i64>>(12).unwrap().unwrap_or(0) as u32;
+        song.num_tracks_in_album = row.get::<_, Option<i64>>(13).unwrap().unwrap_or(0) as u32;
+        song.disc_number = row.get::<_, Option<i64>>(14).unwrap().unwrap_or(0) as u32;
+        song.num_discs = row.get::<_, Option<i64>>(15).unwrap().unwrap_or(0) as u32;
+
+        let song_duration_ms = row.get::<_, Option<f64>>(16).unwrap().unwrap_or(0.0);
+        song.song_duration_s = (song_duration_ms / 1000.0) as u32;
+        song.song_duration_friendly = helpers::convert_seconds_to_human_readable_duration(song.song_duration_s);
+
+        song.file_size_bytes = row.get::<_, Option<i64>>(17).unwrap().unwrap_or(0) as u64;
+        song.file_size_friendly = helpers::convert_bytes_to_human_readable_size(song.file_size_bytes);
+
+        // The folder (e.g. "iTunes_Control/Music/F00") and the filename are stored separately
+        let base_location = row.get::<_, Option<String>>(18).unwrap().unwrap_or_default();
+        let location = row.get::<_, Option<String>>(19).unwrap().unwrap_or_default();
+
+        song.song_filename_raw = location.to_string();
+        song.song_filename = if base_location.is_empty() {
+            location.to_string()
+        } else {
+            format!("{}/{}", base_location, location)
+        };
+
+        if let Some(file_extension) = std::path::Path::new(&location).extension() {
+            song.file_extension = file_extension.to_string_lossy().to_string();
+        }
+
+        song.num_plays = row.get::<_, Option<i64>>(20).unwrap().unwrap_or(0) as u32;
+        song.num_skips = row.get::<_, Option<i64>>(21).unwrap().unwrap_or(0) as u32;
+
+        // Same 0-100 scale as the iTunesDB
+        song.song_rating_raw = row.get::<_, Option<i64>>(22).unwrap().unwrap_or(0) as u8;
+
+        let last_played_epoch = get_cocoa_timestamp_as_mac(row.get::<_, Option<i64>>(23).unwrap());
+        if last_played_epoch != 0 {
+            song.last_played_at = Some(helpers::get_timestamp_as_mac(last_played_epoch));
+        }
+
+        let last_skipped_epoch = get_cocoa_timestamp_as_mac(row.get::<_, Option<i64>>(24).unwrap());
+        if last_skipped_epoch != 0 {
+            song.last_skipped_at = Some(helpers::get_timestamp_as_mac(last_skipped_epoch));
+        }
+
+        song.resume_position_ms = row.get::<_, Option<f64>>(25).unwrap().unwrap_or(0.0) as u32;
+
+        song.song_modified_epoch = get_cocoa_timestamp_as_mac(row.get::<_, Option<i64>>(26).unwrap());
+        if song.song_modified_epoch != 0 {
+            song.song_modified_ts = Some(helpers::get_timestamp_as_mac(song.song_modified_epoch));
+        }
+
+        songs.push(song);
+    }
+
+    return songs;
+}
+
+/// Reads the playlists ("containers") and their items. The items refer to songs by `item_pid`,
+/// which are mapped back to the track IDs `read_media_library_songs` assigned
+pub fn read_media_library_playlists(
+    connection: &rusqlite::Connection,
+    songs: &[itunesdb::Song],
+) -> Vec<itunesdb::Playlist> {
+    let mut playlists: Vec<itunesdb::Playlist> = Vec::new();
+
+    let track_ids_by_dbid: HashMap<u64, u32> = songs
+        .iter()
+        .map(|song| (song.dbid, song.track_id))
+        .collect();
+
+    let columns: Vec<String> = vec![
+        select_column_or_null(connection, "container", "container_pid"),
+        select_column_or_null(connection, "container", "name"),
+        select_column_or_null(connection, "container", "date_created"),
+        select_column_or_null(connection, "container", "smart_criteria"),
+    ];
+
+    let query = format!("SELECT {} FROM container ORDER BY container.container_pid", columns.join(", "));
+
+    let mut statement = match connection.prepare(&query) {
+        Ok(statement) => statement,
+        Err(error) => {
+            eprintln!("Can't read the playlists from the MediaLibrary database: {}", error);
+            return playlists;
+        }
+    };
+
+    let mut rows = statement.query([]).expect("Can't query the MediaLibrary playlists");
+
+    while let Some(row) = rows.next().expect("Can't read MediaLibrary playlist row") {
+        let mut playlist = itunesdb::Playlist::default();
+
+        playlist.persistent_playlist_id = row.get::<_, Option<i64>>(0).unwrap().unwrap_or(0) as u64;
+        playlist.playlist_name = row.get::<_, Option<String>>(1).unwrap().unwrap_or_default();
+
+        let created_epoch = get_cocoa_timestamp_as_mac(row.get::<_, Option<i64>>(2).unwrap());
+        if created_epoch != 0 {
+            playlist.created_ts = helpers::get_timestamp_as_mac(created_epoch);
+        }
+
+        // The rules themselves aren't decoded, but the device keeps the matching items in `container_items` anyway
+        playlist.is_smart_playlist = row.get::<_, Option<Vec<u8>>>(3).unwrap().map_or(false, |criteria| !criteria.is_empty());
+        playlist.playlist_kind = itunesdb::decode_playlist_kind(&playlist);
+
+        playlists.push(playlist);
+    }
+
+    let mut items_statement = match connection.prepare(
+        "SELECT container_pid, item_pid FROM container_items ORDER BY container_pid, position",
+    ) {
+        Ok(items_statement) => items_statement,
+        Err(error) => {
+            eprintln!("Can't read the playlist items from the MediaLibrary database: {}", error);
+            return playlists;
+        }
+    };
+
+    let mut item_rows = items_statement.query([]).expect("Can't query the MediaLibrary playlist items");
+
+    while let Some(item_row) = item_rows.next().expect("Can't read MediaLibrary playlist item row") {
+        let container_pid = item_row.get::<_, i64>(0).unwrap() as u64;
+        let item_pid = item_row.get::<_, i64>(1).unwrap() as u64;
+
+        let track_id = match track_ids_by_dbid.get(&item_pid) {
+            Some(track_id) => *track_id,
+            None => {
+                eprintln!("Playlist {} refers to an item ({}) that isn't in the library", container_pid, item_pid);
+                continue;
+            }
+        };
+
+        if let Some(playlist) = playlists.iter_mut().find(|playlist| playlist.persistent_playlist_id == container_pid) {
+            playlist.stored_track_ids.push(track_id);
+            playlist.track_ids.push(track_id);
+        }
+    }
+
+    return playlists;
+}
+
+/// iPod touch, iPhone and iPad (iOS 5 and later) keep their music library in
+/// `iTunes_Control/iTunes/MediaLibrary.sqlitedb` rather than an iTunesDB
+pub fn parse_media_library_file(media_library_filename: &str, output_format: String) {
+    let connection = rusqlite::Connection::open_with_flags(
+        media_library_filename,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )
+    .expect(&format!("Can't open '{}' as a SQLite database", media_library_filename));
+
+    let songs_found = read_media_library_songs(&connection);
+    let playlists_found = read_media_library_playlists(&connection, &songs_found);
+
+    println!("{} songs found", songs_found.len());
+    println!("{} playlists found", playlists_found.len());
+
+    if output_format == "json" {
+        if !songs_found.is_empty() {
+            helpers::write_json_file("music.json", &songs_found);
+            println!("Created music.json with {} songs", songs_found.len());
+        }
+
+        if !playlists_found.is_empty() {
+            helpers::write_json_file("playlists.json", &playlists_found);
+            println!("Created playlists.json with {} playlists", playlists_found.len());
+        }
+
+        return;
+    }
+
+    if !songs_found.is_empty() {
+        let mut music_csv_writer = helpers::init_csv_writer("music.csv");
+
+        // Only the columns MediaLibrary.sqlitedb has, named the same as in the iTunesDB export
+        music_csv_writer
+            .write_record(&[
+                "DBID",
+                "Media kind",
+                "Song Title",
+                "Artist",
+                "Album",
+                "Year released",
+                "File size",
+                "Song Duration",
+                "Filename",
+                "Genre",
+                "File extension",
+                "File size (bytes)",
+                "Song duration (seconds)",
+                "Play count",
+                "Rating",
+                "Composer",
+                "Album Artist",
+                "Sort Title",
+                "Sort Artist",
+                "Sort Album",
+                "Last played",
+                "Skip count",
+                "Last skipped",
+                "Track number",
+                "Tracks in album",
+                "Disc number",
+                "Discs in set",
+                "Last modified (timestamp)",
+                "Last modified (epoch)",
+                "Resume position (ms)",
+            ])
+            .expect("Can't create CSV file headers for music file");
+
+        for song in songs_found.iter() {
+            music_csv_writer
+                .write_record(&[
+                    song.dbid.to_string(),
+                    song.media_kind.to_string(),
+                    song.song_title.to_string(),
+                    song.song_artist.to_string(),
+                    song.song_album.to_string(),
+                    song.song_year.to_string(),
+                    song.file_size_friendly.to_string(),
+                    song.song_duration_friendly.to_string(),
+                    song.song_filename.to_string(),
+                    song.song_genre.to_string(),
+                    song.file_extension.to_string(),
+                    song.file_size_bytes.to_string(),
+                    song.song_duration_s.to_string(),
+                    song.num_plays.to_string(),
+                    song.song_rating_raw.to_string(),
+                    song.song_composer.to_string(),
+                    song.song_album_artist.to_string(),
+                    song.song_sort_title.to_string(),
+                    song.song_sort_artist.to_string(),
+                    song.song_sort_album.to_string(),
+                    song.last_played_at.map_or("".to_string(), |last_played_at| last_played_at.to_string()),
+                    song.num_skips.to_string(),
+                    song.last_skipped_at.map_or("".to_string(), |last_skipped_at| last_skipped_at.to_string()),
+                    song.track_number.to_string(),
+                    song.num_tracks_in_album.to_string(),
+                    song.disc_number.to_string(),
+                    song.num_discs.to_string(),
+                    song.song_modified_ts.map_or("".to_string(), |modified_ts| modified_ts.to_string()),
+                    song.song_modified_epoch.to_string(),
+                    song.resume_position_ms.to_string(),
+                ])
+                .expect("Can't write row to music CSV file");
+        }
+        println!("Created music.csv with {} songs", songs_found.len());
+    }
+
+    if !playlists_found.is_empty() {
+        let mut playlist_csv_writer = helpers::init_csv_writer("playlists.csv");
+
+        playlist_csv_writer
+            .write_record(&[
+                "Persistent ID",
+                "Playlist Name",
+                "Smart playlist",
+                "Playlist kind",
+                "Track count",
+                "Track IDs",
+                "Created",
+            ])
+            .expect("Can't create CSV file headers for playlist file");
+
+        for playlist in playlists_found.iter() {
+            let track_ids: Vec<String> = playlist.track_ids.iter().map(|id| id.to_string()).collect();
+
+            playlist_csv_writer
+                .write_record(&[
+                    playlist.persistent_playlist_id.to_string(),
+                    playlist.playlist_name.to_string(),
+                    playlist.is_smart_playlist.to_string(),
+                    playlist.playlist_kind.to_string(),
+                    playlist.track_ids.len().to_string(),
+                    track_ids.join(" "),
+                    playlist.created_ts.to_string(),
+                ])
+                .expect("Can't write row to playlist CSV file");
+        }
+        println!("Created playlists.csv with {} playlists", playlists_found.len());
+    }
+}
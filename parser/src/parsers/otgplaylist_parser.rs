@@ -0,0 +1,63 @@
+use crate::constants::otgplaylist_constants;
+
+use crate::helpers::helpers;
+
+/// Returns the Track List positions of the playlist's tracks, in playlist order
+pub fn read_otg_playlist_track_indices(otg_playlist_file_as_bytes: &[u8]) -> Vec<u32> {
+    let mut track_indices: Vec<u32> = Vec::new();
+
+    if !otg_playlist_file_as_bytes.starts_with(otgplaylist_constants::OTG_PLAYLIST_KEY.as_bytes()) {
+        eprintln!(
+            "On-The-Go playlist file doesn't start with a '{}' header",
+            otgplaylist_constants::OTG_PLAYLIST_KEY
+        );
+        return track_indices;
+    }
+
+    let otg_header_len = helpers::get_slice_as_le_u32(
+        0,
+        otg_playlist_file_as_bytes,
+        otgplaylist_constants::OTG_PLAYLIST_HEADER_LEN_OFFSET,
+        otgplaylist_constants::OTG_PLAYLIST_HEADER_LEN_LEN,
+    ) as usize;
+
+    let otg_entry_len = helpers::get_slice_as_le_u32(
+        0,
+        otg_playlist_file_as_bytes,
+        otgplaylist_constants::OTG_PLAYLIST_ENTRY_LEN_OFFSET,
+        otgplaylist_constants::OTG_PLAYLIST_ENTRY_LEN_LEN,
+    ) as usize;
+
+    let num_entries = helpers::get_slice_as_le_u32(
+        0,
+        otg_playlist_file_as_bytes,
+        otgplaylist_constants::OTG_PLAYLIST_NUM_ENTRIES_OFFSET,
+        otgplaylist_constants::OTG_PLAYLIST_NUM_ENTRIES_LEN,
+    );
+
+    if otg_entry_len < otgplaylist_constants::OTG_ENTRY_TRACK_INDEX_LEN {
+        eprintln!("On-The-Go playlist entries are too short ({} bytes)", otg_entry_len);
+        return track_indices;
+    }
+
+    for entry_idx in 0..(num_entries as usize) {
+        let otg_entry_start = otg_header_len + (entry_idx * otg_entry_len);
+
+        if otg_entry_start + otg_entry_len > otg_playlist_file_as_bytes.len() {
+            eprintln!(
+                "On-The-Go playlist file is truncated, only {} of {} entries could be read",
+                entry_idx, num_entries
+            );
+            break;
+        }
+
+        track_indices.push(helpers::get_slice_as_le_u32(
+            otg_entry_start,
+            otg_playlist_file_as_bytes,
+            otgplaylist_constants::OTG_ENTRY_TRACK_INDEX_OFFSET,
+            otgplaylist_constants::OTG_ENTRY_TRACK_INDEX_LEN,
+        ));
+    }
+
+    return track_indices;
+}
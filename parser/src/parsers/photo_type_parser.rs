@@ -1,6 +1,9 @@
 use crate::helpers::helpers;
 use crate::helpers::itunesdb_helpers;
 
+use crate::exif;
+use crate::ithmb;
+use crate::parse_report;
 use crate::photo_database;
 
 use crate::constants::itunesdb_constants;
@@ -33,9 +36,13 @@ pub fn parse_photofolder_albums_file(itunesdb_file_as_bytes: Vec<u8>) {
     }
 }
 
-pub fn parse_photo_type_file(
-    itunesdb_file_as_bytes: Vec<u8>,
-    mut csv_writer_obj: csv::Writer<std::fs::File>,
+/// Besides the Photo Database, this works for the ArtworkDB too, which has the same layout
+pub fn read_photo_database(
+    itunesdb_file_as_bytes: &[u8],
+) -> (
+    Vec<photo_database::Image>,
+    Vec<photo_database::Thumbnail>,
+    Vec<photo_database::PhotoAlbum>,
 ) {
     // Photo Database counters
     let mut num_image_lists = 0;
@@ -45,8 +52,13 @@ pub fn parse_photo_type_file(
     let mut num_photo_data_objects = 0;
 
     let mut images_found: Vec<photo_database::Image> = Vec::new();
+    let mut thumbnails_found: Vec<photo_database::Thumbnail> = Vec::new();
+    let mut photo_albums_found: Vec<photo_database::PhotoAlbum> = Vec::new();
 
     let mut curr_img = photo_database::Image::default();
+    // `curr_img` can be stored (and reset) before all of its Image Names have been parsed
+    let mut curr_image_id = 0;
+    let mut curr_song_dbid = 0;
 
     let mut idx = 0;
 
@@ -74,6 +86,20 @@ pub fn parse_photo_type_file(
         else if potential_photo_section_heading
             == photo_database_constants::IMAGE_ITEM_KEY.as_bytes()
         {
+            let image_item_id = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::IMAGE_ITEM_ID_OFFSET,
+                photo_database_constants::IMAGE_ITEM_ID_LEN,
+            );
+
+            let image_item_song_dbid = helpers::get_slice_as_le_u64(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::IMAGE_ITEM_SONG_DBID_OFFSET,
+                photo_database_constants::IMAGE_ITEM_SONG_DBID_LEN,
+            );
+
             let image_item_rating = helpers::get_slice_as_le_u32(
                 idx,
                 &itunesdb_file_as_bytes,
@@ -117,6 +143,9 @@ pub fn parse_photo_type_file(
             idx += photo_database_constants::IMAGE_ITEM_LAST_OFFSET;
 
             // Populate existing image with properties
+            curr_img.image_id = image_item_id;
+            curr_image_id = image_item_id;
+            curr_song_dbid = image_item_song_dbid;
             curr_img.set_original_date(image_item_orig_date_timestamp_raw as u64);
             curr_img.set_digitized_date(image_item_digitized_timestamp_raw as u64);
         }
@@ -124,6 +153,13 @@ pub fn parse_photo_type_file(
         else if potential_photo_section_heading
             == photo_database_constants::IMAGE_NAME_KEY.as_bytes()
         {
+            let correlation_id = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::IMAGE_NAME_CORRELATION_ID_OFFSET,
+                photo_database_constants::IMAGE_NAME_CORRELATION_ID_LEN,
+            );
+
             let ithmb_offset = helpers::get_slice_as_le_u32(
                 idx,
                 &itunesdb_file_as_bytes,
@@ -153,12 +189,27 @@ pub fn parse_photo_type_file(
                 photo_database_constants::IMAGE_NAME_IMG_WIDTH_LEN,
             );
 
+            let image_name_vertical_padding = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::IMAGE_NAME_VERTICAL_PADDING_OFFSET,
+                photo_database_constants::IMAGE_NAME_VERTICAL_PADDING_LEN,
+            ) as i16;
+
+            let image_name_horizontal_padding = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::IMAGE_NAME_HORIZONTAL_PADDING_OFFSET,
+                photo_database_constants::IMAGE_NAME_HORIZONTAL_PADDING_LEN,
+            ) as i16;
+
             println!(
-                "ImageName#{} : Size= {} bytes, Height={} , Width={} | thumbnail offset {}",
+                "ImageName#{} : Size= {} bytes, Height={} , Width={} | correlation ID {}, thumbnail offset {}",
                 num_image_names,
                 image_name_img_size,
                 image_name_img_height,
                 image_name_img_width,
+                correlation_id,
                 ithmb_offset
             );
             println!("==========");
@@ -171,6 +222,22 @@ pub fn parse_photo_type_file(
             curr_img.set_filesize(image_name_img_size);
 
             curr_img.ithmb_offset = ithmb_offset;
+            curr_img.correlation_id = correlation_id;
+
+            // Its filename comes in the Data Object right after it
+            thumbnails_found.push(photo_database::Thumbnail {
+                image_num: num_image_items.max(1) - 1,
+                image_id: curr_image_id,
+                song_dbid: curr_song_dbid,
+                correlation_id,
+                ithmb_offset,
+                image_size_bytes: image_name_img_size,
+                width: image_name_img_width as u16,
+                height: image_name_img_height as u16,
+                horizontal_padding: image_name_horizontal_padding,
+                vertical_padding: image_name_vertical_padding,
+                ..photo_database::Thumbnail::default()
+            });
         }
         // Parse Photo Album
         else if potential_photo_section_heading
@@ -183,6 +250,20 @@ pub fn parse_photo_type_file(
                 photo_database_constants::PHOTO_ALBUM_ALBUM_ITEM_CNT_LEN,
             );
 
+            let photo_album_id = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::PHOTO_ALBUM_ID_OFFSET,
+                photo_database_constants::PHOTO_ALBUM_ID_LEN,
+            );
+
+            let photo_album_type = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::PHOTO_ALBUM_TYPE_OFFSET,
+                photo_database_constants::PHOTO_ALBUM_TYPE_LEN,
+            );
+
             // println!(
             //     "PhotoAlbum#{} : Item count#={}",
             //     num_photo_albums, photo_album_item_count
@@ -191,8 +272,33 @@ pub fn parse_photo_type_file(
 
             num_photo_albums += 1;
 
+            // Its name comes in the Data Object right after it, then its Album Items
+            photo_albums_found.push(photo_database::PhotoAlbum {
+                album_id: photo_album_id,
+                is_master: photo_album_type == photo_database_constants::PHOTO_ALBUM_TYPE_MASTER,
+                image_ids: Vec::with_capacity(photo_album_item_count as usize),
+                ..photo_database::PhotoAlbum::default()
+            });
+
             idx += photo_database_constants::PHOTO_ALBUM_LAST_OFFSET;
         }
+        // Parse Album Item
+        else if potential_photo_section_heading
+            == photo_database_constants::ALBUM_ITEM_KEY.as_bytes()
+        {
+            let album_item_image_id = helpers::get_slice_as_le_u32(
+                idx,
+                itunesdb_file_as_bytes,
+                photo_database_constants::ALBUM_ITEM_IMAGE_ID_OFFSET,
+                photo_database_constants::ALBUM_ITEM_IMAGE_ID_LEN,
+            );
+
+            if let Some(photo_album) = photo_albums_found.last_mut() {
+                photo_album.image_ids.push(album_item_image_id);
+            }
+
+            idx += photo_database_constants::ALBUM_ITEM_LAST_OFFSET;
+        }
         // Parse Data Object
         else if potential_photo_section_heading
             == photo_database_constants::DATA_OBJECT_KEY.as_bytes()
@@ -223,9 +329,6 @@ pub fn parse_photo_type_file(
 
                 if data_object_subcontainer_encoding == 0 || data_object_subcontainer_encoding == 1
                 {
-                    // TODO: Figure out why I'm off by a width of 4 on the length.
-                    // Same issue with UTF-16 encoding (below)
-
                     let data_object_string_bytes = helpers::get_slice_from_offset_with_len(
                         idx,
                         &itunesdb_file_as_bytes,
@@ -239,14 +342,25 @@ pub fn parse_photo_type_file(
 
                     //println!("MHOD substring = {}", data_object_subcontainer_data);
 
+                    set_thumbnail_ithmb_filename(
+                        &mut thumbnails_found,
+                        data_object_type,
+                        data_object_subcontainer_data,
+                    );
+
+                    set_photo_album_name(
+                        &mut photo_albums_found,
+                        data_object_type,
+                        data_object_subcontainer_data,
+                    );
+
                     curr_img.set_filename(data_object_subcontainer_data.to_string());
                 } else if data_object_subcontainer_encoding == 2 {
                     let data_object_pairwise_combined =
                         &helpers::return_utf16_from_utf8(&helpers::get_slice_from_offset_with_len(
                             idx,
                             &itunesdb_file_as_bytes,
-                            photo_database_constants::DATA_OBJECT_STRING_SUBCONTAINER_DATA_OFFSET
-                                + 4,
+                            photo_database_constants::DATA_OBJECT_STRING_SUBCONTAINER_DATA_OFFSET,
                             (data_object_subcontainer_str_len) as usize,
                         ));
 
@@ -256,6 +370,18 @@ pub fn parse_photo_type_file(
 
                     // println!("MHOD substring = {}", data_object_subcontainer_data);
 
+                    set_thumbnail_ithmb_filename(
+                        &mut thumbnails_found,
+                        data_object_type,
+                        &data_object_subcontainer_data,
+                    );
+
+                    set_photo_album_name(
+                        &mut photo_albums_found,
+                        data_object_type,
+                        &data_object_subcontainer_data,
+                    );
+
                     curr_img.set_filename(data_object_subcontainer_data.to_string());
                 }
 
@@ -290,6 +416,18 @@ pub fn parse_photo_type_file(
 
     println!("{} images found", images_found.len());
 
+    return (images_found, thumbnails_found, photo_albums_found);
+}
+
+/// If the .ithmb files are next to the database, the thumbnails are decoded into `thumbnail_format` files
+pub fn parse_photo_type_file(
+    itunesdb_file_as_bytes: Vec<u8>,
+    mut csv_writer_obj: helpers::CsvWriter,
+    photo_database_dir: Option<&std::path::Path>,
+    thumbnail_format: &str,
+) {
+    let (images_found, mut thumbnails_found, _) = read_photo_database(&itunesdb_file_as_bytes);
+
     // Setup columns of CSV file
     // TODO see if there's a way to get the struct field names as strings?
     csv_writer_obj
@@ -302,6 +440,7 @@ pub fn parse_photo_type_file(
             "Digitized Date (Mac epoch)",
             "Digitized Date",
             "iThmb Offset",
+            "Correlation ID",
         ])
         .expect("Can't create CSV headers"); // TODO better log message
 
@@ -322,7 +461,531 @@ pub fn parse_photo_type_file(
                 image.digitized_date_epoch.to_string(),
                 image.digitized_date_ts.to_string(),
                 image.ithmb_offset.to_string(),
+                image.correlation_id.to_string(),
             ])
             .expect("Can't write row");
     }
+
+    export_thumbnails(&mut thumbnails_found, photo_database_dir, thumbnail_format);
+}
+
+/// Only the Data Object right after an Image Name has its filename
+fn set_thumbnail_ithmb_filename(
+    thumbnails_found: &mut [photo_database::Thumbnail],
+    data_object_type: u32,
+    ithmb_filename: &str,
+) {
+    if data_object_type != (photo_database::MhodType::FileName as u32) {
+        return;
+    }
+
+    if let Some(thumbnail) = thumbnails_found.last_mut() {
+        if thumbnail.ithmb_filename.is_empty() {
+            thumbnail.ithmb_filename = ithmb_filename.to_string();
+        }
+    }
+}
+
+/// Only the Data Object right after a Photo Album has its name
+fn set_photo_album_name(
+    photo_albums_found: &mut [photo_database::PhotoAlbum],
+    data_object_type: u32,
+    album_name: &str,
+) {
+    if data_object_type != (photo_database::MhodType::AlbumName as u32) {
+        return;
+    }
+
+    if let Some(photo_album) = photo_albums_found.last_mut() {
+        if photo_album.album_name.is_empty() && photo_album.image_ids.is_empty() {
+            photo_album.album_name = album_name.to_string();
+        }
+    }
+}
+
+/// Older iPods store ":Thumbs:F1019_1.ithmb" (relative to the Photos folder the database is in),
+/// newer ones ":F04:T102.ithmb" (relative to its Thumbs folder)
+fn get_ithmb_file_path(photo_database_dir: &std::path::Path, ithmb_filename: &str) -> Option<std::path::PathBuf> {
+    let ithmb_relative_path = itunesdb_helpers::get_canonical_path(ithmb_filename.to_string());
+
+    return [
+        photo_database_dir.join(&ithmb_relative_path),
+        photo_database_dir.join("Thumbs").join(&ithmb_relative_path),
+    ]
+    .into_iter()
+    .find(|ithmb_file_path| ithmb_file_path.is_file());
+}
+
+fn read_ithmb_file(photo_database_dir: &std::path::Path, ithmb_filename: &str) -> Option<Vec<u8>> {
+    return std::fs::read(get_ithmb_file_path(photo_database_dir, ithmb_filename)?).ok();
+}
+
+/// Checks every .ithmb file the Image Names refer to before any thumbnail is extracted: whether it's
+/// there, and whether it's long enough for all of the thumbnails supposedly in it.
+/// Anything missing or truncated goes into parse_report.json
+fn check_ithmb_files(thumbnails_found: &[photo_database::Thumbnail], photo_database_dir: &std::path::Path) {
+    let mut parse_report = parse_report::ParseReport::default();
+
+    // Keeps the files in the order they're first referred to
+    let mut ithmb_filenames: Vec<&str> = Vec::new();
+
+    for thumbnail in thumbnails_found.iter().filter(|thumbnail| thumbnail.is_in_ithmb_file()) {
+        if !ithmb_filenames.contains(&thumbnail.ithmb_filename.as_str()) {
+            ithmb_filenames.push(&thumbnail.ithmb_filename);
+        }
+    }
+
+    for ithmb_filename in ithmb_filenames {
+        let ithmb_thumbnails: Vec<&photo_database::Thumbnail> = thumbnails_found
+            .iter()
+            .filter(|thumbnail| thumbnail.is_in_ithmb_file() && thumbnail.ithmb_filename == ithmb_filename)
+            .collect();
+
+        let ithmb_file_len = match get_ithmb_file_path(photo_database_dir, ithmb_filename)
+            .and_then(|ithmb_file_path| std::fs::metadata(ithmb_file_path).ok())
+        {
+            Some(ithmb_file_metadata) => ithmb_file_metadata.len(),
+            None => {
+                parse_report.add_entry(
+                    "Missing .ithmb file",
+                    None,
+                    format!(
+                        "'{}' isn't next to the database, so its {} thumbnails can't be extracted",
+                        ithmb_filename,
+                        ithmb_thumbnails.len()
+                    ),
+                );
+                continue;
+            }
+        };
+
+        let expected_ithmb_file_len = ithmb_thumbnails
+            .iter()
+            .map(|thumbnail| thumbnail.ithmb_offset as u64 + thumbnail.image_size_bytes as u64)
+            .max()
+            .unwrap_or(0);
+
+        if ithmb_file_len < expected_ithmb_file_len {
+            let num_thumbnails_past_end = ithmb_thumbnails
+                .iter()
+                .filter(|thumbnail| thumbnail.ithmb_offset as u64 + thumbnail.image_size_bytes as u64 > ithmb_file_len)
+                .count();
+
+            parse_report.add_entry(
+                "Truncated .ithmb file",
+                None,
+                format!(
+                    "'{}' is {} bytes, but its thumbnails need at least {} ({} of {} thumbnails are past the end)",
+                    ithmb_filename,
+                    ithmb_file_len,
+                    expected_ithmb_file_len,
+                    num_thumbnails_past_end,
+                    ithmb_thumbnails.len()
+                ),
+            );
+        }
+    }
+
+    if !parse_report.is_empty() {
+        parse_report.print_summary();
+
+        helpers::write_json_file("parse_report.json", &parse_report);
+        println!("Created parse_report.json with {} entries", parse_report.entries.len());
+    }
+}
+
+fn export_thumbnails(
+    thumbnails_found: &mut [photo_database::Thumbnail],
+    photo_database_dir: Option<&std::path::Path>,
+    thumbnail_format: &str,
+) {
+    let photo_database_dir = match photo_database_dir {
+        Some(photo_database_dir) => photo_database_dir,
+        None => return,
+    };
+
+    check_ithmb_files(thumbnails_found, photo_database_dir);
+
+    let thumbnails_dir = std::path::Path::new("thumbnails");
+
+    // Each .ithmb file holds many thumbnails, so only read it once
+    let mut ithmb_files: std::collections::HashMap<String, Option<Vec<u8>>> =
+        std::collections::HashMap::new();
+
+    let mut num_thumbnails_exported = 0;
+
+    for thumbnail in thumbnails_found.iter_mut() {
+        if !thumbnail.is_in_ithmb_file() {
+            continue;
+        }
+
+        let ithmb_file_as_bytes = match ithmb_files
+            .entry(thumbnail.ithmb_filename.to_string())
+            .or_insert_with(|| read_ithmb_file(photo_database_dir, &thumbnail.ithmb_filename))
+        {
+            Some(ithmb_file_as_bytes) => ithmb_file_as_bytes,
+            None => continue,
+        };
+
+        let (bitmap_width, bitmap_height, rgb_pixels) =
+            match ithmb::decode_thumbnail(ithmb_file_as_bytes, thumbnail) {
+                Some(decoded_thumbnail) => decoded_thumbnail,
+                None => {
+                    eprintln!(
+                        "Can't decode thumbnail of image #{} ({}x{}, {} bytes at offset {} of '{}')",
+                        thumbnail.image_num,
+                        thumbnail.width,
+                        thumbnail.height,
+                        thumbnail.image_size_bytes,
+                        thumbnail.ithmb_offset,
+                        thumbnail.ithmb_filename
+                    );
+                    continue;
+                }
+            };
+
+        std::fs::create_dir_all(thumbnails_dir).expect("Can't create thumbnails folder");
+
+        let thumbnail_file_path = thumbnails_dir.join(format!(
+            "{}_{}.{}",
+            thumbnail.image_num, thumbnail.correlation_id, thumbnail_format
+        ));
+
+        if ithmb::write_thumbnail_file(&thumbnail_file_path, bitmap_width, bitmap_height, rgb_pixels) {
+            thumbnail.exported_path = Some(thumbnail_file_path.display().to_string());
+            num_thumbnails_exported += 1;
+        }
+    }
+
+    let num_missing_ithmb_files = ithmb_files
+        .values()
+        .filter(|ithmb_file_as_bytes| ithmb_file_as_bytes.is_none())
+        .count();
+
+    if num_missing_ithmb_files > 0 {
+        eprintln!(
+            "{} .ithmb files weren't found next to the database, their thumbnails were skipped",
+            num_missing_ithmb_files
+        );
+    }
+
+    if ithmb_files.is_empty() {
+        return;
+    }
+
+    println!("Exported {} thumbnails to '{}'", num_thumbnails_exported, thumbnails_dir.display());
+
+    let mut thumbnails_csv_writer = helpers::init_csv_writer("thumbnails.csv");
+
+    thumbnails_csv_writer
+        .write_record([
+            "Image #",
+            "Correlation ID",
+            "Format",
+            "iThmb file",
+            "iThmb Offset",
+            "Size (bytes)",
+            "Width",
+            "Height",
+            "Horizontal padding",
+            "Vertical padding",
+            "Exported file",
+        ])
+        .expect("Can't create CSV headers for thumbnails");
+
+    for thumbnail in thumbnails_found.iter().filter(|thumbnail| thumbnail.is_in_ithmb_file()) {
+        thumbnails_csv_writer
+            .write_record(&[
+                thumbnail.image_num.to_string(),
+                thumbnail.correlation_id.to_string(),
+                ithmb::get_thumbnail_format(thumbnail.correlation_id)
+                    .map(|thumbnail_format| thumbnail_format.description)
+                    .unwrap_or("Unknown")
+                    .to_string(),
+                thumbnail.ithmb_filename.to_string(),
+                thumbnail.ithmb_offset.to_string(),
+                thumbnail.image_size_bytes.to_string(),
+                thumbnail.width.to_string(),
+                thumbnail.height.to_string(),
+                thumbnail.horizontal_padding.to_string(),
+                thumbnail.vertical_padding.to_string(),
+                thumbnail.exported_path.clone().unwrap_or_default(),
+            ])
+            .expect("Can't write row");
+    }
+}
+
+/// Album names become folder names, so they can't have any characters Windows or macOS won't allow
+fn get_safe_folder_name(album_name: &str) -> String {
+    let safe_folder_name: String = album_name
+        .chars()
+        .map(|album_name_char| {
+            if album_name_char.is_control() || "/\\:*?\"<>|".contains(album_name_char) {
+                '_'
+            } else {
+                album_name_char
+            }
+        })
+        .collect();
+
+    return safe_folder_name.trim().trim_end_matches('.').to_string();
+}
+
+/// Every regular album gets its own folder, with its photos in the album's order, e.g. "photos/Vacation/001 - IMG_2626.JPG".
+/// Photos that aren't in any of them go into a "Photo Library" folder (the master album).
+/// If the full resolution original was kept on the iPod it's copied as is,
+/// otherwise the biggest thumbnail that can be decoded is written as a `thumbnail_format` file.
+/// Either way, the Image Item's dates are written into it as EXIF, unless it already has its own
+pub fn export_photo_albums(
+    itunesdb_file_as_bytes: Vec<u8>,
+    output_format: &str,
+    photo_database_dir: Option<&std::path::Path>,
+    thumbnail_format: &str,
+) {
+    let (images_found, thumbnails_found, photo_albums_found) =
+        read_photo_database(&itunesdb_file_as_bytes);
+
+    let photo_database_dir = photo_database_dir.unwrap_or(std::path::Path::new("."));
+    let photos_dir = std::path::Path::new("photos");
+
+    check_ithmb_files(&thumbnails_found, photo_database_dir);
+
+    let image_ids_in_albums: std::collections::HashSet<u32> = photo_albums_found
+        .iter()
+        .filter(|photo_album| !photo_album.is_master)
+        .flat_map(|photo_album| photo_album.image_ids.iter().copied())
+        .collect();
+
+    let mut ithmb_files: std::collections::HashMap<String, Option<Vec<u8>>> =
+        std::collections::HashMap::new();
+
+    let mut folder_names_used: std::collections::HashSet<String> = std::collections::HashSet::new();
+    let mut exported_photos: Vec<photo_database::ExportedPhoto> = Vec::new();
+
+    // Regular albums first, so the master album knows which photos are left
+    let photo_albums_in_export_order = photo_albums_found
+        .iter()
+        .filter(|photo_album| !photo_album.is_master)
+        .chain(photo_albums_found.iter().filter(|photo_album| photo_album.is_master));
+
+    for photo_album in photo_albums_in_export_order {
+        let album_name = if photo_album.is_master {
+            "Photo Library".to_string()
+        } else if get_safe_folder_name(&photo_album.album_name).is_empty() {
+            format!("Album {}", photo_album.album_id)
+        } else {
+            photo_album.album_name.to_string()
+        };
+
+        let mut folder_name = get_safe_folder_name(&album_name);
+
+        // Two albums can have the same name
+        if !folder_names_used.insert(folder_name.to_lowercase()) {
+            folder_name = format!("{} ({})", folder_name, photo_album.album_id);
+            folder_names_used.insert(folder_name.to_lowercase());
+        }
+
+        let album_dir = photos_dir.join(&folder_name);
+
+        let album_image_ids = photo_album
+            .image_ids
+            .iter()
+            .filter(|image_id| !photo_album.is_master || !image_ids_in_albums.contains(image_id));
+
+        for (position_in_album, image_id) in album_image_ids.enumerate() {
+            let image_thumbnails: Vec<&photo_database::Thumbnail> = thumbnails_found
+                .iter()
+                .filter(|thumbnail| thumbnail.image_id == *image_id)
+                .collect();
+
+            let original = image_thumbnails
+                .iter()
+                .find(|thumbnail| !thumbnail.is_in_ithmb_file() && !thumbnail.ithmb_filename.is_empty());
+
+            let original_path = original.map(|original| {
+                itunesdb_helpers::get_canonical_path(original.ithmb_filename.to_string())
+            });
+
+            let original_filename = original_path
+                .as_deref()
+                .and_then(|original_path| original_path.rsplit('/').next())
+                .unwrap_or("")
+                .to_string();
+
+            let file_stem = match original_filename.rsplit_once('.') {
+                Some((file_stem, _)) if !file_stem.is_empty() => file_stem.to_string(),
+                _ => format!("Image {}", image_id),
+            };
+
+            let image = images_found.iter().find(|image| image.image_id == *image_id);
+
+            let mut exported_photo = photo_database::ExportedPhoto {
+                album_name: album_name.to_string(),
+                position_in_album: position_in_album as u32 + 1,
+                image_id: *image_id,
+                original_filename: original_filename.to_string(),
+                source: "".to_string(),
+                original_date_ts: image
+                    .filter(|image| image.original_date_epoch > 0)
+                    .map(|image| image.original_date_ts),
+                digitized_date_ts: image
+                    .filter(|image| image.digitized_date_epoch > 0)
+                    .map(|image| image.digitized_date_ts),
+                exported_path: "".to_string(),
+                has_exif_dates_written: false,
+            };
+
+            std::fs::create_dir_all(&album_dir)
+                .unwrap_or_else(|_| panic!("Can't create folder for album '{}'", album_name));
+
+            // Synced "full resolution" originals are stored relative to the Photos folder, e.g. ":Full Resolution:2010:06:20:IMG_2626.JPG".
+            // Otherwise it's the path on the computer they were synced from
+            let original_file_as_bytes = original_path
+                .as_ref()
+                .and_then(|original_path| std::fs::read(photo_database_dir.join(original_path)).ok());
+
+            if let Some(original_file_as_bytes) = original_file_as_bytes {
+                let photo_file_path = album_dir.join(format!(
+                    "{:03} - {}",
+                    exported_photo.position_in_album, original_filename
+                ));
+
+                std::fs::write(&photo_file_path, original_file_as_bytes)
+                    .unwrap_or_else(|_| panic!("Can't write photo '{}'", photo_file_path.display()));
+
+                exported_photo.source = "Full resolution original".to_string();
+                exported_photo.exported_path = photo_file_path.display().to_string();
+            } else {
+                let mut ithmb_thumbnails: Vec<&&photo_database::Thumbnail> = image_thumbnails
+                    .iter()
+                    .filter(|thumbnail| thumbnail.is_in_ithmb_file())
+                    .collect();
+
+                ithmb_thumbnails.sort_by_key(|thumbnail| {
+                    std::cmp::Reverse(thumbnail.width as u32 * thumbnail.height as u32)
+                });
+
+                for thumbnail in ithmb_thumbnails {
+                    let ithmb_file_as_bytes = match ithmb_files
+                        .entry(thumbnail.ithmb_filename.to_string())
+                        .or_insert_with(|| read_ithmb_file(photo_database_dir, &thumbnail.ithmb_filename))
+                    {
+                        Some(ithmb_file_as_bytes) => ithmb_file_as_bytes,
+                        None => continue,
+                    };
+
+                    let (bitmap_width, bitmap_height, rgb_pixels) =
+                        match ithmb::decode_thumbnail(ithmb_file_as_bytes, thumbnail) {
+                            Some(decoded_thumbnail) => decoded_thumbnail,
+                            None => continue,
+                        };
+
+                    let photo_file_path = album_dir.join(format!(
+                        "{:03} - {}.{}",
+                        exported_photo.position_in_album, file_stem, thumbnail_format
+                    ));
+
+                    if ithmb::write_thumbnail_file(&photo_file_path, bitmap_width, bitmap_height, rgb_pixels) {
+                        exported_photo.source = format!(
+                            "Thumbnail ({}x{}, correlation ID {})",
+                            bitmap_width, bitmap_height, thumbnail.correlation_id
+                        );
+                        exported_photo.exported_path = photo_file_path.display().to_string();
+                        break;
+                    }
+                }
+            }
+
+            if !exported_photo.exported_path.is_empty() {
+                exported_photo.has_exif_dates_written = exif::write_exif_dates(
+                    std::path::Path::new(&exported_photo.exported_path),
+                    exported_photo.original_date_ts,
+                    exported_photo.digitized_date_ts,
+                );
+            } else {
+                eprintln!(
+                    "Image {} ('{}') of album '{}' has neither its original nor a thumbnail on the iPod, it was skipped",
+                    image_id, original_filename, album_name
+                );
+            }
+
+            exported_photos.push(exported_photo);
+        }
+    }
+
+    let num_photos_exported = exported_photos
+        .iter()
+        .filter(|exported_photo| !exported_photo.exported_path.is_empty())
+        .count();
+
+    println!(
+        "Exported {} of {} photos from {} albums to '{}'",
+        num_photos_exported,
+        exported_photos.len(),
+        folder_names_used.len(),
+        photos_dir.display()
+    );
+
+    if exported_photos.is_empty() {
+        return;
+    }
+
+    write_exported_photos_index(&exported_photos, photos_dir, output_format);
+}
+
+/// Lists every photo of every album, including the ones that couldn't be exported
+fn write_exported_photos_index(
+    exported_photos: &[photo_database::ExportedPhoto],
+    photos_dir: &std::path::Path,
+    output_format: &str,
+) {
+    if output_format == "json" {
+        let index_file_path = photos_dir.join("index.json");
+
+        helpers::write_json_file(&index_file_path.display().to_string(), &exported_photos);
+
+        println!("Created {}", index_file_path.display());
+        return;
+    }
+
+    let index_file_path = photos_dir.join("index.csv");
+    let mut index_csv_writer = helpers::init_csv_writer(&index_file_path.display().to_string());
+
+    index_csv_writer
+        .write_record([
+            "Album",
+            "# in album",
+            "Image ID",
+            "Original filename",
+            "Source",
+            "Original Date",
+            "Digitized Date",
+            "Exported file",
+            "EXIF dates written",
+        ])
+        .expect("Can't create CSV headers for exported photos");
+
+    for exported_photo in exported_photos.iter() {
+        index_csv_writer
+            .write_record(&[
+                exported_photo.album_name.to_string(),
+                exported_photo.position_in_album.to_string(),
+                exported_photo.image_id.to_string(),
+                exported_photo.original_filename.to_string(),
+                exported_photo.source.to_string(),
+                exported_photo
+                    .original_date_ts
+                    .map(|original_date_ts| original_date_ts.to_string())
+                    .unwrap_or_default(),
+                exported_photo
+                    .digitized_date_ts
+                    .map(|digitized_date_ts| digitized_date_ts.to_string())
+                    .unwrap_or_default(),
+                exported_photo.exported_path.to_string(),
+                exported_photo.has_exif_dates_written.to_string(),
+            ])
+            .expect("Can't write row");
+    }
+
+    println!("Created {}", index_file_path.display());
 }
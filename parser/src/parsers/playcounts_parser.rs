@@ -1,68 +1,116 @@
-
-
 use crate::constants::playcounts_constants;
-use crate::constants::itunesdb_constants;
 
 use crate::helpers::itunesdb_helpers;
 use crate::helpers::helpers;
+use crate::playcounts;
 
-pub fn parse_playcounts(itunesdb_file_as_bytes: Vec<u8>, mut csv_writer_obj : csv::Writer<std::fs::File>) {
+/// Entries are numbered in file order, which is the order of the track items in the iTunesDB
+pub fn read_playcounts_entries(playcounts_file_as_bytes: &[u8]) -> Vec<playcounts::PlayCountsEntry> {
 
+    let mut playcounts_entries : Vec<playcounts::PlayCountsEntry> = Vec::new();
 
-    let mut idx = 0;
+    if !playcounts_file_as_bytes.starts_with(playcounts_constants::PLAYCOUNTS_OBJECT_KEY.as_bytes()) {
+        eprintln!("Play Counts file doesn't start with a '{}' header", playcounts_constants::PLAYCOUNTS_OBJECT_KEY);
+        return playcounts_entries;
+    }
 
-    while idx < (itunesdb_file_as_bytes.len() - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE) {
+    let pc_entry_len = helpers::get_slice_as_le_u32(0, playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_OFFSET, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_LEN) as usize;
 
-        let playcount_file_heading : &[u8] = &itunesdb_file_as_bytes[idx .. idx + itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE];
+    let num_entries = helpers::get_slice_as_le_u32(0, playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_OFFSET, playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_LEN);
 
-        if playcount_file_heading == playcounts_constants::PLAYCOUNTS_OBJECT_KEY.as_bytes() {
+    println!("Playcounts file has {} songs, and each entry has length {}", num_entries, pc_entry_len);
 
-            let pc_entry_len = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_OFFSET, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_LEN);
+    for track_idx in 0 .. (num_entries as usize) {
 
-            let num_entries = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_OFFSET, playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_LEN);
+        let pc_starting_idx = (track_idx * pc_entry_len) + playcounts_constants::PLAYCOUNTS_FILE_HEADER_LENGTH;
 
-            println!("Playcounts file has {} songs, and each entry has length {}", num_entries, pc_entry_len);
+        if pc_starting_idx + pc_entry_len > playcounts_file_as_bytes.len() {
+            eprintln!("Play Counts file is truncated, only {} of {} entries could be read", track_idx, num_entries);
+            break;
+        }
 
-            println!("===========");
+        let mut playcounts_entry = playcounts::PlayCountsEntry {
+            track_index: track_idx as u32,
+            num_plays: helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_NUM_PLAYS_OFFSET, playcounts_constants::PC_ENTRY_NUM_PLAYS_LEN),
+            last_played_epoch: helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_LAST_PLAYED_TIMESTAMP_OFFSET, playcounts_constants::PC_ENTRY_LAST_PLAYED_TIMESTAMP_LEN),
+            audio_bookmark_ms: helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_AUDIO_BOOKMARK_MS_OFFSET, playcounts_constants::PC_ENTRY_AUDIO_BOOKMARK_MS_LEN),
+            ..playcounts::PlayCountsEntry::default()
+        };
+
+        // Each field only exists if the entries are long enough to hold it
+        if pc_entry_len >= playcounts_constants::PC_ENTRY_RATING_OFFSET + playcounts_constants::PC_ENTRY_RATING_LEN {
+            playcounts_entry.rating_raw = Some(helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_RATING_OFFSET, playcounts_constants::PC_ENTRY_RATING_LEN) as u8);
+        }
 
-            if num_entries > 1 {
+        if pc_entry_len >= playcounts_constants::PC_ENTRY_NUM_SKIPS_OFFSET + playcounts_constants::PC_ENTRY_NUM_SKIPS_LEN {
+            playcounts_entry.num_skips = helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_NUM_SKIPS_OFFSET, playcounts_constants::PC_ENTRY_NUM_SKIPS_LEN);
+        }
 
-                csv_writer_obj.write_record(
-                    &["# of times played (since last sync)",
-                "# of times skipped (since last sync)",
-                "Rating",
-                "Last played timestamp",
-                "Last played (epoch)",
-                "Audio playback bookmark (ms)"]).expect("Error creating header column in CSV");
-            }
+        if pc_entry_len >= playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_OFFSET + playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_LEN {
+            playcounts_entry.last_skipped_epoch = helpers::get_slice_as_le_u32(pc_starting_idx, playcounts_file_as_bytes, playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_OFFSET, playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_LEN);
+        }
 
-            for track_idx in 0 .. (num_entries as usize) {
+        playcounts_entries.push(playcounts_entry);
+    }
 
-                let pc_starting_idx = (track_idx * pc_entry_len as usize) + playcounts_constants::PLAYCOUNTS_FILE_HEADER_LENGTH;
+    return playcounts_entries;
+}
 
-                let num_plays = helpers::get_slice_as_le_u32(idx + pc_starting_idx, &itunesdb_file_as_bytes, playcounts_constants::PC_ENTRY_NUM_PLAYS_OFFSET, playcounts_constants::PC_ENTRY_NUM_PLAYS_LEN);
+pub fn parse_playcounts(itunesdb_file_as_bytes: Vec<u8>, mut csv_writer_obj : helpers::CsvWriter) {
 
-                let num_skips : u32 = helpers::get_slice_as_le_u32(idx + pc_starting_idx, &itunesdb_file_as_bytes, playcounts_constants::PC_ENTRY_NUM_SKIPS_OFFSET, playcounts_constants::PC_ENTRY_NUM_SKIPS_LEN);
-                
-                let raw_rating = helpers::get_slice_as_le_u32(idx + pc_starting_idx, &itunesdb_file_as_bytes, playcounts_constants::PC_ENTRY_RATING_OFFSET, playcounts_constants::PC_ENTRY_RATING_LEN) as u8;
+    let playcounts_entries = read_playcounts_entries(&itunesdb_file_as_bytes);
 
-                let last_played_timestamp = helpers::get_slice_as_le_u64(idx + pc_starting_idx, &itunesdb_file_as_bytes, playcounts_constants::PC_ENTRY_AUDIO_BOOKMARK_MS_OFFSET, playcounts_constants::PC_ENTRY_AUDIO_BOOKMARK_MS_LEN);
-                
-                let audio_bookmark_ms = helpers::get_slice_as_le_u32(idx + pc_starting_idx, &itunesdb_file_as_bytes, playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_OFFSET, playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_LEN);
+    println!("===========");
 
-                //println!("Song ID #{} of {} has been played {} times, skipped {} times, and has rating {} ", track_idx, num_entries, num_plays, num_skips, itunesdb_helpers::decode_itunes_stars(raw_track_rating as u8));
+    if playcounts_entries.len() > 1 {
 
-                // Vectors have to be all the same type, hence the `to_string()`
-                csv_writer_obj.write_record(
-                    &[num_plays.to_string(),
-                     num_skips.to_string(),
-                     itunesdb_helpers::decode_itunes_stars(raw_rating),
-                       helpers::get_timestamp_as_mac(last_played_timestamp).to_string(),
-                        last_played_timestamp.to_string(),
-                        audio_bookmark_ms.to_string()]).expect("Unable to write row");
-            }
-        }
+        csv_writer_obj.write_record(
+            ["# of times played (since last sync)",
+        "# of times skipped (since last sync)",
+        "Rating",
+        "Last played timestamp",
+        "Last played (epoch)",
+        "Audio playback bookmark (ms)"]).expect("Error creating header column in CSV");
+    }
 
-        idx += itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+    for playcounts_entry in playcounts_entries.iter() {
+
+        let last_played_timestamp = if playcounts_entry.last_played_epoch > 0 {
+            helpers::get_timestamp_as_mac(playcounts_entry.last_played_epoch as u64).to_string()
+        } else {
+            "".to_string()
+        };
+
+        // Vectors have to be all the same type, hence the `to_string()`
+        csv_writer_obj.write_record(
+            &[playcounts_entry.num_plays.to_string(),
+             playcounts_entry.num_skips.to_string(),
+             itunesdb_helpers::decode_itunes_stars(playcounts_entry.rating_raw.unwrap_or(0)),
+               last_played_timestamp,
+                playcounts_entry.last_played_epoch.to_string(),
+                playcounts_entry.audio_bookmark_ms.to_string()]).expect("Unable to write row");
     }
-}
\ No newline at end of file
+}
+
+/// Writes a copy of the Play Counts file with everything since the last sync zeroed (see `reset_playcounts_entries`),
+/// with as many entries, of the same length, as the original. Copied onto the iPod in place of
+/// "iPod_Control/iTunes/Play Counts", it clears the stats iTunes would otherwise pick up on the next sync
+pub fn reset_playcounts_file(playcounts_file_as_bytes: Vec<u8>, reset_playcounts_filename: &str) {
+
+    let playcounts_entries = read_playcounts_entries(&playcounts_file_as_bytes);
+
+    if playcounts_entries.is_empty() {
+        eprintln!("No Play Counts entries to reset");
+        return;
+    }
+
+    let pc_entry_len = helpers::get_slice_as_le_u32(0, &playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_OFFSET, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_LEN) as usize;
+
+    let num_entries_played = playcounts_entries.iter().filter(|playcounts_entry| playcounts_entry.num_plays > 0 || playcounts_entry.num_skips > 0).count();
+
+    let reset_playcounts_file_as_bytes = playcounts::build_playcounts_file(&playcounts::reset_playcounts_entries(&playcounts_entries), pc_entry_len);
+
+    std::fs::write(reset_playcounts_filename, reset_playcounts_file_as_bytes).expect("Can't write reset Play Counts file");
+
+    println!("Created '{}' with {} entries ({} of them had plays or skips)", reset_playcounts_filename, playcounts_entries.len(), num_entries_played);
+}
@@ -1,4 +1,3 @@
-
 use crate::constants::itunesprefs_constants;
 use crate::constants::preferences_constants;
 use crate::constants::itunesdb_constants;
@@ -8,86 +7,111 @@ use crate::preferences;
 
 use crate::helpers::helpers;
 
-pub fn parse_itunes_prefs_file(itunesdb_file_as_bytes: Vec<u8>) {
+/// Returns `None` if there's no iTunesPrefs object (frpd) in the file
+pub fn read_itunes_prefs(itunesdb_file_as_bytes: &[u8]) -> Option<itunesprefs::ItunesPrefs> {
 
     let mut idx : usize = 0;
 
-    while idx < (itunesdb_file_as_bytes.len() - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE) {
+    while idx + itunesprefs_constants::ITUNESPREFS_OBJECT_LAST_OFFSET < itunesdb_file_as_bytes.len() {
 
         let itunespref_file_heading : &[u8] = &itunesdb_file_as_bytes[idx .. idx + itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE];
 
         if itunespref_file_heading == itunesprefs_constants::ITUNESPREF_OBJECT_KEY.as_bytes() {
 
-            let ipod_is_setup : bool = itunesprefs::has_ipod_been_initialized(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::IPOD_SET_UP_YET_SETTING_OFFSET, itunesprefs_constants::IPOD_SET_UP_YET_SETTING_LEN));
-
-            println!("iPod {} been setup yet", if ipod_is_setup {"has"} else { "has NOT" });
+            let only_update_checked_songs_setting_raw : u32 = helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::ONLY_UPDATE_CHECKED_SONGS_SETTING_OFFSET, itunesprefs_constants::ONLY_UPDATE_CHECKED_SONGS_SETTING_LEN);
+
+            return Some(itunesprefs::ItunesPrefs {
+                is_ipod_set_up: itunesprefs::has_ipod_been_initialized(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::IPOD_SET_UP_YET_SETTING_OFFSET, itunesprefs_constants::IPOD_SET_UP_YET_SETTING_LEN)),
+                auto_open_itunes: itunesprefs::auto_open_itunes_enabled(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::AUTO_OPEN_ITUNES_SETTING_OFFSET, itunesprefs_constants::AUTO_OPEN_ITUNES_SETTING_LEN)),
+                song_sync_mode: itunesprefs::decode_sync_automation_level(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::SONG_SYNC_AUTOMATION_LEVEL_SETTING_OFFSET, itunesprefs_constants::SONG_SYNC_AUTOMATION_LEVEL_SETTING_LEN)),
+                podcast_sync_mode: itunesprefs::decode_sync_automation_level(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::PODCAST_SYNC_AUTOMATION_LEVEL_SETTING_OFFSET, itunesprefs_constants::PODCAST_SYNC_AUTOMATION_LEVEL_SETTING_LEN)),
+                sync_selection: itunesprefs::decode_sync_selection(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::SYNC_SELECTION_SETTING_OFFSET, itunesprefs_constants::SYNC_SELECTION_SETTING_LEN)),
+                only_update_checked_songs: only_update_checked_songs_setting_raw == 1,
+                disk_use_enabled: itunesprefs::disk_use_enabled(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::ENABLE_DISK_USE_SETTING_OFFSET, itunesprefs_constants::ENABLE_DISK_USE_SETTING_LEN)),
+                show_artwork: itunesprefs::should_show_artwork(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, itunesprefs_constants::SHOW_ARTWORK_SETTING_OFFSET, itunesprefs_constants::SHOW_ARTWORK_SETTING_LEN)),
+            });
+        }
 
-            let auto_open_itunes_setting : bool = itunesprefs::auto_open_itunes_enabled(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::AUTO_OPEN_ITUNES_SETTING_OFFSET, itunesprefs_constants::AUTO_OPEN_ITUNES_SETTING_LEN));
+        idx += itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+    }
 
-            println!("Automatically open iTunes when iPod is plugged in? {}", if auto_open_itunes_setting {" Yes "} else { "No" });
+    return None;
+}
 
-            let song_sync_type : String = itunesprefs::decode_sync_automation_level(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::SONG_SYNC_AUTOMATION_LEVEL_SETTING_OFFSET, itunesprefs_constants::SONG_SYNC_AUTOMATION_LEVEL_SETTING_LEN));
+pub fn parse_itunes_prefs_file(itunesdb_file_as_bytes: Vec<u8>) {
 
-            let podcast_sync_type : String = itunesprefs::decode_sync_automation_level(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::PODCAST_SYNC_AUTOMATION_LEVEL_SETTING_OFFSET, itunesprefs_constants::PODCAST_SYNC_AUTOMATION_LEVEL_SETTING_LEN));
+    let itunes_prefs = read_itunes_prefs(&itunesdb_file_as_bytes).expect("No iTunesPrefs object found in file");
 
-            print!("Podcast sync type: {} | Song sync type: {} ", podcast_sync_type, song_sync_type);
+    println!("iPod {} been setup yet", if itunes_prefs.is_ipod_set_up {"has"} else { "has NOT" });
 
-            let only_update_checked_songs_setting_raw : u32 = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::ONLY_UPDATE_CHECKED_SONGS_SETTING_OFFSET, itunesprefs_constants::ONLY_UPDATE_CHECKED_SONGS_SETTING_LEN);
+    println!("Automatically open iTunes when iPod is plugged in? {}", if itunes_prefs.auto_open_itunes {" Yes "} else { "No" });
 
-            if only_update_checked_songs_setting_raw == 1 {
-                println!("(Warning: only updating checked songs!)");
-            } else {
-                print!("\n");
-            }
+    print!("Podcast sync type: {} | Song sync type: {} ", itunes_prefs.podcast_sync_mode, itunes_prefs.song_sync_mode);
 
-            let sync_selection_setting : String = itunesprefs::decode_sync_selection(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::SYNC_SELECTION_SETTING_OFFSET, itunesprefs_constants::SYNC_SELECTION_SETTING_LEN));
+    if itunes_prefs.only_update_checked_songs {
+        println!("(Warning: only updating checked songs!)");
+    } else {
+        println!();
+    }
 
-            println!("Sync Selection setting: {}", sync_selection_setting);
+    println!("Manually manage music? {}", if itunes_prefs.is_music_manually_managed() { "Yes" } else { "No" });
 
-            let disk_use_setting : bool = itunesprefs::disk_use_enabled(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::ENABLE_DISK_USE_SETTING_OFFSET, itunesprefs_constants::ENABLE_DISK_USE_SETTING_LEN));
+    println!("Sync Selection setting: {}", itunes_prefs.sync_selection);
 
-            println!("Allow disk use? {}", if disk_use_setting {" Yes "} else { "No" });
+    println!("Allow disk use? {}", if itunes_prefs.disk_use_enabled {" Yes "} else { "No" });
 
-            let show_artwork_setting = itunesprefs::should_show_artwork(helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, itunesprefs_constants::SHOW_ARTWORK_SETTING_OFFSET, itunesprefs_constants::SHOW_ARTWORK_SETTING_LEN));
+    println!("Show album artwork? {}", if itunes_prefs.show_artwork { "Yes" } else { "No" });
+}
 
-            println!("Show album artwork? {}", if show_artwork_setting { "Yes" } else { "No" });
+/// Returns `None` if the file is too short to be a Preferences file
+pub fn read_preferences(itunesdb_file_as_bytes: &[u8]) -> Option<preferences::DevicePreferences> {
 
-            idx += itunesprefs_constants::ITUNESPREFS_OBJECT_LAST_OFFSET;
-            
-        }
+    let idx : usize = 0;
 
-        idx += itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE;
+    if itunesdb_file_as_bytes.len() < preferences_constants::VOLUME_LIMIT_OFFSET + preferences_constants::VOLUME_LIMIT_LEN {
+        eprintln!("Preferences file is too short ({} bytes)", itunesdb_file_as_bytes.len());
+        return None;
     }
 
-}
-
-pub fn parse_preferences_file(itunesdb_file_as_bytes: Vec<u8>) {
+    let dst_setting_raw = helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, preferences_constants::DST_SETTING_OFFSET, preferences_constants::DST_SETTING_LEN);
 
+    let lang_selection_idx = helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, preferences_constants::LANGUAGE_SELECTION_OFFSET, preferences_constants::LANGUAGE_SELECTION_LEN);
 
-    let idx : usize = 0;
+    let tz_info_raw = helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, preferences_constants::TIMEZONE_INFO_OFFSET, preferences_constants::TIMEZONE_INFO_LEN);
 
-    if idx < (itunesdb_file_as_bytes.len() - itunesdb_constants::DEFAULT_SUBSTRUCTURE_SIZE) {
+    let volume_limit = helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, preferences_constants::VOLUME_LIMIT_OFFSET, preferences_constants::VOLUME_LIMIT_LEN);
 
-        let dst_setting_raw = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, preferences_constants::DST_SETTING_OFFSET, preferences_constants::DST_SETTING_LEN);
+    let region_raw = if itunesdb_file_as_bytes.len() >= preferences_constants::REGION_OFFSET + preferences_constants::REGION_LEN {
+        Some(helpers::get_slice_as_le_u32(idx, itunesdb_file_as_bytes, preferences_constants::REGION_OFFSET, preferences_constants::REGION_LEN))
+    } else {
+        None
+    };
 
-        println!("Daylight Savings Time enabled?: {}", preferences::is_daylight_savings_enabled(dst_setting_raw as u8));
+    return Some(preferences::DevicePreferences {
+        is_daylight_savings_enabled: preferences::is_daylight_savings_enabled(dst_setting_raw as u8),
+        language: preferences::decode_language_from_idx(lang_selection_idx as u8),
+        timezone_raw: tz_info_raw as u8,
+        timezone_gmt_offset: preferences::decode_timezone(tz_info_raw as u8),
+        volume_limit: if volume_limit != 0 { Some(volume_limit) } else { None },
+        region_raw,
+    });
+}
 
-        let lang_selection_idx = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, preferences_constants::LANGUAGE_SELECTION_OFFSET, preferences_constants::LANGUAGE_SELECTION_LEN);
+pub fn parse_preferences_file(itunesdb_file_as_bytes: Vec<u8>) {
 
-        println!("Selected language idx: {} ~ Parses to '{}'", lang_selection_idx, preferences::decode_language_from_idx(lang_selection_idx as u8));
+    let device_preferences = read_preferences(&itunesdb_file_as_bytes).expect("Can't read Preferences file");
 
-        let tz_info_raw = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, preferences_constants::TIMEZONE_INFO_OFFSET, preferences_constants::TIMEZONE_INFO_LEN);
+    println!("Daylight Savings Time enabled?: {}", device_preferences.is_daylight_savings_enabled);
 
-        println!("Raw timezone value: {} | Calculated timezone : GMT+'{}'", tz_info_raw, preferences::decode_timezone(tz_info_raw as u8));
+    println!("Selected language: '{}'", device_preferences.language);
 
-        let volume_limit = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, preferences_constants::VOLUME_LIMIT_OFFSET, preferences_constants::VOLUME_LIMIT_LEN);
-        
-        if volume_limit != 0 {
-            println!("Volume limit (if enabled): {} ", volume_limit);
-        }
+    println!("Raw timezone value: {} | Calculated timezone : GMT+'{}'", device_preferences.timezone_raw, device_preferences.timezone_gmt_offset);
 
-        let region_info = helpers::get_slice_as_le_u32(idx, &itunesdb_file_as_bytes, preferences_constants::REGION_OFFSET, preferences_constants::REGION_LEN);
+    if let Some(volume_limit) = device_preferences.volume_limit {
+        println!("Volume limit (if enabled): {} ", volume_limit);
+    }
 
-        println!("Raw region info: '{}'", region_info);
+    if let Some(region_raw) = device_preferences.region_raw {
+        println!("Raw region info: '{}'", region_raw);
     }
-}
\ No newline at end of file
+}
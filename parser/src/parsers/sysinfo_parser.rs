@@ -0,0 +1,91 @@
+use crate::constants::sysinfo_constants;
+
+use crate::ipod_device_info;
+use crate::parsers::sysinfoextended_parser;
+
+/// Values like "0x06128000 (6.1.2)" have the readable version in parentheses
+fn get_value_in_parentheses(sysinfo_value: &str) -> Option<String> {
+    let (_, value_in_parentheses) = sysinfo_value.split_once('(')?;
+    let (value_in_parentheses, _) = value_in_parentheses.split_once(')')?;
+
+    return Some(value_in_parentheses.trim().to_string());
+}
+
+/// Unknown keys are ignored, and missing ones are left empty
+pub fn read_sysinfo(sysinfo_file_as_bytes: &[u8]) -> ipod_device_info::SysInfo {
+    let mut sysinfo = ipod_device_info::SysInfo::default();
+
+    let mut build_id = "".to_string();
+
+    for sysinfo_line in String::from_utf8_lossy(sysinfo_file_as_bytes).lines() {
+        let (sysinfo_key, sysinfo_value) = match sysinfo_line.split_once(sysinfo_constants::SYSINFO_KEY_VALUE_SEPARATOR) {
+            Some((sysinfo_key, sysinfo_value)) => (sysinfo_key.trim(), sysinfo_value.trim()),
+            None => continue,
+        };
+
+        // The capitalization changed between firmware versions
+        if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_FIREWIRE_GUID_KEY) {
+            sysinfo.firewire_guid = sysinfo_value.trim_start_matches("0x").to_uppercase();
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_MODEL_NUMBER_KEY) {
+            sysinfo.model_number = ipod_device_info::get_model_number(sysinfo_value);
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_SERIAL_NUMBER_KEY) {
+            sysinfo.serial_number = sysinfo_value.to_string();
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_VISIBLE_BUILD_ID_KEY) {
+            sysinfo.firmware_version = get_value_in_parentheses(sysinfo_value).unwrap_or(sysinfo_value.to_string());
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_BUILD_ID_KEY) {
+            build_id = get_value_in_parentheses(sysinfo_value).unwrap_or(sysinfo_value.to_string());
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_BOARD_NAME_KEY) {
+            sysinfo.board_name = sysinfo_value.to_string();
+        } else if sysinfo_key.eq_ignore_ascii_case(sysinfo_constants::SYSINFO_BOARD_REVISION_KEY) {
+            sysinfo.board_revision = get_value_in_parentheses(sysinfo_value).unwrap_or(sysinfo_value.to_string());
+        }
+    }
+
+    // Older firmware only has the one build ID
+    if sysinfo.firmware_version.is_empty() {
+        sysinfo.firmware_version = build_id;
+    }
+
+    return sysinfo;
+}
+
+/// What the iPod mounted there says about itself, from iPod_Control/Device/SysInfo and SysInfoExtended.
+/// None if it has neither
+pub fn read_device_sysinfo(ipod_mount_point: &str) -> Option<ipod_device_info::SysInfo> {
+    let sysinfo_file_path = std::path::Path::new(ipod_mount_point)
+        .join("iPod_Control")
+        .join("Device")
+        .join(sysinfo_constants::SYSINFO_FILENAME);
+
+    let sysinfo_extended_file_path = sysinfo_file_path.with_file_name(sysinfo_constants::SYSINFO_EXTENDED_FILENAME);
+
+    let mut sysinfo = std::fs::read(&sysinfo_file_path)
+        .ok()
+        .map(|sysinfo_file_as_bytes| read_sysinfo(&sysinfo_file_as_bytes));
+
+    // Newer iPods don't always have SysInfo, but SysInfoExtended is exact, so it wins either way
+    if let Ok(sysinfo_extended_file_as_bytes) = std::fs::read(&sysinfo_extended_file_path) {
+        let mut sysinfo_extended = sysinfo.take().unwrap_or_default();
+
+        sysinfoextended_parser::read_sysinfo_extended(&sysinfo_extended_file_as_bytes, &mut sysinfo_extended);
+
+        sysinfo = Some(sysinfo_extended);
+    }
+
+    return sysinfo;
+}
+
+pub fn parse_sysinfo_file(sysinfo_file_as_bytes: Vec<u8>) {
+
+    let sysinfo = read_sysinfo(&sysinfo_file_as_bytes);
+
+    println!("===========");
+    let ipod_device_info = ipod_device_info::IpodDeviceInfo::from_serial(&sysinfo.serial_number);
+
+    println!("Model number: {} ({})", sysinfo.model_number, ipod_device_info::decode_model_number(&sysinfo.model_number).map(|ipod_model| ipod_model.get_description()).unwrap_or("unknown model".to_string()));
+    println!("Model (from the serial number): {}", ipod_device_info.model.map(|ipod_model| ipod_model.get_description()).unwrap_or("unknown".to_string()));
+    println!("Serial number: {}", sysinfo.serial_number);
+    println!("FireWire GUID: {}", sysinfo.firewire_guid);
+    println!("Firmware version: {}", sysinfo.firmware_version);
+    println!("Board: {} ({})", sysinfo.board_name, sysinfo.board_revision);
+}
@@ -0,0 +1,120 @@
+use crate::constants::sysinfo_constants;
+
+use crate::helpers::plist_helpers;
+use crate::ipod_device_info;
+
+/// Build IDs are BCD, e.g. 0x01128000 is "1.1.2"
+fn decode_build_id(build_id: u32) -> String {
+    return format!(
+        "{:x}.{:x}.{:x}",
+        (build_id >> 24) & 0xFF,
+        (build_id >> 20) & 0xF,
+        (build_id >> 16) & 0xF
+    );
+}
+
+/// Four character codes are stored as integers, e.g. 0x4C353635 is "L565"
+fn decode_pixel_format(pixel_format_raw: &plist_helpers::PlistValue) -> String {
+    if let Some(pixel_format_raw) = pixel_format_raw.as_integer() {
+        let pixel_format_bytes = (pixel_format_raw as u32).to_be_bytes();
+
+        if pixel_format_bytes.iter().all(|byte| byte.is_ascii_graphic()) {
+            return String::from_utf8_lossy(&pixel_format_bytes).to_string();
+        }
+
+        return pixel_format_raw.to_string();
+    }
+
+    return pixel_format_raw.as_string().unwrap_or_default();
+}
+
+fn read_artwork_formats(
+    sysinfo_extended: &plist_helpers::PlistValue,
+    artwork_formats_key: &str,
+    usage: &str,
+) -> Vec<ipod_device_info::ArtworkFormat> {
+    let artwork_format_dicts = match sysinfo_extended.get(artwork_formats_key).and_then(|artwork_formats| artwork_formats.as_array()) {
+        Some(artwork_format_dicts) => artwork_format_dicts,
+        None => return Vec::new(),
+    };
+
+    let get_u32 = |artwork_format_dict: &plist_helpers::PlistValue, key: &str| -> u32 {
+        return artwork_format_dict.get(key).and_then(|value| value.as_integer()).unwrap_or(0) as u32;
+    };
+
+    return artwork_format_dicts
+        .iter()
+        .map(|artwork_format_dict| ipod_device_info::ArtworkFormat {
+            usage: usage.to_string(),
+            format_id: get_u32(artwork_format_dict, sysinfo_constants::SYSINFO_EXTENDED_FORMAT_ID_KEY),
+            width: get_u32(artwork_format_dict, sysinfo_constants::SYSINFO_EXTENDED_FORMAT_WIDTH_KEY),
+            height: get_u32(artwork_format_dict, sysinfo_constants::SYSINFO_EXTENDED_FORMAT_HEIGHT_KEY),
+            pixel_format: artwork_format_dict
+                .get(sysinfo_constants::SYSINFO_EXTENDED_FORMAT_PIXEL_FORMAT_KEY)
+                .map(decode_pixel_format)
+                .unwrap_or_default(),
+        })
+        .collect();
+}
+
+/// Its values are exact, so they replace whatever SysInfo said. Returns `false` (leaving `sysinfo` as it was)
+/// if it isn't a readable plist
+pub fn read_sysinfo_extended(sysinfo_extended_file_as_bytes: &[u8], sysinfo: &mut ipod_device_info::SysInfo) -> bool {
+
+    let sysinfo_extended = match plist_helpers::parse_xml_plist(&String::from_utf8_lossy(sysinfo_extended_file_as_bytes)) {
+        Some(sysinfo_extended) => sysinfo_extended,
+        None => {
+            eprintln!("SysInfoExtended isn't an XML plist, it was skipped");
+            return false;
+        }
+    };
+
+    if let Some(serial_number) = sysinfo_extended.get(sysinfo_constants::SYSINFO_EXTENDED_SERIAL_NUMBER_KEY).and_then(|value| value.as_string()) {
+        sysinfo.serial_number = serial_number;
+    }
+
+    if let Some(model_number) = sysinfo_extended.get(sysinfo_constants::SYSINFO_EXTENDED_MODEL_NUMBER_KEY).and_then(|value| value.as_string()) {
+        sysinfo.model_number = ipod_device_info::get_model_number(&model_number);
+    }
+
+    if let Some(firewire_guid) = sysinfo_extended.get(sysinfo_constants::SYSINFO_EXTENDED_FIREWIRE_GUID_KEY).and_then(|value| value.as_string()) {
+        sysinfo.firewire_guid = firewire_guid.trim_start_matches("0x").to_uppercase();
+    }
+
+    if let Some(visible_build_id) = sysinfo_extended.get(sysinfo_constants::SYSINFO_EXTENDED_VISIBLE_BUILD_ID_KEY).and_then(|value| value.as_integer()) {
+        sysinfo.firmware_version = decode_build_id(visible_build_id as u32);
+    }
+
+    sysinfo.artwork_formats = [
+        (sysinfo_constants::SYSINFO_EXTENDED_ALBUM_ART_KEY, "Album art"),
+        (sysinfo_constants::SYSINFO_EXTENDED_PHOTO_FORMATS_KEY, "Photo"),
+        (sysinfo_constants::SYSINFO_EXTENDED_CHAPTER_ART_KEY, "Chapter art"),
+    ]
+    .iter()
+    .flat_map(|(artwork_formats_key, usage)| read_artwork_formats(&sysinfo_extended, artwork_formats_key, usage))
+    .collect();
+
+    return true;
+}
+
+pub fn parse_sysinfo_extended_file(sysinfo_extended_file_as_bytes: Vec<u8>) {
+
+    let mut sysinfo = ipod_device_info::SysInfo::default();
+
+    if !read_sysinfo_extended(&sysinfo_extended_file_as_bytes, &mut sysinfo) {
+        return;
+    }
+
+    println!("===========");
+    let ipod_device_info = ipod_device_info::IpodDeviceInfo::from_serial(&sysinfo.serial_number);
+
+    println!("Model number: {} ({})", sysinfo.model_number, ipod_device_info::decode_model_number(&sysinfo.model_number).map(|ipod_model| ipod_model.get_description()).unwrap_or("unknown model".to_string()));
+    println!("Model (from the serial number): {}", ipod_device_info.model.map(|ipod_model| ipod_model.get_description()).unwrap_or("unknown".to_string()));
+    println!("Serial number: {}", sysinfo.serial_number);
+    println!("FireWire GUID: {}", sysinfo.firewire_guid);
+    println!("Firmware version: {}", sysinfo.firmware_version);
+
+    for artwork_format in sysinfo.artwork_formats.iter() {
+        println!("{} format {}: {}x{} {}", artwork_format.usage, artwork_format.format_id, artwork_format.width, artwork_format.height, artwork_format.pixel_format);
+    }
+}
@@ -4,10 +4,13 @@
  * Provides functionality around working with the Photo Database internals file. Photo analogue of 'itunesdb.rs'
  * http://www.ipodlinux.org/ITunesDB/#Photo_Database
  */
+use serde::Serialize;
+
 use crate::helpers::helpers;
 use crate::helpers::itunesdb_helpers;
 
 pub struct Image {
+    pub image_id: u32,
     pub filename: String,
     /// iPod's filesystem is FAT
     pub file_size_bytes: u32,
@@ -16,7 +19,95 @@ pub struct Image {
     pub original_date_ts: chrono::DateTime<chrono::Utc>,
     pub digitized_date_epoch: u64,
     pub digitized_date_ts: chrono::DateTime<chrono::Utc>,
-    pub ithmb_offset: u32
+    pub ithmb_offset: u32,
+    pub correlation_id: u32
+}
+
+/// One of the sizes an image is stored in, i.e. one Image Name (mhni).
+/// The bitmap is `image_size_bytes` long, starting at `ithmb_offset` in the .ithmb file
+pub struct Thumbnail {
+    /// Counts the Image Items (mhii), so all sizes of the same image share it
+    pub image_num: u32,
+    /// ID of the Image Item it belongs to
+    pub image_id: u32,
+    /// dbid of the track it's the cover art of (ArtworkDB only)
+    pub song_dbid: u64,
+    pub correlation_id: u32,
+    /// As stored, e.g. ":Thumbs:F1019_1.ithmb"
+    pub ithmb_filename: String,
+    pub ithmb_offset: u32,
+    pub image_size_bytes: u32,
+    pub width: u16,
+    pub height: u16,
+    pub horizontal_padding: i16,
+    pub vertical_padding: i16,
+    /// Where the decoded thumbnail was written to, if it was
+    pub exported_path: Option<String>,
+}
+
+impl Default for Thumbnail {
+    fn default() -> Thumbnail {
+        return Thumbnail {
+            image_num: 0,
+            image_id: 0,
+            song_dbid: 0,
+            correlation_id: 0,
+            ithmb_filename: "".to_string(),
+            ithmb_offset: 0,
+            image_size_bytes: 0,
+            width: 0,
+            height: 0,
+            horizontal_padding: 0,
+            vertical_padding: 0,
+            exported_path: None,
+        };
+    }
+}
+
+/// A Photo Album (mhba), and the IDs of the Image Items in it, in order
+pub struct PhotoAlbum {
+    pub album_id: u32,
+    pub album_name: String,
+    /// The "Photo Library", which has every image in it
+    pub is_master: bool,
+    pub image_ids: Vec<u32>,
+}
+
+impl Default for PhotoAlbum {
+    fn default() -> PhotoAlbum {
+        return PhotoAlbum {
+            album_id: 0,
+            album_name: "".to_string(),
+            is_master: false,
+            image_ids: Vec::new(),
+        };
+    }
+}
+
+/// One photo written out by the "export-photos" type
+#[derive(Serialize)]
+pub struct ExportedPhoto {
+    pub album_name: String,
+    /// Starts at 1
+    pub position_in_album: u32,
+    pub image_id: u32,
+    /// Name of the file the photo was synced from, e.g. "IMG_2626.JPG"
+    pub original_filename: String,
+    /// Either the full resolution original, or (if it wasn't kept on the iPod) the biggest thumbnail
+    pub source: String,
+    pub original_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+    pub digitized_date_ts: Option<chrono::DateTime<chrono::Utc>>,
+    /// Empty if neither the original nor a thumbnail could be found
+    pub exported_path: String,
+    /// Originals that already had EXIF are left alone
+    pub has_exif_dates_written: bool,
+}
+
+impl Thumbnail {
+    /// The full resolution originals are regular files (e.g. JPEGs), not bitmaps in an .ithmb file
+    pub fn is_in_ithmb_file(&self) -> bool {
+        return self.ithmb_filename.to_lowercase().ends_with(".ithmb");
+    }
 }
 
 /// Allows instantiation of a "default" Image,
@@ -25,6 +116,7 @@ pub struct Image {
 impl Default for Image {
     fn default() -> Image {
         return Image {
+            image_id: 0,
             filename: "".to_string(),
             file_size_bytes: 0,
             file_size_human_readable: "".to_string(),
@@ -32,7 +124,8 @@ impl Default for Image {
             original_date_ts: helpers::get_timestamp_as_mac(0),
             digitized_date_epoch: 0,
             digitized_date_ts: helpers::get_timestamp_as_mac(0),
-            ithmb_offset : 0
+            ithmb_offset : 0,
+            correlation_id: 0
         };
     }
 }
@@ -0,0 +1,259 @@
+/**
+ * File: playcounts.rs
+ *
+ * The iPod never writes to the iTunesDB itself. Whatever happens on the device between two syncs
+ * (plays, skips, ratings, bookmarks) goes into the "Play Counts" file instead, which has one entry
+ * per track item, in the same order as the iTunesDB's Track List.
+ *
+ */
+use serde::Serialize;
+
+use crate::constants::playcounts_constants;
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+#[derive(Serialize)]
+pub struct PlayCountsEntry {
+    /// Position of the track item in the iTunesDB this entry belongs to
+    pub track_index: u32,
+    /// Everything is counted from the last sync
+    pub num_plays: u32,
+    /// Mac timestamp, 0 if not played since the last sync
+    pub last_played_epoch: u32,
+    pub audio_bookmark_ms: u32,
+    /// Older iPods write shorter entries, without a rating or skip info
+    pub rating_raw: Option<u8>,
+    pub num_skips: u32,
+    /// Mac timestamp, 0 if not skipped since the last sync
+    pub last_skipped_epoch: u32,
+}
+
+impl Default for PlayCountsEntry {
+    fn default() -> PlayCountsEntry {
+        return PlayCountsEntry {
+            track_index: 0,
+            num_plays: 0,
+            last_played_epoch: 0,
+            audio_bookmark_ms: 0,
+            rating_raw: None,
+            num_skips: 0,
+            last_skipped_epoch: 0,
+        };
+    }
+}
+
+/// Adds the plays and skips since the last sync to the songs' totals, and takes over the
+/// last played/skipped times, ratings and bookmarks from the device.
+/// Returns how many songs had an entry
+pub fn merge_playcounts_into_songs(
+    playcounts_entries: &[PlayCountsEntry],
+    songs: &mut [itunesdb::Song],
+) -> usize {
+    let mut num_songs_merged = 0;
+
+    for song in songs.iter_mut() {
+        let playcounts_entry = match playcounts_entries.get(song.track_index as usize) {
+            Some(playcounts_entry) => playcounts_entry,
+            None => continue,
+        };
+
+        song.num_plays += playcounts_entry.num_plays;
+        song.num_skips += playcounts_entry.num_skips;
+
+        if playcounts_entry.last_played_epoch > 0 {
+            song.last_played_at = Some(helpers::get_timestamp_as_mac(
+                playcounts_entry.last_played_epoch as u64,
+            ));
+        }
+
+        if playcounts_entry.last_skipped_epoch > 0 {
+            song.last_skipped_at = Some(helpers::get_timestamp_as_mac(
+                playcounts_entry.last_skipped_epoch as u64,
+            ));
+        }
+
+        if playcounts_entry.audio_bookmark_ms > 0 {
+            song.resume_position_ms = playcounts_entry.audio_bookmark_ms;
+        }
+
+        if let Some(rating_raw) = playcounts_entry.rating_raw {
+            if rating_raw != song.song_rating_raw {
+                song.song_rating_raw = rating_raw;
+                song.rating_source = "iPod".to_string();
+            }
+        }
+
+        num_songs_merged += 1;
+    }
+
+    return num_songs_merged;
+}
+
+/// Same tracks, but as if nothing was played, skipped or bookmarked since the last sync.
+/// The ratings are kept: iTunes takes them from the Play Counts file too, so zeroing them would clear them
+pub fn reset_playcounts_entries(playcounts_entries: &[PlayCountsEntry]) -> Vec<PlayCountsEntry> {
+    return playcounts_entries
+        .iter()
+        .map(|playcounts_entry| PlayCountsEntry {
+            track_index: playcounts_entry.track_index,
+            rating_raw: playcounts_entry.rating_raw,
+            ..PlayCountsEntry::default()
+        })
+        .collect();
+}
+
+/// Lays the entries out the way the iPod does: a header, then one entry per track item, in the
+/// iTunesDB's Track List order. Fields that don't fit in `pc_entry_len` are left out, like on older iPods,
+/// and the bytes nobody knows the meaning of (e.g. at 0x10 in each entry) are left as 0
+pub fn build_playcounts_file(playcounts_entries: &[PlayCountsEntry], pc_entry_len: usize) -> Vec<u8> {
+    let mut playcounts_file_as_bytes: Vec<u8> =
+        vec![0; playcounts_constants::PLAYCOUNTS_FILE_HEADER_LENGTH + (playcounts_entries.len() * pc_entry_len)];
+
+    let header_fields: [(usize, u32); 3] = [
+        (playcounts_constants::PLAYCOUNTS_HEADER_LENGTH_OFFSET, playcounts_constants::PLAYCOUNTS_FILE_HEADER_LENGTH as u32),
+        (playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_OFFSET, pc_entry_len as u32),
+        (playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_OFFSET, playcounts_entries.len() as u32),
+    ];
+
+    playcounts_file_as_bytes[..playcounts_constants::PLAYCOUNTS_OBJECT_KEY.len()]
+        .copy_from_slice(playcounts_constants::PLAYCOUNTS_OBJECT_KEY.as_bytes());
+
+    for (field_offset, field_value) in header_fields {
+        playcounts_file_as_bytes[field_offset..field_offset + 4].copy_from_slice(&field_value.to_le_bytes());
+    }
+
+    for (entry_idx, playcounts_entry) in playcounts_entries.iter().enumerate() {
+        let pc_starting_idx = playcounts_constants::PLAYCOUNTS_FILE_HEADER_LENGTH + (entry_idx * pc_entry_len);
+
+        let entry_fields: [(usize, u32); 6] = [
+            (playcounts_constants::PC_ENTRY_NUM_PLAYS_OFFSET, playcounts_entry.num_plays),
+            (playcounts_constants::PC_ENTRY_LAST_PLAYED_TIMESTAMP_OFFSET, playcounts_entry.last_played_epoch),
+            (playcounts_constants::PC_ENTRY_AUDIO_BOOKMARK_MS_OFFSET, playcounts_entry.audio_bookmark_ms),
+            (playcounts_constants::PC_ENTRY_RATING_OFFSET, playcounts_entry.rating_raw.unwrap_or(0) as u32),
+            (playcounts_constants::PC_ENTRY_NUM_SKIPS_OFFSET, playcounts_entry.num_skips),
+            (playcounts_constants::PC_ENTRY_LAST_SKIPPED_TIMESTAMP_OFFSET, playcounts_entry.last_skipped_epoch),
+        ];
+
+        // All of the entry's fields are 4 bytes
+        for (field_offset, field_value) in entry_fields {
+            if field_offset + 4 <= pc_entry_len {
+                playcounts_file_as_bytes[pc_starting_idx + field_offset..pc_starting_idx + field_offset + 4]
+                    .copy_from_slice(&field_value.to_le_bytes());
+            }
+        }
+    }
+
+    return playcounts_file_as_bytes;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::playcounts_parser;
+
+    fn build_test_entries() -> Vec<PlayCountsEntry> {
+        return vec![
+            PlayCountsEntry {
+                track_index: 0,
+                num_plays: 3,
+                last_played_epoch: 3_400_000_000,
+                audio_bookmark_ms: 61_000,
+                rating_raw: Some(80),
+                num_skips: 2,
+                last_skipped_epoch: 3_400_000_500,
+            },
+            PlayCountsEntry {
+                track_index: 1,
+                rating_raw: Some(0),
+                ..PlayCountsEntry::default()
+            },
+            PlayCountsEntry {
+                track_index: 2,
+                num_plays: 1,
+                last_played_epoch: 3_400_001_000,
+                rating_raw: Some(20),
+                num_skips: 7,
+                last_skipped_epoch: 3_400_002_000,
+                ..PlayCountsEntry::default()
+            },
+        ];
+    }
+
+    fn get_entry_fields(playcounts_entry: &PlayCountsEntry) -> (u32, u32, u32, u32, Option<u8>, u32, u32) {
+        return (
+            playcounts_entry.track_index,
+            playcounts_entry.num_plays,
+            playcounts_entry.last_played_epoch,
+            playcounts_entry.audio_bookmark_ms,
+            playcounts_entry.rating_raw,
+            playcounts_entry.num_skips,
+            playcounts_entry.last_skipped_epoch,
+        );
+    }
+
+    #[test]
+    fn built_file_has_the_documented_header() {
+        let playcounts_file_as_bytes = build_playcounts_file(&build_test_entries(), 0x1c);
+
+        assert!(playcounts_file_as_bytes.starts_with(playcounts_constants::PLAYCOUNTS_OBJECT_KEY.as_bytes()));
+        assert_eq!(helpers::get_slice_as_le_u32(0, &playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_HEADER_LENGTH_OFFSET, 4), 0x60);
+        assert_eq!(helpers::get_slice_as_le_u32(0, &playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_ENTRY_LENGTH_OFFSET, 4), 0x1c);
+        assert_eq!(helpers::get_slice_as_le_u32(0, &playcounts_file_as_bytes, playcounts_constants::PLAYCOUNTS_NUM_ENTRIES_OFFSET, 4), 3);
+        assert_eq!(playcounts_file_as_bytes.len(), 0x60 + (3 * 0x1c));
+    }
+
+    #[test]
+    fn built_file_reads_back_the_same() {
+        let playcounts_entries = build_test_entries();
+
+        let read_entries =
+            playcounts_parser::read_playcounts_entries(&build_playcounts_file(&playcounts_entries, 0x1c));
+
+        assert_eq!(
+            read_entries.iter().map(get_entry_fields).collect::<Vec<_>>(),
+            playcounts_entries.iter().map(get_entry_fields).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn short_entries_leave_out_the_skips() {
+        let playcounts_entries = build_test_entries();
+
+        let read_entries =
+            playcounts_parser::read_playcounts_entries(&build_playcounts_file(&playcounts_entries, 0x10));
+
+        assert_eq!(read_entries.len(), playcounts_entries.len());
+
+        for (read_entry, playcounts_entry) in read_entries.iter().zip(playcounts_entries.iter()) {
+            // The rating still fits in 0x10 bytes, the skip count and date don't
+            let (track_index, num_plays, last_played_epoch, audio_bookmark_ms, rating_raw, _, _) =
+                get_entry_fields(playcounts_entry);
+
+            assert_eq!(
+                get_entry_fields(read_entry),
+                (track_index, num_plays, last_played_epoch, audio_bookmark_ms, rating_raw, 0, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn reset_keeps_only_the_ratings() {
+        let playcounts_entries = build_test_entries();
+
+        for pc_entry_len in [0x10, 0x1c] {
+            let read_entries = playcounts_parser::read_playcounts_entries(&build_playcounts_file(
+                &reset_playcounts_entries(&playcounts_entries),
+                pc_entry_len,
+            ));
+
+            assert_eq!(read_entries.len(), playcounts_entries.len());
+
+            for (read_entry, playcounts_entry) in read_entries.iter().zip(playcounts_entries.iter()) {
+                assert_eq!(
+                    get_entry_fields(read_entry),
+                    (playcounts_entry.track_index, 0, 0, 0, playcounts_entry.rating_raw, 0, 0)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/**
+ * File: playlist_items.rs
+ *
+ * Every playlist's tracks, one row per track and in playlist order, as a join table between the playlists
+ * and the track exports. `playlists.csv` only lists the track IDs, which mean nothing outside of the iTunesDB,
+ * so this is what rebuilding the playlists elsewhere (another player, a streaming service) starts from.
+ *
+ */
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::itunesdb;
+use crate::smart_playlist;
+
+#[derive(Serialize)]
+pub struct PlaylistItem {
+    /// The playlist's place in `playlists.csv`, starting at 1. Persistent IDs can't be used to tell playlists
+    /// apart, since On-The-Go playlists don't have one
+    pub playlist_number: u32,
+    pub persistent_playlist_id: u64,
+    pub playlist_name: String,
+    /// Starting at 1
+    pub position: u32,
+    pub track_id: u32,
+    pub dbid: u64,
+    pub media_kind: String,
+    pub title: String,
+    pub artist: String,
+}
+
+/// What a playlist item needs of a track of any media type
+struct PlaylistTrack<'a> {
+    dbid: u64,
+    media_kind: &'a str,
+    track: &'a dyn smart_playlist::SmartPlaylistTrack,
+}
+
+/// The items of every playlist, smart playlists as evaluated. Tracks that aren't in the exports
+/// (e.g. hidden ones) are left out, and don't take up a position
+pub fn build_playlist_items(
+    playlists: &[itunesdb::Playlist],
+    library_tracks: &itunesdb::LibraryTracks,
+) -> Vec<PlaylistItem> {
+    let itunesdb::LibraryTracks {
+        songs,
+        podcasts,
+        videos,
+        audiobooks,
+        itunesu_items,
+        ringtones,
+        voice_memos,
+    } = *library_tracks;
+
+    let mut playlist_tracks: HashMap<u32, PlaylistTrack> = HashMap::new();
+
+    for song in songs.iter() {
+        playlist_tracks.insert(song.track_id, PlaylistTrack { dbid: song.dbid, media_kind: &song.media_kind, track: song });
+    }
+
+    for podcast in podcasts.iter() {
+        playlist_tracks.insert(podcast.track_id, PlaylistTrack { dbid: podcast.dbid, media_kind: &podcast.media_kind, track: podcast });
+    }
+
+    for video in videos.iter() {
+        playlist_tracks.insert(video.track.track_id, PlaylistTrack { dbid: video.track.dbid, media_kind: &video.track.media_kind, track: &video.track });
+    }
+
+    for audiobook in audiobooks.iter() {
+        playlist_tracks.insert(audiobook.track.track_id, PlaylistTrack { dbid: audiobook.track.dbid, media_kind: &audiobook.track.media_kind, track: &audiobook.track });
+    }
+
+    for itunesu_item in itunesu_items.iter() {
+        playlist_tracks.insert(
+            itunesu_item.track.track_id,
+            PlaylistTrack { dbid: itunesu_item.track.dbid, media_kind: &itunesu_item.track.media_kind, track: &itunesu_item.track },
+        );
+    }
+
+    for ringtone in ringtones.iter() {
+        playlist_tracks.insert(ringtone.track.track_id, PlaylistTrack { dbid: ringtone.track.dbid, media_kind: &ringtone.track.media_kind, track: &ringtone.track });
+    }
+
+    for voice_memo in voice_memos.iter() {
+        playlist_tracks.insert(voice_memo.track.track_id, PlaylistTrack { dbid: voice_memo.track.dbid, media_kind: &voice_memo.track.media_kind, track: &voice_memo.track });
+    }
+
+    let mut playlist_items: Vec<PlaylistItem> = Vec::new();
+
+    for (playlist_idx, playlist) in playlists.iter().enumerate() {
+        let mut position = 0;
+
+        for track_id in playlist.track_ids.iter() {
+            let playlist_track = match playlist_tracks.get(track_id) {
+                Some(playlist_track) => playlist_track,
+                None => continue,
+            };
+
+            position += 1;
+
+            playlist_items.push(PlaylistItem {
+                playlist_number: playlist_idx as u32 + 1,
+                persistent_playlist_id: playlist.persistent_playlist_id,
+                playlist_name: playlist.playlist_name.to_string(),
+                position,
+                track_id: *track_id,
+                dbid: playlist_track.dbid,
+                media_kind: playlist_track.media_kind.to_string(),
+                title: playlist_track
+                    .track
+                    .get_string_field(smart_playlist::SmartPlaylistRuleField::SongName as u32)
+                    .unwrap_or_default(),
+                artist: playlist_track
+                    .track
+                    .get_string_field(smart_playlist::SmartPlaylistRuleField::Artist as u32)
+                    .unwrap_or_default(),
+            });
+        }
+    }
+
+    return playlist_items;
+}
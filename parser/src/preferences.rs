@@ -4,6 +4,22 @@
  * 
  * Provides functionality for working with the Preferences file
  */
+use serde::Serialize;
+
+/// The settings chosen on the iPod itself, from iPod_Control/Device/Preferences
+#[derive(Serialize)]
+pub struct DevicePreferences {
+    pub is_daylight_savings_enabled: bool,
+    /// The language picked on the iPod, not the one iTunes uses (see `IpodDeviceInfo::language_name`)
+    pub language: String,
+    pub timezone_raw: u8,
+    /// See `decode_timezone`
+    pub timezone_gmt_offset: u8,
+    /// `None` if there's no volume limit
+    pub volume_limit: Option<u32>,
+    /// `None` if the file is too short to have it
+    pub region_raw: Option<u32>,
+}
 
 
 pub fn is_daylight_savings_enabled(raw_dst_setting : u8) -> bool {
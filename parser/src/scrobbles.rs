@@ -0,0 +1,67 @@
+/**
+ * File: scrobbles.rs
+ *
+ * Plays on an iPod that was never synced with a Last.fm plugin are lost to Last.fm, but the iTunesDB
+ * still has each song's play count and when it was last played. That's enough to backfill them with
+ * the bulk scrobbling tools (e.g. Universal Scrobbler or Open Scrobbler), which read a CSV of
+ * artist, track, album, timestamp, album artist and duration, without a header row.
+ *
+ */
+use crate::itunesdb;
+
+/// The gap between estimated plays of songs with no duration
+const DEFAULT_PLAY_INTERVAL_S: i64 = 180;
+
+pub struct Scrobble {
+    pub artist: String,
+    pub track: String,
+    pub album: String,
+    /// UTC. Only the last play of each song has its actual time, see `build_scrobbles`
+    pub played_at: chrono::DateTime<chrono::Utc>,
+    pub album_artist: String,
+    pub duration_s: u32,
+}
+
+/// One scrobble per play, oldest first. Only the last play of a song has a known time, so the plays
+/// before it are put back to back, one song's length apart, ending with the last play.
+/// Songs without an artist, or with plays but no last played time, can't be scrobbled and are left out
+pub fn build_scrobbles(songs: &[itunesdb::Song]) -> Vec<Scrobble> {
+    let mut scrobbles: Vec<Scrobble> = Vec::new();
+
+    for song in songs.iter() {
+        let last_played_at = match song.last_played_at {
+            Some(last_played_at) if song.num_plays > 0 && !song.song_artist.is_empty() => last_played_at,
+            _ => continue,
+        };
+
+        let play_interval_s = if song.song_duration_s > 0 {
+            song.song_duration_s as i64
+        } else {
+            DEFAULT_PLAY_INTERVAL_S
+        };
+
+        for play_idx in 0..song.num_plays {
+            scrobbles.push(Scrobble {
+                artist: song.song_artist.to_string(),
+                track: song.song_title.to_string(),
+                album: song.song_album.to_string(),
+                played_at: last_played_at - chrono::Duration::seconds(play_interval_s * play_idx as i64),
+                album_artist: song.song_album_artist.to_string(),
+                duration_s: song.song_duration_s,
+            });
+        }
+    }
+
+    scrobbles.sort_by_key(|scrobble| scrobble.played_at);
+
+    return scrobbles;
+}
+
+/// How many plays `build_scrobbles` had to leave out, for lack of a last played time or an artist
+pub fn get_num_unscrobblable_plays(songs: &[itunesdb::Song]) -> u32 {
+    return songs
+        .iter()
+        .filter(|song| song.last_played_at.is_none() || song.song_artist.is_empty())
+        .map(|song| song.num_plays)
+        .sum();
+}
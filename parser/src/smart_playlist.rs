@@ -0,0 +1,1115 @@
+/**
+ * File: smart_playlist.rs
+ *
+ * Decodes the Smart Playlist data objects (mhod types 50 and 51) and evaluates
+ * their rules against the tracks parsed out of the iTunesDB, see:
+ * http://www.ipodlinux.org/ITunesDB/#Smart_Playlist_Data
+ *
+ */
+use crate::constants::smart_playlist_constants;
+use crate::helpers::helpers;
+use crate::itunesdb;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// The non-rule settings of a Smart Playlist, ie. all the checkboxes in the iTunes dialog
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SmartPlaylistPreferences {
+    pub live_update: bool,
+    pub check_rules: bool,
+    pub check_limits: bool,
+    pub limit_type: u8,
+    pub limit_type_name: String,
+    pub limit_sort: u8,
+    pub limit_sort_name: String,
+    pub limit_value: u32,
+    pub match_checked_only: bool,
+    pub reverse_limit_sort: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SmartPlaylistRule {
+    pub field: u32,
+    pub field_name: String,
+    pub action: u32,
+    pub action_name: String,
+    /// Only present for string rules (e.g. "Artist contains ...")
+    pub string_value: Option<String>,
+    pub from_value: u64,
+    pub from_date: i64,
+    pub from_units: u64,
+    pub to_value: u64,
+    pub to_date: i64,
+    pub to_units: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct SmartPlaylistRules {
+    /// "Match any" (OR) when set, "Match all" (AND) otherwise
+    pub match_any: bool,
+    pub rules: Vec<SmartPlaylistRule>,
+}
+
+/// The rule fields, as listed in the "Smart Playlist Rule Field Types" table
+#[allow(non_camel_case_types)]
+pub enum SmartPlaylistRuleField {
+    SongName = 0x02,
+    Album = 0x03,
+    Artist = 0x04,
+    Bitrate = 0x05,
+    SampleRate = 0x06,
+    Year = 0x07,
+    Genre = 0x08,
+    Kind = 0x09,
+    DateModified = 0x0a,
+    TrackNumber = 0x0b,
+    Size = 0x0c,
+    Time = 0x0d,
+    Comment = 0x0e,
+    DateAdded = 0x10,
+    Composer = 0x12,
+    PlayCount = 0x16,
+    LastPlayed = 0x17,
+    DiscNumber = 0x18,
+    Rating = 0x19,
+    Compilation = 0x1f,
+    Bpm = 0x23,
+    Grouping = 0x27,
+    Playlist = 0x28,
+    Description = 0x36,
+    Category = 0x37,
+    Podcast = 0x39,
+    VideoKind = 0x3c,
+    TV_Show = 0x3e,
+    SeasonNumber = 0x3f,
+    SkipCount = 0x44,
+    LastSkipped = 0x45,
+    AlbumArtist = 0x47,
+}
+
+/// Anything that Smart Playlist rules can be matched against.
+/// Both getters return `None` when the field isn't (yet) parsed for that kind of track,
+/// in which case the rule can't be evaluated.
+pub trait SmartPlaylistTrack {
+    fn get_track_id(&self) -> u32;
+
+    fn get_string_field(&self, rule_field: u32) -> Option<String>;
+
+    /// Timestamps are returned as Mac epoch times, durations in milliseconds
+    fn get_numeric_field(&self, rule_field: u32) -> Option<u64>;
+
+    /// Whether the track's checkbox is ticked in iTunes
+    fn get_checked_state(&self) -> Option<bool> {
+        return None;
+    }
+}
+
+pub fn decode_smart_playlist_rule_field(rule_field: u32) -> String {
+    let field_name: String;
+
+    if rule_field == SmartPlaylistRuleField::SongName as u32 {
+        field_name = "Song Name".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Album as u32 {
+        field_name = "Album".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Artist as u32 {
+        field_name = "Artist".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Bitrate as u32 {
+        field_name = "Bitrate".to_string();
+    } else if rule_field == SmartPlaylistRuleField::SampleRate as u32 {
+        field_name = "Sample Rate".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Year as u32 {
+        field_name = "Year".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Genre as u32 {
+        field_name = "Genre".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Kind as u32 {
+        field_name = "Kind".to_string();
+    } else if rule_field == SmartPlaylistRuleField::DateModified as u32 {
+        field_name = "Date Modified".to_string();
+    } else if rule_field == SmartPlaylistRuleField::TrackNumber as u32 {
+        field_name = "Track Number".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Size as u32 {
+        field_name = "Size".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Time as u32 {
+        field_name = "Time".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Comment as u32 {
+        field_name = "Comment".to_string();
+    } else if rule_field == SmartPlaylistRuleField::DateAdded as u32 {
+        field_name = "Date Added".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Composer as u32 {
+        field_name = "Composer".to_string();
+    } else if rule_field == SmartPlaylistRuleField::PlayCount as u32 {
+        field_name = "Play Count".to_string();
+    } else if rule_field == SmartPlaylistRuleField::LastPlayed as u32 {
+        field_name = "Last Played".to_string();
+    } else if rule_field == SmartPlaylistRuleField::DiscNumber as u32 {
+        field_name = "Disc Number".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Rating as u32 {
+        field_name = "Rating".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Compilation as u32 {
+        field_name = "Compilation".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Bpm as u32 {
+        field_name = "BPM".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Grouping as u32 {
+        field_name = "Grouping".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Playlist as u32 {
+        field_name = "Playlist".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Description as u32 {
+        field_name = "Description".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Category as u32 {
+        field_name = "Category".to_string();
+    } else if rule_field == SmartPlaylistRuleField::Podcast as u32 {
+        field_name = "Podcast".to_string();
+    } else if rule_field == SmartPlaylistRuleField::VideoKind as u32 {
+        field_name = "Video Kind".to_string();
+    } else if rule_field == SmartPlaylistRuleField::TV_Show as u32 {
+        field_name = "TV Show".to_string();
+    } else if rule_field == SmartPlaylistRuleField::SeasonNumber as u32 {
+        field_name = "Season Number".to_string();
+    } else if rule_field == SmartPlaylistRuleField::SkipCount as u32 {
+        field_name = "Skip Count".to_string();
+    } else if rule_field == SmartPlaylistRuleField::LastSkipped as u32 {
+        field_name = "Last Skipped".to_string();
+    } else if rule_field == SmartPlaylistRuleField::AlbumArtist as u32 {
+        field_name = "Album Artist".to_string();
+    } else {
+        field_name = format!("N/A ({})", rule_field);
+    }
+
+    return field_name;
+}
+
+/// The action is a bitmapped value: the high byte says whether it's a string
+/// comparison and whether it's negated, the low bytes say which comparison it is
+pub fn decode_smart_playlist_rule_action(rule_action: u32) -> String {
+    let mut action_name: String = String::new();
+
+    if (rule_action & smart_playlist_constants::SPL_ACTION_NOT_FLAG) != 0 {
+        action_name.push_str("NOT ");
+    }
+
+    let comparison = rule_action & 0x0000FFFF;
+
+    if comparison == smart_playlist_constants::SPL_ACTION_IS {
+        action_name.push_str("is");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_CONTAINS {
+        action_name.push_str("contains");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_STARTS_WITH {
+        action_name.push_str("starts with");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_ENDS_WITH {
+        action_name.push_str("ends with");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_GREATER_THAN {
+        action_name.push_str("greater than");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_GREATER_THAN_OR_EQUAL {
+        action_name.push_str("greater than or equal to");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_LESS_THAN {
+        action_name.push_str("less than");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_LESS_THAN_OR_EQUAL {
+        action_name.push_str("less than or equal to");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_IN_RANGE {
+        action_name.push_str("in the range");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_IN_THE_LAST {
+        action_name.push_str("in the last");
+    } else if comparison == smart_playlist_constants::SPL_ACTION_BINARY_AND {
+        action_name.push_str("is (binary AND)");
+    } else {
+        action_name.push_str(&format!("N/A ({})", rule_action));
+    }
+
+    return action_name;
+}
+
+pub fn decode_smart_playlist_limit_type(limit_type: u8) -> String {
+    let limit_type_name: String;
+
+    if limit_type == 1 {
+        limit_type_name = "Minutes".to_string();
+    } else if limit_type == 2 {
+        limit_type_name = "Megabytes".to_string();
+    } else if limit_type == 3 {
+        limit_type_name = "Songs".to_string();
+    } else if limit_type == 4 {
+        limit_type_name = "Hours".to_string();
+    } else if limit_type == 5 {
+        limit_type_name = "Gigabytes".to_string();
+    } else {
+        limit_type_name = format!("N/A ({})", limit_type);
+    }
+
+    return limit_type_name;
+}
+
+pub fn decode_smart_playlist_limit_sort(limit_sort: u8) -> String {
+    let limit_sort_name: String;
+
+    if limit_sort == 0x02 {
+        limit_sort_name = "Random".to_string();
+    } else if limit_sort == 0x03 {
+        limit_sort_name = "Song Name".to_string();
+    } else if limit_sort == 0x04 {
+        limit_sort_name = "Album".to_string();
+    } else if limit_sort == 0x05 {
+        limit_sort_name = "Artist".to_string();
+    } else if limit_sort == 0x07 {
+        limit_sort_name = "Genre".to_string();
+    } else if limit_sort == 0x10 {
+        limit_sort_name = "Most Recently Added".to_string();
+    } else if limit_sort == 0x14 {
+        limit_sort_name = "Most Often Played".to_string();
+    } else if limit_sort == 0x15 {
+        limit_sort_name = "Most Recently Played".to_string();
+    } else if limit_sort == 0x17 {
+        limit_sort_name = "Highest Rating".to_string();
+    } else {
+        limit_sort_name = format!("N/A ({})", limit_sort);
+    }
+
+    return limit_sort_name;
+}
+
+/// Parses the Smart Playlist Data object (mhod type 50), starting at `mhod_start_idx`
+pub fn parse_smart_playlist_preferences(
+    mhod_start_idx: usize,
+    file_as_bytes: &[u8],
+) -> SmartPlaylistPreferences {
+    let limit_type = helpers::get_slice_as_le_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        smart_playlist_constants::SPL_DATA_LIMIT_TYPE_OFFSET,
+        smart_playlist_constants::SPL_DATA_LIMIT_TYPE_LEN,
+    ) as u8;
+
+    let limit_sort = helpers::get_slice_as_le_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        smart_playlist_constants::SPL_DATA_LIMIT_SORT_OFFSET,
+        smart_playlist_constants::SPL_DATA_LIMIT_SORT_LEN,
+    ) as u8;
+
+    return SmartPlaylistPreferences {
+        live_update: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_LIVE_UPDATE_SETTING_OFFSET,
+            smart_playlist_constants::SPL_DATA_LIVE_UPDATE_SETTING_LEN,
+        ) == 1,
+        check_rules: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_CHECK_RULES_SETTING_OFFSET,
+            smart_playlist_constants::SPL_DATA_CHECK_RULES_SETTING_LEN,
+        ) == 1,
+        check_limits: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_CHECK_LIMITS_SETTING_OFFSET,
+            smart_playlist_constants::SPL_DATA_CHECK_LIMITS_SETTING_LEN,
+        ) == 1,
+        limit_type,
+        limit_type_name: decode_smart_playlist_limit_type(limit_type),
+        limit_sort,
+        limit_sort_name: decode_smart_playlist_limit_sort(limit_sort),
+        limit_value: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_LIMIT_VALUE_OFFSET,
+            smart_playlist_constants::SPL_DATA_LIMIT_VALUE_LEN,
+        ),
+        match_checked_only: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_MATCH_CHECKED_ONLY_SETTING_OFFSET,
+            smart_playlist_constants::SPL_DATA_MATCH_CHECKED_ONLY_SETTING_LEN,
+        ) == 1,
+        reverse_limit_sort: helpers::get_slice_as_le_u32(
+            mhod_start_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_DATA_REVERSE_LIMIT_SORT_SETTING_OFFSET,
+            smart_playlist_constants::SPL_DATA_REVERSE_LIMIT_SORT_SETTING_LEN,
+        ) == 1,
+    };
+}
+
+/// Parses the Smart Playlist Rules object (mhod type 51), starting at `mhod_start_idx`.
+/// Remember that everything after the "SLst" identifier is big-endian.
+pub fn parse_smart_playlist_rules(mhod_start_idx: usize, file_as_bytes: &[u8]) -> SmartPlaylistRules {
+    let mut smart_playlist_rules = SmartPlaylistRules {
+        match_any: false,
+        rules: Vec::new(),
+    };
+
+    let rules_identifier = helpers::get_slice_from_offset_with_len(
+        mhod_start_idx,
+        file_as_bytes,
+        smart_playlist_constants::SPL_RULES_IDENTIFIER_OFFSET,
+        smart_playlist_constants::SPL_RULES_IDENTIFIER_LEN,
+    );
+
+    if rules_identifier != smart_playlist_constants::SPL_RULES_IDENTIFIER_KEY.as_bytes() {
+        eprintln!(
+            "Smart Playlist rules at idx {} are missing the '{}' identifier, skipping",
+            mhod_start_idx,
+            smart_playlist_constants::SPL_RULES_IDENTIFIER_KEY
+        );
+        return smart_playlist_rules;
+    }
+
+    let num_rules = helpers::get_slice_as_be_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        smart_playlist_constants::SPL_RULES_NUM_RULES_OFFSET,
+        smart_playlist_constants::SPL_RULES_NUM_RULES_LEN,
+    );
+
+    smart_playlist_rules.match_any = helpers::get_slice_as_be_u32(
+        mhod_start_idx,
+        file_as_bytes,
+        smart_playlist_constants::SPL_RULES_OPERATOR_OFFSET,
+        smart_playlist_constants::SPL_RULES_OPERATOR_LEN,
+    ) == 1;
+
+    let mut rule_idx = mhod_start_idx + smart_playlist_constants::SPL_RULES_FIRST_RULE_OFFSET;
+
+    for _ in 0..num_rules {
+        let rule_field = helpers::get_slice_as_be_u32(
+            rule_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_RULE_FIELD_OFFSET,
+            smart_playlist_constants::SPL_RULE_FIELD_LEN,
+        );
+
+        let rule_action = helpers::get_slice_as_be_u32(
+            rule_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_RULE_ACTION_OFFSET,
+            smart_playlist_constants::SPL_RULE_ACTION_LEN,
+        );
+
+        let rule_data_len = helpers::get_slice_as_be_u32(
+            rule_idx,
+            file_as_bytes,
+            smart_playlist_constants::SPL_RULE_DATA_LENGTH_OFFSET,
+            smart_playlist_constants::SPL_RULE_DATA_LENGTH_LEN,
+        ) as usize;
+
+        let mut rule = SmartPlaylistRule {
+            field: rule_field,
+            field_name: decode_smart_playlist_rule_field(rule_field),
+            action: rule_action,
+            action_name: decode_smart_playlist_rule_action(rule_action),
+            string_value: None,
+            from_value: 0,
+            from_date: 0,
+            from_units: 0,
+            to_value: 0,
+            to_date: 0,
+            to_units: 0,
+        };
+
+        if (rule_action & smart_playlist_constants::SPL_ACTION_IS_STRING_FLAG) != 0 {
+            let rule_str_bytes = helpers::get_slice_from_offset_with_len(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_DATA_OFFSET,
+                rule_data_len,
+            );
+
+            rule.string_value = Some(String::from_utf16_lossy(&helpers::return_utf16_from_be_bytes(
+                &rule_str_bytes,
+            )));
+        } else {
+            rule.from_value = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_FROM_VALUE_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            );
+            rule.from_date = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_FROM_DATE_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            ) as i64;
+            rule.from_units = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_FROM_UNITS_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            );
+            rule.to_value = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_TO_VALUE_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            );
+            rule.to_date = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_TO_DATE_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            ) as i64;
+            rule.to_units = helpers::get_slice_as_be_u64(
+                rule_idx,
+                file_as_bytes,
+                smart_playlist_constants::SPL_RULE_TO_UNITS_OFFSET,
+                smart_playlist_constants::SPL_RULE_VALUE_LEN,
+            );
+        }
+
+        smart_playlist_rules.rules.push(rule);
+
+        // "rules are NOT zero padded at the end"
+        rule_idx += smart_playlist_constants::SPL_RULE_DATA_OFFSET + rule_data_len;
+    }
+
+    return smart_playlist_rules;
+}
+
+/// Human-readable version of the rules, e.g. for the CSV output
+pub fn describe_smart_playlist_rules(smart_playlist_rules: &SmartPlaylistRules) -> String {
+    let mut rule_descriptions: Vec<String> = Vec::new();
+
+    for rule in smart_playlist_rules.rules.iter() {
+        let rule_value: String;
+
+        if let Some(string_value) = &rule.string_value {
+            rule_value = format!("'{}'", string_value);
+        } else if (rule.action & 0x0000FFFF) == smart_playlist_constants::SPL_ACTION_IN_RANGE {
+            rule_value = format!("{} to {}", rule.from_value, rule.to_value);
+        } else if (rule.action & 0x0000FFFF) == smart_playlist_constants::SPL_ACTION_BINARY_AND {
+            rule_value = format!("{:#x}", rule.from_value);
+        } else if (rule.action & 0x0000FFFF) == smart_playlist_constants::SPL_ACTION_IN_THE_LAST {
+            rule_value = format!("{} x {} seconds", rule.from_date.saturating_neg(), rule.from_units);
+        } else {
+            rule_value = format!("{}", rule.from_value);
+        }
+
+        rule_descriptions.push(format!(
+            "{} {} {}",
+            rule.field_name, rule.action_name, rule_value
+        ));
+    }
+
+    let operator = if smart_playlist_rules.match_any { " OR " } else { " AND " };
+
+    return rule_descriptions.join(operator);
+}
+
+/// "compare the contents of the field in question with (value+date*unit), and replacing the "value"
+/// with the current timestamp ("now") when it is equal to 0x2dae2dae2dae2dae".
+/// `None` if that doesn't fit in an i64, which no real rule does
+fn get_rule_comparison_value(value: u64, date: i64, units: u64, reference_timestamp_mac: u64) -> Option<i64> {
+    let base_value: i64 = if value == smart_playlist_constants::SPL_RULE_VALUE_NOW {
+        i64::try_from(reference_timestamp_mac).ok()?
+    } else {
+        i64::try_from(value).ok()?
+    };
+
+    return base_value.checked_add(date.checked_mul(i64::try_from(units).ok()?)?);
+}
+
+/// Returns whether the track matches a single rule, or `None` if the field the rule
+/// is about isn't available for this track
+fn does_track_match_rule(
+    track: &dyn SmartPlaylistTrack,
+    rule: &SmartPlaylistRule,
+    reference_timestamp_mac: u64,
+) -> Option<bool> {
+    let comparison = rule.action & 0x0000FFFF;
+    let is_match: bool;
+
+    if let Some(rule_str) = &rule.string_value {
+        let track_str = track.get_string_field(rule.field)?.to_lowercase();
+        let rule_str = rule_str.to_lowercase();
+
+        if comparison == smart_playlist_constants::SPL_ACTION_IS {
+            is_match = track_str == rule_str;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_CONTAINS {
+            is_match = track_str.contains(&rule_str);
+        } else if comparison == smart_playlist_constants::SPL_ACTION_STARTS_WITH {
+            is_match = track_str.starts_with(&rule_str);
+        } else if comparison == smart_playlist_constants::SPL_ACTION_ENDS_WITH {
+            is_match = track_str.ends_with(&rule_str);
+        } else {
+            return None;
+        }
+    } else {
+        let track_value = track.get_numeric_field(rule.field)? as i64;
+
+        let from_value = get_rule_comparison_value(
+            rule.from_value,
+            rule.from_date,
+            rule.from_units,
+            reference_timestamp_mac,
+        )?;
+
+        if comparison == smart_playlist_constants::SPL_ACTION_IS {
+            is_match = track_value == from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_GREATER_THAN {
+            is_match = track_value > from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_GREATER_THAN_OR_EQUAL {
+            is_match = track_value >= from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_LESS_THAN {
+            is_match = track_value < from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_LESS_THAN_OR_EQUAL {
+            is_match = track_value <= from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_IN_RANGE {
+            let to_value = get_rule_comparison_value(
+                rule.to_value,
+                rule.to_date,
+                rule.to_units,
+                reference_timestamp_mac,
+            )?;
+
+            is_match = (track_value >= from_value.min(to_value))
+                && (track_value <= from_value.max(to_value));
+        } else if comparison == smart_playlist_constants::SPL_ACTION_IN_THE_LAST {
+            is_match = track_value >= from_value;
+        } else if comparison == smart_playlist_constants::SPL_ACTION_BINARY_AND {
+            is_match = (track_value & from_value) != 0;
+        } else {
+            return None;
+        }
+    }
+
+    if (rule.action & smart_playlist_constants::SPL_ACTION_NOT_FLAG) != 0 {
+        return Some(!is_match);
+    }
+
+    return Some(is_match);
+}
+
+/// Applies the limit settings ("Limit to 25 songs selected by most recently added")
+/// to the tracks that matched the rules. Returns `None` if the limit can't be reproduced.
+fn apply_smart_playlist_limits(
+    preferences: &SmartPlaylistPreferences,
+    matching_tracks: Vec<&dyn SmartPlaylistTrack>,
+) -> Option<Vec<u32>> {
+    let mut sorted_tracks = matching_tracks;
+
+    // There's no way to reproduce the iPod's random selection
+    let limit_sort_field: u32;
+    let is_ascending_sort: bool;
+
+    if preferences.limit_sort == 0x03 {
+        limit_sort_field = SmartPlaylistRuleField::SongName as u32;
+        is_ascending_sort = true;
+    } else if preferences.limit_sort == 0x04 {
+        limit_sort_field = SmartPlaylistRuleField::Album as u32;
+        is_ascending_sort = true;
+    } else if preferences.limit_sort == 0x05 {
+        limit_sort_field = SmartPlaylistRuleField::Artist as u32;
+        is_ascending_sort = true;
+    } else if preferences.limit_sort == 0x07 {
+        limit_sort_field = SmartPlaylistRuleField::Genre as u32;
+        is_ascending_sort = true;
+    } else if preferences.limit_sort == 0x10 {
+        limit_sort_field = SmartPlaylistRuleField::DateAdded as u32;
+        is_ascending_sort = false;
+    } else if preferences.limit_sort == 0x14 {
+        limit_sort_field = SmartPlaylistRuleField::PlayCount as u32;
+        is_ascending_sort = false;
+    } else if preferences.limit_sort == 0x15 {
+        limit_sort_field = SmartPlaylistRuleField::LastPlayed as u32;
+        is_ascending_sort = false;
+    } else if preferences.limit_sort == 0x17 {
+        limit_sort_field = SmartPlaylistRuleField::Rating as u32;
+        is_ascending_sort = false;
+    } else {
+        return None;
+    }
+
+    if is_ascending_sort {
+        for track in sorted_tracks.iter() {
+            track.get_string_field(limit_sort_field)?;
+        }
+
+        sorted_tracks.sort_by_key(|track| {
+            track
+                .get_string_field(limit_sort_field)
+                .unwrap()
+                .to_lowercase()
+        });
+    } else {
+        for track in sorted_tracks.iter() {
+            track.get_numeric_field(limit_sort_field)?;
+        }
+
+        sorted_tracks.sort_by_key(|track| {
+            std::cmp::Reverse(track.get_numeric_field(limit_sort_field).unwrap())
+        });
+    }
+
+    if preferences.reverse_limit_sort {
+        sorted_tracks.reverse();
+    }
+
+    const ONE_MB_AS_BYTES: u64 = 1024 * 1024;
+    const ONE_MINUTE_AS_MS: u64 = 60 * 1000;
+
+    // Durations are compared in milliseconds, sizes in bytes
+    let limit_value = preferences.limit_value as u64;
+    let limit_total: u64;
+    let limit_track_field: Option<u32>;
+
+    if preferences.limit_type == 1 {
+        limit_total = limit_value * ONE_MINUTE_AS_MS;
+        limit_track_field = Some(SmartPlaylistRuleField::Time as u32);
+    } else if preferences.limit_type == 2 {
+        limit_total = limit_value * ONE_MB_AS_BYTES;
+        limit_track_field = Some(SmartPlaylistRuleField::Size as u32);
+    } else if preferences.limit_type == 3 {
+        limit_total = limit_value;
+        limit_track_field = None;
+    } else if preferences.limit_type == 4 {
+        limit_total = limit_value * 60 * ONE_MINUTE_AS_MS;
+        limit_track_field = Some(SmartPlaylistRuleField::Time as u32);
+    } else if preferences.limit_type == 5 {
+        limit_total = limit_value * 1024 * ONE_MB_AS_BYTES;
+        limit_track_field = Some(SmartPlaylistRuleField::Size as u32);
+    } else {
+        return None;
+    }
+
+    let mut limited_track_ids: Vec<u32> = Vec::new();
+    let mut limit_running_total: u64 = 0;
+
+    for track in sorted_tracks.iter() {
+        let track_amount: u64 = match limit_track_field {
+            Some(field) => track.get_numeric_field(field)?,
+            None => 1,
+        };
+
+        if limit_running_total + track_amount > limit_total {
+            break;
+        }
+
+        limit_running_total += track_amount;
+        limited_track_ids.push(track.get_track_id());
+    }
+
+    return Some(limited_track_ids);
+}
+
+/// Evaluates a Smart Playlist against the given tracks, as of `reference_timestamp_mac`
+/// (which is what "now" means for rules like "Last Played in the last 2 weeks").
+/// Returns the IDs of the matching tracks, in library order, or `None` if any of the rules
+/// or limits relies on a field that isn't parsed yet.
+pub fn evaluate_smart_playlist(
+    preferences: &SmartPlaylistPreferences,
+    smart_playlist_rules: &SmartPlaylistRules,
+    library: &[&dyn SmartPlaylistTrack],
+    reference_timestamp_mac: u64,
+) -> Option<Vec<u32>> {
+    let mut matching_tracks: Vec<&dyn SmartPlaylistTrack> = Vec::new();
+
+    for track in library.iter() {
+        if preferences.match_checked_only && !track.get_checked_state()? {
+            continue;
+        }
+
+        let mut is_match = !smart_playlist_rules.match_any;
+
+        if preferences.check_rules && !smart_playlist_rules.rules.is_empty() {
+            for rule in smart_playlist_rules.rules.iter() {
+                let does_rule_match =
+                    does_track_match_rule(*track, rule, reference_timestamp_mac)?;
+
+                if smart_playlist_rules.match_any {
+                    is_match = is_match || does_rule_match;
+                } else {
+                    is_match = is_match && does_rule_match;
+                }
+            }
+        } else {
+            is_match = true;
+        }
+
+        if is_match {
+            matching_tracks.push(*track);
+        }
+    }
+
+    if preferences.check_limits {
+        return apply_smart_playlist_limits(preferences, matching_tracks);
+    }
+
+    return Some(matching_tracks.iter().map(|track| track.get_track_id()).collect());
+}
+
+/// Fills in the actual membership of a Smart Playlist. Falls back to the tracks stored
+/// in the playlist items when the rules can't be evaluated.
+pub fn materialize_smart_playlist(
+    playlist: &mut itunesdb::Playlist,
+    library: &[&dyn SmartPlaylistTrack],
+    reference_timestamp_mac: u64,
+) {
+    if let (Some(preferences), Some(smart_playlist_rules)) = (
+        &playlist.smart_playlist_preferences,
+        &playlist.smart_playlist_rules,
+    ) {
+        match evaluate_smart_playlist(
+            preferences,
+            smart_playlist_rules,
+            library,
+            reference_timestamp_mac,
+        ) {
+            Some(evaluated_track_ids) => {
+                playlist.track_ids = evaluated_track_ids;
+                playlist.membership_source = "Evaluated from smart playlist rules".to_string();
+            }
+            None => {
+                eprintln!(
+                    "Smart playlist '{}' uses rules or limits that can't be evaluated yet, using the stored playlist items instead",
+                    playlist.playlist_name
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mac epoch time used as "now" by every test
+    const NOW_MAC: u64 = 3_700_000_000;
+    const ONE_DAY_S: u64 = 24 * 60 * 60;
+
+    struct TestTrack {
+        track_id: u32,
+        artist: &'static str,
+        song_name: &'static str,
+        year: u64,
+        play_count: u64,
+        duration_ms: u64,
+        size_bytes: u64,
+        date_added_mac: u64,
+        is_checked: bool,
+    }
+
+    impl SmartPlaylistTrack for TestTrack {
+        fn get_track_id(&self) -> u32 {
+            return self.track_id;
+        }
+
+        fn get_string_field(&self, rule_field: u32) -> Option<String> {
+            if rule_field == SmartPlaylistRuleField::Artist as u32 {
+                return Some(self.artist.to_string());
+            } else if rule_field == SmartPlaylistRuleField::SongName as u32 {
+                return Some(self.song_name.to_string());
+            }
+
+            return None;
+        }
+
+        fn get_numeric_field(&self, rule_field: u32) -> Option<u64> {
+            if rule_field == SmartPlaylistRuleField::Year as u32 {
+                return Some(self.year);
+            } else if rule_field == SmartPlaylistRuleField::PlayCount as u32 {
+                return Some(self.play_count);
+            } else if rule_field == SmartPlaylistRuleField::Time as u32 {
+                return Some(self.duration_ms);
+            } else if rule_field == SmartPlaylistRuleField::Size as u32 {
+                return Some(self.size_bytes);
+            } else if rule_field == SmartPlaylistRuleField::DateAdded as u32 {
+                return Some(self.date_added_mac);
+            }
+
+            return None;
+        }
+
+        fn get_checked_state(&self) -> Option<bool> {
+            return Some(self.is_checked);
+        }
+    }
+
+    fn get_test_library() -> Vec<TestTrack> {
+        return vec![
+            TestTrack {
+                track_id: 1,
+                artist: "Justice",
+                song_name: "Genesis",
+                year: 2007,
+                play_count: 12,
+                duration_ms: 234_000,
+                size_bytes: 9 * 1024 * 1024,
+                date_added_mac: NOW_MAC - 2 * ONE_DAY_S,
+                is_checked: true,
+            },
+            TestTrack {
+                track_id: 2,
+                artist: "Digitalism",
+                song_name: "Pogo",
+                year: 2007,
+                play_count: 3,
+                duration_ms: 180_000,
+                size_bytes: 7 * 1024 * 1024,
+                date_added_mac: NOW_MAC - 30 * ONE_DAY_S,
+                is_checked: false,
+            },
+            TestTrack {
+                track_id: 3,
+                artist: "MGMT",
+                song_name: "Kids",
+                year: 2008,
+                play_count: 40,
+                duration_ms: 302_000,
+                size_bytes: 11 * 1024 * 1024,
+                date_added_mac: NOW_MAC - 400 * ONE_DAY_S,
+                is_checked: true,
+            },
+            TestTrack {
+                track_id: 4,
+                artist: "Justice Crew",
+                song_name: "Boom Boom",
+                year: 2011,
+                play_count: 0,
+                duration_ms: 200_000,
+                size_bytes: 8 * 1024 * 1024,
+                date_added_mac: NOW_MAC - 5 * ONE_DAY_S,
+                is_checked: true,
+            },
+        ];
+    }
+
+    fn get_string_rule(rule_field: SmartPlaylistRuleField, comparison: u32, value: &str) -> SmartPlaylistRule {
+        let action = smart_playlist_constants::SPL_ACTION_IS_STRING_FLAG | comparison;
+
+        return SmartPlaylistRule {
+            field: rule_field as u32,
+            field_name: String::new(),
+            action,
+            action_name: decode_smart_playlist_rule_action(action),
+            string_value: Some(value.to_string()),
+            from_value: 0,
+            from_date: 0,
+            from_units: 0,
+            to_value: 0,
+            to_date: 0,
+            to_units: 0,
+        };
+    }
+
+    fn get_numeric_rule(rule_field: SmartPlaylistRuleField, action: u32, from_value: u64, to_value: u64) -> SmartPlaylistRule {
+        return SmartPlaylistRule {
+            field: rule_field as u32,
+            field_name: String::new(),
+            action,
+            action_name: decode_smart_playlist_rule_action(action),
+            string_value: None,
+            from_value,
+            from_date: 0,
+            from_units: 0,
+            to_value,
+            to_date: 0,
+            to_units: 0,
+        };
+    }
+
+    /// "Date Added is in the last `num_units` x `unit_s` seconds"
+    fn get_in_the_last_rule(num_units: i64, unit_s: u64) -> SmartPlaylistRule {
+        let mut rule = get_numeric_rule(
+            SmartPlaylistRuleField::DateAdded,
+            smart_playlist_constants::SPL_ACTION_IN_THE_LAST,
+            smart_playlist_constants::SPL_RULE_VALUE_NOW,
+            0,
+        );
+
+        rule.from_date = -num_units;
+        rule.from_units = unit_s;
+
+        return rule;
+    }
+
+    fn get_preferences(check_limits: bool, limit_type: u8, limit_sort: u8, limit_value: u32) -> SmartPlaylistPreferences {
+        return SmartPlaylistPreferences {
+            live_update: true,
+            check_rules: true,
+            check_limits,
+            limit_type,
+            limit_type_name: decode_smart_playlist_limit_type(limit_type),
+            limit_sort,
+            limit_sort_name: decode_smart_playlist_limit_sort(limit_sort),
+            limit_value,
+            match_checked_only: false,
+            reverse_limit_sort: false,
+        };
+    }
+
+    fn evaluate(preferences: &SmartPlaylistPreferences, match_any: bool, rules: Vec<SmartPlaylistRule>) -> Option<Vec<u32>> {
+        let library = get_test_library();
+        let library_tracks: Vec<&dyn SmartPlaylistTrack> =
+            library.iter().map(|track| track as &dyn SmartPlaylistTrack).collect();
+
+        return evaluate_smart_playlist(
+            preferences,
+            &SmartPlaylistRules { match_any, rules },
+            &library_tracks,
+            NOW_MAC,
+        );
+    }
+
+    #[test]
+    fn test_string_rules() {
+        let not_contains = smart_playlist_constants::SPL_ACTION_NOT_FLAG | smart_playlist_constants::SPL_ACTION_CONTAINS;
+
+        let test_cases: Vec<(&str, SmartPlaylistRule, Vec<u32>)> = vec![
+            ("is, ignoring case", get_string_rule(SmartPlaylistRuleField::Artist, smart_playlist_constants::SPL_ACTION_IS, "justice"), vec![1]),
+            ("contains", get_string_rule(SmartPlaylistRuleField::Artist, smart_playlist_constants::SPL_ACTION_CONTAINS, "ust"), vec![1, 4]),
+            ("starts with", get_string_rule(SmartPlaylistRuleField::SongName, smart_playlist_constants::SPL_ACTION_STARTS_WITH, "Gen"), vec![1]),
+            ("ends with", get_string_rule(SmartPlaylistRuleField::SongName, smart_playlist_constants::SPL_ACTION_ENDS_WITH, "boom"), vec![4]),
+            ("does not contain", get_string_rule(SmartPlaylistRuleField::Artist, not_contains, "justice"), vec![2, 3]),
+        ];
+
+        for (test_name, rule, expected_track_ids) in test_cases {
+            assert_eq!(
+                evaluate(&get_preferences(false, 3, 0x03, 0), false, vec![rule]),
+                Some(expected_track_ids),
+                "{}",
+                test_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_numeric_rules() {
+        let test_cases: Vec<(&str, SmartPlaylistRule, Vec<u32>)> = vec![
+            ("is", get_numeric_rule(SmartPlaylistRuleField::Year, smart_playlist_constants::SPL_ACTION_IS, 2007, 0), vec![1, 2]),
+            ("greater than", get_numeric_rule(SmartPlaylistRuleField::PlayCount, smart_playlist_constants::SPL_ACTION_GREATER_THAN, 12, 0), vec![3]),
+            ("greater than or equal to", get_numeric_rule(SmartPlaylistRuleField::PlayCount, smart_playlist_constants::SPL_ACTION_GREATER_THAN_OR_EQUAL, 12, 0), vec![1, 3]),
+            ("less than", get_numeric_rule(SmartPlaylistRuleField::PlayCount, smart_playlist_constants::SPL_ACTION_LESS_THAN, 3, 0), vec![4]),
+            ("less than or equal to", get_numeric_rule(SmartPlaylistRuleField::PlayCount, smart_playlist_constants::SPL_ACTION_LESS_THAN_OR_EQUAL, 3, 0), vec![2, 4]),
+            ("in the range, either way round", get_numeric_rule(SmartPlaylistRuleField::Year, smart_playlist_constants::SPL_ACTION_IN_RANGE, 2011, 2008), vec![3, 4]),
+            ("binary AND", get_numeric_rule(SmartPlaylistRuleField::Year, smart_playlist_constants::SPL_ACTION_BINARY_AND, 0x1, 0), vec![1, 2, 4]),
+            (
+                "is not",
+                get_numeric_rule(
+                    SmartPlaylistRuleField::Year,
+                    smart_playlist_constants::SPL_ACTION_NOT_FLAG | smart_playlist_constants::SPL_ACTION_IS,
+                    2007,
+                    0,
+                ),
+                vec![3, 4],
+            ),
+        ];
+
+        for (test_name, rule, expected_track_ids) in test_cases {
+            assert_eq!(
+                evaluate(&get_preferences(false, 3, 0x03, 0), false, vec![rule]),
+                Some(expected_track_ids),
+                "{}",
+                test_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_date_rules() {
+        let test_cases: Vec<(&str, SmartPlaylistRule, Vec<u32>)> = vec![
+            ("in the last 7 days", get_in_the_last_rule(7, ONE_DAY_S), vec![1, 4]),
+            ("in the last 2 months", get_in_the_last_rule(2, 30 * ONE_DAY_S), vec![1, 2, 4]),
+            (
+                "not in the last 7 days",
+                {
+                    let mut rule = get_in_the_last_rule(7, ONE_DAY_S);
+                    rule.action |= smart_playlist_constants::SPL_ACTION_NOT_FLAG;
+                    rule
+                },
+                vec![2, 3],
+            ),
+            (
+                "before an absolute date",
+                get_numeric_rule(SmartPlaylistRuleField::DateAdded, smart_playlist_constants::SPL_ACTION_LESS_THAN, NOW_MAC - 100 * ONE_DAY_S, 0),
+                vec![3],
+            ),
+        ];
+
+        for (test_name, rule, expected_track_ids) in test_cases {
+            assert_eq!(
+                evaluate(&get_preferences(false, 3, 0x03, 0), false, vec![rule]),
+                Some(expected_track_ids),
+                "{}",
+                test_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_match_any_and_match_all() {
+        let get_rules = || {
+            vec![
+                get_string_rule(SmartPlaylistRuleField::Artist, smart_playlist_constants::SPL_ACTION_CONTAINS, "justice"),
+                get_numeric_rule(SmartPlaylistRuleField::Year, smart_playlist_constants::SPL_ACTION_IS, 2007, 0),
+            ]
+        };
+
+        let preferences = get_preferences(false, 3, 0x03, 0);
+
+        assert_eq!(evaluate(&preferences, false, get_rules()), Some(vec![1]), "match all");
+        assert_eq!(evaluate(&preferences, true, get_rules()), Some(vec![1, 2, 4]), "match any");
+        assert_eq!(evaluate(&preferences, false, Vec::new()), Some(vec![1, 2, 3, 4]), "no rules");
+
+        let mut preferences_without_rules = get_preferences(false, 3, 0x03, 0);
+        preferences_without_rules.check_rules = false;
+        assert_eq!(evaluate(&preferences_without_rules, false, get_rules()), Some(vec![1, 2, 3, 4]), "rules unchecked");
+
+        let mut preferences_checked_only = get_preferences(false, 3, 0x03, 0);
+        preferences_checked_only.match_checked_only = true;
+        assert_eq!(evaluate(&preferences_checked_only, true, get_rules()), Some(vec![1, 4]), "checked tracks only");
+    }
+
+    #[test]
+    fn test_limits() {
+        // (name, limit type, limit sort, limit value, reversed, expected track IDs)
+        let test_cases = vec![
+            ("2 songs by song name", 3, 0x03, 2, false, vec![4, 1]),
+            ("2 songs by song name, reversed", 3, 0x03, 2, true, vec![2, 3]),
+            ("1 song by most often played", 3, 0x14, 1, false, vec![3]),
+            ("2 songs by most recently added", 3, 0x10, 2, false, vec![1, 4]),
+            ("8 minutes by artist", 1, 0x05, 8, false, vec![2, 1]),
+            ("20 MB by most often played", 2, 0x14, 20, false, vec![3, 1]),
+            ("1 hour fits everything", 4, 0x03, 1, false, vec![4, 1, 3, 2]),
+        ];
+
+        for (test_name, limit_type, limit_sort, limit_value, is_reversed, expected_track_ids) in test_cases {
+            let mut preferences = get_preferences(true, limit_type, limit_sort, limit_value);
+            preferences.reverse_limit_sort = is_reversed;
+
+            assert_eq!(evaluate(&preferences, false, Vec::new()), Some(expected_track_ids), "{}", test_name);
+        }
+    }
+
+    #[test]
+    fn test_unevaluable_playlists() {
+        // Composer isn't a field of the test tracks, so the rule can't be evaluated
+        assert_eq!(
+            evaluate(
+                &get_preferences(false, 3, 0x03, 0),
+                false,
+                vec![get_string_rule(SmartPlaylistRuleField::Composer, smart_playlist_constants::SPL_ACTION_IS, "Bach")],
+            ),
+            None
+        );
+
+        // Neither can the iPod's random selection
+        assert_eq!(evaluate(&get_preferences(true, 3, 0x02, 2), false, Vec::new()), None);
+
+        // Nor rules whose value + date * units overflows
+        let mut overflowing_rule = get_in_the_last_rule(i64::MAX, ONE_DAY_S);
+        assert_eq!(evaluate(&get_preferences(false, 3, 0x03, 0), false, vec![overflowing_rule.clone()]), None);
+
+        overflowing_rule.from_date = -1;
+        overflowing_rule.from_units = u64::MAX;
+        assert_eq!(evaluate(&get_preferences(false, 3, 0x03, 0), false, vec![overflowing_rule.clone()]), None);
+
+        // Describing them still works
+        overflowing_rule.from_date = i64::MIN;
+        assert!(describe_smart_playlist_rules(&SmartPlaylistRules {
+            match_any: false,
+            rules: vec![overflowing_rule],
+        })
+        .contains(&i64::MAX.to_string()));
+    }
+}
@@ -0,0 +1,340 @@
+/**
+ * File: sqlite_export.rs
+ *
+ * The CSV exports are one file per media type, joined by hand on the DBID. The SQLite export puts the
+ * same library into related tables instead, so it can be queried with SQL, e.g. the plays per album,
+ * or which playlists a track is in. Only built with `--features sqlite`.
+ *
+ */
+use std::collections::HashMap;
+
+use crate::helpers::helpers;
+use crate::itunesdb;
+use crate::smart_playlist;
+
+/// SQLite has no unsigned 64-bit integers, so DBIDs and persistent IDs above 2^63 come out negative.
+/// The bits are the same, e.g. `printf('%X', dbid)` gives the same hex as elsewhere
+const ITUNESDB_SQLITE_SCHEMA: &str = "
+    CREATE TABLE albums (
+        album_id INTEGER PRIMARY KEY,
+        title TEXT NOT NULL,
+        artist TEXT NOT NULL
+    );
+
+    CREATE TABLE tracks (
+        dbid INTEGER PRIMARY KEY,
+        track_id INTEGER NOT NULL UNIQUE,
+        media_kind TEXT NOT NULL,
+        title TEXT,
+        artist TEXT,
+        album_id INTEGER REFERENCES albums (album_id),
+        genre TEXT,
+        composer TEXT,
+        year INTEGER,
+        track_number INTEGER,
+        disc_number INTEGER,
+        duration_ms INTEGER,
+        bitrate_kbps INTEGER,
+        file_size_bytes INTEGER,
+        play_count INTEGER,
+        rating INTEGER,
+        added_at TEXT,
+        filename TEXT
+    );
+
+    CREATE TABLE podcasts (
+        dbid INTEGER PRIMARY KEY REFERENCES tracks (dbid),
+        show TEXT,
+        episode_order INTEGER,
+        subtitle TEXT,
+        description TEXT,
+        released_at TEXT,
+        duration_s INTEGER,
+        is_played INTEGER NOT NULL,
+        is_new INTEGER NOT NULL,
+        enclosure_url TEXT,
+        rss_url TEXT
+    );
+
+    CREATE TABLE playlists (
+        playlist_id INTEGER PRIMARY KEY,
+        persistent_id INTEGER,
+        name TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        is_smart INTEGER NOT NULL,
+        created_at TEXT,
+        membership_source TEXT
+    );
+
+    CREATE TABLE playlist_items (
+        playlist_id INTEGER NOT NULL REFERENCES playlists (playlist_id),
+        position INTEGER NOT NULL,
+        dbid INTEGER NOT NULL REFERENCES tracks (dbid),
+        PRIMARY KEY (playlist_id, position)
+    );
+
+    CREATE INDEX playlist_items_dbid ON playlist_items (dbid);
+";
+
+/// A track of any media type, with what only the concrete types know
+struct SqliteTrack<'a> {
+    dbid: u64,
+    media_kind: &'a str,
+    track: &'a dyn smart_playlist::SmartPlaylistTrack,
+}
+
+/// In a format SQLite's date functions understand. 0 means the date was never set
+fn get_sqlite_timestamp(mac_timestamp: u64) -> Option<String> {
+    if mac_timestamp == 0 {
+        return None;
+    }
+
+    return Some(helpers::get_timestamp_as_mac(mac_timestamp).format("%Y-%m-%d %H:%M:%S").to_string());
+}
+
+/// Writes the tracks of every media type, their albums, the podcast episodes and the playlists
+/// (with their current contents, so smart playlists as evaluated) into a new SQLite file.
+/// Columns a media type doesn't have are NULL
+pub fn write_itunesdb_sqlite(
+    sqlite_filename: &str,
+    songs: &[itunesdb::Song],
+    podcasts: &[itunesdb::Podcast],
+    videos: &[itunesdb::Video],
+    audiobooks: &[itunesdb::Audiobook],
+    itunesu_items: &[itunesdb::ITunesUItem],
+    ringtones: &[itunesdb::Ringtone],
+    voice_memos: &[itunesdb::VoiceMemo],
+    playlists: &[itunesdb::Playlist],
+    track_filenames: &HashMap<u32, String>,
+) {
+    // Tables can't be created twice, and nothing from an older export should be left over
+    if std::path::Path::new(sqlite_filename).exists() {
+        std::fs::remove_file(sqlite_filename)
+            .expect(&format!("Can't replace the existing '{}'", sqlite_filename));
+    }
+
+    let mut connection = rusqlite::Connection::open(sqlite_filename)
+        .expect(&format!("Can't create SQLite database '{}'", sqlite_filename));
+
+    let transaction = connection.transaction().expect("Can't start SQLite transaction");
+
+    transaction
+        .execute_batch(ITUNESDB_SQLITE_SCHEMA)
+        .expect("Can't create the SQLite tables");
+
+    let mut sqlite_tracks: Vec<SqliteTrack> = Vec::new();
+
+    for song in songs.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: song.dbid, media_kind: &song.media_kind, track: song });
+    }
+
+    for podcast in podcasts.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: podcast.dbid, media_kind: &podcast.media_kind, track: podcast });
+    }
+
+    for video in videos.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: video.track.dbid, media_kind: &video.track.media_kind, track: &video.track });
+    }
+
+    for audiobook in audiobooks.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: audiobook.track.dbid, media_kind: &audiobook.track.media_kind, track: &audiobook.track });
+    }
+
+    for itunesu_item in itunesu_items.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: itunesu_item.track.dbid, media_kind: &itunesu_item.track.media_kind, track: &itunesu_item.track });
+    }
+
+    for ringtone in ringtones.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: ringtone.track.dbid, media_kind: &ringtone.track.media_kind, track: &ringtone.track });
+    }
+
+    for voice_memo in voice_memos.iter() {
+        sqlite_tracks.push(SqliteTrack { dbid: voice_memo.track.dbid, media_kind: &voice_memo.track.media_kind, track: &voice_memo.track });
+    }
+
+    // Albums are told apart by their album artist (or the artist, if there's none), like the iPod's Albums menu does
+    let mut album_ids: HashMap<(String, String), i64> = HashMap::new();
+
+    // Playlist items refer to track IDs, the tables to DBIDs
+    let mut dbids_by_track_id: HashMap<u32, u64> = HashMap::new();
+
+    let mut num_tracks_written = 0;
+
+    {
+        let mut album_statement = transaction
+            .prepare("INSERT INTO albums (album_id, title, artist) VALUES (?1, ?2, ?3)")
+            .expect("Can't prepare SQLite album insert");
+
+        let mut track_statement = transaction
+            .prepare(
+                "INSERT OR IGNORE INTO tracks (dbid, track_id, media_kind, title, artist, album_id, genre, composer,
+                    year, track_number, disc_number, duration_ms, bitrate_kbps, file_size_bytes, play_count, rating,
+                    added_at, filename)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            )
+            .expect("Can't prepare SQLite track insert");
+
+        for sqlite_track in sqlite_tracks.iter() {
+            let get_string = |rule_field: smart_playlist::SmartPlaylistRuleField| -> Option<String> {
+                return sqlite_track
+                    .track
+                    .get_string_field(rule_field as u32)
+                    .filter(|string_field| !string_field.is_empty());
+            };
+
+            let get_numeric = |rule_field: smart_playlist::SmartPlaylistRuleField| -> Option<i64> {
+                return sqlite_track
+                    .track
+                    .get_numeric_field(rule_field as u32)
+                    .map(|numeric_field| numeric_field as i64);
+            };
+
+            let artist = get_string(smart_playlist::SmartPlaylistRuleField::Artist);
+
+            let album_id: Option<i64> = match get_string(smart_playlist::SmartPlaylistRuleField::Album) {
+                Some(album) => {
+                    let album_artist = get_string(smart_playlist::SmartPlaylistRuleField::AlbumArtist)
+                        .or(artist.clone())
+                        .unwrap_or_default();
+
+                    let next_album_id = album_ids.len() as i64 + 1;
+
+                    let album_id = *album_ids
+                        .entry((album.to_string(), album_artist.to_string()))
+                        .or_insert_with(|| {
+                            album_statement
+                                .execute(rusqlite::params![next_album_id, album, album_artist])
+                                .expect("Can't write album to SQLite");
+
+                            return next_album_id;
+                        });
+
+                    Some(album_id)
+                }
+                None => None,
+            };
+
+            let num_rows_inserted = track_statement
+                .execute(rusqlite::params![
+                    sqlite_track.dbid as i64,
+                    sqlite_track.track.get_track_id(),
+                    sqlite_track.media_kind,
+                    get_string(smart_playlist::SmartPlaylistRuleField::SongName),
+                    artist,
+                    album_id,
+                    get_string(smart_playlist::SmartPlaylistRuleField::Genre),
+                    get_string(smart_playlist::SmartPlaylistRuleField::Composer),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::Year),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::TrackNumber),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::DiscNumber),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::Time),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::Bitrate),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::Size),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::PlayCount),
+                    get_numeric(smart_playlist::SmartPlaylistRuleField::Rating),
+                    sqlite_track
+                        .track
+                        .get_numeric_field(smart_playlist::SmartPlaylistRuleField::DateAdded as u32)
+                        .and_then(get_sqlite_timestamp),
+                    track_filenames.get(&sqlite_track.track.get_track_id()),
+                ])
+                .expect("Can't write track to SQLite");
+
+            // The DBID is the primary key, a second track with the same one can't be referred to anyway
+            if num_rows_inserted == 0 {
+                eprintln!(
+                    "Track {} has the same DBID as another track ({}), it was left out of the SQLite export",
+                    sqlite_track.track.get_track_id(),
+                    sqlite_track.dbid
+                );
+                continue;
+            }
+
+            dbids_by_track_id.insert(sqlite_track.track.get_track_id(), sqlite_track.dbid);
+            num_tracks_written += 1;
+        }
+
+        let mut podcast_statement = transaction
+            .prepare(
+                "INSERT OR IGNORE INTO podcasts (dbid, show, episode_order, subtitle, description, released_at, duration_s,
+                    is_played, is_new, enclosure_url, rss_url)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )
+            .expect("Can't prepare SQLite podcast insert");
+
+        for podcast in podcasts.iter() {
+            podcast_statement
+                .execute(rusqlite::params![
+                    podcast.dbid as i64,
+                    Some(&podcast.podcast_show).filter(|podcast_show| !podcast_show.is_empty()),
+                    podcast.episode_order,
+                    podcast.podcast_subtitle,
+                    podcast.podcast_description,
+                    podcast
+                        .release_date
+                        .map(|release_date| release_date.format("%Y-%m-%d %H:%M:%S").to_string()),
+                    podcast.duration_s,
+                    podcast.is_played,
+                    podcast.is_new,
+                    podcast.enclosure_url,
+                    podcast.rss_url,
+                ])
+                .expect("Can't write podcast to SQLite");
+        }
+
+        let mut playlist_statement = transaction
+            .prepare(
+                "INSERT INTO playlists (playlist_id, persistent_id, name, kind, is_smart, created_at, membership_source)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .expect("Can't prepare SQLite playlist insert");
+
+        let mut playlist_item_statement = transaction
+            .prepare("INSERT INTO playlist_items (playlist_id, position, dbid) VALUES (?1, ?2, ?3)")
+            .expect("Can't prepare SQLite playlist item insert");
+
+        // Persistent IDs aren't unique (On-The-Go playlists don't have one), so the playlists are numbered in order
+        for (playlist_idx, playlist) in playlists.iter().enumerate() {
+            let playlist_id = playlist_idx as i64 + 1;
+
+            playlist_statement
+                .execute(rusqlite::params![
+                    playlist_id,
+                    playlist.persistent_playlist_id as i64,
+                    playlist.playlist_name,
+                    playlist.playlist_kind.to_string(),
+                    playlist.is_smart_playlist,
+                    playlist.created_ts.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    playlist.membership_source,
+                ])
+                .expect("Can't write playlist to SQLite");
+
+            let mut position = 0;
+
+            for track_id in playlist.track_ids.iter() {
+                // e.g. hidden tracks, which were left out of the exports
+                let dbid = match dbids_by_track_id.get(track_id) {
+                    Some(dbid) => dbid,
+                    None => continue,
+                };
+
+                position += 1;
+
+                playlist_item_statement
+                    .execute(rusqlite::params![playlist_id, position, *dbid as i64])
+                    .expect("Can't write playlist item to SQLite");
+            }
+        }
+    }
+
+    transaction.commit().expect("Can't write SQLite database");
+
+    println!(
+        "Created {} with {} tracks, {} albums, {} podcasts and {} playlists",
+        sqlite_filename,
+        num_tracks_written,
+        album_ids.len(),
+        podcasts.len(),
+        playlists.len()
+    );
+}
@@ -0,0 +1,93 @@
+/**
+ * File: track_files.rs
+ *
+ * Every track item (whatever its media type) points to an audio or video file on the iPod.
+ * With the iPod's mount point, those files can be checked before anyone tries to copy them off:
+ * old iPod hard drives lose files, and interrupted syncs leave them half-written.
+ *
+ */
+use serde::Serialize;
+
+use crate::helpers::helpers;
+
+#[derive(Serialize)]
+pub struct TrackFile {
+    pub track_id: u32,
+    pub dbid: u64,
+    pub media_kind: String,
+    pub title: String,
+    pub artist: String,
+    /// Relative to the iPod's root, e.g. "iPod_Control/Music/F12/ABCD.mp3"
+    pub filename: String,
+    /// What the track item says, unwrapped past 4GB (see `itunesdb::get_unwrapped_file_size`)
+    pub database_size_bytes: u64,
+    pub bitrate_kbps: u32,
+    pub duration_ms: u32,
+    /// `None` if the file isn't there (or the mount point wasn't given)
+    pub on_device_size_bytes: Option<u64>,
+    /// "OK", "Missing", "Empty", "Likely corrupt" or "Size mismatch", empty until the file was checked
+    pub file_status: String,
+    /// Lowercase hex MD5 or SHA-1 of the file, if asked for (`--checksum=md5` or `--checksum=sha1`)
+    pub file_checksum: Option<String>,
+}
+
+impl Default for TrackFile {
+    fn default() -> TrackFile {
+        return TrackFile {
+            track_id: 0,
+            dbid: 0,
+            media_kind: "".to_string(),
+            title: "".to_string(),
+            artist: "".to_string(),
+            filename: "".to_string(),
+            database_size_bytes: 0,
+            bitrate_kbps: 0,
+            duration_ms: 0,
+            on_device_size_bytes: None,
+            file_status: "".to_string(),
+            file_checksum: None,
+        };
+    }
+}
+
+impl TrackFile {
+    /// Bitrate times duration, 0 if the track item doesn't have both
+    pub fn get_estimated_size_bytes(&self) -> u64 {
+        return (self.bitrate_kbps as u64) * (self.duration_ms as u64) / 8;
+    }
+}
+
+/// Looks each file up under the mount point. Anything less than half the size its bitrate and
+/// duration call for can't hold the whole track, and is reported as "Likely corrupt".
+/// iTunes writes the file's exact size into the track item, so any other size on the iPod
+/// ("Size mismatch") points to an interrupted sync, or a file that was replaced behind iTunes' back.
+/// The file is only read (to be hashed) if `checksum_algorithm` is given
+pub fn check_track_file(track_file: &mut TrackFile, ipod_mount_point: &str, checksum_algorithm: Option<&str>) {
+    let track_file_path = std::path::Path::new(ipod_mount_point).join(&track_file.filename);
+
+    track_file.on_device_size_bytes = std::fs::metadata(&track_file_path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len());
+
+    let estimated_size_bytes = track_file.get_estimated_size_bytes();
+
+    track_file.file_status = match track_file.on_device_size_bytes {
+        None => "Missing".to_string(),
+        Some(0) => "Empty".to_string(),
+        Some(on_device_size_bytes) if on_device_size_bytes < estimated_size_bytes / 2 => "Likely corrupt".to_string(),
+        // Some tools that write the database leave the size at 0
+        Some(on_device_size_bytes)
+            if track_file.database_size_bytes != 0 && on_device_size_bytes != track_file.database_size_bytes =>
+        {
+            "Size mismatch".to_string()
+        }
+        Some(_) => "OK".to_string(),
+    };
+
+    if let Some(checksum_algorithm) = checksum_algorithm {
+        if track_file.on_device_size_bytes.is_some() {
+            track_file.file_checksum = helpers::compute_file_checksum(&track_file_path, checksum_algorithm);
+        }
+    }
+}
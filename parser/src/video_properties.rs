@@ -0,0 +1,99 @@
+/**
+ * File: video_properties.rs
+ *
+ * The iTunesDB doesn't store the frame size or codec of videos, but every video the iPod plays
+ * is an MP4 (.mp4/.m4v/.mov) file, where both are in the sample description (stsd) of its video track.
+ *
+ */
+use crate::helpers::helpers;
+
+/// The sample description box has a 4-byte version/flags field and a 4-byte entry count
+const MP4_STSD_PREAMBLE_LEN: usize = 8;
+const MP4_SAMPLE_ENTRY_HEADER_LEN: usize = 8;
+/// Visual sample entries start with 6 reserved bytes, a 2-byte data reference index and
+/// 16 bytes that are reserved or pre-defined, then the width (2 bytes) and height (2 bytes)
+const MP4_VISUAL_SAMPLE_ENTRY_WIDTH_OFFSET: usize = 24;
+const MP4_VISUAL_SAMPLE_ENTRY_HEIGHT_OFFSET: usize = 26;
+
+pub struct VideoProperties {
+    pub codec: String,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// The iPod plays MPEG-4 Part 2 (up to 640x480) and H.264 video
+fn decode_video_sample_entry_type(sample_entry_type: &[u8]) -> Option<String> {
+    if sample_entry_type == b"mp4v" {
+        return Some("MPEG-4".to_string());
+    } else if (sample_entry_type == b"avc1") || (sample_entry_type == b"avc3") {
+        return Some("H.264".to_string());
+    }
+
+    return None;
+}
+
+/// Returns `None` if the file can't be read, or has no MPEG-4 or H.264 video track
+pub fn read_video_properties_from_video_file(
+    video_file_path: &std::path::Path,
+) -> Option<VideoProperties> {
+    let video_file_as_bytes = std::fs::read(video_file_path).ok()?;
+
+    let (moov_start, moov_end) =
+        helpers::find_mp4_atom(&video_file_as_bytes, 0, video_file_as_bytes.len(), b"moov")?;
+
+    // The audio track can come before the video track, so check each one
+    let mut trak_search_start = moov_start;
+
+    while let Some((trak_start, trak_end)) =
+        helpers::find_mp4_atom(&video_file_as_bytes, trak_search_start, moov_end, b"trak")
+    {
+        if let Some(video_properties) =
+            read_video_track_properties(&video_file_as_bytes, trak_start, trak_end)
+        {
+            return Some(video_properties);
+        }
+
+        trak_search_start = trak_end;
+    }
+
+    return None;
+}
+
+/// Looks at the first sample entry of moov > trak > mdia > minf > stbl > stsd
+fn read_video_track_properties(
+    file_as_bytes: &[u8],
+    trak_start: usize,
+    trak_end: usize,
+) -> Option<VideoProperties> {
+    let (mdia_start, mdia_end) = helpers::find_mp4_atom(file_as_bytes, trak_start, trak_end, b"mdia")?;
+    let (minf_start, minf_end) = helpers::find_mp4_atom(file_as_bytes, mdia_start, mdia_end, b"minf")?;
+    let (stbl_start, stbl_end) = helpers::find_mp4_atom(file_as_bytes, minf_start, minf_end, b"stbl")?;
+    let (stsd_start, stsd_end) = helpers::find_mp4_atom(file_as_bytes, stbl_start, stbl_end, b"stsd")?;
+
+    let sample_entry_start = stsd_start + MP4_STSD_PREAMBLE_LEN;
+    let sample_entry_fields_start = sample_entry_start + MP4_SAMPLE_ENTRY_HEADER_LEN;
+
+    if sample_entry_fields_start + MP4_VISUAL_SAMPLE_ENTRY_HEIGHT_OFFSET + 2 > stsd_end {
+        return None;
+    }
+
+    let codec = decode_video_sample_entry_type(
+        &file_as_bytes[sample_entry_start + 4..sample_entry_start + MP4_SAMPLE_ENTRY_HEADER_LEN],
+    )?;
+
+    let width = helpers::build_be_u32_from_bytes(
+        &file_as_bytes[sample_entry_fields_start + MP4_VISUAL_SAMPLE_ENTRY_WIDTH_OFFSET
+            ..sample_entry_fields_start + MP4_VISUAL_SAMPLE_ENTRY_WIDTH_OFFSET + 2],
+    ) as u16;
+
+    let height = helpers::build_be_u32_from_bytes(
+        &file_as_bytes[sample_entry_fields_start + MP4_VISUAL_SAMPLE_ENTRY_HEIGHT_OFFSET
+            ..sample_entry_fields_start + MP4_VISUAL_SAMPLE_ENTRY_HEIGHT_OFFSET + 2],
+    ) as u16;
+
+    return Some(VideoProperties {
+        codec,
+        width,
+        height,
+    });
+}
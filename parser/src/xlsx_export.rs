@@ -0,0 +1,308 @@
+/**
+ * File: xlsx_export.rs
+ *
+ * The CSV exports trip up spreadsheet apps: numbers and dates come in as text (or the other way around),
+ * and commas in titles get split into columns depending on the locale. This writes the main exports into a
+ * single Excel workbook instead, with typed columns and a frozen, filterable header row on every sheet.
+ * Only built with `--features xlsx`.
+ *
+ */
+use rust_xlsxwriter::{ExcelDateTime, Format, Workbook, Worksheet};
+
+use crate::helpers::helpers;
+use crate::ipod_device_info;
+use crate::itunesdb;
+
+/// A typed cell, so each sheet can be written like a CSV record
+enum XlsxCell {
+    Text(String),
+    Number(f64),
+    Boolean(bool),
+    /// Unix timestamp. `None` if the date was never set, which leaves the cell empty
+    DateTime(Option<i64>),
+}
+
+/// Excel keeps numbers as doubles, which can't hold every 64-bit DBID, so those are written as text
+fn get_dbid_cell(dbid: u64) -> XlsxCell {
+    return XlsxCell::Text(dbid.to_string());
+}
+
+/// 0 means the date was never set
+fn get_mac_timestamp_cell(mac_timestamp: u64) -> XlsxCell {
+    if mac_timestamp == 0 {
+        return XlsxCell::DateTime(None);
+    }
+
+    return XlsxCell::DateTime(Some(helpers::get_timestamp_as_mac(mac_timestamp).timestamp()));
+}
+
+fn get_timestamp_cell(timestamp: Option<chrono::DateTime<chrono::Utc>>) -> XlsxCell {
+    return XlsxCell::DateTime(timestamp.map(|timestamp| timestamp.timestamp()));
+}
+
+fn write_row(worksheet: &mut Worksheet, row_idx: u32, row_cells: Vec<XlsxCell>, datetime_format: &Format) {
+    for (col_idx, row_cell) in row_cells.into_iter().enumerate() {
+        let col_idx = col_idx as u16;
+
+        match row_cell {
+            XlsxCell::Text(text_value) => {
+                // Empty cells stay blank, rather than holding an empty string
+                if !text_value.is_empty() {
+                    worksheet
+                        .write_string(row_idx, col_idx, text_value)
+                        .expect("Can't write text cell to XLSX sheet");
+                }
+            }
+            XlsxCell::Number(number_value) => {
+                worksheet
+                    .write_number(row_idx, col_idx, number_value)
+                    .expect("Can't write number cell to XLSX sheet");
+            }
+            XlsxCell::Boolean(boolean_value) => {
+                worksheet
+                    .write_boolean(row_idx, col_idx, boolean_value)
+                    .expect("Can't write boolean cell to XLSX sheet");
+            }
+            XlsxCell::DateTime(Some(timestamp)) => {
+                let excel_datetime = ExcelDateTime::from_timestamp(timestamp)
+                    .expect("Can't convert timestamp for XLSX sheet");
+
+                worksheet
+                    .write_datetime_with_format(row_idx, col_idx, &excel_datetime, datetime_format)
+                    .expect("Can't write date cell to XLSX sheet");
+            }
+            XlsxCell::DateTime(None) => {}
+        }
+    }
+}
+
+/// Adds a sheet with a bold header row, which stays in place when scrolling and can be filtered by
+fn write_sheet(workbook: &mut Workbook, sheet_name: &str, headers: &[&str], rows: Vec<Vec<XlsxCell>>) {
+    let header_format = Format::new().set_bold();
+    let datetime_format = Format::new().set_num_format("yyyy-mm-dd hh:mm:ss");
+
+    let num_rows = rows.len() as u32;
+
+    let worksheet = workbook.add_worksheet();
+
+    worksheet
+        .set_name(sheet_name)
+        .expect(&format!("Can't name XLSX sheet '{}'", sheet_name));
+
+    for (col_idx, header) in headers.iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, col_idx as u16, *header, &header_format)
+            .expect("Can't write header to XLSX sheet");
+    }
+
+    for (row_idx, row_cells) in rows.into_iter().enumerate() {
+        write_row(worksheet, row_idx as u32 + 1, row_cells, &datetime_format);
+    }
+
+    worksheet
+        .set_freeze_panes(1, 0)
+        .expect("Can't freeze the header row of XLSX sheet");
+
+    worksheet
+        .autofilter(0, 0, num_rows, headers.len() as u16 - 1)
+        .expect("Can't add filter to XLSX sheet");
+
+    worksheet.autofit();
+}
+
+/// Writes the songs, podcasts, playlists and device info to one sheet each. The column names are the CSV ones,
+/// minus the duplicates that are only there for text (e.g. "File size" next to "File size (bytes)")
+pub fn write_itunesdb_workbook(
+    xlsx_filename: &str,
+    songs: &[itunesdb::Song],
+    podcasts: &[itunesdb::Podcast],
+    playlists: &[itunesdb::Playlist],
+    ipod_device_info: &ipod_device_info::IpodDeviceInfo,
+) {
+    let mut workbook = Workbook::new();
+
+    write_sheet(
+        &mut workbook,
+        "Music",
+        &[
+            "DBID",
+            "Media kind",
+            "Song Title",
+            "Artist",
+            "Album",
+            "Album Artist",
+            "Composer",
+            "Genre",
+            "Year released",
+            "Track number",
+            "Tracks in album",
+            "Disc number",
+            "Discs in set",
+            "Song duration (seconds)",
+            "Bitrate (kbps)",
+            "Sample Rate (Hz)",
+            "File size (bytes)",
+            "File extension",
+            "Play count",
+            "Skip count",
+            "Rating (stars)",
+            "Compilation",
+            "Added to library on",
+            "Last played",
+            "Last skipped",
+            "Filename",
+        ],
+        songs
+            .iter()
+            .map(|song| {
+                vec![
+                    get_dbid_cell(song.dbid),
+                    XlsxCell::Text(song.media_kind.to_string()),
+                    XlsxCell::Text(song.song_title.to_string()),
+                    XlsxCell::Text(song.song_artist.to_string()),
+                    XlsxCell::Text(song.song_album.to_string()),
+                    XlsxCell::Text(song.song_album_artist.to_string()),
+                    XlsxCell::Text(song.song_composer.to_string()),
+                    XlsxCell::Text(song.song_genre.to_string()),
+                    XlsxCell::Number(song.song_year as f64),
+                    XlsxCell::Number(song.track_number as f64),
+                    XlsxCell::Number(song.num_tracks_in_album as f64),
+                    XlsxCell::Number(song.disc_number as f64),
+                    XlsxCell::Number(song.num_discs as f64),
+                    XlsxCell::Number(song.song_duration_s as f64),
+                    XlsxCell::Number(song.bitrate_kbps as f64),
+                    XlsxCell::Number(song.sample_rate_hz as f64),
+                    XlsxCell::Number(song.file_size_bytes as f64),
+                    XlsxCell::Text(song.file_extension.to_string()),
+                    XlsxCell::Number(song.num_plays as f64),
+                    XlsxCell::Number(song.num_skips as f64),
+                    // The iPod stores 20 per star
+                    XlsxCell::Number(song.song_rating_raw as f64 / 20.0),
+                    XlsxCell::Boolean(song.is_compilation),
+                    get_mac_timestamp_cell(song.song_added_to_library_epoch),
+                    get_timestamp_cell(song.last_played_at),
+                    get_timestamp_cell(song.last_skipped_at),
+                    XlsxCell::Text(song.song_filename.to_string()),
+                ]
+            })
+            .collect(),
+    );
+
+    write_sheet(
+        &mut workbook,
+        "Podcasts",
+        &[
+            "DBID",
+            "Media kind",
+            "Episode Title",
+            "Show",
+            "Episode order",
+            "Publisher",
+            "Genre",
+            "Subtitle",
+            "Description",
+            "Release date",
+            "Duration (seconds)",
+            "Played",
+            "New",
+            "Enclosure URL",
+            "RSS URL",
+        ],
+        podcasts
+            .iter()
+            .map(|podcast| {
+                vec![
+                    get_dbid_cell(podcast.dbid),
+                    XlsxCell::Text(podcast.media_kind.to_string()),
+                    XlsxCell::Text(podcast.podcast_title.to_string()),
+                    XlsxCell::Text(podcast.podcast_show.to_string()),
+                    podcast
+                        .episode_order
+                        .map_or(XlsxCell::Text("".to_string()), |episode_order| XlsxCell::Number(episode_order as f64)),
+                    XlsxCell::Text(podcast.podcast_publisher.to_string()),
+                    XlsxCell::Text(podcast.podcast_genre.to_string()),
+                    XlsxCell::Text(podcast.podcast_subtitle.to_string()),
+                    XlsxCell::Text(podcast.podcast_description.to_string()),
+                    get_timestamp_cell(podcast.release_date),
+                    XlsxCell::Number(podcast.duration_s as f64),
+                    XlsxCell::Boolean(podcast.is_played),
+                    XlsxCell::Boolean(podcast.is_new),
+                    XlsxCell::Text(podcast.enclosure_url.to_string()),
+                    XlsxCell::Text(podcast.rss_url.to_string()),
+                ]
+            })
+            .collect(),
+    );
+
+    write_sheet(
+        &mut workbook,
+        "Playlists",
+        &[
+            "Persistent ID",
+            "Playlist Name",
+            "Playlist kind",
+            "Smart playlist",
+            "Track count",
+            "Membership source",
+            "Created",
+        ],
+        playlists
+            .iter()
+            .map(|playlist| {
+                vec![
+                    get_dbid_cell(playlist.persistent_playlist_id),
+                    XlsxCell::Text(playlist.playlist_name.to_string()),
+                    XlsxCell::Text(playlist.playlist_kind.to_string()),
+                    XlsxCell::Boolean(playlist.is_smart_playlist),
+                    XlsxCell::Number(playlist.track_ids.len() as f64),
+                    XlsxCell::Text(playlist.membership_source.to_string()),
+                    get_timestamp_cell(Some(playlist.created_ts)),
+                ]
+            })
+            .collect(),
+    );
+
+    // A single record, so it's laid out as one field per row
+    let sysinfo = ipod_device_info.sysinfo.as_ref();
+
+    let device_info_fields: Vec<(&str, XlsxCell)> = vec![
+        ("iPod name", XlsxCell::Text(ipod_device_info.ipod_name.to_string())),
+        ("User name", XlsxCell::Text(ipod_device_info.user_name.to_string())),
+        ("Host computer", XlsxCell::Text(ipod_device_info.host_computer_name.to_string())),
+        ("Language", XlsxCell::Text(ipod_device_info.language_name.to_string())),
+        ("Database version", XlsxCell::Number(ipod_device_info.database_version as f64)),
+        ("iTunes version", XlsxCell::Text(ipod_device_info.itunes_version.to_string())),
+        ("Hashing scheme", XlsxCell::Text(ipod_device_info.hashing_scheme.to_string())),
+        (
+            "Model",
+            XlsxCell::Text(
+                ipod_device_info
+                    .model
+                    .as_ref()
+                    .map(|ipod_model| ipod_model.get_description())
+                    .unwrap_or_default(),
+            ),
+        ),
+        ("Model number", XlsxCell::Text(sysinfo.map(|sysinfo| sysinfo.model_number.to_string()).unwrap_or_default())),
+        ("Serial number", XlsxCell::Text(sysinfo.map(|sysinfo| sysinfo.serial_number.to_string()).unwrap_or_default())),
+        ("FireWire GUID", XlsxCell::Text(sysinfo.map(|sysinfo| sysinfo.firewire_guid.to_string()).unwrap_or_default())),
+        ("Firmware version", XlsxCell::Text(sysinfo.map(|sysinfo| sysinfo.firmware_version.to_string()).unwrap_or_default())),
+        ("Songs", XlsxCell::Number(songs.len() as f64)),
+        ("Podcasts", XlsxCell::Number(podcasts.len() as f64)),
+        ("Playlists", XlsxCell::Number(playlists.len() as f64)),
+    ];
+
+    write_sheet(
+        &mut workbook,
+        "Device Info",
+        &["Field", "Value"],
+        device_info_fields
+            .into_iter()
+            .map(|(field_name, field_value)| vec![XlsxCell::Text(field_name.to_string()), field_value])
+            .collect(),
+    );
+
+    workbook
+        .save(xlsx_filename)
+        .expect(&format!("Can't write XLSX workbook '{}'", xlsx_filename));
+}